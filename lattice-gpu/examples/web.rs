@@ -0,0 +1,64 @@
+// Browser demo: seeds a sphere, steps it on WebGPU and lists the energy on
+// the page
+//
+// Build and serve with
+//
+//   cargo build --example web --target wasm32-unknown-unknown --release
+//   wasm-bindgen --target web --out-dir web/pkg \
+//       target/wasm32-unknown-unknown/release/examples/web.wasm
+//   python3 -m http.server --directory web
+//
+// then open http://localhost:8000 in a browser with WebGPU enabled.
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    wasm_bindgen_futures::spawn_local(run());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    eprintln!("This demo runs in a browser; build it for wasm32-unknown-unknown");
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn run() {
+    use lattice_gpu::DiscreteLatticeGPU;
+
+    let mut lattice = match DiscreteLatticeGPU::new(64, 64, 64).await {
+        Ok(lattice) => lattice,
+        Err(err) => {
+            report(&format!("Failed to create lattice: {}", err));
+            return;
+        }
+    };
+    lattice.seed_sphere((32, 32, 32), 6, 3);
+    report(&format!(
+        "Seeded {} quanta",
+        lattice.get_total_energy().await
+    ));
+
+    // Readbacks await the browser's event loop, so the page stays live
+    for _ in 0..20 {
+        let timing = lattice.timed_run(10, 0).await;
+        let occupied = lattice.count_occupied_sites().await;
+        report(&format!(
+            "Step {}: {} quanta on {} sites, {:.2} ms per step",
+            lattice.generation(),
+            lattice.get_total_energy().await,
+            occupied,
+            timing.ms_per_step()
+        ));
+    }
+}
+
+// Append a line to the page's output element
+#[cfg(target_arch = "wasm32")]
+fn report(line: &str) {
+    let output = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id("output"));
+    if let Some(output) = output {
+        let text = output.text_content().unwrap_or_default();
+        output.set_text_content(Some(&format!("{}{}\n", text, line)));
+    }
+}
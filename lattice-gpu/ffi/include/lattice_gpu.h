@@ -0,0 +1,94 @@
+/*
+ * C API for the GPU lattice
+ *
+ * Link against liblattice_gpu_ffi (built by `cargo build -p lattice-gpu-ffi`
+ * as a shared and a static library). Every call blocks until its GPU work is
+ * done. Functions return LATTICE_STATUS_OK on success; otherwise
+ * lattice_gpu_last_error() describes the failure. A handle may be used from
+ * any thread, but from one thread at a time.
+ *
+ *     LatticeGpu *lattice;
+ *     if (lattice_gpu_create(64, 64, 64, &lattice) != LATTICE_STATUS_OK) {
+ *         fprintf(stderr, "%s\n", lattice_gpu_last_error());
+ *         return 1;
+ *     }
+ *     lattice_gpu_add_energy(lattice, 32, 32, 32, 3);
+ *     lattice_gpu_propagate(lattice, 100);
+ *     uint64_t total;
+ *     lattice_gpu_total_energy(lattice, &total);
+ *     lattice_gpu_destroy(lattice);
+ */
+
+#ifndef LATTICE_GPU_H
+#define LATTICE_GPU_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque lattice handle */
+typedef struct LatticeGpu LatticeGpu;
+
+typedef enum LatticeStatus {
+    LATTICE_STATUS_OK = 0,
+    /* A required pointer was null */
+    LATTICE_STATUS_NULL_POINTER = 1,
+    /* A zero dimension, a site outside the lattice or a buffer of the wrong
+     * length */
+    LATTICE_STATUS_INVALID_ARGUMENT = 2,
+    /* No GPU adapter is available */
+    LATTICE_STATUS_ADAPTER_NOT_FOUND = 3,
+    /* Creating the device or its buffers failed */
+    LATTICE_STATUS_DEVICE_ERROR = 4,
+    /* The library panicked; the handle should not be used again */
+    LATTICE_STATUS_PANIC = 5,
+} LatticeStatus;
+
+/* Creates an empty width x height x depth lattice on the default GPU */
+LatticeStatus lattice_gpu_create(uint32_t width, uint32_t height, uint32_t depth,
+                                 LatticeGpu **out);
+
+/* Frees a lattice; null is ignored */
+void lattice_gpu_destroy(LatticeGpu *lattice);
+
+LatticeStatus lattice_gpu_dimensions(const LatticeGpu *lattice, uint32_t *width,
+                                     uint32_t *height, uint32_t *depth);
+
+/* Empties every site, resets the generation to 0 and clears the energy
+ * counters */
+LatticeStatus lattice_gpu_initialize_vacuum(LatticeGpu *lattice);
+
+/* Adds quanta to one site, capped at the maximum level */
+LatticeStatus lattice_gpu_add_energy(LatticeGpu *lattice, uint32_t x, uint32_t y,
+                                     uint32_t z, uint32_t quanta);
+
+/* Advances the lattice `steps` steps */
+LatticeStatus lattice_gpu_propagate(LatticeGpu *lattice, uint32_t steps);
+
+/* Steps taken so far */
+LatticeStatus lattice_gpu_generation(const LatticeGpu *lattice, uint64_t *out);
+
+/* Total quanta on the lattice */
+LatticeStatus lattice_gpu_total_energy(const LatticeGpu *lattice, uint64_t *out);
+
+/* Copies every site's energy into `out`, indexed
+ * z * width * height + y * width + x; `len` must be at least the site count */
+LatticeStatus lattice_gpu_read_state(const LatticeGpu *lattice, uint32_t *out,
+                                     size_t len);
+
+/* Replaces every site's energy; `len` must equal the site count */
+LatticeStatus lattice_gpu_set_state(LatticeGpu *lattice, const uint32_t *energy,
+                                    size_t len);
+
+/* Message of the last failed call on this thread, or null. Valid until the
+ * next failing call on the same thread */
+const char *lattice_gpu_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LATTICE_GPU_H */
@@ -0,0 +1,363 @@
+// C API for the GPU lattice
+//
+// An opaque handle wraps a BlockingLattice, so every call returns once its
+// work is done and C callers need no async runtime. Every function returns
+// a LatticeStatus; on failure, lattice_gpu_last_error gives a message for
+// the calling thread. Panics are caught at the boundary and reported as
+// LATTICE_STATUS_PANIC rather than unwinding into C. The declarations for C
+// are in include/lattice_gpu.h.
+
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::{Lattice, LatticeError};
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Opaque lattice handle, created by [`lattice_gpu_create`] and freed by
+/// [`lattice_gpu_destroy`].
+pub struct LatticeGpu {
+    lattice: BlockingLattice,
+}
+
+/// Result of every call.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LatticeStatus {
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// An argument was out of range: a zero dimension, a site outside the
+    /// lattice or a buffer of the wrong length.
+    InvalidArgument = 2,
+    /// No GPU adapter is available.
+    AdapterNotFound = 3,
+    /// Creating the device or its buffers failed.
+    DeviceError = 4,
+    /// The library panicked; the handle should not be used again.
+    Panic = 5,
+}
+
+thread_local! {
+    // Message of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+// Record `message` as this thread's last error and return `status`
+fn fail(status: LatticeStatus, message: impl Into<String>) -> LatticeStatus {
+    // Interior NULs would truncate the message in C, so drop them
+    let message = message.into().replace('\0', "");
+    let message = CString::new(message).expect("NULs were removed");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    status
+}
+
+// Run `f`, turning a panic into LatticeStatus::Panic
+fn guard(f: impl FnOnce() -> LatticeStatus) -> LatticeStatus {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        fail(LatticeStatus::Panic, message)
+    })
+}
+
+// Borrow the lattice behind `handle`, or fail with NullPointer
+unsafe fn lattice_mut<'a>(
+    handle: *mut LatticeGpu,
+) -> Result<&'a mut BlockingLattice, LatticeStatus> {
+    handle
+        .as_mut()
+        .map(|handle| &mut handle.lattice)
+        .ok_or_else(|| fail(LatticeStatus::NullPointer, "lattice handle is null"))
+}
+
+unsafe fn lattice_ref<'a>(handle: *const LatticeGpu) -> Result<&'a BlockingLattice, LatticeStatus> {
+    handle
+        .as_ref()
+        .map(|handle| &handle.lattice)
+        .ok_or_else(|| fail(LatticeStatus::NullPointer, "lattice handle is null"))
+}
+
+// Write `value` through `out`, or fail with NullPointer
+unsafe fn write_out<T>(out: *mut T, value: T) -> LatticeStatus {
+    match out.as_mut() {
+        Some(out) => {
+            *out = value;
+            LatticeStatus::Ok
+        }
+        None => fail(LatticeStatus::NullPointer, "output pointer is null"),
+    }
+}
+
+/// Creates a `width × height × depth` lattice on the default GPU and
+/// stores its handle in `*out`. The lattice starts empty.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_create(
+    width: u32,
+    height: u32,
+    depth: u32,
+    out: *mut *mut LatticeGpu,
+) -> LatticeStatus {
+    guard(|| {
+        if out.is_null() {
+            return fail(LatticeStatus::NullPointer, "output pointer is null");
+        }
+        if width == 0 || height == 0 || depth == 0 {
+            return fail(
+                LatticeStatus::InvalidArgument,
+                "lattice dimensions must be nonzero",
+            );
+        }
+        match BlockingLattice::new(width, height, depth) {
+            Ok(lattice) => write_out(out, Box::into_raw(Box::new(LatticeGpu { lattice }))),
+            Err(err) => {
+                let status = match err {
+                    LatticeError::AdapterNotFound => LatticeStatus::AdapterNotFound,
+                    _ => LatticeStatus::DeviceError,
+                };
+                fail(status, err.to_string())
+            }
+        }
+    })
+}
+
+/// Frees a lattice and its GPU resources. Null is ignored.
+///
+/// # Safety
+///
+/// `lattice` must be null or a handle from [`lattice_gpu_create`] that has
+/// not been destroyed already.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_destroy(lattice: *mut LatticeGpu) {
+    if !lattice.is_null() {
+        // Dropping releases GPU resources; a panic there must not unwind
+        // into C
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(lattice))));
+    }
+}
+
+/// Stores the lattice's width, height and depth in sites.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle; each output must be null or
+/// valid for writing.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_dimensions(
+    lattice: *const LatticeGpu,
+    width: *mut u32,
+    height: *mut u32,
+    depth: *mut u32,
+) -> LatticeStatus {
+    guard(|| {
+        let lattice = match lattice_ref(lattice) {
+            Ok(lattice) => lattice,
+            Err(status) => return status,
+        };
+        let (w, h, d) = lattice.dimensions();
+        for (out, value) in [(width, w), (height, h), (depth, d)] {
+            let status = write_out(out, value);
+            if status != LatticeStatus::Ok {
+                return status;
+            }
+        }
+        LatticeStatus::Ok
+    })
+}
+
+/// Empties every site, resets the generation to 0 and clears the energy
+/// counters.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle not in use on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_initialize_vacuum(lattice: *mut LatticeGpu) -> LatticeStatus {
+    guard(|| match lattice_mut(lattice) {
+        Ok(lattice) => {
+            lattice.reset();
+            LatticeStatus::Ok
+        }
+        Err(status) => status,
+    })
+}
+
+/// Adds `quanta` to site `(x, y, z)`, capped at the maximum level. Fails
+/// with `InvalidArgument` for a site outside the lattice.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle not in use on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_add_energy(
+    lattice: *mut LatticeGpu,
+    x: u32,
+    y: u32,
+    z: u32,
+    quanta: u32,
+) -> LatticeStatus {
+    guard(|| {
+        let lattice = match lattice_mut(lattice) {
+            Ok(lattice) => lattice,
+            Err(status) => return status,
+        };
+        let (w, h, d) = lattice.dimensions();
+        if x >= w || y >= h || z >= d {
+            return fail(
+                LatticeStatus::InvalidArgument,
+                format!(
+                    "site ({}, {}, {}) is outside the {}x{}x{} lattice",
+                    x, y, z, w, h, d
+                ),
+            );
+        }
+        lattice.add_energy_quantum(x, y, z, quanta);
+        LatticeStatus::Ok
+    })
+}
+
+/// Advances the lattice `steps` steps.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle not in use on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_propagate(
+    lattice: *mut LatticeGpu,
+    steps: u32,
+) -> LatticeStatus {
+    guard(|| match lattice_mut(lattice) {
+        Ok(lattice) => {
+            Lattice::propagate_n(&mut **lattice, steps);
+            LatticeStatus::Ok
+        }
+        Err(status) => status,
+    })
+}
+
+/// Stores the number of steps taken so far in `*out`.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle; `out` must be null or valid for
+/// writing.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_generation(
+    lattice: *const LatticeGpu,
+    out: *mut u64,
+) -> LatticeStatus {
+    guard(|| match lattice_ref(lattice) {
+        Ok(lattice) => write_out(out, lattice.generation()),
+        Err(status) => status,
+    })
+}
+
+/// Stores the total quanta on the lattice in `*out`.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle; `out` must be null or valid for
+/// writing.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_total_energy(
+    lattice: *const LatticeGpu,
+    out: *mut u64,
+) -> LatticeStatus {
+    guard(|| match lattice_ref(lattice) {
+        Ok(lattice) => write_out(out, lattice.get_total_energy()),
+        Err(status) => status,
+    })
+}
+
+/// Copies the energy of every site into `out`, indexed
+/// `z * width * height + y * width + x`. `len` is the number of values
+/// `out` can hold and must be at least `width * height * depth`.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle; `out` must be null or valid for
+/// writing `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_read_state(
+    lattice: *const LatticeGpu,
+    out: *mut u32,
+    len: usize,
+) -> LatticeStatus {
+    guard(|| {
+        let lattice = match lattice_ref(lattice) {
+            Ok(lattice) => lattice,
+            Err(status) => return status,
+        };
+        if out.is_null() {
+            return fail(LatticeStatus::NullPointer, "output pointer is null");
+        }
+        if len < lattice.total_sites() {
+            return fail(
+                LatticeStatus::InvalidArgument,
+                format!(
+                    "buffer holds {} values but the lattice has {} sites",
+                    len,
+                    lattice.total_sites()
+                ),
+            );
+        }
+        let state = lattice.get_state();
+        std::slice::from_raw_parts_mut(out, state.len()).copy_from_slice(&state);
+        LatticeStatus::Ok
+    })
+}
+
+/// Replaces the energy of every site with the `len` values at `energy`, in
+/// [`lattice_gpu_read_state`] layout. `len` must equal
+/// `width * height * depth`.
+///
+/// # Safety
+///
+/// `lattice` must be null or a live handle not in use on another thread;
+/// `energy` must be null or valid for reading `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn lattice_gpu_set_state(
+    lattice: *mut LatticeGpu,
+    energy: *const u32,
+    len: usize,
+) -> LatticeStatus {
+    guard(|| {
+        let lattice = match lattice_mut(lattice) {
+            Ok(lattice) => lattice,
+            Err(status) => return status,
+        };
+        if energy.is_null() {
+            return fail(LatticeStatus::NullPointer, "energy pointer is null");
+        }
+        if len != lattice.total_sites() {
+            return fail(
+                LatticeStatus::InvalidArgument,
+                format!(
+                    "got {} values but the lattice has {} sites",
+                    len,
+                    lattice.total_sites()
+                ),
+            );
+        }
+        lattice.set_state(std::slice::from_raw_parts(energy, len));
+        LatticeStatus::Ok
+    })
+}
+
+/// Message describing the last failed call on this thread, or null if
+/// none has failed. The string stays valid until the next failing call on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn lattice_gpu_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
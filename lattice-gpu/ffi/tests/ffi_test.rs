@@ -0,0 +1,151 @@
+use lattice_gpu_ffi::*;
+use std::ffi::CStr;
+use std::ptr;
+
+fn create(width: u32, height: u32, depth: u32) -> *mut LatticeGpu {
+    let mut lattice = ptr::null_mut();
+    let status = unsafe { lattice_gpu_create(width, height, depth, &mut lattice) };
+    assert_eq!(status, LatticeStatus::Ok);
+    assert!(!lattice.is_null());
+    lattice
+}
+
+fn last_error() -> String {
+    let message = lattice_gpu_last_error();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn test_create_add_propagate_read() {
+    let lattice = create(8, 6, 4);
+    unsafe {
+        let (mut w, mut h, mut d) = (0, 0, 0);
+        assert_eq!(
+            lattice_gpu_dimensions(lattice, &mut w, &mut h, &mut d),
+            LatticeStatus::Ok
+        );
+        assert_eq!((w, h, d), (8, 6, 4));
+
+        assert_eq!(
+            lattice_gpu_add_energy(lattice, 4, 3, 2, 3),
+            LatticeStatus::Ok
+        );
+        assert_eq!(
+            lattice_gpu_add_energy(lattice, 0, 0, 0, 2),
+            LatticeStatus::Ok
+        );
+        assert_eq!(lattice_gpu_propagate(lattice, 10), LatticeStatus::Ok);
+
+        let mut generation = 0;
+        assert_eq!(
+            lattice_gpu_generation(lattice, &mut generation),
+            LatticeStatus::Ok
+        );
+        assert_eq!(generation, 10);
+
+        let mut total = 0;
+        assert_eq!(
+            lattice_gpu_total_energy(lattice, &mut total),
+            LatticeStatus::Ok
+        );
+        assert_eq!(total, 5);
+
+        let mut state = vec![0u32; 8 * 6 * 4];
+        assert_eq!(
+            lattice_gpu_read_state(lattice, state.as_mut_ptr(), state.len()),
+            LatticeStatus::Ok
+        );
+        assert_eq!(state.iter().map(|&e| e as u64).sum::<u64>(), 5);
+
+        lattice_gpu_destroy(lattice);
+    }
+}
+
+#[test]
+fn test_set_state_round_trips() {
+    let lattice = create(4, 4, 4);
+    let energy: Vec<u32> = (0..64).map(|i| i % 4).collect();
+    unsafe {
+        assert_eq!(
+            lattice_gpu_set_state(lattice, energy.as_ptr(), energy.len()),
+            LatticeStatus::Ok
+        );
+        let mut state = vec![0u32; 64];
+        assert_eq!(
+            lattice_gpu_read_state(lattice, state.as_mut_ptr(), state.len()),
+            LatticeStatus::Ok
+        );
+        assert_eq!(state, energy);
+
+        assert_eq!(lattice_gpu_propagate(lattice, 3), LatticeStatus::Ok);
+        assert_eq!(lattice_gpu_initialize_vacuum(lattice), LatticeStatus::Ok);
+        let mut total = 1;
+        lattice_gpu_total_energy(lattice, &mut total);
+        assert_eq!(total, 0);
+        let mut generation = 1;
+        assert_eq!(
+            lattice_gpu_generation(lattice, &mut generation),
+            LatticeStatus::Ok
+        );
+        assert_eq!(generation, 0);
+
+        lattice_gpu_destroy(lattice);
+    }
+}
+
+#[test]
+fn test_invalid_arguments_are_reported() {
+    let mut lattice = ptr::null_mut();
+    unsafe {
+        assert_eq!(
+            lattice_gpu_create(0, 4, 4, &mut lattice),
+            LatticeStatus::InvalidArgument
+        );
+        assert!(lattice.is_null());
+        assert!(last_error().contains("nonzero"));
+
+        let lattice = create(4, 4, 4);
+        assert_eq!(
+            lattice_gpu_add_energy(lattice, 4, 0, 0, 1),
+            LatticeStatus::InvalidArgument
+        );
+        assert!(last_error().contains("outside"));
+
+        let mut short = vec![0u32; 10];
+        assert_eq!(
+            lattice_gpu_read_state(lattice, short.as_mut_ptr(), short.len()),
+            LatticeStatus::InvalidArgument
+        );
+        assert_eq!(
+            lattice_gpu_set_state(lattice, short.as_ptr(), short.len()),
+            LatticeStatus::InvalidArgument
+        );
+        lattice_gpu_destroy(lattice);
+    }
+}
+
+#[test]
+fn test_null_pointers_are_reported() {
+    unsafe {
+        assert_eq!(
+            lattice_gpu_propagate(ptr::null_mut(), 1),
+            LatticeStatus::NullPointer
+        );
+        assert_eq!(
+            lattice_gpu_create(4, 4, 4, ptr::null_mut()),
+            LatticeStatus::NullPointer
+        );
+        assert!(last_error().contains("null"));
+
+        let lattice = create(4, 4, 4);
+        assert_eq!(
+            lattice_gpu_total_energy(lattice, ptr::null_mut()),
+            LatticeStatus::NullPointer
+        );
+        lattice_gpu_destroy(lattice);
+        lattice_gpu_destroy(ptr::null_mut());
+    }
+}
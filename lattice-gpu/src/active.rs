@@ -0,0 +1,222 @@
+// Active-region tracking: dispatching propagation only where energy is
+//
+// Before each step, a mark pass over the current state flags every block
+// (one propagation workgroup's worth of sites) that holds energy, borders
+// one that does, or holds stale energy in the output buffer. The flagged
+// blocks are compacted into a list on the GPU, and the propagation passes
+// are dispatched indirectly with one workgroup per listed block. The host
+// never sees the list, so a step still costs no readback.
+
+use crate::{
+    create_propagation_layout, create_propagation_pipelines, workgroup_constants,
+    DiscreteLatticeGPU, ACTIVE_PROPAGATION_ENTRY_POINTS,
+};
+
+// Workgroup size of compact_active_blocks
+const COMPACT_WORKGROUP_SIZE: u32 = 64;
+
+// Most workgroups a dispatch may have along one dimension
+const MAX_WORKGROUPS: u32 = 65535;
+
+// Block list buffers and the pipelines that build and use them. Built for
+// one workgroup size, so rebuilt along with the propagation pipelines
+pub(crate) struct ActiveRegions {
+    blocks: [u32; 3],
+    block_flags: wgpu::Buffer,
+    // Count, then block indices
+    pub(crate) active_blocks: wgpu::Buffer,
+    dispatch: wgpu::Buffer,
+    mark_bind_group: wgpu::BindGroup,
+    list_bind_group: wgpu::BindGroup,
+    mark_pipeline: wgpu::ComputePipeline,
+    compact_pipeline: wgpu::ComputePipeline,
+    finalize_pipeline: wgpu::ComputePipeline,
+    pub(crate) copy_pipeline: wgpu::ComputePipeline,
+    pub(crate) propagate_pipeline: wgpu::ComputePipeline,
+    pub(crate) gather_pipeline: wgpu::ComputePipeline,
+}
+
+impl ActiveRegions {
+    // Tracking for `lattice`'s size and workgroup size, with its propagation
+    // passes compiled from `source` and the lattice's rule and neighborhood
+    pub(crate) fn new(lattice: &DiscreteLatticeGPU, source: &str) -> Self {
+        let device = &lattice.device;
+        let blocks = lattice.workgroup_count(lattice.workgroup_size);
+        let block_count = blocks.iter().product::<u32>() as u64;
+
+        let block_flags = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Block Flags Buffer"),
+            size: block_count * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let active_blocks = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Active Blocks Buffer"),
+            size: (block_count + 1) * 4,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let dispatch = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Active Dispatch Buffer"),
+            size: 12,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
+            mapped_at_creation: false,
+        });
+
+        let mark_layout = storage_layout(device, "Block Mark Bind Group Layout", &[false; 3]);
+        let list_layout = storage_layout(device, "Active Blocks Bind Group Layout", &[true]);
+        let mark_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Block Mark Bind Group"),
+            layout: &mark_layout,
+            entries: &[&block_flags, &active_blocks, &dispatch]
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+        let list_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Active Blocks Bind Group"),
+            layout: &list_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: active_blocks.as_entire_binding(),
+            }],
+        });
+
+        let cache = lattice.pipeline_cache.as_ref().map(|file| &file.cache);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Active Blocks Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "{}\n{}",
+                    include_str!("active_blocks.wgsl"),
+                    workgroup_constants(lattice.workgroup_size)
+                )
+                .into(),
+            ),
+        });
+        let mark_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Block Mark Pipeline Layout"),
+            bind_group_layouts: &[&lattice.bind_group_layout, &mark_layout],
+            push_constant_ranges: &[],
+        });
+        let [mark_pipeline, compact_pipeline, finalize_pipeline] = [
+            ("Block Mark Pipeline", "mark_active_blocks"),
+            ("Block Compact Pipeline", "compact_active_blocks"),
+            ("Active Dispatch Pipeline", "finalize_active_dispatch"),
+        ]
+        .map(|(label, entry_point)| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&mark_pipeline_layout),
+                module: &shader,
+                entry_point,
+                compilation_options: Default::default(),
+                cache,
+            })
+        });
+
+        let (copy_pipeline, propagate_pipeline, gather_pipeline) = create_propagation_pipelines(
+            device,
+            &create_propagation_layout(
+                device,
+                &[&lattice.bind_group_layout, &list_layout],
+                lattice.push_constants,
+            ),
+            source,
+            &lattice.rule_source,
+            lattice.neighborhood,
+            lattice.workgroup_size,
+            lattice.push_constants,
+            cache,
+            ACTIVE_PROPAGATION_ENTRY_POINTS,
+        );
+
+        Self {
+            blocks,
+            block_flags,
+            active_blocks,
+            dispatch,
+            mark_bind_group,
+            list_bind_group,
+            mark_pipeline,
+            compact_pipeline,
+            finalize_pipeline,
+            copy_pipeline,
+            propagate_pipeline,
+            gather_pipeline,
+        }
+    }
+
+    // Record the passes that list the blocks a step reading through
+    // `step_bind_group` must dispatch, and size its indirect dispatch
+    pub(crate) fn encode_mark(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        step_bind_group: &wgpu::BindGroup,
+    ) {
+        encoder.clear_buffer(&self.block_flags, 0, None);
+        encoder.clear_buffer(&self.active_blocks, 0, Some(4));
+
+        let [blocks_x, blocks_y, blocks_z] = self.blocks;
+        let groups = (blocks_x * blocks_y * blocks_z).div_ceil(COMPACT_WORKGROUP_SIZE);
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Active Blocks Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_bind_group(0, step_bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.mark_bind_group, &[]);
+        compute_pass.set_pipeline(&self.mark_pipeline);
+        compute_pass.dispatch_workgroups(blocks_x, blocks_y, blocks_z);
+        compute_pass.set_pipeline(&self.compact_pipeline);
+        compute_pass.dispatch_workgroups(
+            groups.min(MAX_WORKGROUPS),
+            groups.div_ceil(MAX_WORKGROUPS),
+            1,
+        );
+        compute_pass.set_pipeline(&self.finalize_pipeline);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+    }
+
+    // Free the block list buffers, for DiscreteLatticeGPU::shutdown
+    pub(crate) fn destroy(&self) {
+        self.block_flags.destroy();
+        self.active_blocks.destroy();
+        self.dispatch.destroy();
+    }
+
+    // Dispatch the pipeline set on `pass` over the listed blocks
+    pub(crate) fn dispatch(&self, pass: &mut wgpu::ComputePass) {
+        pass.set_bind_group(1, &self.list_bind_group, &[]);
+        pass.dispatch_workgroups_indirect(&self.dispatch, 0);
+    }
+}
+
+// A bind group layout of storage buffers at bindings 0.., read-only where
+// `read_only` says so
+fn storage_layout(device: &wgpu::Device, label: &str, read_only: &[bool]) -> wgpu::BindGroupLayout {
+    let entries: Vec<wgpu::BindGroupLayoutEntry> = read_only
+        .iter()
+        .enumerate()
+        .map(|(binding, &read_only)| wgpu::BindGroupLayoutEntry {
+            binding: binding as u32,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        })
+        .collect();
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &entries,
+    })
+}
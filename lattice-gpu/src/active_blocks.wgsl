@@ -0,0 +1,113 @@
+// Active-block list for the propagation passes
+//
+// The lattice is tiled into blocks of one propagation workgroup each. A
+// block is active when it or any of its 26 neighboring blocks holds energy,
+// since a quantum moves at most one site per step, or when it still holds
+// stale energy in energy_out that the step must overwrite. Every other block
+// of energy_out is all zero and stays that way, so the propagation passes
+// skip it.
+//
+// Binds the step bind group as group 0, so the mark pass reads the same
+// energy_in and energy_out as the step it prepares.
+
+struct Params {
+    width: u32,
+    height: u32,
+    depth: u32,
+    step_count: u32,
+    weight_x: u32,
+    weight_y: u32,
+    weight_z: u32,
+    boundary_modes: u32,
+    seed: u32,
+    decay_threshold: u32,
+    capacity_map: u32,
+    z_offset: u32,
+    lattice_depth: u32,
+    _padding2: u32,
+    _padding3: u32,
+    _padding4: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> energy_in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> energy_out: array<atomic<u32>>;
+// Bindings 3-6 are part of the shared layout but unused here
+
+// Nonzero for each block the step must dispatch
+@group(1) @binding(0) var<storage, read_write> block_flags: array<atomic<u32>>;
+// Number of active blocks, then their indices in no particular order
+@group(1) @binding(1) var<storage, read_write> active_blocks: array<atomic<u32>>;
+// Indirect dispatch arguments of the propagation passes
+@group(1) @binding(2) var<storage, read_write> dispatch: array<u32, 3>;
+
+// Most workgroups a dispatch may have along one dimension
+const MAX_WORKGROUPS: u32 = 65535u;
+
+var<workgroup> block_occupied: atomic<u32>;
+var<workgroup> block_stale: atomic<u32>;
+
+// Flag the blocks the next step must dispatch. Runs one workgroup per
+// block; workgroup memory starts zeroed, so the block's sites only ever
+// raise the two markers
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z)
+fn mark_active_blocks(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) block: vec3<u32>,
+    @builtin(num_workgroups) blocks: vec3<u32>,
+) {
+    if (global_id.x < params.width && global_id.y < params.height && global_id.z < params.depth) {
+        let idx = global_id.z * params.width * params.height + global_id.y * params.width + global_id.x;
+        if (energy_in[idx] != 0u) {
+            atomicStore(&block_occupied, 1u);
+        }
+        if (atomicLoad(&energy_out[idx]) != 0u) {
+            atomicStore(&block_stale, 1u);
+        }
+    }
+    workgroupBarrier();
+
+    if (local_index != 0u) {
+        return;
+    }
+    if (atomicLoad(&block_occupied) != 0u) {
+        // Neighbors wrap on every axis: flagging a block across a closed
+        // face costs a little work but never changes the result
+        for (var dz = 0u; dz < 3u; dz++) {
+            for (var dy = 0u; dy < 3u; dy++) {
+                for (var dx = 0u; dx < 3u; dx++) {
+                    let n = (block + blocks + vec3<u32>(dx, dy, dz) - 1u) % blocks;
+                    atomicStore(&block_flags[(n.z * blocks.y + n.y) * blocks.x + n.x], 1u);
+                }
+            }
+        }
+    } else if (atomicLoad(&block_stale) != 0u) {
+        atomicStore(&block_flags[(block.z * blocks.y + block.y) * blocks.x + block.x], 1u);
+    }
+}
+
+// Append every flagged block to active_blocks. Dispatched over two
+// dimensions so lattices of more than 65535 * 64 blocks fit
+@compute @workgroup_size(64)
+fn compact_active_blocks(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let block = global_id.y * groups.x * 64u + global_id.x;
+    if (block >= arrayLength(&block_flags) || atomicLoad(&block_flags[block]) == 0u) {
+        return;
+    }
+    let slot = atomicAdd(&active_blocks[0], 1u);
+    atomicStore(&active_blocks[slot + 1u], block);
+}
+
+// Size the indirect dispatch to one workgroup per active block, wrapping
+// into Y past the per-dimension limit
+@compute @workgroup_size(1)
+fn finalize_active_dispatch() {
+    let count = atomicLoad(&active_blocks[0]);
+    dispatch[0] = min(count, MAX_WORKGROUPS);
+    dispatch[1] = (count + MAX_WORKGROUPS - 1u) / MAX_WORKGROUPS;
+    dispatch[2] = 1u;
+}
@@ -0,0 +1,100 @@
+// Choosing the adapter a lattice runs on
+
+use crate::LatticeError;
+
+/// How to choose the adapter for
+/// [`DiscreteLatticeGPU::new_with_options`](crate::DiscreteLatticeGPU::new_with_options).
+///
+/// The default picks the high-performance adapter on any backend, as
+/// [`DiscreteLatticeGPU::new`](crate::DiscreteLatticeGPU::new) does.
+#[derive(Clone, Debug)]
+pub struct AdapterOptions {
+    /// Backends to consider, e.g. `wgpu::Backends::VULKAN` to force Vulkan.
+    pub backends: wgpu::Backends,
+    /// Preference between low-power and high-performance adapters. Ignored
+    /// when `name` or `index` is set.
+    pub power_preference: wgpu::PowerPreference,
+    /// Only consider the fallback (software) adapter, such as lavapipe.
+    pub force_fallback_adapter: bool,
+    /// Only consider adapters whose name contains this, ignoring case.
+    /// Browsers don't list their adapters, so no adapter matches there.
+    pub name: Option<String>,
+    /// Take the adapter at this position in `list_adapters` order, counting
+    /// only those that match `name`. As with `name`, no adapter matches in
+    /// a browser.
+    pub index: Option<usize>,
+}
+
+impl Default for AdapterOptions {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            name: None,
+            index: None,
+        }
+    }
+}
+
+impl AdapterOptions {
+    /// Finds the adapter these options describe.
+    ///
+    /// Fails with [`LatticeError::AdapterNotFound`] if no adapter matches.
+    pub async fn request_adapter(&self) -> Result<wgpu::Adapter, LatticeError> {
+        let instance = instance(self.backends);
+        if self.name.is_none() && self.index.is_none() {
+            return instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: self.power_preference,
+                    force_fallback_adapter: self.force_fallback_adapter,
+                    compatible_surface: None,
+                })
+                .await
+                .ok_or(LatticeError::AdapterNotFound);
+        }
+
+        self.find_listed(&instance)
+            .ok_or(LatticeError::AdapterNotFound)
+    }
+
+    // The adapter `name` and `index` pick out of the listed ones
+    #[cfg(not(target_arch = "wasm32"))]
+    fn find_listed(&self, instance: &wgpu::Instance) -> Option<wgpu::Adapter> {
+        let name = self.name.as_deref().map(str::to_lowercase);
+        instance
+            .enumerate_adapters(self.backends)
+            .into_iter()
+            .filter(|adapter| {
+                let info = adapter.get_info();
+                let named = name
+                    .as_ref()
+                    .is_none_or(|name| info.name.to_lowercase().contains(name));
+                named && (!self.force_fallback_adapter || info.device_type == wgpu::DeviceType::Cpu)
+            })
+            .nth(self.index.unwrap_or(0))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn find_listed(&self, _instance: &wgpu::Instance) -> Option<wgpu::Adapter> {
+        None
+    }
+}
+
+/// Every adapter on `backends`, in the order [`AdapterOptions::index`]
+/// counts them. Not available in a browser.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_adapters(backends: wgpu::Backends) -> Vec<wgpu::AdapterInfo> {
+    instance(backends)
+        .enumerate_adapters(backends)
+        .iter()
+        .map(wgpu::Adapter::get_info)
+        .collect()
+}
+
+fn instance(backends: wgpu::Backends) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    })
+}
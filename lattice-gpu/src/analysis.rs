@@ -0,0 +1,148 @@
+// CPU-side field analysis
+//
+// Functions here operate on a host copy of the energy buffer laid out the
+// same way as on the GPU: index = z * width * height + y * width + x.
+// Neighbors follow the per-axis boundary modes as in shader.wgsl.
+
+use crate::cpu::{can_step, step_coord};
+use crate::BoundaryMode;
+
+// Sum of |e(site) - e(neighbor)| over every pair of adjacent sites, each
+// pair counted once by only looking in the +X, +Y and +Z directions. Only
+// periodic axes pair the last layer with the first; other faces add no pair
+pub(crate) fn total_variation(
+    energy: &[u32],
+    (width, height, depth): (u32, u32, u32),
+    modes: [BoundaryMode; 3],
+) -> u64 {
+    let extents = [width, height, depth];
+    let mut total = 0u64;
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                let c = [x, y, z];
+                let here = energy[index(x, y, z, width, height)];
+                for axis in 0..3 {
+                    if c[axis] + 1 == extents[axis] && modes[axis] != BoundaryMode::Periodic {
+                        continue;
+                    }
+                    let mut n = c;
+                    n[axis] = (c[axis] + 1) % extents[axis];
+                    total += here.abs_diff(energy[index(n[0], n[1], n[2], width, height)]) as u64;
+                }
+            }
+        }
+    }
+    total
+}
+
+// Discrete Laplacian: sum over the six neighbors of e(neighbor) - e(site),
+// with neighbors as the propagation rule sees them. Beyond a closed face
+// there is no neighbor, and beyond an absorbing one an empty sink
+pub(crate) fn laplacian(
+    energy: &[u32],
+    (width, height, depth): (u32, u32, u32),
+    modes: [BoundaryMode; 3],
+) -> Vec<i32> {
+    let extents = [width, height, depth];
+    let mut result = Vec::with_capacity(energy.len());
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                let c = [x, y, z];
+                let here = energy[index(x, y, z, width, height)] as i64;
+                let mut sum = 0i64;
+                for axis in 0..3 {
+                    for delta in [1, -1] {
+                        if can_step(c[axis], delta, extents[axis], modes[axis]) {
+                            let mut n = c;
+                            n[axis] = step_coord(c[axis], delta, extents[axis], modes[axis]);
+                            sum += energy[index(n[0], n[1], n[2], width, height)] as i64 - here;
+                        } else if modes[axis] == BoundaryMode::Absorbing {
+                            sum -= here;
+                        }
+                    }
+                }
+                result.push(sum as i32);
+            }
+        }
+    }
+    result
+}
+
+// Energy-weighted mean site coordinate, or None for an empty lattice
+pub(crate) fn center_of_mass(
+    energy: &[u32],
+    (width, height, depth): (u32, u32, u32),
+) -> Option<[f64; 3]> {
+    let mut total = 0u64;
+    let mut weighted = [0f64; 3];
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                let e = energy[index(x, y, z, width, height)];
+                if e == 0 {
+                    continue;
+                }
+                total += e as u64;
+                weighted[0] += x as f64 * e as f64;
+                weighted[1] += y as f64 * e as f64;
+                weighted[2] += z as f64 * e as f64;
+            }
+        }
+    }
+
+    if total == 0 {
+        return None;
+    }
+    Some(weighted.map(|w| w / total as f64))
+}
+
+// Energy-weighted variance of each coordinate about the center of mass
+pub(crate) fn second_moment(energy: &[u32], dims: (u32, u32, u32)) -> Option<[f64; 3]> {
+    let center = center_of_mass(energy, dims)?;
+    let (width, height, depth) = dims;
+
+    let mut total = 0u64;
+    let mut moment = [0f64; 3];
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                let e = energy[index(x, y, z, width, height)];
+                if e == 0 {
+                    continue;
+                }
+                total += e as u64;
+                for (axis, coord) in [x, y, z].into_iter().enumerate() {
+                    let offset = coord as f64 - center[axis];
+                    moment[axis] += offset * offset * e as f64;
+                }
+            }
+        }
+    }
+    Some(moment.map(|m| m / total as f64))
+}
+
+// Every site where the two states disagree, as (x, y, z, a, b)
+pub(crate) fn diff(
+    a: &[u32],
+    b: &[u32],
+    (width, height, _depth): (u32, u32, u32),
+) -> Vec<(u32, u32, u32, u32, u32)> {
+    a.iter()
+        .zip(b)
+        .enumerate()
+        .filter(|(_, (ea, eb))| ea != eb)
+        .map(|(idx, (&ea, &eb))| {
+            let idx = idx as u32;
+            let x = idx % width;
+            let y = (idx / width) % height;
+            let z = idx / (width * height);
+            (x, y, z, ea, eb)
+        })
+        .collect()
+}
+
+fn index(x: u32, y: u32, z: u32, width: u32, height: u32) -> usize {
+    (z * width * height + y * width + x) as usize
+}
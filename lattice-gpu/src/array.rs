@@ -0,0 +1,66 @@
+// Conversions between lattice states and ndarray arrays
+//
+// Arrays are indexed [[x, y, z]], like every coordinate in the API. The
+// lattice stores x fastest, which is column-major (Fortran) order for that
+// indexing, so to_array wraps the downloaded state without reordering it.
+
+use crate::{DiscreteLatticeGPU, LatticeError};
+use ndarray::{Array3, ArrayView3, ShapeBuilder};
+
+impl DiscreteLatticeGPU {
+    /// Creates a lattice the shape of `energy` on the default adapter, with
+    /// `energy[[x, y, z]]` quanta at site `(x, y, z)`.
+    ///
+    /// Values are uploaded as given, as with
+    /// [`set_state`](Self::set_state). Any memory layout is accepted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a dimension of `energy` exceeds `u32::MAX`.
+    pub async fn from_array(energy: &Array3<u32>) -> Result<Self, LatticeError> {
+        let (width, height, depth) = array_dimensions(&energy.view());
+        let mut lattice = Self::new(width, height, depth).await?;
+        lattice.set_state_array(&energy.view());
+        Ok(lattice)
+    }
+
+    /// Replaces the energy of every site with `energy[[x, y, z]]`, as
+    /// [`set_state`](Self::set_state) does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shape of `energy` is not `(width, height, depth)`.
+    pub fn set_state_array(&mut self, energy: &ArrayView3<u32>) {
+        assert_eq!(
+            array_dimensions(energy),
+            (self.width, self.height, self.depth),
+            "Array shape must match the lattice"
+        );
+        self.set_state(&state_from_array(energy));
+    }
+
+    /// Downloads the energy of every site as an array indexed
+    /// `[[x, y, z]]`, in column-major layout.
+    pub async fn to_array(&self) -> Array3<u32> {
+        let state = self.get_state().await;
+        state_to_array(state, (self.width, self.height, self.depth))
+    }
+}
+
+// `(width, height, depth)` of an array indexed [[x, y, z]]
+fn array_dimensions(energy: &ArrayView3<u32>) -> (u32, u32, u32) {
+    let dimension = |len: usize| u32::try_from(len).expect("Array dimension exceeds u32::MAX");
+    let (width, height, depth) = energy.dim();
+    (dimension(width), dimension(height), dimension(depth))
+}
+
+// The array's values in get_state order. The reversed view iterates z,
+// y, x with x fastest, whatever the array's memory layout
+fn state_from_array(energy: &ArrayView3<u32>) -> Vec<u32> {
+    energy.t().iter().copied().collect()
+}
+
+fn state_to_array(state: Vec<u32>, (width, height, depth): (u32, u32, u32)) -> Array3<u32> {
+    Array3::from_shape_vec((width as usize, height as usize, depth as usize).f(), state)
+        .expect("State holds one value per site")
+}
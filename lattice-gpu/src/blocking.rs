@@ -0,0 +1,281 @@
+// Blocking conveniences over the async GPU API
+//
+// Everything else in the crate is async and never blocks the calling
+// thread on a future, so it can be driven from any executor. This module is
+// the one place futures are driven to completion with pollster, for scripts,
+// tests and other callers without a runtime of their own. Don't use it from
+// inside an async runtime: blocking a runtime thread on GPU work can stall
+// or deadlock it.
+
+use crate::{
+    CancellationToken, ChunkedLattice, Diagnostics, DiscreteLatticeGPU, Lattice, LatticeConfig,
+    LatticeError, LatticeState, Measurement, MultiGpuLattice, OutOfCoreLattice, PropagationRule,
+};
+use std::io;
+use std::ops::{ControlFlow, Deref, DerefMut};
+use std::path::Path;
+
+/// A [`DiscreteLatticeGPU`] whose readbacks block until the data arrives.
+///
+/// Derefs to the lattice, so every synchronous method is available as is;
+/// the async readbacks are replaced by blocking methods of the same name.
+pub struct BlockingLattice {
+    inner: DiscreteLatticeGPU,
+}
+
+impl BlockingLattice {
+    /// Blocking version of [`DiscreteLatticeGPU::new`].
+    pub fn new(width: u32, height: u32, depth: u32) -> Result<Self, LatticeError> {
+        pollster::block_on(DiscreteLatticeGPU::new(width, height, depth)).map(Self::from)
+    }
+
+    pub fn into_inner(self) -> DiscreteLatticeGPU {
+        self.inner
+    }
+
+    pub fn get_total_energy(&self) -> u64 {
+        pollster::block_on(self.inner.get_total_energy())
+    }
+
+    pub fn get_state(&self) -> Vec<u32> {
+        pollster::block_on(self.inner.get_state())
+    }
+
+    pub fn get_energy_at(&self, x: u32, y: u32, z: u32) -> u32 {
+        pollster::block_on(self.inner.get_energy_at(x, y, z))
+    }
+
+    pub fn absorbed_energy(&self) -> u64 {
+        pollster::block_on(self.inner.absorbed_energy())
+    }
+
+    pub fn decayed_energy(&self) -> u64 {
+        pollster::block_on(self.inner.decayed_energy())
+    }
+
+    pub fn injected_energy(&self) -> u64 {
+        pollster::block_on(self.inner.injected_energy())
+    }
+
+    pub fn save_state(&self) -> LatticeState {
+        pollster::block_on(self.inner.save_state())
+    }
+
+    /// Blocking version of [`DiscreteLatticeGPU::from_checkpoint`].
+    pub fn from_checkpoint(path: &Path) -> Result<Self, LatticeError> {
+        pollster::block_on(DiscreteLatticeGPU::from_checkpoint(path)).map(Self::from)
+    }
+
+    pub fn save_checkpoint(&self, path: &Path) -> io::Result<()> {
+        pollster::block_on(self.inner.save_checkpoint(path))
+    }
+
+    pub fn tick(&mut self, measure: Measurement) -> Option<f64> {
+        pollster::block_on(self.inner.tick(measure))
+    }
+
+    pub fn propagate_observed<F>(&mut self, steps: u32, interval: u32, observer: F) -> u32
+    where
+        F: FnMut(&Diagnostics) -> ControlFlow<()>,
+    {
+        pollster::block_on(self.inner.propagate_observed(steps, interval, observer))
+    }
+
+    pub fn propagate_cancellable(
+        &mut self,
+        steps: u32,
+        batch: u32,
+        token: &CancellationToken,
+    ) -> u32 {
+        pollster::block_on(self.inner.propagate_cancellable(steps, batch, token))
+    }
+
+    pub fn set_rule(&mut self, rule: &dyn PropagationRule) -> Result<(), LatticeError> {
+        pollster::block_on(self.inner.set_rule(rule))
+    }
+
+    /// Blocking version of [`DiscreteLatticeGPU::from_config`].
+    pub fn from_config(config: &LatticeConfig) -> Result<Self, LatticeError> {
+        pollster::block_on(DiscreteLatticeGPU::from_config(config)).map(Self::from)
+    }
+
+    pub fn apply_config(&mut self, config: &LatticeConfig) -> Result<(), LatticeError> {
+        pollster::block_on(self.inner.apply_config(config))
+    }
+
+    /// Blocking version of [`DiscreteLatticeGPU::from_array`].
+    #[cfg(feature = "ndarray")]
+    pub fn from_array(energy: &ndarray::Array3<u32>) -> Result<Self, LatticeError> {
+        pollster::block_on(DiscreteLatticeGPU::from_array(energy)).map(Self::from)
+    }
+
+    #[cfg(feature = "ndarray")]
+    pub fn to_array(&self) -> ndarray::Array3<u32> {
+        pollster::block_on(self.inner.to_array())
+    }
+}
+
+/// Blocking version of [`run_from_config`](crate::run_from_config).
+pub fn run_from_config(path: &Path) -> Result<BlockingLattice, LatticeError> {
+    pollster::block_on(crate::run_from_config(path)).map(BlockingLattice::from)
+}
+
+impl From<DiscreteLatticeGPU> for BlockingLattice {
+    fn from(inner: DiscreteLatticeGPU) -> Self {
+        Self { inner }
+    }
+}
+
+impl Deref for BlockingLattice {
+    type Target = DiscreteLatticeGPU;
+
+    fn deref(&self) -> &DiscreteLatticeGPU {
+        &self.inner
+    }
+}
+
+impl DerefMut for BlockingLattice {
+    fn deref_mut(&mut self) -> &mut DiscreteLatticeGPU {
+        &mut self.inner
+    }
+}
+
+impl Lattice for DiscreteLatticeGPU {
+    fn dimensions(&self) -> (u32, u32, u32) {
+        (self.width(), self.height(), self.depth())
+    }
+
+    fn initialize_vacuum(&mut self) {
+        DiscreteLatticeGPU::initialize_vacuum(self);
+    }
+
+    fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        DiscreteLatticeGPU::add_energy_quantum(self, x, y, z, quanta);
+    }
+
+    fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        DiscreteLatticeGPU::add_energy_batch(self, edits);
+    }
+
+    fn propagate_energy(&mut self) {
+        DiscreteLatticeGPU::propagate_energy(self);
+    }
+
+    fn propagate_n(&mut self, steps: u32) {
+        DiscreteLatticeGPU::propagate_n(self, steps);
+    }
+
+    fn total_energy(&self) -> u64 {
+        pollster::block_on(self.get_total_energy())
+    }
+
+    fn read_state(&self) -> Vec<u32> {
+        pollster::block_on(self.get_state())
+    }
+
+    fn generation(&self) -> u64 {
+        DiscreteLatticeGPU::generation(self)
+    }
+}
+
+impl Lattice for ChunkedLattice {
+    fn dimensions(&self) -> (u32, u32, u32) {
+        ChunkedLattice::dimensions(self)
+    }
+
+    fn initialize_vacuum(&mut self) {
+        ChunkedLattice::initialize_vacuum(self);
+    }
+
+    fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        ChunkedLattice::add_energy_quantum(self, x, y, z, quanta);
+    }
+
+    fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        ChunkedLattice::add_energy_batch(self, edits);
+    }
+
+    fn propagate_energy(&mut self) {
+        ChunkedLattice::propagate_energy(self);
+    }
+
+    fn total_energy(&self) -> u64 {
+        pollster::block_on(self.get_total_energy())
+    }
+
+    fn read_state(&self) -> Vec<u32> {
+        pollster::block_on(self.get_state())
+    }
+
+    fn generation(&self) -> u64 {
+        ChunkedLattice::generation(self)
+    }
+}
+
+impl Lattice for MultiGpuLattice {
+    fn dimensions(&self) -> (u32, u32, u32) {
+        MultiGpuLattice::dimensions(self)
+    }
+
+    fn initialize_vacuum(&mut self) {
+        MultiGpuLattice::initialize_vacuum(self);
+    }
+
+    fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        MultiGpuLattice::add_energy_quantum(self, x, y, z, quanta);
+    }
+
+    fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        MultiGpuLattice::add_energy_batch(self, edits);
+    }
+
+    fn propagate_energy(&mut self) {
+        pollster::block_on(MultiGpuLattice::propagate_energy(self));
+    }
+
+    fn total_energy(&self) -> u64 {
+        pollster::block_on(self.get_total_energy())
+    }
+
+    fn read_state(&self) -> Vec<u32> {
+        pollster::block_on(self.get_state())
+    }
+
+    fn generation(&self) -> u64 {
+        MultiGpuLattice::generation(self)
+    }
+}
+
+impl Lattice for OutOfCoreLattice {
+    fn dimensions(&self) -> (u32, u32, u32) {
+        OutOfCoreLattice::dimensions(self)
+    }
+
+    fn initialize_vacuum(&mut self) {
+        OutOfCoreLattice::initialize_vacuum(self);
+    }
+
+    fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        OutOfCoreLattice::add_energy_quantum(self, x, y, z, quanta);
+    }
+
+    fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        OutOfCoreLattice::add_energy_batch(self, edits);
+    }
+
+    fn propagate_energy(&mut self) {
+        pollster::block_on(OutOfCoreLattice::propagate_energy(self));
+    }
+
+    fn total_energy(&self) -> u64 {
+        self.get_total_energy()
+    }
+
+    fn read_state(&self) -> Vec<u32> {
+        self.get_state().to_vec()
+    }
+
+    fn generation(&self) -> u64 {
+        OutOfCoreLattice::generation(self)
+    }
+}
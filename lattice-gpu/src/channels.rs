@@ -0,0 +1,149 @@
+// Several energy channels on one lattice
+//
+// Each channel is a full DiscreteLatticeGPU with its own buffers, so it can
+// run its own rule (weights, boundaries, neighborhood, frozen sites...).
+// All channels share one device and queue. Channels interact only through
+// optional coupling terms, which feed one channel's energy into another's
+// potential.
+
+use crate::{DiscreteLatticeGPU, LatticeError};
+
+pub struct ChannelLattice {
+    channels: Vec<DiscreteLatticeGPU>,
+    // coupling[target][source]: potential added to target per quantum of
+    // source at the same site
+    coupling: Vec<Vec<f32>>,
+}
+
+impl ChannelLattice {
+    /// Creates `channels` independent channels of `width × height × depth`
+    /// sites on the default adapter, sharing one device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channels` is 0.
+    pub async fn new(
+        width: u32,
+        height: u32,
+        depth: u32,
+        channels: usize,
+    ) -> Result<Self, LatticeError> {
+        assert!(channels > 0, "A lattice needs at least one channel");
+
+        let first = DiscreteLatticeGPU::new(width, height, depth).await?;
+        let (device, queue) = (first.device.clone(), first.queue.clone());
+        let mut lattices = vec![first];
+        for _ in 1..channels {
+            lattices.push(DiscreteLatticeGPU::new_with_device(
+                device.clone(),
+                queue.clone(),
+                width,
+                height,
+                depth,
+            )?);
+        }
+
+        Ok(Self {
+            channels: lattices,
+            coupling: vec![vec![0.0; channels]; channels],
+        })
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn channel(&self, channel: usize) -> &DiscreteLatticeGPU {
+        &self.channels[channel]
+    }
+
+    /// The lattice holding one channel, for setting its rule or reading its
+    /// state.
+    pub fn channel_mut(&mut self, channel: usize) -> &mut DiscreteLatticeGPU {
+        &mut self.channels[channel]
+    }
+
+    /// Empties every channel.
+    pub fn initialize_vacuum(&mut self) {
+        for lattice in &mut self.channels {
+            lattice.initialize_vacuum();
+        }
+    }
+
+    /// Adds quanta to one site of one channel, capped at
+    /// [`MAX_LEVEL`](crate::MAX_LEVEL) like
+    /// [`add_energy_quantum`](DiscreteLatticeGPU::add_energy_quantum).
+    pub fn add_energy_quantum_channel(
+        &mut self,
+        channel: usize,
+        x: u32,
+        y: u32,
+        z: u32,
+        quanta: u32,
+    ) {
+        self.channels[channel].add_energy_quantum(x, y, z, quanta);
+    }
+
+    /// Couples channel `target` to channel `source`: before each step the
+    /// potential of `target` is set to the sum over sources of
+    /// `strength × source energy` at each site.
+    ///
+    /// A positive strength makes `target` quanta avoid sites holding
+    /// `source` quanta, a negative one draws them in. A coupled channel's
+    /// potential is rewritten every step, replacing any set with
+    /// [`set_potential`](DiscreteLatticeGPU::set_potential). Coupling reads
+    /// each source's state back to the host once per step, so it costs a
+    /// full readback per source channel.
+    pub fn set_coupling(&mut self, target: usize, source: usize, strength: f32) {
+        assert!(
+            strength.is_finite(),
+            "Coupling strength must be finite, got {}",
+            strength
+        );
+        assert!(source < self.channels.len(), "No channel {}", source);
+        self.coupling[target][source] = strength;
+    }
+
+    /// Advances every channel one step, after refreshing coupled potentials.
+    pub async fn propagate_energy(&mut self) {
+        self.apply_coupling().await;
+        for lattice in &mut self.channels {
+            lattice.propagate_energy();
+        }
+    }
+
+    /// Total energy of each channel, reduced on the GPU.
+    pub async fn channel_totals(&self) -> Vec<u64> {
+        let mut totals = Vec::with_capacity(self.channels.len());
+        for lattice in &self.channels {
+            totals.push(lattice.get_total_energy().await);
+        }
+        totals
+    }
+
+    // Set each coupled channel's potential from the current source states,
+    // reading every source back at most once
+    async fn apply_coupling(&mut self) {
+        let mut states: Vec<Option<Vec<u32>>> = vec![None; self.channels.len()];
+        for target in 0..self.channels.len() {
+            if self.coupling[target].iter().all(|&s| s == 0.0) {
+                continue;
+            }
+
+            let mut potential = vec![0.0f32; self.channels[target].total_sites()];
+            for (source, &strength) in self.coupling[target].iter().enumerate() {
+                if strength == 0.0 {
+                    continue;
+                }
+                if states[source].is_none() {
+                    states[source] = Some(self.channels[source].get_state().await);
+                }
+                let state = states[source].as_ref().unwrap();
+                for (v, &e) in potential.iter_mut().zip(state.iter()) {
+                    *v += strength * e as f32;
+                }
+            }
+            self.channels[target].set_potential(&potential);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+// Checkpoint files for resuming runs
+//
+// A checkpoint holds what a run needs to continue bit for bit: the
+// dimensions, the generation (which seeds each step's random choices), the
+// RNG seed and the energy. Most of a lattice is usually empty, so the
+// energy is stored as runs of empty and occupied sites and then deflated;
+// a mostly empty 700³ lattice takes megabytes rather than 1.3 GB. Layout,
+// all little-endian:
+//
+//   magic "LGCK", format version (u32)
+//   width, height, depth (u32), generation (u64), seed (u32)
+//   zlib stream of the energy:
+//     version 1: one u32 per site in get_state order
+//     version 2: runs, each a count of empty sites, a count of occupied
+//                sites and the occupied sites' energy, all LEB128 varints
+//
+// Files of any earlier version still load; newer versions are rejected.
+
+use crate::{DiscreteLatticeGPU, LatticeError, LatticeState};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"LGCK";
+const VERSION: u32 = 2;
+// Most sites to reserve room for before their energy has been read
+const MAX_RESERVED_SITES: usize = 1 << 24;
+
+/// A lattice's state plus the RNG seed, as saved in a checkpoint file by
+/// [`DiscreteLatticeGPU::save_checkpoint`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub state: LatticeState,
+    pub seed: u32,
+}
+
+impl Checkpoint {
+    /// Writes the checkpoint to `path` through a temporary file, so a crash
+    /// mid-write leaves any earlier checkpoint at `path` intact.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let temp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        self.write_to(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        std::fs::rename(&temp, path)
+    }
+
+    /// Writes the checkpoint format to any writer.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let state = &self.state;
+        writer.write_all(MAGIC)?;
+        for value in [VERSION, state.width, state.height, state.depth] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&state.generation.to_le_bytes())?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+
+        // Buffered, since runs are written a few bytes at a time
+        let mut encoder = BufWriter::new(ZlibEncoder::new(writer, Compression::fast()));
+        write_runs(&mut encoder, &state.energy)?;
+        encoder
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .finish()?;
+        Ok(())
+    }
+
+    /// Reads a checkpoint written by [`write`](Self::write).
+    ///
+    /// Reads every format version up to the current one. Fails with
+    /// [`io::ErrorKind::InvalidData`] if the file is not a checkpoint, comes
+    /// from a newer format version or its energy does not fit its
+    /// dimensions, and with another error if it is truncated.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Reads the checkpoint format from any reader.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a lattice checkpoint"));
+        }
+        let version = read_u32(reader)?;
+        if version == 0 || version > VERSION {
+            return Err(invalid_data(format!(
+                "checkpoint version {} is not supported (newest is {})",
+                version, VERSION
+            )));
+        }
+        let (width, height, depth) = (read_u32(reader)?, read_u32(reader)?, read_u32(reader)?);
+        let mut generation = [0; 8];
+        reader.read_exact(&mut generation)?;
+        let seed = read_u32(reader)?;
+
+        let sites = width as usize * height as usize * depth as usize;
+        let mut decoder = BufReader::new(ZlibDecoder::new(reader));
+        let energy = match version {
+            1 => read_dense(&mut decoder, sites)?,
+            _ => read_runs(&mut decoder, sites)?,
+        };
+
+        Ok(Self {
+            state: LatticeState {
+                width,
+                height,
+                depth,
+                generation: u64::from_le_bytes(generation),
+                energy,
+            },
+            seed,
+        })
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+// Version 1 energy: one u32 per site
+fn read_dense(reader: &mut impl Read, sites: usize) -> io::Result<Vec<u32>> {
+    let mut bytes = Vec::with_capacity(sites.min(MAX_RESERVED_SITES) * 4);
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() != sites * 4 {
+        return Err(invalid_data(format!(
+            "checkpoint holds {} bytes of energy, expected {}",
+            bytes.len(),
+            sites * 4
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+fn write_runs(writer: &mut impl Write, energy: &[u32]) -> io::Result<()> {
+    let mut rest = energy;
+    while !rest.is_empty() {
+        let empty = rest.iter().take_while(|&&e| e == 0).count();
+        let occupied = rest[empty..].iter().take_while(|&&e| e != 0).count();
+        write_varint(writer, empty as u64)?;
+        write_varint(writer, occupied as u64)?;
+        for &e in &rest[empty..empty + occupied] {
+            write_varint(writer, e as u64)?;
+        }
+        rest = &rest[empty + occupied..];
+    }
+    Ok(())
+}
+
+// Version 2 energy: runs covering exactly `sites` sites
+fn read_runs(reader: &mut impl BufRead, sites: usize) -> io::Result<Vec<u32>> {
+    let mut energy = Vec::with_capacity(sites.min(MAX_RESERVED_SITES));
+    while energy.len() < sites {
+        let empty = read_varint(reader)?;
+        let occupied = read_varint(reader)?;
+        let remaining = (sites - energy.len()) as u64;
+        if empty + occupied == 0 || empty > remaining || occupied > remaining - empty {
+            return Err(invalid_data(
+                "checkpoint energy runs do not cover the lattice",
+            ));
+        }
+        energy.resize(energy.len() + empty as usize, 0);
+        for _ in 0..occupied {
+            let value = u32::try_from(read_varint(reader)?)
+                .map_err(|_| invalid_data("checkpoint energy exceeds u32"))?;
+            energy.push(value);
+        }
+    }
+    if !reader.fill_buf()?.is_empty() {
+        return Err(invalid_data("checkpoint has data past the last site"));
+    }
+    Ok(energy)
+}
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+    while value >= 0x80 {
+        writer.write_all(&[value as u8 | 0x80])?;
+        value >>= 7;
+    }
+    writer.write_all(&[value as u8])
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("checkpoint varint is too long"))
+}
+
+impl DiscreteLatticeGPU {
+    /// Creates a lattice on the default adapter sized and filled from a
+    /// checkpoint file written by [`save_checkpoint`](Self::save_checkpoint),
+    /// with its generation and seed.
+    ///
+    /// Settings other than the seed take their defaults; apply the run's
+    /// [`LatticeConfig`](crate::LatticeConfig) to continue it exactly.
+    /// Fails with [`LatticeError::Io`] if the file cannot be read or is not
+    /// a valid checkpoint.
+    pub async fn from_checkpoint(path: &Path) -> Result<Self, LatticeError> {
+        let checkpoint = Checkpoint::read(path)?;
+        let state = &checkpoint.state;
+        let mut lattice = Self::new(state.width, state.height, state.depth).await?;
+        lattice.load_state(state);
+        lattice.seed = checkpoint.seed;
+        Ok(lattice)
+    }
+
+    /// Saves the energy, generation and seed to a checkpoint file at
+    /// `path`, replacing it atomically.
+    ///
+    /// Other settings are not saved: to resume, build a lattice with the
+    /// same settings (e.g. from the same
+    /// [`LatticeConfig`](crate::LatticeConfig)) and call
+    /// [`load_checkpoint`](Self::load_checkpoint). The run then continues
+    /// bit for bit as if it had never stopped.
+    pub async fn save_checkpoint(&self, path: &Path) -> io::Result<()> {
+        Checkpoint {
+            state: self.save_state().await,
+            seed: self.seed,
+        }
+        .write(path)
+    }
+
+    /// Restores the energy, generation and seed from a checkpoint file
+    /// written by [`save_checkpoint`](Self::save_checkpoint).
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`], leaving the lattice as it
+    /// was, if the file is not a valid checkpoint or was saved from a
+    /// lattice of another size.
+    pub fn load_checkpoint(&mut self, path: &Path) -> io::Result<()> {
+        let checkpoint = Checkpoint::read(path)?;
+        let state = &checkpoint.state;
+        if (state.width, state.height, state.depth) != (self.width, self.height, self.depth) {
+            return Err(invalid_data(format!(
+                "checkpoint is {}x{}x{} but the lattice is {}x{}x{}",
+                state.width, state.height, state.depth, self.width, self.height, self.depth
+            )));
+        }
+        self.load_state(state);
+        self.seed = checkpoint.seed;
+        Ok(())
+    }
+}
@@ -0,0 +1,530 @@
+// Lattices deeper than one storage buffer can hold
+//
+// The Z axis is split into slabs, each an ordinary DiscreteLatticeGPU on a
+// shared device that also stores HALO_LAYERS layers of its neighbors on
+// either side. A site's next state depends on sites up to two layers away
+// (whether a neighbor sends to it depends on that neighbor's neighbors), so
+// with two halo layers every interior site steps exactly as it would in one
+// big lattice. Halo sites step too, possibly wrongly at the slab's faces,
+// but the halo exchange before each step overwrites them.
+//
+// MultiGpuLattice shares this code, with its chunks spread over several
+// devices and the exchange going through the host.
+
+use crate::{
+    default_adapter, request_device, BoundaryMode, BoundingBox, DiscreteLatticeGPU, LatticeError,
+    Neighborhood, PropagationMode,
+};
+use std::sync::Arc;
+
+// Layers of each neighboring slab a chunk keeps a copy of
+pub(crate) const HALO_LAYERS: u32 = 2;
+
+/// A lattice split along Z into chunks, for lattices whose energy buffer
+/// would exceed the device's storage buffer binding limit.
+///
+/// Each chunk is a [`DiscreteLatticeGPU`] holding a slab of layers plus
+/// copies of the two nearest layers of each neighboring slab. Before every
+/// step the outermost layers of each slab are copied into its neighbors'
+/// halos on the GPU, so nothing goes through the host and the result
+/// matches one lattice of the full size bit for bit.
+///
+/// The chunks support the settings that apply to every site alike:
+/// propagation mode, neighborhood, boundary modes, axis weights, seed,
+/// decay and workgroup size. Per-site maps (frozen sites, obstacles,
+/// potential, capacity), sources and sinks, custom rules and the loss
+/// counters are not available across chunks.
+pub struct ChunkedLattice {
+    width: u32,
+    height: u32,
+    depth: u32,
+    chunks: Vec<Chunk>,
+}
+
+// One slab: global layers z_start..z_start + layers, stored from local
+// layer halo_below of its lattice, with halo_above layers after them
+struct Chunk {
+    lattice: DiscreteLatticeGPU,
+    z_start: u32,
+    layers: u32,
+    halo_below: u32,
+    halo_above: u32,
+}
+
+impl Chunk {
+    // Local layers holding global layer `z`; a thin ring of chunks can hold
+    // a layer in both halos
+    fn local_layers(&self, z: u32, depth: u32) -> impl Iterator<Item = u32> {
+        let first = (z + depth - self.lattice.z_offset) % depth;
+        (first..self.lattice.depth).step_by(depth as usize)
+    }
+
+    // Local regions of the halo layers below and above the slab
+    fn halos(&self) -> impl Iterator<Item = BoundingBox> {
+        let [max_x, max_y] = [self.lattice.width - 1, self.lattice.height - 1];
+        let below = (self.halo_below > 0).then(|| BoundingBox {
+            min: [0, 0, 0],
+            max: [max_x, max_y, self.halo_below - 1],
+        });
+        let top = self.halo_below + self.layers;
+        let above = (self.halo_above > 0).then(|| BoundingBox {
+            min: [0, 0, top],
+            max: [max_x, max_y, top + self.halo_above - 1],
+        });
+        below.into_iter().chain(above)
+    }
+}
+
+impl ChunkedLattice {
+    /// Creates a lattice on the default high-performance adapter, split into
+    /// as few chunks as the device's buffer limits allow.
+    ///
+    /// Fails with [`LatticeError::BufferTooLarge`] if even the thinnest
+    /// chunk would exceed those limits.
+    pub async fn new(width: u32, height: u32, depth: u32) -> Result<Self, LatticeError> {
+        let adapter = default_adapter().await?;
+        let (device, queue) = request_device(&adapter).await?;
+
+        let max_chunk_depth = max_chunk_depth(&device, width, height, depth)?;
+        Self::new_with_device(device, queue, width, height, depth, max_chunk_depth)
+    }
+
+    /// Builds the lattice on an existing device with at most
+    /// `max_chunk_depth` layers per chunk, not counting halos.
+    ///
+    /// The layers are shared out evenly, so chunks differ in depth by at
+    /// most one. A `max_chunk_depth` of at least `depth` gives a single
+    /// chunk, which runs exactly like a plain [`DiscreteLatticeGPU`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lattice needs more than one chunk and `max_chunk_depth`
+    /// is less than 4, since each chunk must be deep enough to fill its
+    /// neighbors' halos.
+    pub fn new_with_device(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        width: u32,
+        height: u32,
+        depth: u32,
+        max_chunk_depth: u32,
+    ) -> Result<Self, LatticeError> {
+        let count = if max_chunk_depth >= depth {
+            1
+        } else {
+            assert!(
+                max_chunk_depth >= 2 * HALO_LAYERS,
+                "Chunks must be at least {} layers deep",
+                2 * HALO_LAYERS
+            );
+            depth.div_ceil(max_chunk_depth)
+        };
+
+        Self::new_across(&[(device, queue)], (width, height, depth), count)
+    }
+
+    // Split the lattice into `count` chunks, chunk i on device
+    // i % devices.len()
+    pub(crate) fn new_across(
+        devices: &[(Arc<wgpu::Device>, Arc<wgpu::Queue>)],
+        (width, height, depth): (u32, u32, u32),
+        count: u32,
+    ) -> Result<Self, LatticeError> {
+        let ring = BoundaryMode::default() == BoundaryMode::Periodic;
+        let chunks = build_chunks(devices, (width, height, depth), count, ring)?;
+        Ok(Self {
+            width,
+            height,
+            depth,
+            chunks,
+        })
+    }
+
+    /// `(width, height, depth)` of the whole lattice, in sites.
+    pub fn dimensions(&self) -> (u32, u32, u32) {
+        (self.width, self.height, self.depth)
+    }
+
+    /// Number of chunks the lattice is split into.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Steps taken so far.
+    pub fn generation(&self) -> u64 {
+        self.chunks[0].lattice.generation()
+    }
+
+    /// Empties every site.
+    pub fn initialize_vacuum(&mut self) {
+        for chunk in &mut self.chunks {
+            chunk.lattice.initialize_vacuum();
+        }
+    }
+
+    pub fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        self.add_energy_batch(&[(x, y, z, quanta)]);
+    }
+
+    /// Adds quanta to many sites, skipping points outside the lattice, as
+    /// [`DiscreteLatticeGPU::add_energy_batch`] does.
+    ///
+    /// Edits near a slab boundary go to the halos holding the site as well,
+    /// so halos stay current without an exchange.
+    pub fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        let depth = self.depth;
+        for chunk in &mut self.chunks {
+            let local: Vec<_> = edits
+                .iter()
+                .filter(|&&(_, _, z, _)| z < depth)
+                .flat_map(|&(x, y, z, quanta)| {
+                    chunk
+                        .local_layers(z, depth)
+                        .map(move |local_z| (x, y, local_z, quanta))
+                })
+                .collect();
+            if !local.is_empty() {
+                chunk.lattice.add_energy_batch(&local);
+            }
+        }
+    }
+
+    /// Replaces the energy of every site with `energy`, in
+    /// [`get_state`](Self::get_state) layout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `energy` does not hold one value per site.
+    pub fn set_state(&mut self, energy: &[u32]) {
+        let layer = self.width as usize * self.height as usize;
+        assert_eq!(
+            energy.len(),
+            layer * self.depth as usize,
+            "State must have one value per site"
+        );
+
+        for chunk in &mut self.chunks {
+            let local: Vec<u32> = (0..chunk.lattice.depth)
+                .flat_map(|local_z| {
+                    let z = ((chunk.lattice.z_offset + local_z) % self.depth) as usize;
+                    &energy[z * layer..(z + 1) * layer]
+                })
+                .copied()
+                .collect();
+            chunk.lattice.set_state(&local);
+        }
+    }
+
+    /// Refreshes the halos, then advances every chunk one step.
+    pub fn propagate_energy(&mut self) {
+        self.exchange_halos();
+        self.step_chunks();
+    }
+
+    pub(crate) fn step_chunks(&mut self) {
+        for chunk in &mut self.chunks {
+            chunk.lattice.propagate_energy();
+        }
+    }
+
+    /// Downloads the energy of every site, indexed
+    /// `z * width * height + y * width + x`.
+    pub async fn get_state(&self) -> Vec<u32> {
+        let layer = self.width as usize * self.height as usize;
+        let mut state = Vec::with_capacity(layer * self.depth as usize);
+        for chunk in &self.chunks {
+            let local = chunk.lattice.get_state().await;
+            let start = chunk.halo_below as usize * layer;
+            state.extend_from_slice(&local[start..start + chunk.layers as usize * layer]);
+        }
+        state
+    }
+
+    /// Total quanta on the lattice, reduced on the GPU chunk by chunk. Only
+    /// the halo layers are read back, to subtract their copies.
+    pub async fn get_total_energy(&self) -> u64 {
+        let mut total = 0;
+        for chunk in &self.chunks {
+            total += chunk.lattice.get_total_energy().await;
+            for halo in chunk.halos() {
+                let copies = chunk.lattice.get_energy_region(halo).await;
+                total -= copies.iter().map(|&energy| energy as u64).sum::<u64>();
+            }
+        }
+        total
+    }
+
+    /// Downloads only the sites inside `region`, as
+    /// [`DiscreteLatticeGPU::get_energy_region`] does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `region` extends past the lattice.
+    pub async fn get_energy_region(&self, region: BoundingBox) -> Vec<u32> {
+        assert!(
+            region.max[0] < self.width && region.max[1] < self.height && region.max[2] < self.depth,
+            "Region must lie inside the lattice"
+        );
+
+        // Each chunk contributes the layers of the region it owns, in order
+        let mut values = Vec::new();
+        for chunk in &self.chunks {
+            let first = region.min[2].max(chunk.z_start);
+            let last = region.max[2].min(chunk.z_start + chunk.layers - 1);
+            if first > last {
+                continue;
+            }
+            let local = BoundingBox {
+                min: [
+                    region.min[0],
+                    region.min[1],
+                    first - chunk.z_start + chunk.halo_below,
+                ],
+                max: [
+                    region.max[0],
+                    region.max[1],
+                    last - chunk.z_start + chunk.halo_below,
+                ],
+            };
+            values.extend(chunk.lattice.get_energy_region(local).await);
+        }
+        values
+    }
+
+    pub fn set_propagation_mode(&mut self, mode: PropagationMode) {
+        for chunk in &mut self.chunks {
+            chunk.lattice.set_propagation_mode(mode);
+        }
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        for chunk in &mut self.chunks {
+            chunk.lattice.set_neighborhood(neighborhood);
+        }
+    }
+
+    pub fn set_axis_weights(&mut self, wx: f32, wy: f32, wz: f32) {
+        for chunk in &mut self.chunks {
+            chunk.lattice.set_axis_weights(wx, wy, wz);
+        }
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        for chunk in &mut self.chunks {
+            chunk.lattice.set_seed(seed);
+        }
+    }
+
+    pub fn set_decay_rate(&mut self, rate: f32) {
+        for chunk in &mut self.chunks {
+            chunk.lattice.set_decay_rate(rate);
+        }
+    }
+
+    pub fn set_workgroup_size(&mut self, size: [u32; 3]) -> Result<(), LatticeError> {
+        for chunk in &mut self.chunks {
+            chunk.lattice.set_workgroup_size(size)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the boundary mode of each axis.
+    ///
+    /// A periodic Z axis joins the first and last chunks through their
+    /// halos, so switching Z between periodic and any other mode rebuilds
+    /// the chunks, copying the state across on the GPU; each chunk stays on
+    /// its device. Needs memory for a second copy of the lattice while that
+    /// happens.
+    pub fn set_axis_boundary_modes(
+        &mut self,
+        x: BoundaryMode,
+        y: BoundaryMode,
+        z: BoundaryMode,
+    ) -> Result<(), LatticeError> {
+        let ring = z == BoundaryMode::Periodic;
+        let first = &self.chunks[0];
+        if self.chunks.len() > 1 && (first.halo_below > 0) != ring {
+            self.relayout(ring)?;
+        }
+        for chunk in &mut self.chunks {
+            chunk.lattice.set_axis_boundary_modes(x, y, z);
+        }
+        Ok(())
+    }
+
+    // Rebuild the chunks with or without halos joining the ends, keeping
+    // the slabs, state and settings. The halos are filled by the next
+    // step's exchange
+    fn relayout(&mut self, ring: bool) -> Result<(), LatticeError> {
+        let dims = (self.width, self.height, self.depth);
+        let devices: Vec<_> = self
+            .chunks
+            .iter()
+            .map(|chunk| (chunk.lattice.device.clone(), chunk.lattice.queue.clone()))
+            .collect();
+        let mut chunks = build_chunks(&devices, dims, self.chunks.len() as u32, ring)?;
+
+        let layer_bytes = self.width as u64 * self.height as u64 * 4;
+        for (old, new) in self.chunks.iter().zip(&mut chunks) {
+            let from = &old.lattice;
+            let to = &mut new.lattice;
+            to.axis_weights = from.axis_weights;
+            to.propagation_mode = from.propagation_mode;
+            to.seed = from.seed;
+            to.decay_threshold = from.decay_threshold;
+            to.boundary_modes = from.boundary_modes;
+            to.set_neighborhood(from.neighborhood);
+            to.set_workgroup_size(from.workgroup_size)?;
+            to.set_step_count(from.generation());
+
+            let mut encoder = to.device.create_command_encoder(&Default::default());
+            encoder.copy_buffer_to_buffer(
+                from.get_energy_buffer(),
+                old.halo_below as u64 * layer_bytes,
+                to.get_energy_buffer(),
+                new.halo_below as u64 * layer_bytes,
+                old.layers as u64 * layer_bytes,
+            );
+            to.queue.submit(Some(encoder.finish()));
+        }
+
+        self.chunks = chunks;
+        Ok(())
+    }
+
+    // Copy each slab's outermost layers into the halos of its neighbors on
+    // the GPU, for chunks that share a device
+    fn exchange_halos(&self) {
+        let layer_bytes = self.width as u64 * self.height as u64 * 4;
+        let halo_bytes = HALO_LAYERS as u64 * layer_bytes;
+        for copy in self.halo_copies() {
+            let from = &self.chunks[copy.from].lattice;
+            let to = &self.chunks[copy.to].lattice;
+            let mut encoder = to.device.create_command_encoder(&Default::default());
+            encoder.copy_buffer_to_buffer(
+                from.get_energy_buffer(),
+                copy.from_layer as u64 * layer_bytes,
+                to.get_energy_buffer(),
+                copy.to_layer as u64 * layer_bytes,
+                halo_bytes,
+            );
+            to.queue.submit(Some(encoder.finish()));
+        }
+    }
+
+    // The same exchange for chunks on different devices, reading each
+    // slab's outermost layers back and uploading them to its neighbors
+    pub(crate) async fn exchange_halos_through_host(&self) {
+        let layer_bytes = self.width as u64 * self.height as u64 * 4;
+        for copy in self.halo_copies() {
+            let from = &self.chunks[copy.from].lattice;
+            let to = &self.chunks[copy.to].lattice;
+            let layers = from.read_layers(copy.from_layer, HALO_LAYERS).await;
+            to.upload_at(
+                to.get_energy_buffer(),
+                copy.to_layer as u64 * layer_bytes,
+                bytemuck::cast_slice(&layers),
+            );
+        }
+    }
+
+    // Every halo and the slab layers that fill it, as local first layers
+    fn halo_copies(&self) -> Vec<HaloCopy> {
+        let count = self.chunks.len();
+        let mut copies = Vec::new();
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            if chunk.halo_below > 0 {
+                let from = (i + count - 1) % count;
+                let below = &self.chunks[from];
+                copies.push(HaloCopy {
+                    from,
+                    from_layer: below.halo_below + below.layers - HALO_LAYERS,
+                    to: i,
+                    to_layer: 0,
+                });
+            }
+            if chunk.halo_above > 0 {
+                let from = (i + 1) % count;
+                copies.push(HaloCopy {
+                    from,
+                    from_layer: self.chunks[from].halo_below,
+                    to: i,
+                    to_layer: chunk.halo_below + chunk.layers,
+                });
+            }
+        }
+        copies
+    }
+}
+
+// HALO_LAYERS layers copied from chunk `from` to chunk `to`
+struct HaloCopy {
+    from: usize,
+    from_layer: u32,
+    to: usize,
+    to_layer: u32,
+}
+
+// The most layers a chunk of a `width × height × depth` lattice can hold on
+// `device` besides its halos, or `depth` if the whole lattice fits in one
+// buffer
+pub(crate) fn max_chunk_depth(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    depth: u32,
+) -> Result<u32, LatticeError> {
+    let limits = device.limits();
+    let max = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+    let layer_bytes = width as u64 * height as u64 * 4;
+    let max_layers = (max / layer_bytes).min(u32::MAX as u64) as u32;
+    if max_layers >= depth {
+        return Ok(depth);
+    }
+    if max_layers < 4 * HALO_LAYERS {
+        return Err(LatticeError::BufferTooLarge {
+            requested: layer_bytes * 4 * HALO_LAYERS as u64,
+            max,
+        });
+    }
+    Ok(max_layers - 2 * HALO_LAYERS)
+}
+
+// Split `depth` layers evenly into `count` chunks, chunk i on device
+// i % devices.len(). With `ring`, the first and last chunks hold halos of
+// each other, for a periodic Z axis
+fn build_chunks(
+    devices: &[(Arc<wgpu::Device>, Arc<wgpu::Queue>)],
+    (width, height, depth): (u32, u32, u32),
+    count: u32,
+    ring: bool,
+) -> Result<Vec<Chunk>, LatticeError> {
+    let boundary = |count: u32, i: u32| (i as u64 * depth as u64 / count as u64) as u32;
+    (0..count)
+        .map(|i| {
+            let z_start = boundary(count, i);
+            let layers = boundary(count, i + 1) - z_start;
+            let halo = |present: bool| if count > 1 && present { HALO_LAYERS } else { 0 };
+            let halo_below = halo(ring || i > 0);
+            let halo_above = halo(ring || i + 1 < count);
+
+            let (device, queue) = &devices[i as usize % devices.len()];
+            let mut lattice = DiscreteLatticeGPU::new_with_device(
+                device.clone(),
+                queue.clone(),
+                width,
+                height,
+                halo_below + layers + halo_above,
+            )?;
+            lattice.z_offset = (z_start + depth - halo_below) % depth;
+            lattice.lattice_depth = depth;
+            Ok(Chunk {
+                lattice,
+                z_start,
+                layers,
+                halo_below,
+                halo_above,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,170 @@
+// Lattice configuration as plain data
+//
+// Everything that shapes a run apart from its state, kept separate from the
+// GPU object so experiments can be written down in any serde format and
+// rebuilt with DiscreteLatticeGPU::from_config.
+
+use crate::{
+    BoundaryMode, DiscreteLatticeGPU, GradientRule, LatticeError, Neighborhood, PropagationMode,
+    PropagationRule, RandomWalkRule, WgslRule, WORKGROUP_SIZE,
+};
+use serde::{Deserialize, Serialize};
+
+/// A transfer rule, by name or as WGSL source.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleConfig {
+    /// [`GradientRule`].
+    #[default]
+    Gradient,
+    /// [`RandomWalkRule`].
+    RandomWalk,
+    /// A [`WgslRule`] with this source.
+    Wgsl(String),
+}
+
+impl RuleConfig {
+    pub fn to_rule(&self) -> Box<dyn PropagationRule> {
+        match self {
+            RuleConfig::Gradient => Box::new(GradientRule),
+            RuleConfig::RandomWalk => Box::new(RandomWalkRule),
+            RuleConfig::Wgsl(source) => Box::new(WgslRule::new(source.clone())),
+        }
+    }
+}
+
+/// The size and settings of a lattice, without its state.
+///
+/// Only the dimensions are required when deserializing; every other field
+/// falls back to the same default as a new [`DiscreteLatticeGPU`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LatticeConfig {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    #[serde(default)]
+    pub propagation_mode: PropagationMode,
+    #[serde(default)]
+    pub neighborhood: Neighborhood,
+    /// Boundary mode of the X, Y and Z faces.
+    #[serde(default)]
+    pub boundary_modes: [BoundaryMode; 3],
+    /// Relative transfer weights of the X, Y and Z axes.
+    #[serde(default = "default_axis_weights")]
+    pub axis_weights: [f32; 3],
+    #[serde(default)]
+    pub seed: u32,
+    /// Probability that an occupied site loses a quantum each step.
+    #[serde(default)]
+    pub decay_rate: f32,
+    #[serde(default = "default_workgroup_size")]
+    pub workgroup_size: [u32; 3],
+    #[serde(default)]
+    pub active_region_tracking: bool,
+    #[serde(default)]
+    pub rule: RuleConfig,
+}
+
+fn default_axis_weights() -> [f32; 3] {
+    [1.0; 3]
+}
+
+fn default_workgroup_size() -> [u32; 3] {
+    WORKGROUP_SIZE
+}
+
+impl LatticeConfig {
+    /// A `width × height × depth` lattice with default settings.
+    pub fn new(width: u32, height: u32, depth: u32) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+            propagation_mode: PropagationMode::default(),
+            neighborhood: Neighborhood::default(),
+            boundary_modes: [BoundaryMode::default(); 3],
+            axis_weights: default_axis_weights(),
+            seed: 0,
+            decay_rate: 0.0,
+            workgroup_size: default_workgroup_size(),
+            active_region_tracking: false,
+            rule: RuleConfig::default(),
+        }
+    }
+
+    // Reject settings the setters would panic on
+    fn validate(&self) -> Result<(), LatticeError> {
+        let invalid = |msg: String| Err(LatticeError::InvalidConfig(msg));
+        if self.width == 0 || self.height == 0 || self.depth == 0 {
+            return invalid(format!(
+                "dimensions must be nonzero, got {}x{}x{}",
+                self.width, self.height, self.depth
+            ));
+        }
+        if !self.axis_weights.iter().all(|w| w.is_finite() && *w >= 0.0) {
+            return invalid(format!(
+                "axis weights must be finite and non-negative, got {:?}",
+                self.axis_weights
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.decay_rate) {
+            return invalid(format!(
+                "decay rate must be in 0..=1, got {}",
+                self.decay_rate
+            ));
+        }
+        if self.workgroup_size.contains(&0) {
+            return invalid(format!(
+                "workgroup dimensions must be nonzero, got {:?}",
+                self.workgroup_size
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl DiscreteLatticeGPU {
+    /// Creates an empty lattice on the default adapter with the size and
+    /// settings in `config`.
+    ///
+    /// Fails with [`LatticeError::InvalidConfig`] for settings out of range,
+    /// and as [`set_rule`](Self::set_rule) and
+    /// [`set_workgroup_size`](Self::set_workgroup_size) do.
+    pub async fn from_config(config: &LatticeConfig) -> Result<Self, LatticeError> {
+        config.validate()?;
+        let mut lattice = Self::new(config.width, config.height, config.depth).await?;
+        lattice.apply_config(config).await?;
+        Ok(lattice)
+    }
+
+    /// Applies every setting in `config` except its dimensions, keeping the
+    /// current state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimensions in `config` differ from the lattice's.
+    pub async fn apply_config(&mut self, config: &LatticeConfig) -> Result<(), LatticeError> {
+        assert_eq!(
+            (config.width, config.height, config.depth),
+            (self.width, self.height, self.depth),
+            "Config dimensions must match the lattice"
+        );
+        config.validate()?;
+        // Skip recompiling a rule already in place
+        let rule = config.rule.to_rule();
+        if rule.wgsl() != self.rule_source {
+            self.set_rule(rule.as_ref()).await?;
+        }
+        self.set_workgroup_size(config.workgroup_size)?;
+        self.set_propagation_mode(config.propagation_mode);
+        self.set_neighborhood(config.neighborhood);
+        let [x, y, z] = config.boundary_modes;
+        self.set_axis_boundary_modes(x, y, z);
+        let [wx, wy, wz] = config.axis_weights;
+        self.set_axis_weights(wx, wy, wz);
+        self.set_seed(config.seed);
+        self.set_decay_rate(config.decay_rate);
+        self.set_active_region_tracking(config.active_region_tracking);
+        Ok(())
+    }
+}
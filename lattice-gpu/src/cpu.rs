@@ -0,0 +1,500 @@
+// Pure-CPU reference implementation of the propagation rule
+//
+// A line-by-line port of the scatter mode in shader.wgsl with the default
+// gradient rule: the same neighbor order, direction weights, boundary
+// handling and PCG draws, so a CPU and a GPU lattice with the same settings
+// produce bit-identical states. Useful where no GPU is available and as a
+// golden reference for the kernels.
+
+use crate::{
+    decay_threshold, fixed_point_weights, sphere_points, BoundaryMode, Lattice, Neighborhood,
+    MAX_LEVEL, SITE_FROZEN, SITE_OBSTACLE, WEIGHT_SCALE,
+};
+
+// Transfer markers, matching shader.wgsl
+const NO_TARGET: u32 = 0xffffffff;
+const ABSORBED: u32 = 0xfffffffe;
+const DECAYED: u32 = 0xfffffffd;
+
+/// A lattice stepped on the host, following the same rule as
+/// [`DiscreteLatticeGPU`](crate::DiscreteLatticeGPU).
+///
+/// Supports the default [`GradientRule`](crate::GradientRule) with axis
+/// weights, boundary modes, either neighborhood, seeds, decay, frozen sites
+/// and obstacles. Potentials, capacity maps, sources and sinks and custom
+/// WGSL rules are GPU-only. Every step visits every site, so it is meant for
+/// small lattices and tests rather than production runs.
+pub struct DiscreteLatticeCPU {
+    width: u32,
+    height: u32,
+    depth: u32,
+    energy: Vec<u32>,
+    site_flags: Vec<u32>,
+    generation: u64,
+    axis_weights: [u32; 3],
+    neighborhood: Neighborhood,
+    boundary_modes: [BoundaryMode; 3],
+    seed: u32,
+    decay_threshold: u32,
+    absorbed: u64,
+    decayed: u64,
+}
+
+impl DiscreteLatticeCPU {
+    /// Creates an empty lattice of `width × height × depth` sites with the
+    /// same defaults as [`DiscreteLatticeGPU::new`](crate::DiscreteLatticeGPU::new).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any dimension is 0.
+    pub fn new(width: u32, height: u32, depth: u32) -> Self {
+        assert!(
+            width > 0 && height > 0 && depth > 0,
+            "Lattice dimensions must be nonzero, got {}x{}x{}",
+            width,
+            height,
+            depth
+        );
+        let total_sites = (width * height * depth) as usize;
+        Self {
+            width,
+            height,
+            depth,
+            energy: vec![0; total_sites],
+            site_flags: vec![0; total_sites],
+            generation: 0,
+            axis_weights: [WEIGHT_SCALE as u32; 3],
+            neighborhood: Neighborhood::default(),
+            boundary_modes: [BoundaryMode::default(); 3],
+            seed: 0,
+            decay_threshold: 0,
+            absorbed: 0,
+            decayed: 0,
+        }
+    }
+
+    /// Empties every site and resets the absorbed and decayed counts.
+    pub fn initialize_vacuum(&mut self) {
+        self.energy.fill(0);
+        self.absorbed = 0;
+        self.decayed = 0;
+    }
+
+    pub fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        self.add_energy_batch(&[(x, y, z, quanta)]);
+    }
+
+    /// Fills a solid sphere with `quanta` per site, skipping any part of the
+    /// sphere that falls outside the lattice.
+    pub fn seed_sphere(&mut self, center: (u32, u32, u32), radius: u32, quanta: u32) {
+        let points = sphere_points(
+            center,
+            radius,
+            quanta,
+            (self.width, self.height, self.depth),
+        );
+        self.add_energy_batch(&points);
+    }
+
+    /// Adds quanta to many sites, skipping points outside the lattice.
+    /// Sites are capped at [`MAX_LEVEL`] but never lowered.
+    pub fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        for &(x, y, z, quanta) in edits {
+            if x < self.width && y < self.height && z < self.depth {
+                let idx = self.index(x, y, z) as usize;
+                let old = self.energy[idx];
+                self.energy[idx] = old.saturating_add(quanta).min(MAX_LEVEL).max(old);
+            }
+        }
+    }
+
+    /// See [`DiscreteLatticeGPU::set_axis_weights`](crate::DiscreteLatticeGPU::set_axis_weights).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite.
+    pub fn set_axis_weights(&mut self, wx: f32, wy: f32, wz: f32) {
+        self.axis_weights = fixed_point_weights([wx, wy, wz]);
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.neighborhood = neighborhood;
+    }
+
+    pub fn set_boundary_mode(&mut self, mode: BoundaryMode) {
+        self.boundary_modes = [mode; 3];
+    }
+
+    pub fn set_axis_boundary_modes(&mut self, x: BoundaryMode, y: BoundaryMode, z: BoundaryMode) {
+        self.boundary_modes = [x, y, z];
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+
+    /// See [`DiscreteLatticeGPU::set_decay_rate`](crate::DiscreteLatticeGPU::set_decay_rate).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is outside `0.0..=1.0`.
+    pub fn set_decay_rate(&mut self, rate: f32) {
+        self.decay_threshold = decay_threshold(rate);
+    }
+
+    /// Freezes the given sites, in addition to any already frozen. Sites
+    /// outside the lattice are skipped.
+    pub fn set_frozen(&mut self, sites: &[(u32, u32, u32)]) {
+        for &(x, y, z) in sites {
+            if x < self.width && y < self.height && z < self.depth {
+                let idx = self.index(x, y, z) as usize;
+                self.site_flags[idx] |= SITE_FROZEN;
+            }
+        }
+    }
+
+    /// Marks the sites where `mask` is `true` as obstacles, replacing any
+    /// previous obstacles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mask` does not hold one value per site.
+    pub fn set_obstacles(&mut self, mask: &[bool]) {
+        assert_eq!(
+            mask.len(),
+            self.energy.len(),
+            "Obstacle mask must have one value per site"
+        );
+        for (flag, &blocked) in self.site_flags.iter_mut().zip(mask) {
+            if blocked {
+                *flag |= SITE_OBSTACLE;
+            } else {
+                *flag &= !SITE_OBSTACLE;
+            }
+        }
+    }
+
+    /// Advances the lattice one step.
+    pub fn propagate_energy(&mut self) {
+        let step = self.generation as u32;
+        let mut next = self.energy.clone();
+        for z in 0..self.depth {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let target = self.transfer_target(x, y, z, step);
+                    if target == NO_TARGET {
+                        continue;
+                    }
+                    next[self.index(x, y, z) as usize] -= 1;
+                    match target {
+                        ABSORBED => self.absorbed += 1,
+                        DECAYED => self.decayed += 1,
+                        _ => next[target as usize] += 1,
+                    }
+                }
+            }
+        }
+        self.energy = next;
+        self.generation += 1;
+    }
+
+    pub fn propagate_n(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.propagate_energy();
+        }
+    }
+
+    /// Replaces the energy of every site, keeping the generation. Values
+    /// are taken as given, without capping at [`MAX_LEVEL`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `energy` does not hold one value per site.
+    pub fn set_state(&mut self, energy: &[u32]) {
+        assert_eq!(
+            energy.len(),
+            self.energy.len(),
+            "State must have one value per site"
+        );
+        self.energy.copy_from_slice(energy);
+    }
+
+    /// Energy of every site, indexed `z * width * height + y * width + x`.
+    pub fn get_state(&self) -> &[u32] {
+        &self.energy
+    }
+
+    pub fn get_total_energy(&self) -> u64 {
+        self.energy.iter().map(|&e| e as u64).sum()
+    }
+
+    /// Total quanta removed at absorbing faces.
+    pub fn absorbed_energy(&self) -> u64 {
+        self.absorbed
+    }
+
+    /// Total quanta removed by decay.
+    pub fn decayed_energy(&self) -> u64 {
+        self.decayed
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    fn index(&self, x: u32, y: u32, z: u32) -> u32 {
+        z * self.width * self.height + y * self.width + x
+    }
+
+    fn extents(&self) -> [u32; 3] {
+        [self.width, self.height, self.depth]
+    }
+
+    fn neighbor_count(&self) -> u32 {
+        match self.neighborhood {
+            Neighborhood::VonNeumann => 6,
+            Neighborhood::Moore => 26,
+        }
+    }
+
+    fn direction_offset(&self, dir: u32) -> [i32; 3] {
+        match self.neighborhood {
+            Neighborhood::VonNeumann => {
+                let mut offset = [0; 3];
+                offset[(dir / 2) as usize] = if dir.is_multiple_of(2) { 1 } else { -1 };
+                offset
+            }
+            Neighborhood::Moore => {
+                let cell = dir + (dir >= 13) as u32;
+                [
+                    (cell % 3) as i32 - 1,
+                    (cell / 3 % 3) as i32 - 1,
+                    (cell / 9) as i32 - 1,
+                ]
+            }
+        }
+    }
+
+    fn opposite_direction(&self, dir: u32) -> u32 {
+        match self.neighborhood {
+            Neighborhood::VonNeumann => dir ^ 1,
+            Neighborhood::Moore => 25 - dir,
+        }
+    }
+
+    // Only valid where has_neighbor is true
+    fn neighbor_coords(&self, c: [u32; 3], dir: u32) -> [u32; 3] {
+        let offset = self.direction_offset(dir);
+        let extents = self.extents();
+        let mut n = c;
+        for axis in 0..3 {
+            if offset[axis] != 0 {
+                n[axis] = step_coord(
+                    n[axis],
+                    offset[axis],
+                    extents[axis],
+                    self.boundary_modes[axis],
+                );
+            }
+        }
+        n
+    }
+
+    fn has_neighbor(&self, c: [u32; 3], dir: u32) -> bool {
+        let offset = self.direction_offset(dir);
+        let extents = self.extents();
+        (0..3).all(|axis| {
+            offset[axis] == 0
+                || can_step(
+                    c[axis],
+                    offset[axis],
+                    extents[axis],
+                    self.boundary_modes[axis],
+                )
+        })
+    }
+
+    fn leaves_through_absorbing(&self, c: [u32; 3], dir: u32) -> bool {
+        let offset = self.direction_offset(dir);
+        let extents = self.extents();
+        (0..3).all(|axis| {
+            let mode = self.boundary_modes[axis];
+            offset[axis] == 0
+                || can_step(c[axis], offset[axis], extents[axis], mode)
+                || mode == BoundaryMode::Absorbing
+        })
+    }
+
+    fn direction_weight(&self, dir: u32) -> u32 {
+        let offset = self.direction_offset(dir);
+        let mut axes = 0;
+        let mut total = 0;
+        for (&delta, &weight) in offset.iter().zip(&self.axis_weights) {
+            if delta != 0 {
+                if weight == 0 {
+                    return 0;
+                }
+                axes += 1;
+                total += weight;
+            }
+        }
+        total / (axes * axes)
+    }
+
+    fn pseudo_random(&self, idx: u32, step: u32) -> u32 {
+        pcg_hash(idx ^ pcg_hash(step ^ pcg_hash(self.seed)))
+    }
+
+    // Weighted choice among neighbors below `below` that accept a quantum,
+    // as pick_neighbor in shader.wgsl with a flat potential
+    fn pick_neighbor(&self, c: [u32; 3], below: u32, step: u32) -> u32 {
+        let idx = self.index(c[0], c[1], c[2]);
+        let mut candidates: Vec<(u32, u32)> = Vec::with_capacity(26);
+
+        for dir in 0..self.neighbor_count() {
+            let weight = self.direction_weight(dir);
+            if !self.has_neighbor(c, dir) {
+                if self.leaves_through_absorbing(c, dir) && weight > 0 {
+                    candidates.push((ABSORBED, weight));
+                }
+                continue;
+            }
+            let mut n = self.neighbor_coords(c, dir);
+            let mut n_idx = self.index(n[0], n[1], n[2]);
+
+            if self.site_flags[n_idx as usize] & SITE_OBSTACLE != 0 {
+                let opposite = self.opposite_direction(dir);
+                if !self.has_neighbor(c, opposite) {
+                    continue;
+                }
+                n = self.neighbor_coords(c, opposite);
+                n_idx = self.index(n[0], n[1], n[2]);
+                if self.site_flags[n_idx as usize] & SITE_OBSTACLE != 0 {
+                    continue;
+                }
+            }
+            let n_energy = self.energy[n_idx as usize];
+
+            let accepts =
+                self.site_flags[n_idx as usize] & SITE_FROZEN == 0 && n_energy < MAX_LEVEL;
+            if n_energy < below && weight > 0 && accepts {
+                candidates.push((n_idx, weight));
+            }
+        }
+
+        if candidates.is_empty() {
+            return NO_TARGET;
+        }
+
+        let total_weight: u32 = candidates.iter().map(|&(_, w)| w).sum();
+        let mut choice_weight = self.pseudo_random(idx, step) % total_weight;
+        for &(target, weight) in &candidates {
+            if choice_weight < weight {
+                return target;
+            }
+            choice_weight -= weight;
+        }
+        unreachable!("choice lies below the total weight")
+    }
+
+    fn transfer_target(&self, x: u32, y: u32, z: u32, step: u32) -> u32 {
+        let idx = self.index(x, y, z);
+        let energy = self.energy[idx as usize];
+
+        if energy == 0 || self.site_flags[idx as usize] & (SITE_FROZEN | SITE_OBSTACLE) != 0 {
+            return NO_TARGET;
+        }
+
+        if self.decay_threshold > 0
+            && pcg_hash(self.pseudo_random(idx, step)) < self.decay_threshold
+        {
+            return DECAYED;
+        }
+
+        self.pick_neighbor([x, y, z], energy, step)
+    }
+}
+
+impl Lattice for DiscreteLatticeCPU {
+    fn dimensions(&self) -> (u32, u32, u32) {
+        (self.width, self.height, self.depth)
+    }
+
+    fn initialize_vacuum(&mut self) {
+        DiscreteLatticeCPU::initialize_vacuum(self);
+    }
+
+    fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        DiscreteLatticeCPU::add_energy_quantum(self, x, y, z, quanta);
+    }
+
+    fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        DiscreteLatticeCPU::add_energy_batch(self, edits);
+    }
+
+    fn propagate_energy(&mut self) {
+        DiscreteLatticeCPU::propagate_energy(self);
+    }
+
+    fn propagate_n(&mut self, steps: u32) {
+        DiscreteLatticeCPU::propagate_n(self, steps);
+    }
+
+    fn total_energy(&self) -> u64 {
+        self.get_total_energy()
+    }
+
+    fn read_state(&self) -> Vec<u32> {
+        self.energy.clone()
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+// Step one coordinate by delta, as step_coord in shader.wgsl
+pub(crate) fn step_coord(c: u32, delta: i32, extent: u32, mode: BoundaryMode) -> u32 {
+    let n = c as i32 + delta;
+    if n >= 0 && n < extent as i32 {
+        return n as u32;
+    }
+    if mode == BoundaryMode::Reflective {
+        return (c as i32 - delta) as u32;
+    }
+    ((n + extent as i32) % extent as i32) as u32
+}
+
+// Whether one coordinate can step by delta, as can_step in shader.wgsl
+pub(crate) fn can_step(c: u32, delta: i32, extent: u32, mode: BoundaryMode) -> bool {
+    match mode {
+        BoundaryMode::Periodic => true,
+        BoundaryMode::Reflective => extent > 1,
+        BoundaryMode::Closed | BoundaryMode::Absorbing => {
+            if delta > 0 {
+                c + 1 < extent
+            } else {
+                c > 0
+            }
+        }
+    }
+}
+
+// PCG hash, as pcg_hash in shader.wgsl
+fn pcg_hash(input: u32) -> u32 {
+    let state = input.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
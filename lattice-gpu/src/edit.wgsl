@@ -0,0 +1,102 @@
+// Energy edits applied on the GPU
+//
+// Shares the propagation bind group layout. The input binding holds the
+// edits as (site index, quanta) pairs and the output binding is the active
+// energy buffer, so seeding touches only the edited sites instead of
+// round-tripping the whole lattice through the host. The same edit lists
+// drive the per-step source and sink passes.
+
+struct Params {
+    width: u32,
+    height: u32,
+    depth: u32,
+    step_count: u32,
+    weight_x: u32,
+    weight_y: u32,
+    weight_z: u32,
+    boundary_modes: u32,
+    seed: u32,
+    decay_threshold: u32,
+    capacity_map: u32,
+    z_offset: u32,
+    lattice_depth: u32,
+    _padding2: u32,
+    _padding3: u32,
+    _padding4: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> edits: array<u32>;
+@group(0) @binding(2) var<storage, read_write> energy: array<atomic<u32>>;
+@group(0) @binding(4) var<storage, read_write> counter: array<atomic<u32>, 2>;  // Quanta moved (lo, hi)
+@group(0) @binding(6) var<storage, read> capacity: array<u32>;
+// Bindings 3 and 5 (site flags, potential) are part of the shared layout
+// but unused here
+
+// Highest quantum level a site can hold without a capacity map
+const MAX_LEVEL: u32 = 3u;
+
+// Most quanta site idx can hold
+fn site_capacity(idx: u32) -> u32 {
+    if (params.capacity_map != 0u) {
+        return capacity[idx];
+    }
+    return MAX_LEVEL;
+}
+
+// One edit per invocation: add its quanta to the site, capped at its
+// capacity but never lowering a site already above it, and count what was
+// added.
+// Each site appears in at most one edit, so no other invocation touches it
+@compute @workgroup_size(4, 4, 4)
+fn apply_edits(
+    @builtin(workgroup_id) group_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let edit = group_id.x * 64u + local_index;
+    if (edit >= arrayLength(&edits) / 2u) {
+        return;
+    }
+    let idx = edits[2u * edit];
+    let quanta = edits[2u * edit + 1u];
+
+    let old = atomicLoad(&energy[idx]);
+    var sum = old + quanta;
+    if (sum < old) {
+        sum = 0xffffffffu;
+    }
+    let new_value = max(min(sum, site_capacity(idx)), old);
+    atomicStore(&energy[idx], new_value);
+
+    count_quanta(new_value - old);
+}
+
+// One edit per invocation: remove up to its quanta from the site and count
+// what was removed
+@compute @workgroup_size(4, 4, 4)
+fn drain_edits(
+    @builtin(workgroup_id) group_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let edit = group_id.x * 64u + local_index;
+    if (edit >= arrayLength(&edits) / 2u) {
+        return;
+    }
+    let idx = edits[2u * edit];
+    let quanta = edits[2u * edit + 1u];
+
+    let old = atomicLoad(&energy[idx]);
+    let taken = min(old, quanta);
+    atomicStore(&energy[idx], old - taken);
+    count_quanta(taken);
+}
+
+// Add to the 64-bit counter, carrying into the high word
+fn count_quanta(quanta: u32) {
+    if (quanta != 0u) {
+        let prev = atomicAdd(&counter[0], quanta);
+        if (prev + quanta < prev) {
+            atomicAdd(&counter[1], 1u);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// Error types for lattice construction, GPU operations and exports
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum LatticeError {
+    /// No GPU adapter is available.
+    AdapterNotFound,
+    /// A buffer would exceed the device's size limits.
+    BufferTooLarge { requested: u64, max: u64 },
+    /// The adapter refused to create a device with the requested limits.
+    DeviceRequest(wgpu::RequestDeviceError),
+    /// A shader failed to compile; holds the compiler's message.
+    ShaderCompile(String),
+    /// An input image could not be read or decoded.
+    Image(image::ImageError),
+    /// The compute workgroup size exceeds the device's limits.
+    WorkgroupTooLarge {
+        size: [u32; 3],
+        max_size: [u32; 3],
+        max_invocations: u32,
+    },
+    /// A [`LatticeConfig`](crate::LatticeConfig) holds an invalid setting.
+    InvalidConfig(String),
+    /// An input file could not be read.
+    Io(std::io::Error),
+    /// An argument or the contents of an input file are malformed or don't
+    /// fit the lattice; holds a description of the problem.
+    InvalidInput(String),
+}
+
+impl fmt::Display for LatticeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatticeError::AdapterNotFound => write!(f, "no GPU adapter found"),
+            LatticeError::BufferTooLarge { requested, max } => write!(
+                f,
+                "buffer of {} bytes exceeds the device limit of {} bytes",
+                requested, max
+            ),
+            LatticeError::DeviceRequest(err) => write!(f, "failed to create device: {}", err),
+            LatticeError::ShaderCompile(msg) => write!(f, "shader compilation failed: {}", msg),
+            LatticeError::Image(err) => write!(f, "failed to load image: {}", err),
+            LatticeError::WorkgroupTooLarge {
+                size,
+                max_size,
+                max_invocations,
+            } => write!(
+                f,
+                "workgroup size {:?} exceeds device limits (max {:?}, {} invocations)",
+                size, max_size, max_invocations
+            ),
+            LatticeError::InvalidConfig(msg) => write!(f, "invalid configuration: {}", msg),
+            LatticeError::Io(err) => write!(f, "failed to read input: {}", err),
+            LatticeError::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LatticeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LatticeError::DeviceRequest(err) => Some(err),
+            LatticeError::Image(err) => Some(err),
+            LatticeError::Io(err) => Some(err),
+            LatticeError::AdapterNotFound
+            | LatticeError::BufferTooLarge { .. }
+            | LatticeError::ShaderCompile(_)
+            | LatticeError::WorkgroupTooLarge { .. }
+            | LatticeError::InvalidConfig(_)
+            | LatticeError::InvalidInput(_) => None,
+        }
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for LatticeError {
+    fn from(err: wgpu::RequestDeviceError) -> Self {
+        LatticeError::DeviceRequest(err)
+    }
+}
+
+impl From<image::ImageError> for LatticeError {
+    fn from(err: image::ImageError) -> Self {
+        LatticeError::Image(err)
+    }
+}
+
+impl From<std::io::Error> for LatticeError {
+    fn from(err: std::io::Error) -> Self {
+        LatticeError::Io(err)
+    }
+}
+
+/// Error from writing an export file such as a GIF.
+#[derive(Debug)]
+pub enum ExportError {
+    /// Creating the lattice or its device failed.
+    Lattice(LatticeError),
+    /// Writing the output file failed.
+    Io(std::io::Error),
+    /// The GIF encoder rejected a frame or the file header.
+    Gif(gif::EncodingError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Lattice(err) => write!(f, "{}", err),
+            ExportError::Io(err) => write!(f, "failed to write export: {}", err),
+            ExportError::Gif(err) => write!(f, "failed to encode GIF: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportError::Lattice(err) => Some(err),
+            ExportError::Io(err) => Some(err),
+            ExportError::Gif(err) => Some(err),
+        }
+    }
+}
+
+impl From<LatticeError> for ExportError {
+    fn from(err: LatticeError) -> Self {
+        ExportError::Lattice(err)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(err: std::io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
+
+impl From<gif::EncodingError> for ExportError {
+    fn from(err: gif::EncodingError) -> Self {
+        ExportError::Gif(err)
+    }
+}
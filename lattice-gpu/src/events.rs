@@ -0,0 +1,64 @@
+// Device errors and loss, reported from wgpu's callbacks
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Events kept for a lattice whose receivers aren't drained; the oldest is
+// dropped to make room for a new one
+pub(crate) const EVENT_CAPACITY: usize = 64;
+
+pub(crate) type EventChannel = (flume::Sender<LatticeEvent>, flume::Receiver<LatticeEvent>);
+
+/// Something that happened to a lattice's device outside any call, as
+/// delivered by [`DiscreteLatticeGPU::events`](crate::DiscreteLatticeGPU::events).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LatticeEvent {
+    /// The device is gone, e.g. after a driver reset or GPU hang. Nothing
+    /// more can run on it; see
+    /// [`recover`](crate::DiscreteLatticeGPU::recover).
+    DeviceLost { message: String },
+    /// wgpu reported an error that no error scope caught, such as running
+    /// out of memory.
+    UncapturedError { message: String },
+}
+
+// A channel for watch_device, holding at most EVENT_CAPACITY events
+pub(crate) fn event_channel() -> EventChannel {
+    flume::bounded(EVENT_CAPACITY)
+}
+
+// Queue `event`, dropping the oldest queued event if the channel is full
+fn push(events: &EventChannel, mut event: LatticeEvent) {
+    let (sender, receiver) = events;
+    while let Err(flume::TrySendError::Full(rejected)) = sender.try_send(event) {
+        let _ = receiver.try_recv();
+        event = rejected;
+    }
+}
+
+// Install callbacks on `device` that log its errors, queue them and its
+// loss on `events` and set `lost`. Replaces whatever callbacks the device
+// had
+pub(crate) fn watch_device(device: &wgpu::Device, events: &EventChannel, lost: &Arc<AtomicBool>) {
+    let channel = events.clone();
+    device.on_uncaptured_error(Box::new(move |error| {
+        let message = error.to_string();
+        log::error!("Uncaptured wgpu error: {}", message);
+        push(&channel, LatticeEvent::UncapturedError { message });
+    }));
+
+    let channel = events.clone();
+    let lost = lost.clone();
+    device.set_device_lost_callback(move |reason, message| {
+        // Dropping the device or replacing this callback also lands here
+        if matches!(
+            reason,
+            wgpu::DeviceLostReason::Dropped | wgpu::DeviceLostReason::ReplacedCallback
+        ) {
+            return;
+        }
+        lost.store(true, Ordering::Release);
+        log::error!("Device lost: {}", message);
+        push(&channel, LatticeEvent::DeviceLost { message });
+    });
+}
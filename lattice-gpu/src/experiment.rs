@@ -0,0 +1,234 @@
+// Experiments described by TOML files
+//
+// An experiment is a lattice configuration plus everything needed to run it
+// end to end: the initial energy, continuous sources and sinks, the number
+// of steps and the files to write. Keeping all of it in one file makes a
+// run reproducible and shareable without recompiling anything. A minimal
+// experiment:
+//
+//   steps = 100
+//
+//   [lattice]
+//   width = 64
+//   height = 64
+//   depth = 64
+//
+//   [[initial]]
+//   type = "sphere"
+//   center = [32, 32, 32]
+//   radius = 4
+//   quanta = 3
+//
+//   [output]
+//   checkpoint = "final.lgck"
+
+use crate::{CancellationToken, DiscreteLatticeGPU, LatticeConfig, LatticeError, VtkSeries};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Steps between checks for cancellation
+const CANCEL_CHECK_STEPS: u32 = 100;
+
+/// One part of an experiment's initial energy, tagged by `type`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InitialCondition {
+    /// `quanta` at one site.
+    Point { site: [u32; 3], quanta: u32 },
+    /// A solid sphere, as [`DiscreteLatticeGPU::seed_sphere`] fills it.
+    Sphere {
+        center: [u32; 3],
+        radius: u32,
+        quanta: u32,
+    },
+    /// A line, as [`DiscreteLatticeGPU::add_energy_line`] draws it.
+    Line {
+        from: [u32; 3],
+        to: [u32; 3],
+        quanta: u32,
+    },
+    /// The points of an `x,y,z,quanta` CSV file, as
+    /// [`DiscreteLatticeGPU::add_energy_csv`] reads them.
+    Csv { path: PathBuf },
+}
+
+/// Files an experiment writes. Every output is optional.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Checkpoint of the final state, see
+    /// [`DiscreteLatticeGPU::save_checkpoint`].
+    pub checkpoint: Option<PathBuf>,
+    /// Final state as a NumPy `.npy` file.
+    pub npy: Option<PathBuf>,
+    /// Final state as a VTK `.vti` file.
+    pub vti: Option<PathBuf>,
+    /// Final state as a raw volume with an `.mhd` header.
+    pub raw: Option<PathBuf>,
+    /// Collection file of a VTK time series with a frame every
+    /// `series_interval` steps, see [`VtkSeries`].
+    pub vti_series: Option<PathBuf>,
+    /// Steps between frames of `vti_series`; 0 writes only the first and
+    /// last frames.
+    pub series_interval: u32,
+}
+
+/// A complete, reproducible run: settings, initial condition, length and
+/// outputs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    pub lattice: LatticeConfig,
+    /// Energy placed before the first step, in order.
+    #[serde(default)]
+    pub initial: Vec<InitialCondition>,
+    /// Continuous sources as `[x, y, z, quanta per step]`, see
+    /// [`DiscreteLatticeGPU::add_sources`].
+    #[serde(default)]
+    pub sources: Vec<[u32; 4]>,
+    /// Continuous sinks as `[x, y, z, quanta per step]`, see
+    /// [`DiscreteLatticeGPU::add_sinks`].
+    #[serde(default)]
+    pub sinks: Vec<[u32; 4]>,
+    /// Number of steps to run.
+    pub steps: u32,
+    #[serde(default)]
+    pub output: OutputConfig,
+}
+
+impl ExperimentConfig {
+    /// Parses an experiment from TOML. Relative paths are kept as written.
+    pub fn from_toml(text: &str) -> Result<Self, LatticeError> {
+        toml::from_str(text).map_err(|err| LatticeError::InvalidConfig(err.to_string()))
+    }
+
+    /// Writes the experiment as TOML.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Experiments always serialize to TOML")
+    }
+
+    /// Reads an experiment from a TOML file. Relative paths in it are
+    /// taken relative to the file's directory, so an experiment and its
+    /// inputs can be moved together.
+    pub fn read(path: &Path) -> Result<Self, LatticeError> {
+        let text = std::fs::read_to_string(path)?;
+        let mut config = Self::from_toml(&text).map_err(|err| match err {
+            LatticeError::InvalidConfig(msg) => {
+                LatticeError::InvalidConfig(format!("{}: {}", path.display(), msg))
+            }
+            err => err,
+        })?;
+        if let Some(dir) = path.parent() {
+            config.resolve_paths(dir);
+        }
+        Ok(config)
+    }
+
+    // Make every relative path relative to `dir` instead
+    fn resolve_paths(&mut self, dir: &Path) {
+        let resolve = |path: &mut PathBuf| {
+            if path.is_relative() {
+                *path = dir.join(&*path);
+            }
+        };
+        for condition in &mut self.initial {
+            if let InitialCondition::Csv { path } = condition {
+                resolve(path);
+            }
+        }
+        let output = &mut self.output;
+        [
+            &mut output.checkpoint,
+            &mut output.npy,
+            &mut output.vti,
+            &mut output.raw,
+            &mut output.vti_series,
+        ]
+        .into_iter()
+        .flatten()
+        .for_each(resolve);
+    }
+
+    /// Builds the lattice, places the initial energy, runs every step and
+    /// writes the outputs. Returns the lattice for further inspection.
+    pub async fn run(&self) -> Result<DiscreteLatticeGPU, LatticeError> {
+        self.run_cancellable(&CancellationToken::new()).await
+    }
+
+    /// Like [`run`](Self::run), but stops early once `token` is cancelled.
+    ///
+    /// The outputs are still written, so a cancelled run leaves a
+    /// checkpoint of the state it reached to resume from; the returned
+    /// lattice's generation tells how far it got.
+    pub async fn run_cancellable(
+        &self,
+        token: &CancellationToken,
+    ) -> Result<DiscreteLatticeGPU, LatticeError> {
+        let mut lattice = DiscreteLatticeGPU::from_config(&self.lattice).await?;
+        for condition in &self.initial {
+            match condition {
+                InitialCondition::Point { site, quanta } => {
+                    let [x, y, z] = *site;
+                    lattice.add_energy_quantum(x, y, z, *quanta);
+                }
+                InitialCondition::Sphere {
+                    center,
+                    radius,
+                    quanta,
+                } => {
+                    let [x, y, z] = *center;
+                    lattice.seed_sphere((x, y, z), *radius, *quanta);
+                }
+                InitialCondition::Line { from, to, quanta } => {
+                    let ([x0, y0, z0], [x1, y1, z1]) = (*from, *to);
+                    lattice.add_energy_line((x0, y0, z0), (x1, y1, z1), *quanta);
+                }
+                InitialCondition::Csv { path } => lattice.add_energy_csv(path)?,
+            }
+        }
+        let as_tuples = |sites: &[[u32; 4]]| -> Vec<(u32, u32, u32, u32)> {
+            sites.iter().map(|&[x, y, z, q]| (x, y, z, q)).collect()
+        };
+        lattice.add_sources(&as_tuples(&self.sources));
+        lattice.add_sinks(&as_tuples(&self.sinks));
+
+        let output = &self.output;
+        let mut series = output.vti_series.as_ref().map(VtkSeries::new);
+        if let Some(series) = &mut series {
+            series.append(&lattice).await?;
+        }
+        let interval = match output.series_interval {
+            interval if interval > 0 && series.is_some() => interval,
+            _ => self.steps.max(1),
+        };
+        let mut remaining = self.steps;
+        while remaining > 0 && !token.is_cancelled() {
+            let steps = remaining.min(interval);
+            remaining -= lattice
+                .propagate_cancellable(steps, CANCEL_CHECK_STEPS, token)
+                .await;
+            if let Some(series) = &mut series {
+                series.append(&lattice).await?;
+            }
+        }
+
+        if let Some(path) = &output.checkpoint {
+            lattice.save_checkpoint(path).await?;
+        }
+        if let Some(path) = &output.npy {
+            lattice.export_npy(path).await?;
+        }
+        if let Some(path) = &output.vti {
+            lattice.export_vti(path).await?;
+        }
+        if let Some(path) = &output.raw {
+            lattice.export_raw(path).await?;
+        }
+        Ok(lattice)
+    }
+}
+
+/// Reads the experiment in the TOML file at `path` and runs it, see
+/// [`ExperimentConfig::run`].
+pub async fn run_from_config(path: &Path) -> Result<DiscreteLatticeGPU, LatticeError> {
+    ExperimentConfig::read(path)?.run().await
+}
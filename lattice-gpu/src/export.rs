@@ -0,0 +1,198 @@
+// File exporters for lattice snapshots
+
+use crate::DiscreteLatticeGPU;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+// Writes a NumPy .npy v1.0 file holding a C-ordered little-endian uint32
+// array of shape (depth, height, width)
+pub(crate) fn write_npy<W: Write>(
+    writer: &mut W,
+    energy: &[u32],
+    (width, height, depth): (u32, u32, u32),
+) -> io::Result<()> {
+    let mut header = format!(
+        "{{'descr': '<u4', 'fortran_order': False, 'shape': ({}, {}, {}), }}",
+        depth, height, width
+    );
+
+    // Magic (6) + version (2) + header length (2) + header must be a multiple
+    // of 64 bytes, with the header terminated by a newline
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY")?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in energy {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+// Writes a VTK XML ImageData (.vti) file with the energy as a UInt32 point
+// array named "energy", stored raw in the appended section. VTK orders
+// points x fastest, then y, then z, the same as the state. The generation
+// goes in the TimeValue field, which ParaView reads as the time step
+pub(crate) fn write_vti<W: Write>(
+    writer: &mut W,
+    energy: &[u32],
+    (width, height, depth): (u32, u32, u32),
+    generation: u64,
+) -> io::Result<()> {
+    let extent = format!("0 {} 0 {} 0 {}", width - 1, height - 1, depth - 1);
+    write!(
+        writer,
+        concat!(
+            "<?xml version=\"1.0\"?>\n",
+            "<VTKFile type=\"ImageData\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">\n",
+            "  <ImageData WholeExtent=\"{extent}\" Origin=\"0 0 0\" Spacing=\"1 1 1\">\n",
+            "    <FieldData>\n",
+            "      <DataArray type=\"Float64\" Name=\"TimeValue\" NumberOfTuples=\"1\" format=\"ascii\">{generation}</DataArray>\n",
+            "    </FieldData>\n",
+            "    <Piece Extent=\"{extent}\">\n",
+            "      <PointData Scalars=\"energy\">\n",
+            "        <DataArray type=\"UInt32\" Name=\"energy\" format=\"appended\" offset=\"0\"/>\n",
+            "      </PointData>\n",
+            "    </Piece>\n",
+            "  </ImageData>\n",
+            "  <AppendedData encoding=\"raw\">\n",
+            "_",
+        ),
+        extent = extent,
+        generation = generation,
+    )?;
+    // Each appended array is prefixed by its length in bytes, as header_type
+    writer.write_all(&(energy.len() as u64 * 4).to_le_bytes())?;
+    for value in energy {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.write_all(b"\n  </AppendedData>\n</VTKFile>\n")
+}
+
+// Element type of a raw volume export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RawElement {
+    U32,
+    // Saturated at 255
+    U8,
+}
+
+// Writes the energy as headerless little-endian values in state order
+pub(crate) fn write_raw<W: Write>(
+    writer: &mut W,
+    energy: &[u32],
+    element: RawElement,
+) -> io::Result<()> {
+    match element {
+        RawElement::U32 => {
+            for value in energy {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            Ok(())
+        }
+        RawElement::U8 => {
+            let bytes: Vec<u8> = energy.iter().map(|&value| value.min(255) as u8).collect();
+            writer.write_all(&bytes)
+        }
+    }
+}
+
+// Writes a MetaImage (.mhd) header describing a raw volume in `data_file`,
+// which ITK, 3D Slicer and ImageJ read as an image of unit spacing
+pub(crate) fn write_mhd<W: Write>(
+    writer: &mut W,
+    (width, height, depth): (u32, u32, u32),
+    element: RawElement,
+    data_file: &str,
+) -> io::Result<()> {
+    let element_type = match element {
+        RawElement::U32 => "MET_UINT",
+        RawElement::U8 => "MET_UCHAR",
+    };
+    writeln!(writer, "ObjectType = Image")?;
+    writeln!(writer, "NDims = 3")?;
+    writeln!(writer, "DimSize = {} {} {}", width, height, depth)?;
+    writeln!(writer, "ElementType = {}", element_type)?;
+    writeln!(writer, "ElementSpacing = 1 1 1")?;
+    writeln!(writer, "ElementByteOrderMSB = False")?;
+    writeln!(writer, "ElementDataFile = {}", data_file)
+}
+
+/// A time series of `.vti` files, tied together by a ParaView collection
+/// (`.pvd`) file so ParaView and VisIt open it as one animated dataset.
+pub struct VtkSeries {
+    path: PathBuf,
+    // Generation and file name of each frame, in the order appended
+    frames: Vec<(u64, String)>,
+}
+
+impl VtkSeries {
+    /// Starts an empty series whose collection file is `path`. Each frame
+    /// is written beside it as `<stem>_<generation>.vti`. Nothing is
+    /// written until the first [`append`](Self::append).
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Number of frames written so far.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Writes the lattice's current state as a new frame, timed by its
+    /// generation, and rewrites the collection file to include it. A frame
+    /// at a generation already in the series replaces the earlier one.
+    pub async fn append(&mut self, lattice: &DiscreteLatticeGPU) -> io::Result<()> {
+        let generation = lattice.generation();
+        let stem = self
+            .path
+            .file_stem()
+            .map_or("series".into(), |stem| stem.to_string_lossy());
+        let name = format!("{}_{:06}.vti", stem, generation);
+        lattice.export_vti(&self.path.with_file_name(&name)).await?;
+
+        self.frames.retain(|&(frame, _)| frame != generation);
+        self.frames.push((generation, name));
+        self.write_collection()
+    }
+
+    fn write_collection(&self) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        writeln!(writer, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            writer,
+            "<VTKFile type=\"Collection\" version=\"1.0\" byte_order=\"LittleEndian\">"
+        )?;
+        writeln!(writer, "  <Collection>")?;
+        for (generation, name) in &self.frames {
+            writeln!(
+                writer,
+                "    <DataSet timestep=\"{}\" part=\"0\" file=\"{}\"/>",
+                generation,
+                escape_xml(name)
+            )?;
+        }
+        writeln!(writer, "  </Collection>")?;
+        writeln!(writer, "</VTKFile>")?;
+        writer.into_inner()?.sync_all()
+    }
+}
+
+// Escape text for an XML attribute value
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,142 @@
+// Lattice geometry helpers
+//
+// Pure functions that compute sets of lattice points for seeding.
+// Points that fall outside the lattice are dropped rather than wrapped,
+// so callers never see negative coordinates cast to huge u32 values.
+
+/// Axis-aligned box of lattice sites, inclusive on both ends.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: [u32; 3],
+    pub max: [u32; 3],
+}
+
+impl BoundingBox {
+    /// Number of sites along each axis.
+    pub fn size(&self) -> [u32; 3] {
+        [
+            self.max[0] - self.min[0] + 1,
+            self.max[1] - self.min[1] + 1,
+            self.max[2] - self.min[2] + 1,
+        ]
+    }
+
+    pub fn contains(&self, x: u32, y: u32, z: u32) -> bool {
+        (self.min[0]..=self.max[0]).contains(&x)
+            && (self.min[1]..=self.max[1]).contains(&y)
+            && (self.min[2]..=self.max[2]).contains(&z)
+    }
+}
+
+/// One of the three lattice axes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Position of the axis in `[x, y, z]` arrays.
+    pub fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// Returns every `(x, y, z, quanta)` point of a solid sphere that lies inside
+/// a lattice of size `dims`.
+///
+/// A site is included when its squared distance from `center` is at most
+/// `radius²`. Sites outside `0..dims` on any axis are skipped.
+pub fn sphere_points(
+    center: (u32, u32, u32),
+    radius: u32,
+    quanta: u32,
+    dims: (u32, u32, u32),
+) -> Vec<(u32, u32, u32, u32)> {
+    let (width, height, depth) = dims;
+    let r = radius as i64;
+    let (cx, cy, cz) = (center.0 as i64, center.1 as i64, center.2 as i64);
+
+    let mut points = Vec::new();
+    for dz in -r..=r {
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if dx * dx + dy * dy + dz * dz > r * r {
+                    continue;
+                }
+
+                let (x, y, z) = (cx + dx, cy + dy, cz + dz);
+                if in_bounds(x, width) && in_bounds(y, height) && in_bounds(z, depth) {
+                    points.push((x as u32, y as u32, z as u32, quanta));
+                }
+            }
+        }
+    }
+    points
+}
+
+/// Returns the `(x, y, z, quanta)` points of a 3D Bresenham line from `from`
+/// to `to`, both ends included, that lie inside a lattice of size `dims`.
+///
+/// The line takes exactly one site per step along its longest axis, so it
+/// is 6-connected only when it runs along a single axis. Endpoints may lie
+/// outside the lattice; those parts of the line are skipped.
+pub fn line_points(
+    from: (u32, u32, u32),
+    to: (u32, u32, u32),
+    quanta: u32,
+    dims: (u32, u32, u32),
+) -> Vec<(u32, u32, u32, u32)> {
+    let (width, height, depth) = dims;
+    let start = [from.0 as i64, from.1 as i64, from.2 as i64];
+    let end = [to.0 as i64, to.1 as i64, to.2 as i64];
+    let delta = [
+        (end[0] - start[0]).abs(),
+        (end[1] - start[1]).abs(),
+        (end[2] - start[2]).abs(),
+    ];
+    let step = [
+        (end[0] - start[0]).signum(),
+        (end[1] - start[1]).signum(),
+        (end[2] - start[2]).signum(),
+    ];
+
+    // Walk the driving axis one site at a time, stepping the other two
+    // whenever their accumulated error crosses half a site
+    let driver = (0..3).max_by_key(|&axis| delta[axis]).unwrap();
+    let steps = delta[driver];
+    let mut error = [0i64; 3];
+    for axis in 0..3 {
+        error[axis] = 2 * delta[axis] - steps;
+    }
+
+    let mut pos = start;
+    let mut points = Vec::with_capacity(steps as usize + 1);
+    for _ in 0..=steps {
+        if in_bounds(pos[0], width) && in_bounds(pos[1], height) && in_bounds(pos[2], depth) {
+            points.push((pos[0] as u32, pos[1] as u32, pos[2] as u32, quanta));
+        }
+
+        for axis in 0..3 {
+            if axis == driver {
+                continue;
+            }
+            if error[axis] > 0 {
+                pos[axis] += step[axis];
+                error[axis] -= 2 * steps;
+            }
+            error[axis] += 2 * delta[axis];
+        }
+        pos[driver] += step[driver];
+    }
+    points
+}
+
+fn in_bounds(coord: i64, extent: u32) -> bool {
+    coord >= 0 && coord < extent as i64
+}
@@ -0,0 +1,63 @@
+// Shared handle for driving a lattice from several threads
+//
+// wgpu::Device and wgpu::Queue are Send + Sync, so the lattice can live on
+// a worker thread while a UI thread reads diagnostics. The handle
+// serializes access with a mutex; clones share the same lattice.
+
+use crate::{DiscreteLatticeCPU, DiscreteLatticeGPU, Lattice};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// Compile-time guarantee that both backends can cross thread boundaries.
+// wgpu's browser types are not Send, and a browser has no threads to share
+// the GPU lattice with anyway
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    #[cfg(not(target_arch = "wasm32"))]
+    assert_send_sync::<DiscreteLatticeGPU>();
+    assert_send_sync::<DiscreteLatticeCPU>();
+};
+
+/// A lattice shared between threads. Works with any [`Lattice`] backend and
+/// holds a [`DiscreteLatticeGPU`] unless told otherwise.
+pub struct LatticeHandle<L = DiscreteLatticeGPU> {
+    inner: Arc<Mutex<L>>,
+}
+
+// Derived Clone would require L: Clone, but only the Arc is cloned
+impl<L> Clone for LatticeHandle<L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<L: Lattice> LatticeHandle<L> {
+    pub fn new(lattice: L) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(lattice)),
+        }
+    }
+
+    /// Propagates `steps` times while holding the lock once.
+    pub fn propagate(&self, steps: u32) {
+        let mut lattice = self.lock();
+        for _ in 0..steps {
+            lattice.propagate_energy();
+        }
+    }
+
+    /// Blocks until the total energy has been read back.
+    pub fn total_energy(&self) -> u64 {
+        self.lock().total_energy()
+    }
+
+    /// Runs `f` with exclusive access to the lattice.
+    pub fn with<R>(&self, f: impl FnOnce(&mut L) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, L> {
+        self.inner.lock().expect("Lattice mutex poisoned")
+    }
+}
@@ -0,0 +1,279 @@
+// HDF5 time series, enabled with the `hdf5` feature
+//
+// A long run saved as loose .vti or .npy files leaves thousands of files
+// behind; an Hdf5Series keeps every frame in one file instead. Frames are
+// datasets /frames/<step> of shape depth x height x width (z, y, x, so the
+// fastest-varying axis is x as in get_state), compressed with deflate and
+// carrying their step, total energy and wall-clock time as attributes.
+// Written through the HDF5 C library's API, which this crate links when
+// the feature is on.
+
+use crate::DiscreteLatticeGPU;
+use hdf5_metno_sys::{h5, h5a, h5d, h5e, h5f, h5g, h5i, h5l, h5p, h5s, h5t};
+use std::ffi::{c_void, CString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// deflate level of each frame; frames are mostly empty, so a fast level
+// already gets most of the gain
+const DEFLATE_LEVEL: u32 = 4;
+
+// Target size of a chunk. HDF5 rejects chunks of 4 GiB or more, and a
+// partial read decompresses every chunk it touches, so frames are split
+// into Z-slabs of about this many bytes rather than stored whole
+const CHUNK_BYTES: u64 = 4 << 20;
+
+/// A time series of lattice states in one HDF5 file.
+///
+/// Each [`append`](Self::append) adds a dataset `/frames/<step>` (the
+/// generation, zero-padded to six digits) holding the energy as `u32` with
+/// shape `[depth, height, width]`, and the attributes `step`,
+/// `total_energy` and `timestamp` (seconds since the Unix epoch). Frames
+/// are chunked in Z-slabs of a few MiB, so reading a few slices of a large
+/// frame only decompresses the slabs holding them.
+pub struct Hdf5Series {
+    path: PathBuf,
+    // The open file and its /frames group, once the first frame is written
+    file: Option<(Handle, Handle)>,
+    // Generations written by this series
+    generations: Vec<u64>,
+}
+
+impl Hdf5Series {
+    /// Starts a series writing to `path`. An existing file is opened and
+    /// frames are added to it; otherwise it is created by the first
+    /// [`append`](Self::append).
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: None,
+            generations: Vec::new(),
+        }
+    }
+
+    /// Number of frames written so far by this series.
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.generations.is_empty()
+    }
+
+    /// Writes the lattice's current state as a new frame named by its
+    /// generation. A frame at a generation already in the file replaces the
+    /// earlier one. The file is flushed after every frame, so a crashed run
+    /// keeps the frames written before it.
+    pub async fn append(&mut self, lattice: &DiscreteLatticeGPU) -> io::Result<()> {
+        let energy = lattice.get_state().await;
+        let total_energy = lattice.get_total_energy().await;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        let dims = [lattice.depth(), lattice.height(), lattice.width()].map(|n| n as u64);
+
+        if self.file.is_none() {
+            self.file = Some(open_series(&self.path)?);
+        }
+        let (file, frames) = self.file.as_ref().expect("Series file was just opened");
+        let generation = lattice.generation();
+        let name = c_string(&format!("{:06}", generation))?;
+        unsafe {
+            if h5l::H5Lexists(frames.0, name.as_ptr(), h5p::H5P_DEFAULT) > 0 {
+                check(
+                    h5l::H5Ldelete(frames.0, name.as_ptr(), h5p::H5P_DEFAULT),
+                    "H5Ldelete",
+                )?;
+            }
+
+            let space = Handle::new(
+                h5s::H5Screate_simple(3, dims.as_ptr(), std::ptr::null()),
+                h5s::H5Sclose,
+                "H5Screate_simple",
+            )?;
+            let properties = Handle::new(
+                h5p::H5Pcreate(*h5p::H5P_CLS_DATASET_CREATE),
+                h5p::H5Pclose,
+                "H5Pcreate",
+            )?;
+            check(
+                h5p::H5Pset_chunk(properties.0, 3, chunk_dims(dims).as_ptr()),
+                "H5Pset_chunk",
+            )?;
+            check(
+                h5p::H5Pset_deflate(properties.0, DEFLATE_LEVEL),
+                "H5Pset_deflate",
+            )?;
+            let dataset = Handle::new(
+                h5d::H5Dcreate2(
+                    frames.0,
+                    name.as_ptr(),
+                    *h5t::H5T_NATIVE_UINT32,
+                    space.0,
+                    h5p::H5P_DEFAULT,
+                    properties.0,
+                    h5p::H5P_DEFAULT,
+                ),
+                h5d::H5Dclose,
+                "H5Dcreate2",
+            )?;
+            check(
+                h5d::H5Dwrite(
+                    dataset.0,
+                    *h5t::H5T_NATIVE_UINT32,
+                    h5s::H5S_ALL,
+                    h5s::H5S_ALL,
+                    h5p::H5P_DEFAULT,
+                    energy.as_ptr() as *const c_void,
+                ),
+                "H5Dwrite",
+            )?;
+
+            write_attribute(&dataset, "step", *h5t::H5T_NATIVE_UINT64, &generation)?;
+            write_attribute(
+                &dataset,
+                "total_energy",
+                *h5t::H5T_NATIVE_UINT64,
+                &total_energy,
+            )?;
+            write_attribute(&dataset, "timestamp", *h5t::H5T_NATIVE_DOUBLE, &timestamp)?;
+            check(
+                h5f::H5Fflush(file.0, h5f::H5F_scope_t::H5F_SCOPE_LOCAL),
+                "H5Fflush",
+            )?;
+        }
+        self.generations.retain(|&frame| frame != generation);
+        self.generations.push(generation);
+        Ok(())
+    }
+}
+
+// An HDF5 identifier, closed with `close` when dropped
+struct Handle(h5i::hid_t, unsafe extern "C" fn(h5i::hid_t) -> h5::herr_t);
+
+impl Handle {
+    // Wrap the result of an HDF5 call that returns an identifier, failing
+    // if it returned a negative one
+    fn new(
+        id: h5i::hid_t,
+        close: unsafe extern "C" fn(h5i::hid_t) -> h5::herr_t,
+        call: &str,
+    ) -> io::Result<Self> {
+        if id < 0 {
+            return Err(hdf5_error(call));
+        }
+        Ok(Self(id, close))
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe {
+            (self.1)(self.0);
+        }
+    }
+}
+
+// Open the series file at `path`, or create it, and its /frames group
+fn open_series(path: &Path) -> io::Result<(Handle, Handle)> {
+    let name = c_string(&path.to_string_lossy())?;
+    let frames_name = c_string("frames")?;
+    unsafe {
+        check(h5::H5open(), "H5open")?;
+        // Failures are reported through io::Error; HDF5's own printing to
+        // stderr would only duplicate them
+        h5e::H5Eset_auto2(h5e::H5E_DEFAULT, None, std::ptr::null_mut());
+
+        let file = if path.exists() {
+            Handle::new(
+                h5f::H5Fopen(name.as_ptr(), h5f::H5F_ACC_RDWR, h5p::H5P_DEFAULT),
+                h5f::H5Fclose,
+                "H5Fopen",
+            )?
+        } else {
+            Handle::new(
+                h5f::H5Fcreate(
+                    name.as_ptr(),
+                    h5f::H5F_ACC_TRUNC,
+                    h5p::H5P_DEFAULT,
+                    h5p::H5P_DEFAULT,
+                ),
+                h5f::H5Fclose,
+                "H5Fcreate",
+            )?
+        };
+        let frames = if h5l::H5Lexists(file.0, frames_name.as_ptr(), h5p::H5P_DEFAULT) > 0 {
+            h5g::H5Gopen2(file.0, frames_name.as_ptr(), h5p::H5P_DEFAULT)
+        } else {
+            h5g::H5Gcreate2(
+                file.0,
+                frames_name.as_ptr(),
+                h5p::H5P_DEFAULT,
+                h5p::H5P_DEFAULT,
+                h5p::H5P_DEFAULT,
+            )
+        };
+        let frames = Handle::new(frames, h5g::H5Gclose, "opening /frames")?;
+        Ok((file, frames))
+    }
+}
+
+// Chunk shape for a frame of shape `[depth, height, width]`: whole XY
+// slices, as many as fit in CHUNK_BYTES, or runs of rows when a single
+// slice is larger than that
+fn chunk_dims([depth, height, width]: [u64; 3]) -> [u64; 3] {
+    let row = width * std::mem::size_of::<u32>() as u64;
+    let slice = row * height;
+    if slice <= CHUNK_BYTES {
+        [(CHUNK_BYTES / slice).clamp(1, depth), height, width]
+    } else {
+        [1, (CHUNK_BYTES / row).clamp(1, height), width]
+    }
+}
+
+// Attach a scalar attribute `name` of HDF5 type `type_id` to `object`
+unsafe fn write_attribute<T>(
+    object: &Handle,
+    name: &str,
+    type_id: h5i::hid_t,
+    value: &T,
+) -> io::Result<()> {
+    let name = c_string(name)?;
+    let space = Handle::new(
+        h5s::H5Screate(h5s::H5S_class_t::H5S_SCALAR),
+        h5s::H5Sclose,
+        "H5Screate",
+    )?;
+    let attribute = Handle::new(
+        h5a::H5Acreate2(
+            object.0,
+            name.as_ptr(),
+            type_id,
+            space.0,
+            h5p::H5P_DEFAULT,
+            h5p::H5P_DEFAULT,
+        ),
+        h5a::H5Aclose,
+        "H5Acreate2",
+    )?;
+    check(
+        h5a::H5Awrite(attribute.0, type_id, value as *const T as *const c_void),
+        "H5Awrite",
+    )
+}
+
+fn check(status: h5::herr_t, call: &str) -> io::Result<()> {
+    if status < 0 {
+        return Err(hdf5_error(call));
+    }
+    Ok(())
+}
+
+fn hdf5_error(call: &str) -> io::Error {
+    io::Error::other(format!("HDF5 {} failed", call))
+}
+
+fn c_string(text: &str) -> io::Result<CString> {
+    CString::new(text).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
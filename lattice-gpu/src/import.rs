@@ -0,0 +1,67 @@
+// Initial conditions loaded from files
+
+use crate::{LatticeError, MAX_LEVEL};
+use image::GrayImage;
+
+// Reject a luminance scale no site could hold
+pub(crate) fn check_max_level(max_level: u32) -> Result<(), LatticeError> {
+    if max_level > MAX_LEVEL {
+        return Err(LatticeError::InvalidInput(format!(
+            "max_level is {} but must be at most {}",
+            max_level, MAX_LEVEL
+        )));
+    }
+    Ok(())
+}
+
+// Map each pixel's luminance to an energy level in 0..=max_level, rounding
+// to the nearest level, laid out row-major like one z-slice of the lattice
+pub(crate) fn luminance_levels(image: &GrayImage, max_level: u32) -> Vec<u32> {
+    image
+        .pixels()
+        .map(|pixel| (pixel.0[0] as u32 * max_level + 127) / 255)
+        .collect()
+}
+
+// Parse `x,y,z,quanta` points, one per line. Blank lines, `#` comments and
+// a non-numeric first line (a header) are skipped. Errors name the line
+pub(crate) fn parse_points_csv(
+    text: &str,
+    (width, height, depth): (u32, u32, u32),
+) -> Result<Vec<(u32, u32, u32, u32)>, String> {
+    let mut points = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let values: Result<Vec<u32>, _> = fields.iter().map(|field| field.parse()).collect();
+        let values = match values {
+            Ok(values) => values,
+            Err(_) if index == 0 => continue,
+            Err(err) => return Err(format!("line {}: {}", index + 1, err)),
+        };
+        let &[x, y, z, quanta] = values.as_slice() else {
+            return Err(format!(
+                "line {}: expected x,y,z,quanta but found {} fields",
+                index + 1,
+                values.len()
+            ));
+        };
+        if x >= width || y >= height || z >= depth {
+            return Err(format!(
+                "line {}: site ({}, {}, {}) is outside the {}x{}x{} lattice",
+                index + 1,
+                x,
+                y,
+                z,
+                width,
+                height,
+                depth
+            ));
+        }
+        points.push((x, y, z, quanta));
+    }
+    Ok(points)
+}
@@ -0,0 +1,53 @@
+// Backend-independent lattice interface
+//
+// Implemented by the GPU lattice and the CPU reference, so code that only
+// seeds, steps and reads energy can run on either.
+
+use crate::sphere_points;
+
+/// The operations every lattice backend provides.
+///
+/// Reads block until the data is on the host. The GPU implementation lives
+/// in the [`blocking`](crate::blocking) layer and waits on its readback, so
+/// call reads between batches of steps rather than every step.
+pub trait Lattice {
+    /// `(width, height, depth)` in sites.
+    fn dimensions(&self) -> (u32, u32, u32);
+
+    /// Empties every site.
+    fn initialize_vacuum(&mut self);
+
+    /// Adds quanta to one site, capped at [`MAX_LEVEL`](crate::MAX_LEVEL).
+    /// Points outside the lattice are skipped.
+    fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32);
+
+    /// Adds quanta to many sites, as
+    /// [`add_energy_quantum`](Self::add_energy_quantum) does to one.
+    fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]);
+
+    /// Fills a solid sphere with `quanta` per site, skipping any part of the
+    /// sphere that falls outside the lattice.
+    fn seed_sphere(&mut self, center: (u32, u32, u32), radius: u32, quanta: u32) {
+        let points = sphere_points(center, radius, quanta, self.dimensions());
+        self.add_energy_batch(&points);
+    }
+
+    /// Advances one step.
+    fn propagate_energy(&mut self);
+
+    /// Advances `steps` steps.
+    fn propagate_n(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.propagate_energy();
+        }
+    }
+
+    /// Total quanta on the lattice.
+    fn total_energy(&self) -> u64;
+
+    /// Energy of every site, indexed `z * width * height + y * width + x`.
+    fn read_state(&self) -> Vec<u32>;
+
+    /// Steps taken so far.
+    fn generation(&self) -> u64;
+}
@@ -5,80 +5,582 @@
 // - Two-pass algorithm ensures perfect energy conservation
 // - Supports up to 700³ lattices (~343M sites, 1.3GB) on RTX 4080
 
+mod active;
+mod adapter;
+mod analysis;
+#[cfg(feature = "ndarray")]
+mod array;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod blocking;
+mod channels;
+mod checkpoint;
+mod chunked;
+mod config;
+mod cpu;
+mod error;
+mod events;
+mod experiment;
+mod export;
+mod geometry;
+mod handle;
+#[cfg(feature = "hdf5")]
+mod hdf5;
+mod import;
+mod lattice;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod multi_gpu;
+mod out_of_core;
+mod pipeline_cache;
+mod progress;
+mod quantum_walk;
+mod readback;
+#[cfg(not(target_arch = "wasm32"))]
+mod recording;
+mod resample;
+mod rule;
+mod state;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timing;
+mod workgroup;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use adapter::list_adapters;
+pub use adapter::AdapterOptions;
+pub use channels::ChannelLattice;
+pub use checkpoint::Checkpoint;
+pub use chunked::ChunkedLattice;
+pub use config::{LatticeConfig, RuleConfig};
+pub use cpu::DiscreteLatticeCPU;
+pub use error::{ExportError, LatticeError};
+pub use events::LatticeEvent;
+pub use experiment::{run_from_config, ExperimentConfig, InitialCondition, OutputConfig};
+pub use export::VtkSeries;
+pub use geometry::{line_points, sphere_points, Axis, BoundingBox};
+pub use handle::LatticeHandle;
+#[cfg(feature = "hdf5")]
+pub use hdf5::Hdf5Series;
+pub use lattice::Lattice;
+#[cfg(feature = "metrics")]
+pub use metrics::{LatticeMetrics, MetricsServer};
+pub use multi_gpu::MultiGpuLattice;
+pub use out_of_core::OutOfCoreLattice;
+pub use progress::{CancellationToken, Diagnostics};
+pub use quantum_walk::{QuantumWalk, WALK_DIRECTIONS};
+pub use readback::Readback;
+#[cfg(not(target_arch = "wasm32"))]
+pub use recording::{render_gif, GifConfig};
+pub use rule::{GradientRule, PropagationRule, RandomWalkRule, WgslRule};
+pub use state::{LatticeSnapshot, LatticeState};
+#[cfg(feature = "profiling")]
+pub use timing::StepTimings;
+pub use timing::{RunTiming, TransferStats};
+pub use workgroup::{check_workgroup_size, WORKGROUP_SIZE, WORKGROUP_SIZE_CANDIDATES};
+
+use active::ActiveRegions;
 use bytemuck::{Pod, Zeroable};
-use std::sync::Arc;
+use pipeline_cache::PipelineCacheFile;
+use readback::StagingPool;
+use serde::{Deserialize, Serialize};
+use state::HistoryEntry;
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use timing::TransferCounters;
+use web_time::Instant;
 use wgpu::util::DeviceExt;
 
+/// Texture format produced by [`DiscreteLatticeGPU::create_energy_texture`].
+pub const ENERGY_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+/// Optional readback performed by [`DiscreteLatticeGPU::tick`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Measurement {
+    /// Only propagate, no readback.
+    #[default]
+    None,
+    /// Total energy in quanta.
+    Total,
+    /// Energy-weighted mean X coordinate.
+    CenterOfMassX,
+    /// Energy-weighted mean Y coordinate.
+    CenterOfMassY,
+    /// Energy-weighted mean Z coordinate.
+    CenterOfMassZ,
+    /// Highest energy level held by any site.
+    MaxLevel,
+}
+
+/// How each propagation step is executed on the GPU.
+///
+/// Both modes apply the same transfer rule and produce identical states.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropagationMode {
+    /// One pass: every site re-evaluates its neighbors' transfer decisions
+    /// and writes its complete next state. Skips the copy pass, halving
+    /// memory traffic.
+    #[default]
+    Gather,
+    /// Two passes: copy the state, then each site atomically pushes its
+    /// quantum into the chosen neighbor.
+    ///
+    /// The output buffer is `array<atomic<u32>>` and every transfer is an
+    /// `atomicSub` on the source plus an `atomicAdd` on the target, so any
+    /// number of sites may push into the same neighbor in one step without
+    /// losing quanta. This is the mode to start from when writing a
+    /// push-style rule that has no gather equivalent.
+    Scatter,
+}
+
+/// Which neighbors a site can hand a quantum to.
+///
+/// The choice is compiled into the propagation pipelines, so changing it
+/// rebuilds them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Neighborhood {
+    /// The 6 face neighbors.
+    #[default]
+    VonNeumann,
+    /// All 26 face, edge and corner neighbors. A diagonal move along `k`
+    /// axes is weighted by the sum of their axis weights over `k²`, so with
+    /// equal axis weights edges get 1/2 and corners 1/3 of a face's weight.
+    Moore,
+}
+
+/// What happens to quanta at the faces of the lattice.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryMode {
+    /// Each face wraps around to the opposite one, so energy leaving one
+    /// face re-enters on the other and there are no edge sites.
+    #[default]
+    Periodic,
+    /// Faces are walls: sites on a face have no neighbor beyond it and
+    /// never send quanta across.
+    Closed,
+    /// Faces are open: beyond each face is an empty sink that always
+    /// accepts, and quanta sent into it leave the lattice. The removed
+    /// quanta are counted by
+    /// [`absorbed_energy`](DiscreteLatticeGPU::absorbed_energy).
+    Absorbing,
+    /// Faces are mirrors: the neighbor beyond a face is the site just inside
+    /// it, so quanta heading out bounce back into the lattice.
+    Reflective,
+}
+
+impl BoundaryMode {
+    // Matches the BOUNDARY_* constants in shader.wgsl
+    fn shader_value(self) -> u32 {
+        match self {
+            BoundaryMode::Periodic => 0,
+            BoundaryMode::Closed => 1,
+            BoundaryMode::Absorbing => 2,
+            BoundaryMode::Reflective => 3,
+        }
+    }
+}
+
 #[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
 struct Params {
     width: u32,
     height: u32,
     depth: u32,
+    // Left at 0 when the step is passed as a push constant
     step_count: u32,
+    // Relative transfer weights along each axis, 16-bit fixed point
+    weight_x: u32,
+    weight_y: u32,
+    weight_z: u32,
+    // BoundaryMode of each axis, 8 bits apiece: X, Y, Z
+    boundary_modes: u32,
+    seed: u32,
+    // Per-site decay probability, as a fraction of 2^32
+    decay_threshold: u32,
+    // Nonzero when the capacity buffer holds per-site capacities
+    capacity_map: u32,
+    // Global Z of layer 0 and the whole lattice's depth, for a chunk of a
+    // ChunkedLattice; 0 and depth otherwise
+    z_offset: u32,
+    lattice_depth: u32,
+    _padding: [u32; 3],
 }
 
+/// Highest quantum level a site accepts a transfer at, and the cap applied
+/// when seeding and injecting.
+///
+/// It is not a bound on site energy: a site below the cap can receive from
+/// several neighbors in the same step, so propagation routinely leaves
+/// sites above it, and uploaded states are taken as given.
+pub const MAX_LEVEL: u32 = 3;
+
+// Site flag bits, matching FLAG_* in shader.wgsl
+const SITE_FROZEN: u32 = 1;
+const SITE_OBSTACLE: u32 = 2;
+
+// Fixed-point value of the largest axis weight
+const WEIGHT_SCALE: f32 = 65535.0;
+
+// Bytes of push constants used by the propagation pipelines: the step
+const PUSH_CONSTANT_SIZE: u32 = 4;
+
+// Size of the reduction result buffer in bytes
+const REDUCE_BUFFER_SIZE: u64 = 32;
+
+// Size of a GPU counter: a 64-bit count split into two u32 words
+const COUNTER_BUFFER_SIZE: u64 = 8;
+
+// Offsets of the absorbed and decayed counters in the loss buffer
+const ABSORBED_COUNTER: usize = 0;
+const DECAYED_COUNTER: usize = 2;
+
+// Size of the capacity buffer while no capacity map is set; the shaders
+// never read it then, but the binding needs a buffer
+const CAPACITY_PLACEHOLDER_SIZE: u64 = 4;
+
+// Edits per apply_edits dispatch, so the workgroup count stays within the
+// guaranteed per-dimension limit
+const MAX_EDITS_PER_DISPATCH: usize = 65535 * 64;
+
 pub struct DiscreteLatticeGPU {
     device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
     copy_pipeline: wgpu::ComputePipeline,
     propagate_pipeline: wgpu::ComputePipeline,
+    gather_pipeline: wgpu::ComputePipeline,
+    edit_pipeline: wgpu::ComputePipeline,
+    drain_pipeline: wgpu::ComputePipeline,
     bind_group_layout: wgpu::BindGroupLayout,
+    // Whether the step goes to the propagation pipelines as a push constant
+    push_constants: bool,
+    // Params as last written to params_buffer, to skip identical uploads
+    uploaded_params: Params,
+    // Step bind groups by parity: [0] reads buffer A and writes B, [1] the
+    // reverse. Rebuilt whenever a buffer they bind is replaced
+    step_bind_groups: [wgpu::BindGroup; 2],
     params_buffer: wgpu::Buffer,
     energy_buffer_a: wgpu::Buffer,
     energy_buffer_b: wgpu::Buffer,
+    site_flags_buffer: wgpu::Buffer,
+    potential_buffer: wgpu::Buffer,
+    capacity_buffer: wgpu::Buffer,
     staging_buffer: wgpu::Buffer,
+    // Reduction pass and its small result/readback buffers
+    total_pipeline: wgpu::ComputePipeline,
+    saturated_pipeline: wgpu::ComputePipeline,
+    occupied_pipeline: wgpu::ComputePipeline,
+    max_pipeline: wgpu::ComputePipeline,
+    compact_pipeline: wgpu::ComputePipeline,
+    bounds_pipeline: wgpu::ComputePipeline,
+    reduce_buffer: wgpu::Buffer,
+    loss_buffer: wgpu::Buffer,
+    reduce_staging_buffer: wgpu::Buffer,
     width: u32,
     height: u32,
     depth: u32,
     total_sites: usize,
-    step_count: u32,
+    // Physics steps taken; never reused to pick buffers
+    generation: u64,
+    // Which ping-pong buffer holds the current state (false = A)
+    parity: bool,
+    axis_weights: [u32; 3],
+    propagation_mode: PropagationMode,
+    neighborhood: Neighborhood,
+    // Workgroup size of the copy, scatter and gather passes
+    workgroup_size: [u32; 3],
+    // Where this lattice sits when it is a chunk of a ChunkedLattice: the
+    // global Z of its layer 0 and the full depth. 0 and depth otherwise
+    z_offset: u32,
+    lattice_depth: u32,
+    // WGSL of the transfer rule, appended to shader.wgsl
+    rule_source: String,
+    boundary_modes: [BoundaryMode; 3],
+    seed: u32,
+    decay_threshold: u32,
+    // Pre-step states for propagate_backward, oldest first, and how many
+    // to keep
+    history: VecDeque<HistoryEntry>,
+    history_depth: usize,
+    // State captured by the previous is_steady call
+    steady_reference: Option<Vec<u32>>,
+    // Injection schedule; the mutex only makes the closure Sync and is never
+    // contended, since calling it needs &mut self
+    injection: Option<Mutex<InjectionFn>>,
+    injected_buffer: wgpu::Buffer,
+    // Count of quanta added by add_energy_batch, which nobody reads; the
+    // edit kernel needs somewhere to put it
+    edit_counter_buffer: wgpu::Buffer,
+    // One-off injections by the generation they fire at, as quanta per site
+    // index
+    scheduled: BTreeMap<u64, HashMap<u32, u32>>,
+    // Per-step sources and sinks: quanta per site index, plus the same
+    // list as (index, quanta) pairs on the GPU, split into buffers of at
+    // most MAX_EDITS_PER_DISPATCH pairs
+    sources: HashMap<u32, u32>,
+    source_buffer: Vec<wgpu::Buffer>,
+    sinks: HashMap<u32, u32>,
+    sink_buffer: Vec<wgpu::Buffer>,
+    // Called after each step; wrapped in a mutex for the same reason
+    step_callback: Option<Mutex<StepCallback>>,
+    // Host copy of site_flags_buffer, allocated on first use
+    site_flags: Option<Vec<u32>>,
+    potential: Option<Vec<f32>>,
+    // Host copy of the per-site capacities, when a map is set
+    capacity: Option<Vec<u32>>,
+    transfer: Arc<TransferCounters>,
+    // Staging buffers for queued readbacks, back from the ones collected
+    staging_pool: StagingPool,
+    // Device errors and loss, sent by the callbacks watch_device installs
+    events: events::EventChannel,
+    device_lost: Arc<AtomicBool>,
+    // Where recover gets a new device, and the state it restores
+    recovery_adapter: AdapterOptions,
+    // Adapter of a device the lattice created itself
+    adapter_info: Option<wgpu::AdapterInfo>,
+    checkpoint: Option<LatticeState>,
+    // Driver cache every pipeline is compiled through, when kept on disk
+    pipeline_cache: Option<PipelineCacheFile>,
+    // Active-block list and its pipelines, while tracking is on
+    active_regions: Option<ActiveRegions>,
 }
 
+type InjectionFn = Box<dyn FnMut(u32) -> Vec<(u32, u32, u32, u32)> + Send>;
+type StepCallback = Box<dyn FnMut(&wgpu::Buffer, u64) + Send>;
+
 impl DiscreteLatticeGPU {
-    pub async fn new(width: u32, height: u32, depth: u32) -> Self {
+    /// Creates a lattice on the default high-performance adapter with its
+    /// own device.
+    pub async fn new(width: u32, height: u32, depth: u32) -> Result<Self, LatticeError> {
         // Initialize GPU
-        let instance = wgpu::Instance::default();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                ..Default::default()
-            })
-            .await
-            .expect("Failed to find GPU adapter");
+        let adapter = default_adapter().await?;
+
+        Self::new_with_adapter(&adapter, width, height, depth).await
+    }
+
+    /// Builds a `width × height × 1` lattice from a grayscale image, with one
+    /// site per pixel.
+    ///
+    /// Color images are converted to luminance first. A pixel of luminance
+    /// `l` (0–255) becomes level `round(l * max_level / 255)`, so black is
+    /// empty and white holds `max_level`. Pixel `(x, y)` maps to site
+    /// `(x, y, 0)`. Fails with [`LatticeError::InvalidInput`] if `max_level`
+    /// exceeds [`MAX_LEVEL`].
+    pub async fn from_image(path: &Path, max_level: u32) -> Result<Self, LatticeError> {
+        import::check_max_level(max_level)?;
+        let image = image::open(path)?.into_luma8();
+        let (width, height) = image.dimensions();
+
+        let adapter = default_adapter().await?;
 
-        // Query adapter's actual limits
-        let adapter_limits = adapter.limits();
+        let mut lattice = Self::new_with_adapter(&adapter, width, height, 1).await?;
+        lattice.initialize_vacuum();
+        let levels = import::luminance_levels(&image, max_level);
+        lattice.upload(lattice.get_energy_buffer(), bytemuck::cast_slice(&levels));
+        Ok(lattice)
+    }
+
+    /// Builds a `width × height × n` lattice from `n` grayscale images of
+    /// the same size, with image `z` as slice `z`.
+    ///
+    /// Each image is mapped to energy levels as in
+    /// [`from_image`](Self::from_image). Fails with
+    /// [`LatticeError::InvalidInput`] if `paths` is empty, the images
+    /// differ in size or `max_level` exceeds [`MAX_LEVEL`].
+    pub async fn from_image_stack<P: AsRef<Path>>(
+        paths: &[P],
+        max_level: u32,
+    ) -> Result<Self, LatticeError> {
+        import::check_max_level(max_level)?;
+        let mut levels = Vec::new();
+        let mut slice_size = None;
+        for path in paths {
+            let image = image::open(path)?.into_luma8();
+            match slice_size {
+                None => slice_size = Some(image.dimensions()),
+                Some(size) if size != image.dimensions() => {
+                    let (width, height) = image.dimensions();
+                    return Err(LatticeError::InvalidInput(format!(
+                        "{} is {}x{} but the first image is {}x{}",
+                        path.as_ref().display(),
+                        width,
+                        height,
+                        size.0,
+                        size.1
+                    )));
+                }
+                Some(_) => {}
+            }
+            levels.extend(import::luminance_levels(&image, max_level));
+        }
+        let Some((width, height)) = slice_size else {
+            return Err(LatticeError::InvalidInput(
+                "image stack is empty".to_string(),
+            ));
+        };
+
+        let adapter = default_adapter().await?;
+
+        let depth = paths.len() as u32;
+        let mut lattice = Self::new_with_adapter(&adapter, width, height, depth).await?;
+        lattice.initialize_vacuum();
+        lattice.upload(lattice.get_energy_buffer(), bytemuck::cast_slice(&levels));
+        Ok(lattice)
+    }
+
+    /// Creates a lattice with its own device on the adapter `options`
+    /// describe, e.g. a forced backend or a named GPU.
+    pub async fn new_with_options(
+        options: &AdapterOptions,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<Self, LatticeError> {
+        let adapter = options.request_adapter().await?;
+        Self::new_with_adapter(&adapter, width, height, depth).await
+    }
+
+    /// Requests a device from an already chosen adapter, asking for the
+    /// largest storage buffers the adapter supports, and builds the lattice
+    /// on it.
+    ///
+    /// The lattice owns the device, so it watches it for errors and loss;
+    /// see [`events`](Self::events).
+    pub async fn new_with_adapter(
+        adapter: &wgpu::Adapter,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<Self, LatticeError> {
+        let (device, queue) = request_device(adapter).await?;
+        let mut lattice = Self::new_with_device(device, queue, width, height, depth)?;
+        lattice.adopt_device(adapter);
+        Ok(lattice)
+    }
+
+    /// Creates a lattice on the default high-performance adapter, keeping
+    /// its compiled pipelines in `cache_dir` between runs.
+    ///
+    /// Pipelines an earlier run compiled on the same GPU and driver are
+    /// loaded rather than compiled again, and the cache is written back
+    /// once this lattice's pipelines exist. Call
+    /// [`save_pipeline_cache`](Self::save_pipeline_cache) to also keep
+    /// pipelines compiled later, e.g. by [`set_rule`](Self::set_rule). Only
+    /// Vulkan supports pipeline caches; elsewhere this is the same as
+    /// [`new`](Self::new).
+    pub async fn new_with_pipeline_cache(
+        cache_dir: &Path,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<Self, LatticeError> {
+        let adapter = default_adapter().await?;
+        let (device, queue) = request_device(&adapter).await?;
+        let cache = PipelineCacheFile::open(&device, &adapter.get_info(), cache_dir);
+        let mut lattice = Self::build(device, queue, width, height, depth, cache)?;
+        lattice.adopt_device(&adapter);
+
+        // A cache that can't be written only costs the next run its compile
+        // time
+        let _ = lattice.save_pipeline_cache();
+        Ok(lattice)
+    }
 
-        // Request maximum limits, but don't exceed what adapter supports
-        // RTX 4080: 2 GB, lavapipe: 2 GB - 1 byte
-        let limits = wgpu::Limits {
-            max_storage_buffer_binding_size: adapter_limits.max_storage_buffer_binding_size,
-            max_buffer_size: adapter_limits.max_buffer_size,
+    // Watch a device the lattice created for itself, and remember where
+    // to find its adapter again if the device has to be replaced
+    fn adopt_device(&mut self, adapter: &wgpu::Adapter) {
+        let info = adapter.get_info();
+        self.recovery_adapter = AdapterOptions {
+            backends: info.backend.into(),
+            name: Some(info.name.clone()),
             ..Default::default()
         };
+        self.adapter_info = Some(info);
+        events::watch_device(&self.device, &self.events, &self.device_lost);
+    }
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("Quantum Lattice GPU"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: limits,
-                    memory_hints: Default::default(),
-                },
-                None,
-            )
-            .await
-            .expect("Failed to create device");
+    /// Builds the lattice on an existing device, e.g. one shared with a
+    /// renderer.
+    ///
+    /// The device's error and loss callbacks are left to its owner, so
+    /// [`events`](Self::events) stays silent.
+    ///
+    /// wgpu exposes a single queue per device, so when `queue` is shared the
+    /// lattice's compute submissions and the renderer's passes execute in
+    /// submission order. A render pass submitted after
+    /// [`propagate_energy`](Self::propagate_energy) therefore always sees the
+    /// completed step, with no extra synchronization. The flip side is that
+    /// a long step delays the frames queued behind it; to decouple them, give
+    /// the lattice its own device and copy results across on the host.
+    ///
+    /// Fails with [`LatticeError::BufferTooLarge`] if one energy buffer
+    /// would exceed the device's buffer limits, and with
+    /// [`LatticeError::WorkgroupTooLarge`] if the device cannot run
+    /// [`WORKGROUP_SIZE`] workgroups.
+    pub fn new_with_device(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<Self, LatticeError> {
+        Self::build(device, queue, width, height, depth, None)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(device, queue, pipeline_cache))
+    )]
+    fn build(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        width: u32,
+        height: u32,
+        depth: u32,
+        pipeline_cache: Option<PipelineCacheFile>,
+    ) -> Result<Self, LatticeError> {
+        // Fail with a readable error before any buffer or pipeline is created
+        if width == 0 || height == 0 || depth == 0 {
+            return Err(LatticeError::InvalidInput(format!(
+                "lattice dimensions must be nonzero, got {}x{}x{}",
+                width, height, depth
+            )));
+        }
+        let limits = device.limits();
+        check_workgroup_size(WORKGROUP_SIZE, &limits)?;
+        let requested = width as u64 * height as u64 * depth as u64 * 4;
+        let max = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        if requested > max {
+            return Err(LatticeError::BufferTooLarge { requested, max });
+        }
 
         let total_sites = (width * height * depth) as usize;
+        let push_constants = device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+            && limits.max_push_constant_size >= PUSH_CONSTANT_SIZE;
 
         // Create buffers
+        let axis_weights = [WEIGHT_SCALE as u32; 3];
         let params = Params {
             width,
             height,
             depth,
             step_count: 0,
+            weight_x: axis_weights[0],
+            weight_y: axis_weights[1],
+            weight_z: axis_weights[2],
+            boundary_modes: pack_boundary_modes([BoundaryMode::default(); 3]),
+            seed: 0,
+            decay_threshold: 0,
+            capacity_map: 0,
+            z_offset: 0,
+            lattice_depth: depth,
+            _padding: [0; 3],
         };
 
         let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -106,6 +608,25 @@ impl DiscreteLatticeGPU {
             mapped_at_creation: false,
         });
 
+        // Per-site SITE_* flag bits, all clear
+        let site_flags_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Site Flags Buffer"),
+            size: (total_sites * std::mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // External potential per site; zero everywhere leaves transfers
+        // unbiased
+        let potential_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Potential Buffer"),
+            size: (total_sites * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let capacity_buffer = create_capacity_buffer(&device, CAPACITY_PLACEHOLDER_SIZE);
+
         // Staging buffer for reading results back
         let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Staging Buffer"),
@@ -114,12 +635,6 @@ impl DiscreteLatticeGPU {
             mapped_at_creation: false,
         });
 
-        // Load shader
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Compute Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
-
         // Create bind group layout
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Bind Group Layout"),
@@ -154,6 +669,46 @@ impl DiscreteLatticeGPU {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -164,245 +719,2154 @@ impl DiscreteLatticeGPU {
             push_constant_ranges: &[],
         });
 
-        let copy_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Copy Pipeline"),
+        let cache = pipeline_cache.as_ref().map(|file| &file.cache);
+        let rule_source = GradientRule.wgsl().into_owned();
+        let (copy_pipeline, propagate_pipeline, gather_pipeline) = create_propagation_pipelines(
+            &device,
+            &create_propagation_layout(&device, &[&bind_group_layout], push_constants),
+            include_str!("shader.wgsl"),
+            &rule_source,
+            Neighborhood::default(),
+            WORKGROUP_SIZE,
+            push_constants,
+            cache,
+            PROPAGATION_ENTRY_POINTS,
+        );
+
+        let edit_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Edit Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("edit.wgsl").into()),
+        });
+
+        let edit_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Energy Edit Pipeline"),
             layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: "copy_energy",
+            module: &edit_shader,
+            entry_point: "apply_edits",
             compilation_options: Default::default(),
-            cache: None,
+            cache,
         });
 
-        let propagate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Propagate Pipeline"),
+        let drain_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Energy Drain Pipeline"),
             layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: "propagate_energy",
+            module: &edit_shader,
+            entry_point: "drain_edits",
             compilation_options: Default::default(),
-            cache: None,
+            cache,
         });
 
-        Self {
-            device: Arc::new(device),
-            queue: Arc::new(queue),
-            copy_pipeline,
-            propagate_pipeline,
-            bind_group_layout,
-            params_buffer,
-            energy_buffer_a,
-            energy_buffer_b,
-            staging_buffer,
-            width,
-            height,
-            depth,
-            total_sites,
-            step_count: 0,
-        }
-    }
+        let reduce_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Reduce Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("reduce.wgsl").into()),
+        });
 
-    pub fn new_with_device(
-        device: Arc<wgpu::Device>,
-        queue: Arc<wgpu::Queue>,
-        width: u32,
-        height: u32,
-        depth: u32,
-    ) -> Self {
-        let total_sites = (width * height * depth) as usize;
+        let total_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Total Energy Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &reduce_shader,
+            entry_point: "total_energy",
+            compilation_options: Default::default(),
+            cache,
+        });
 
-        // Create buffers
-        let params = Params {
-            width,
-            height,
-            depth,
-            step_count: 0,
-        };
+        let saturated_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Saturated Count Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &reduce_shader,
+            entry_point: "count_saturated",
+            compilation_options: Default::default(),
+            cache,
+        });
 
-        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Params Buffer"),
-            contents: bytemuck::cast_slice(&[params]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        let occupied_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Occupied Count Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &reduce_shader,
+            entry_point: "count_occupied",
+            compilation_options: Default::default(),
+            cache,
         });
 
-        // Energy buffers (ping-pong)
-        let energy_buffer_a = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Energy Buffer A"),
-            size: (total_sites * std::mem::size_of::<u32>()) as u64,
+        let max_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Max Energy Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &reduce_shader,
+            entry_point: "max_energy",
+            compilation_options: Default::default(),
+            cache,
+        });
+
+        let compact_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Compact Occupied Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &reduce_shader,
+            entry_point: "compact_occupied",
+            compilation_options: Default::default(),
+            cache,
+        });
+
+        let bounds_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Occupied Bounds Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &reduce_shader,
+            entry_point: "occupied_bounds",
+            compilation_options: Default::default(),
+            cache,
+        });
+
+        let reduce_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reduce Buffer"),
+            size: REDUCE_BUFFER_SIZE,
             usage: wgpu::BufferUsages::STORAGE
                 | wgpu::BufferUsages::COPY_DST
                 | wgpu::BufferUsages::COPY_SRC,
             mapped_at_creation: false,
         });
 
-        let energy_buffer_b = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Energy Buffer B"),
-            size: (total_sites * std::mem::size_of::<u32>()) as u64,
+        // 64-bit counts of quanta removed at absorbing faces and by decay,
+        // as (lo, hi) each
+        let loss_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Loss Buffer"),
+            size: 2 * COUNTER_BUFFER_SIZE,
             usage: wgpu::BufferUsages::STORAGE
                 | wgpu::BufferUsages::COPY_DST
                 | wgpu::BufferUsages::COPY_SRC,
             mapped_at_creation: false,
         });
 
-        // Staging buffer for reading results back
-        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Staging Buffer"),
-            size: (total_sites * std::mem::size_of::<u32>()) as u64,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        // 64-bit count of quanta added by the injection schedule, as (lo, hi)
+        let injected_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Injected Buffer"),
+            size: COUNTER_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
             mapped_at_creation: false,
         });
 
-        // Load shader
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Compute Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
-
-        // Create bind group layout
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
+        let edit_counter_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Edit Counter Buffer"),
+            size: COUNTER_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
         });
 
-        // Create compute pipelines
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
+        let reduce_staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reduce Staging Buffer"),
+            size: REDUCE_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
-        let copy_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Copy Pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: "copy_energy",
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            bytes = [
+                &energy_buffer_a,
+                &energy_buffer_b,
+                &site_flags_buffer,
+                &potential_buffer,
+                &staging_buffer,
+            ]
+            .iter()
+            .map(|buffer| buffer.size())
+            .sum::<u64>(),
+            "Allocated lattice buffers"
+        );
 
-        let propagate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Propagate Pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: "propagate_energy",
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        let step_bind_groups = create_step_bind_groups(
+            &device,
+            &bind_group_layout,
+            &params_buffer,
+            [&energy_buffer_a, &energy_buffer_b],
+            [
+                &site_flags_buffer,
+                &loss_buffer,
+                &potential_buffer,
+                &capacity_buffer,
+            ],
+        );
 
-        Self {
+        Ok(Self {
             device,
             queue,
             copy_pipeline,
             propagate_pipeline,
+            gather_pipeline,
+            edit_pipeline,
+            drain_pipeline,
             bind_group_layout,
+            push_constants,
+            uploaded_params: params,
+            step_bind_groups,
             params_buffer,
             energy_buffer_a,
             energy_buffer_b,
+            site_flags_buffer,
+            potential_buffer,
+            capacity_buffer,
             staging_buffer,
+            total_pipeline,
+            saturated_pipeline,
+            occupied_pipeline,
+            max_pipeline,
+            compact_pipeline,
+            bounds_pipeline,
+            reduce_buffer,
+            reduce_staging_buffer,
+            loss_buffer,
             width,
             height,
             depth,
             total_sites,
-            step_count: 0,
-        }
+            generation: 0,
+            parity: false,
+            axis_weights,
+            propagation_mode: PropagationMode::default(),
+            neighborhood: Neighborhood::default(),
+            workgroup_size: WORKGROUP_SIZE,
+            z_offset: 0,
+            lattice_depth: depth,
+            rule_source,
+            boundary_modes: [BoundaryMode::default(); 3],
+            seed: 0,
+            decay_threshold: 0,
+            history: VecDeque::new(),
+            history_depth: 0,
+            steady_reference: None,
+            injection: None,
+            injected_buffer,
+            edit_counter_buffer,
+            scheduled: BTreeMap::new(),
+            sources: HashMap::new(),
+            source_buffer: Vec::new(),
+            sinks: HashMap::new(),
+            sink_buffer: Vec::new(),
+            step_callback: None,
+            site_flags: None,
+            potential: None,
+            capacity: None,
+            transfer: Arc::default(),
+            staging_pool: StagingPool::default(),
+            events: events::event_channel(),
+            device_lost: Arc::new(AtomicBool::new(false)),
+            recovery_adapter: AdapterOptions::default(),
+            adapter_info: None,
+            checkpoint: None,
+            pipeline_cache,
+            active_regions: None,
+        })
     }
 
+    /// Empties every site and resets the absorbed-energy count.
     pub fn initialize_vacuum(&mut self) {
         let zero_data = vec![0u32; self.total_sites];
-        self.queue
-            .write_buffer(&self.energy_buffer_a, 0, bytemuck::cast_slice(&zero_data));
-        self.queue
-            .write_buffer(&self.energy_buffer_b, 0, bytemuck::cast_slice(&zero_data));
+        self.upload(&self.energy_buffer_a, bytemuck::cast_slice(&zero_data));
+        self.upload(&self.energy_buffer_b, bytemuck::cast_slice(&zero_data));
+        self.clear_losses();
+        self.history.clear();
+    }
+
+    /// Returns the lattice to the state of a freshly created one: every site
+    /// empty, generation zero, the absorbed, injected and transfer counters
+    /// cleared, and no pulses pending from
+    /// [`schedule_injection`](Self::schedule_injection). Pulses belong to
+    /// the run they were scheduled for, so schedule them again for the next
+    /// one.
+    ///
+    /// Buffers are cleared on the GPU and pipelines are kept, so this is
+    /// much cheaper than building a new lattice. Configuration stays as it
+    /// is: propagation and boundary modes, axis weights, frozen sites,
+    /// obstacles, sources and sinks, and any injection schedule or step
+    /// callback.
+    pub fn reset(&mut self) {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.clear_buffer(&self.energy_buffer_a, 0, None);
+        encoder.clear_buffer(&self.energy_buffer_b, 0, None);
+        encoder.clear_buffer(&self.loss_buffer, 0, None);
+        encoder.clear_buffer(&self.injected_buffer, 0, None);
+        self.queue.submit(Some(encoder.finish()));
+
+        self.generation = 0;
+        self.parity = false;
+        self.steady_reference = None;
+        self.history.clear();
+        self.scheduled.clear();
+        self.transfer.reset();
     }
 
     pub fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
-        let idx = (z * self.width * self.height + y * self.width + x) as usize;
+        self.add_energy_batch(&[(x, y, z, quanta)]);
+    }
 
-        // Read current state
-        let mut energy_data = vec![0u32; self.total_sites];
-        pollster::block_on(async {
-            let mut encoder = self.device.create_command_encoder(&Default::default());
-            encoder.copy_buffer_to_buffer(
-                &self.energy_buffer_a,
-                0,
-                &self.staging_buffer,
-                0,
+    /// Fills a solid sphere with `quanta` per site, skipping any part of the
+    /// sphere that falls outside the lattice.
+    pub fn seed_sphere(&mut self, center: (u32, u32, u32), radius: u32, quanta: u32) {
+        let points = sphere_points(
+            center,
+            radius,
+            quanta,
+            (self.width, self.height, self.depth),
+        );
+        self.add_energy_batch(&points);
+    }
+
+    /// Adds `quanta` to every site on the straight line from `from` to `to`,
+    /// skipping any part of the line that falls outside the lattice.
+    ///
+    /// See [`line_points`] for how the line is rasterized.
+    pub fn add_energy_line(&mut self, from: (u32, u32, u32), to: (u32, u32, u32), quanta: u32) {
+        let points = line_points(from, to, quanta, (self.width, self.height, self.depth));
+        self.add_energy_batch(&points);
+    }
+
+    /// Adds quanta to many sites at once, skipping points outside the
+    /// lattice. Sites are capped at [`MAX_LEVEL`].
+    ///
+    /// The edits are applied by a compute kernel, so only the edit list is
+    /// uploaded and nothing is read back, however large the lattice. Use
+    /// this rather than repeated
+    /// [`add_energy_quantum`](Self::add_energy_quantum) calls when seeding
+    /// many sites.
+    pub fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        self.apply_energy_edits(edits, &self.edit_counter_buffer);
+    }
+
+    /// Adds the quanta listed in a CSV file of `x,y,z,quanta` points, as
+    /// [`add_energy_batch`](Self::add_energy_batch) does.
+    ///
+    /// Blank lines, lines starting with `#` and a header line are ignored.
+    /// Fails with [`LatticeError::InvalidInput`], adding nothing, if a line
+    /// is malformed or names a site outside the lattice.
+    pub fn add_energy_csv(&mut self, path: &Path) -> Result<(), LatticeError> {
+        let text = std::fs::read_to_string(path)?;
+        let points = import::parse_points_csv(&text, (self.width, self.height, self.depth))
+            .map_err(|msg| LatticeError::InvalidInput(format!("{}: {}", path.display(), msg)))?;
+        self.add_energy_batch(&points);
+        Ok(())
+    }
+
+    // Add quanta to the given sites on the GPU, skipping points outside the
+    // lattice. The quanta actually added after capping at MAX_LEVEL are
+    // accumulated into the 64-bit `counter`
+    fn apply_energy_edits(&self, edits: &[(u32, u32, u32, u32)], counter: &wgpu::Buffer) {
+        // Merge edits of the same site so the kernel never sees a site twice
+        let mut pairs: Vec<u32> = Vec::with_capacity(2 * edits.len());
+        let mut slots: HashMap<u32, usize> = HashMap::new();
+        for (idx, quanta) in self.site_edits(edits) {
+            match slots.entry(idx) {
+                Entry::Occupied(slot) => {
+                    let quanta_slot = &mut pairs[*slot.get() + 1];
+                    *quanta_slot = quanta_slot.saturating_add(quanta);
+                }
+                Entry::Vacant(slot) => {
+                    slot.insert(pairs.len());
+                    pairs.extend([idx, quanta]);
+                }
+            }
+        }
+
+        for chunk in pairs.chunks(2 * MAX_EDITS_PER_DISPATCH) {
+            let edit_buffer = self.create_edit_buffer(chunk);
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            self.encode_edit_pass(
+                &mut encoder,
+                &self.edit_pipeline,
+                &edit_buffer,
+                self.get_energy_buffer(),
+                counter,
+            );
+            self.queue.submit(Some(encoder.finish()));
+        }
+    }
+
+    // Upload (index, quanta) pairs into a new buffer for the edit kernels
+    fn create_edit_buffer(&self, pairs: &[u32]) -> wgpu::Buffer {
+        let edit_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Edit Buffer"),
+            size: std::mem::size_of_val(pairs) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.upload(&edit_buffer, bytemuck::cast_slice(pairs));
+        edit_buffer
+    }
+
+    // Record an edit.wgsl pass applying `edits` to `energy` and counting the
+    // quanta moved into `counter`
+    fn encode_edit_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        edits: &wgpu::Buffer,
+        energy: &wgpu::Buffer,
+        counter: &wgpu::Buffer,
+    ) {
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Edit Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: edits.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: energy.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.site_flags_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: counter.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: self.potential_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: self.capacity_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Energy Edit Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        let edit_count = (edits.size() / 8) as u32;
+        compute_pass.dispatch_workgroups(edit_count.div_ceil(64), 1, 1);
+    }
+
+    /// Registers sites that gain quanta every step, as `(x, y, z, quanta)`.
+    ///
+    /// Before each step's transfers a GPU pass adds each source's quanta,
+    /// capped at [`MAX_LEVEL`], and counts what was added in
+    /// [`injected_energy`](Self::injected_energy). Sources accumulate
+    /// across calls, and listing a site again adds to its rate. Sites
+    /// outside the lattice are skipped.
+    pub fn add_sources(&mut self, sources: &[(u32, u32, u32, u32)]) {
+        for (idx, quanta) in self.site_edits(sources) {
+            let rate = self.sources.entry(idx).or_insert(0);
+            *rate = rate.saturating_add(quanta);
+        }
+        self.source_buffer = self.edit_list_buffer(&self.sources);
+    }
+
+    /// Registers sites that lose up to `quanta` every step, as
+    /// `(x, y, z, quanta)`.
+    ///
+    /// The drain runs right after the sources, before the step's
+    /// transfers, and the removed quanta are counted in
+    /// [`absorbed_energy`](Self::absorbed_energy), so
+    /// `get_total_energy() + absorbed_energy() - injected_energy()` stays
+    /// constant. Sinks accumulate like sources.
+    pub fn add_sinks(&mut self, sinks: &[(u32, u32, u32, u32)]) {
+        for (idx, quanta) in self.site_edits(sinks) {
+            let rate = self.sinks.entry(idx).or_insert(0);
+            *rate = rate.saturating_add(quanta);
+        }
+        self.sink_buffer = self.edit_list_buffer(&self.sinks);
+    }
+
+    /// Removes every source and sink. Quanta already moved stay counted.
+    pub fn clear_sources_and_sinks(&mut self) {
+        self.sources.clear();
+        self.sinks.clear();
+        self.source_buffer.clear();
+        self.sink_buffer.clear();
+    }
+
+    // Site indices and quanta of the in-bounds points
+    fn site_edits(&self, points: &[(u32, u32, u32, u32)]) -> Vec<(u32, u32)> {
+        points
+            .iter()
+            .filter(|&&(x, y, z, _)| x < self.width && y < self.height && z < self.depth)
+            .map(|&(x, y, z, quanta)| (z * self.width * self.height + y * self.width + x, quanta))
+            .collect()
+    }
+
+    // GPU copy of a source or sink list, one buffer per edit pass, so a
+    // list of any length dispatches within the workgroup limit
+    fn edit_list_buffer(&self, list: &HashMap<u32, u32>) -> Vec<wgpu::Buffer> {
+        let mut pairs: Vec<(u32, u32)> = list.iter().map(|(&idx, &quanta)| (idx, quanta)).collect();
+        pairs.sort_unstable();
+        let pairs: Vec<u32> = pairs
+            .into_iter()
+            .flat_map(|(idx, quanta)| [idx, quanta])
+            .collect();
+        pairs
+            .chunks(2 * MAX_EDITS_PER_DISPATCH)
+            .map(|chunk| self.create_edit_buffer(chunk))
+            .collect()
+    }
+
+    // Record the source and sink passes ahead of the step that reads the
+    // buffer selected by `parity`
+    fn encode_sources_and_sinks(&self, encoder: &mut wgpu::CommandEncoder, parity: bool) {
+        let energy = if parity {
+            &self.energy_buffer_b
+        } else {
+            &self.energy_buffer_a
+        };
+        for sources in &self.source_buffer {
+            self.encode_edit_pass(
+                encoder,
+                &self.edit_pipeline,
+                sources,
+                energy,
+                &self.injected_buffer,
+            );
+        }
+        for sinks in &self.sink_buffer {
+            self.encode_edit_pass(
+                encoder,
+                &self.drain_pipeline,
+                sinks,
+                energy,
+                &self.loss_buffer,
+            );
+        }
+    }
+
+    /// Installs a schedule that injects energy before every propagation
+    /// step, replacing any previous one.
+    ///
+    /// Before each step `f` is called with the generation about to run and
+    /// returns the `(x, y, z, quanta)` points to add. Points outside the
+    /// lattice are skipped and sites are capped at [`MAX_LEVEL`]. From
+    /// generation `u32::MAX` on, `f` is passed `u32::MAX` rather than a
+    /// wrapped value.
+    ///
+    /// Injection makes the lattice an open system, so the total energy is no
+    /// longer conserved. The quanta actually added are tracked by
+    /// [`injected_energy`](Self::injected_energy), and the total minus that
+    /// stays constant.
+    pub fn set_injection<F>(&mut self, f: F)
+    where
+        F: FnMut(u32) -> Vec<(u32, u32, u32, u32)> + Send + 'static,
+    {
+        self.injection = Some(Mutex::new(Box::new(f)));
+    }
+
+    /// Removes the injection schedule. Already injected energy stays.
+    pub fn clear_injection(&mut self) {
+        self.injection = None;
+    }
+
+    /// Schedules a one-off pulse: `quanta` are added to `(x, y, z)`, capped
+    /// at [`MAX_LEVEL`], just before the step that runs at generation
+    /// `step`, at the same point as the injection schedule.
+    ///
+    /// Pending events are uploaded and applied on the GPU as part of the
+    /// step, so a long [`propagate_n`](Self::propagate_n) fires them
+    /// without any host round-trip. Added quanta are counted by
+    /// [`injected_energy`](Self::injected_energy). Events for the same step
+    /// and site add up; points outside the lattice are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is before the current generation.
+    pub fn schedule_injection(&mut self, step: u64, x: u32, y: u32, z: u32, quanta: u32) {
+        assert!(
+            step >= self.generation,
+            "Cannot schedule an injection at step {} before generation {}",
+            step,
+            self.generation
+        );
+        for (idx, quanta) in self.site_edits(&[(x, y, z, quanta)]) {
+            let events = self.scheduled.entry(step).or_default();
+            let pending = events.entry(idx).or_insert(0);
+            *pending = pending.saturating_add(quanta);
+        }
+    }
+
+    /// Drops every scheduled injection that has not fired yet.
+    pub fn clear_scheduled_injections(&mut self) {
+        self.scheduled.clear();
+    }
+
+    // Record the scheduled injections for the step at `generation`, which
+    // reads the buffer selected by `parity`, and drop them from the schedule
+    fn encode_scheduled(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        parity: bool,
+        generation: u64,
+    ) {
+        let Some(events) = self.scheduled.remove(&generation) else {
+            return;
+        };
+        let mut pairs: Vec<(u32, u32)> = events.into_iter().collect();
+        pairs.sort_unstable();
+        let pairs: Vec<u32> = pairs
+            .into_iter()
+            .flat_map(|(idx, quanta)| [idx, quanta])
+            .collect();
+
+        let energy = if parity {
+            &self.energy_buffer_b
+        } else {
+            &self.energy_buffer_a
+        };
+        for chunk in pairs.chunks(2 * MAX_EDITS_PER_DISPATCH) {
+            let edit_buffer = self.create_edit_buffer(chunk);
+            self.encode_edit_pass(
+                encoder,
+                &self.edit_pipeline,
+                &edit_buffer,
+                energy,
+                &self.injected_buffer,
+            );
+        }
+    }
+
+    /// Total quanta added by the injection schedule, by
+    /// [scheduled injections](Self::schedule_injection) and by
+    /// [sources](Self::add_sources) so far.
+    ///
+    /// Counted on the GPU as edits are applied; calling this reads back the
+    /// count.
+    pub async fn injected_energy(&self) -> u64 {
+        let result = self
+            .read_staged(
+                &self.injected_buffer,
+                &self.reduce_staging_buffer,
+                COUNTER_BUFFER_SIZE,
+            )
+            .await;
+        result[0] as u64 | (result[1] as u64) << 32
+    }
+
+    /// Registers a callback run at the end of every
+    /// [`propagate_energy`](Self::propagate_energy), replacing any previous
+    /// one.
+    ///
+    /// It receives the buffer now holding the current state (the same one
+    /// [`get_energy_buffer`](Self::get_energy_buffer) returns) and the new
+    /// generation. The step's commands have been submitted but not
+    /// necessarily executed, so the callback should record GPU work that
+    /// reads the buffer rather than block on a readback, which would stall
+    /// every step.
+    pub fn on_step<F>(&mut self, f: F)
+    where
+        F: FnMut(&wgpu::Buffer, u64) + Send + 'static,
+    {
+        self.step_callback = Some(Mutex::new(Box::new(f)));
+    }
+
+    /// Removes the callback registered with [`on_step`](Self::on_step).
+    pub fn clear_on_step(&mut self) {
+        self.step_callback = None;
+    }
+
+    // Run the injection schedule for the step about to be taken
+    fn inject(&mut self) {
+        let Some(injection) = self.injection.as_mut() else {
+            return;
+        };
+        let schedule = injection.get_mut().expect("Injection mutex poisoned");
+        let points = schedule(u32::try_from(self.generation).unwrap_or(u32::MAX));
+        self.apply_energy_edits(&points, &self.injected_buffer);
+    }
+
+    /// Freezes the given sites, in addition to any already frozen.
+    ///
+    /// A frozen site keeps its energy: it never hands a quantum on and its
+    /// neighbors never pick it as a target, so it acts as a wall or a fixed
+    /// value boundary. The energy on unfrozen sites is still conserved; see
+    /// [`frozen_energy`](Self::frozen_energy). Sites outside the lattice are
+    /// skipped.
+    pub fn set_frozen(&mut self, sites: &[(u32, u32, u32)]) {
+        let total_sites = self.total_sites;
+        let flags = self.site_flags.get_or_insert_with(|| vec![0; total_sites]);
+        for &(x, y, z) in sites {
+            if x < self.width && y < self.height && z < self.depth {
+                let idx = (z * self.width * self.height + y * self.width + x) as usize;
+                flags[idx] |= SITE_FROZEN;
+            }
+        }
+        self.upload_site_flags();
+    }
+
+    /// Unfreezes every site.
+    pub fn clear_frozen(&mut self) {
+        if let Some(flags) = self.site_flags.as_mut() {
+            for flag in flags.iter_mut() {
+                *flag &= !SITE_FROZEN;
+            }
+            self.upload_site_flags();
+        }
+    }
+
+    /// Marks the sites where `mask` is `true` as obstacles, replacing any
+    /// previous obstacles. `mask` uses the [`get_state`](Self::get_state)
+    /// layout.
+    ///
+    /// An obstacle is an impermeable wall: a quantum aimed at it bounces
+    /// back to the neighbor on the opposite side instead, or stays put if
+    /// that side is blocked too. Energy already on an obstacle site stays
+    /// there, so seed obstacles into vacuum to keep all energy mobile. Energy
+    /// is conserved either way.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mask` does not hold one value per site.
+    pub fn set_obstacles(&mut self, mask: &[bool]) {
+        assert_eq!(
+            mask.len(),
+            self.total_sites,
+            "Obstacle mask must have one value per site"
+        );
+        let total_sites = self.total_sites;
+        let flags = self.site_flags.get_or_insert_with(|| vec![0; total_sites]);
+        for (flag, &blocked) in flags.iter_mut().zip(mask) {
+            if blocked {
+                *flag |= SITE_OBSTACLE;
+            } else {
+                *flag &= !SITE_OBSTACLE;
+            }
+        }
+        self.upload_site_flags();
+    }
+
+    /// Removes every obstacle.
+    pub fn clear_obstacles(&mut self) {
+        if let Some(flags) = self.site_flags.as_mut() {
+            for flag in flags.iter_mut() {
+                *flag &= !SITE_OBSTACLE;
+            }
+            self.upload_site_flags();
+        }
+    }
+
+    fn upload_site_flags(&self) {
+        if let Some(flags) = self.site_flags.as_ref() {
+            self.upload(&self.site_flags_buffer, bytemuck::cast_slice(flags));
+        }
+    }
+
+    /// Total energy held on frozen sites. It never changes while the frozen
+    /// set stays the same, so `get_total_energy() - frozen_energy()` is the
+    /// conserved mobile energy.
+    pub async fn frozen_energy(&self) -> u64 {
+        let Some(flags) = self.site_flags.as_ref() else {
+            return 0;
+        };
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        energy_data
+            .iter()
+            .zip(flags)
+            .filter(|(_, &flag)| flag & SITE_FROZEN != 0)
+            .map(|(&e, _)| e as u64)
+            .sum()
+    }
+
+    /// Uploads an external potential, one value per site in the
+    /// [`get_state`](Self::get_state) layout, replacing any previous one.
+    ///
+    /// The potential biases which lower-energy neighbor receives a quantum:
+    /// each candidate's axis weight is scaled by `exp(V_site - V_neighbor)`,
+    /// so energy tends to flow downhill into wells and away from barriers.
+    /// Differences beyond ±8 are clamped. A quantum still only moves to a
+    /// neighbor with less energy, so the rule stays conservative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `potential` does not hold one value per site or contains a
+    /// value that is not finite.
+    pub fn set_potential(&mut self, potential: &[f32]) {
+        assert_eq!(
+            potential.len(),
+            self.total_sites,
+            "Potential must have one value per site"
+        );
+        assert!(
+            potential.iter().all(|v| v.is_finite()),
+            "Potential values must be finite"
+        );
+        self.upload(&self.potential_buffer, bytemuck::cast_slice(potential));
+        self.potential = Some(potential.to_vec());
+    }
+
+    /// Gives each site its own maximum energy, one value per site in the
+    /// [`get_state`](Self::get_state) layout, replacing the global cap of
+    /// [`MAX_LEVEL`] everywhere. Takes effect on the next step.
+    ///
+    /// Transfers never push a site past its capacity, and seeding, sources
+    /// and injection cap at it. Capacities may exceed [`MAX_LEVEL`], which
+    /// lets dense regions hold more than the rest of the lattice. Energy
+    /// already above a site's new capacity stays until it flows away.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` does not hold one value per site.
+    pub fn set_capacity(&mut self, capacity: &[u32]) {
+        assert_eq!(
+            capacity.len(),
+            self.total_sites,
+            "Capacity map must have one value per site"
+        );
+        if self.capacity.is_none() {
+            self.capacity_buffer = create_capacity_buffer(
+                &self.device,
                 (self.total_sites * std::mem::size_of::<u32>()) as u64,
             );
+            self.rebuild_step_bind_groups();
+        }
+        self.upload(&self.capacity_buffer, bytemuck::cast_slice(capacity));
+        self.capacity = Some(capacity.to_vec());
+
+        // Edits read the flag from the params of the last step
+        self.write_params(self.step_params(self.generation));
+    }
+
+    /// Removes the capacity map, restoring the global cap of [`MAX_LEVEL`]
+    /// and freeing the map's buffer.
+    pub fn clear_capacity(&mut self) {
+        if self.capacity.take().is_some() {
+            self.capacity_buffer = create_capacity_buffer(&self.device, CAPACITY_PLACEHOLDER_SIZE);
+            self.rebuild_step_bind_groups();
+            self.write_params(self.step_params(self.generation));
+        }
+    }
+
+    /// Removes the potential, so transfers are unbiased again.
+    pub fn clear_potential(&mut self) {
+        if self.potential.take().is_some() {
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            encoder.clear_buffer(&self.potential_buffer, 0, None);
+            self.queue.submit(Some(encoder.finish()));
+        }
+    }
+
+    /// Sets how strongly transfers favor each axis.
+    ///
+    /// When a site has several lower-energy neighbors, the one receiving the
+    /// quantum is picked with probability proportional to the weight of the
+    /// axis it lies on. Only the ratios matter; equal weights (the default)
+    /// give isotropic propagation and a zero weight blocks an axis entirely.
+    /// Every transfer still moves exactly one quantum, so energy is conserved
+    /// for any weights.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite.
+    pub fn set_axis_weights(&mut self, wx: f32, wy: f32, wz: f32) {
+        self.axis_weights = fixed_point_weights([wx, wy, wz]);
+    }
+
+    pub fn set_propagation_mode(&mut self, mode: PropagationMode) {
+        self.propagation_mode = mode;
+    }
+
+    /// Sets which neighbors take part in transfers. Takes effect on the next
+    /// step; the propagation pipelines are rebuilt when the neighborhood
+    /// changes.
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        if neighborhood == self.neighborhood {
+            return;
+        }
+        self.neighborhood = neighborhood;
+        self.recompile_propagation_pipelines();
+    }
+
+    /// Current workgroup size of the propagation passes.
+    pub fn workgroup_size(&self) -> [u32; 3] {
+        self.workgroup_size
+    }
+
+    /// Sets the workgroup size of the copy, scatter and gather passes,
+    /// rebuilding their pipelines. Takes effect on the next step.
+    ///
+    /// The size only affects speed, never results. The default,
+    /// [`WORKGROUP_SIZE`], leaves most desktop GPUs underused;
+    /// [`tune_workgroup_size`](Self::tune_workgroup_size) picks one by
+    /// measurement. Fails with [`LatticeError::WorkgroupTooLarge`], keeping
+    /// the current size, if the device cannot run `size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any dimension is zero.
+    pub fn set_workgroup_size(&mut self, size: [u32; 3]) -> Result<(), LatticeError> {
+        assert!(
+            size.iter().all(|&s| s > 0),
+            "Workgroup dimensions must be nonzero"
+        );
+        check_workgroup_size(size, &self.device.limits())?;
+        if size != self.workgroup_size {
+            self.workgroup_size = size;
+            self.recompile_propagation_pipelines();
+        }
+        Ok(())
+    }
+
+    /// Turns active-region tracking on or off. Takes effect on the next
+    /// step.
+    ///
+    /// With tracking on, each step first lists the blocks of sites, one
+    /// propagation workgroup each, that hold energy or border a block that
+    /// does, and propagation is dispatched over those blocks alone. A mostly
+    /// empty lattice then steps in a fraction of the time, at the cost of a
+    /// cheap extra pass over the state; a lattice with energy everywhere
+    /// gains nothing. Results are the same bit for bit either way. Off by
+    /// default.
+    pub fn set_active_region_tracking(&mut self, enabled: bool) {
+        if enabled == self.active_regions.is_some() {
+            return;
+        }
+        self.active_regions =
+            enabled.then(|| ActiveRegions::new(self, include_str!("shader.wgsl")));
+    }
+
+    /// Whether active-region tracking is on.
+    pub fn active_region_tracking(&self) -> bool {
+        self.active_regions.is_some()
+    }
+
+    /// Number of blocks the last step dispatched with active-region
+    /// tracking on, out of one per workgroup of
+    /// [`workgroup_size`](Self::workgroup_size) needed to cover the lattice.
+    /// `None` if tracking is off; 0 if no step has run since it was turned
+    /// on or the workgroup size changed.
+    pub async fn active_block_count(&self) -> Option<u32> {
+        let active = self.active_regions.as_ref()?;
+        let count = self
+            .read_staged(&active.active_blocks, &self.reduce_staging_buffer, 4)
+            .await;
+        Some(count[0])
+    }
+
+    /// Times `steps` steps with each of [`WORKGROUP_SIZE_CANDIDATES`] the
+    /// device can run, switches to the fastest and returns it.
+    ///
+    /// The steps run on this lattice's current state, so tune after seeding
+    /// for representative timings. The state is then put back from a
+    /// [`snapshot`](Self::snapshot), which needs memory for one extra copy
+    /// of the lattice; as with [`restore`](Self::restore), the
+    /// absorbed-energy count starts again from zero.
+    pub async fn tune_workgroup_size(&mut self, steps: u32) -> [u32; 3] {
+        let snapshot = self.snapshot();
+        let limits = self.device.limits();
+        let mut fastest = (self.workgroup_size, Duration::MAX);
+        for size in WORKGROUP_SIZE_CANDIDATES {
+            if check_workgroup_size(size, &limits).is_err() {
+                continue;
+            }
+            self.set_workgroup_size(size)
+                .expect("Candidate was checked against the device limits");
+            // One warmup step so pipeline setup isn't timed
+            let timing = self.timed_run(steps, 1).await;
+            self.restore(&snapshot);
+            if timing.elapsed < fastest.1 {
+                fastest = (size, timing.elapsed);
+            }
+        }
+
+        self.set_workgroup_size(fastest.0)
+            .expect("Candidate was checked against the device limits");
+        fastest.0
+    }
+
+    // Rebuild the propagation pipelines after a change to the neighborhood
+    // or workgroup size. The rule already compiled with the previous
+    // settings, so this is built without an error scope; a failure goes to
+    // the device's uncaptured error callback, which for a lattice-owned
+    // device logs it and queues it on events()
+    fn recompile_propagation_pipelines(&mut self) {
+        let pipeline_layout = self.propagation_pipeline_layout();
+        (
+            self.copy_pipeline,
+            self.propagate_pipeline,
+            self.gather_pipeline,
+        ) = create_propagation_pipelines(
+            &self.device,
+            &pipeline_layout,
+            include_str!("shader.wgsl"),
+            &self.rule_source,
+            self.neighborhood,
+            self.workgroup_size,
+            self.push_constants,
+            self.pipeline_cache.as_ref().map(|file| &file.cache),
+            PROPAGATION_ENTRY_POINTS,
+        );
+        if self.active_regions.is_some() {
+            self.active_regions = Some(ActiveRegions::new(self, include_str!("shader.wgsl")));
+        }
+    }
+
+    /// Replaces the transfer rule, recompiling the propagation pipelines.
+    /// Takes effect on the next step.
+    ///
+    /// The default is [`GradientRule`]. On a compile error the previous rule
+    /// and pipelines stay in place.
+    pub async fn set_rule(&mut self, rule: &dyn PropagationRule) -> Result<(), LatticeError> {
+        let previous = std::mem::replace(&mut self.rule_source, rule.wgsl().into_owned());
+        let result = self
+            .rebuild_propagation_pipelines(include_str!("shader.wgsl"))
+            .await;
+        if result.is_err() {
+            self.rule_source = previous;
+        }
+        result
+    }
+
+    // Compile `source` in place of shader.wgsl, with the current rule and
+    // neighborhood, and swap in the new pipelines. On error the previous
+    // pipelines stay in place.
+    async fn rebuild_propagation_pipelines(&mut self, source: &str) -> Result<(), LatticeError> {
+        let pipeline_layout = self.propagation_pipeline_layout();
+
+        // Catch compile errors instead of letting wgpu's default handler panic
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipelines = create_propagation_pipelines(
+            &self.device,
+            &pipeline_layout,
+            source,
+            &self.rule_source,
+            self.neighborhood,
+            self.workgroup_size,
+            self.push_constants,
+            self.pipeline_cache.as_ref().map(|file| &file.cache),
+            PROPAGATION_ENTRY_POINTS,
+        );
+        let active_regions = self
+            .active_regions
+            .is_some()
+            .then(|| ActiveRegions::new(self, source));
+        if let Some(err) = self.device.pop_error_scope().await {
+            return Err(LatticeError::ShaderCompile(err.to_string()));
+        }
+
+        (
+            self.copy_pipeline,
+            self.propagate_pipeline,
+            self.gather_pipeline,
+        ) = pipelines;
+        if active_regions.is_some() {
+            self.active_regions = active_regions;
+        }
+        Ok(())
+    }
+
+    fn propagation_pipeline_layout(&self) -> wgpu::PipelineLayout {
+        create_propagation_layout(
+            &self.device,
+            &[&self.bind_group_layout],
+            self.push_constants,
+        )
+    }
+
+    /// Sets the seed of the transfer RNG. Takes effect on the next step.
+    ///
+    /// Each transfer choice is drawn from a PCG hash of the site index, the
+    /// generation and this seed, so two lattices with the same state, seed
+    /// and settings follow the same run on the same backend, while
+    /// different seeds give independent runs. The default seed is 0. The
+    /// seed is configuration rather than state: it is not part of
+    /// [`LatticeState`] or snapshots.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+
+    /// Seed of the transfer RNG.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Sets the probability that an occupied site loses one quantum each
+    /// step. Takes effect on the next step; 0 (the default) disables decay.
+    ///
+    /// A decaying site drops its quantum instead of transferring one that
+    /// step, so decay and propagation never compete for the same quantum.
+    /// Frozen sites and obstacles don't decay. The removed quanta are
+    /// counted by [`decayed_energy`](Self::decayed_energy).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is outside `0.0..=1.0`.
+    pub fn set_decay_rate(&mut self, rate: f32) {
+        self.decay_threshold = decay_threshold(rate);
+    }
+
+    /// Sets how quanta behave at every lattice face. Takes effect on the
+    /// next step.
+    pub fn set_boundary_mode(&mut self, mode: BoundaryMode) {
+        self.boundary_modes = [mode; 3];
+    }
+
+    /// Sets the boundary mode of each axis separately, e.g. periodic X and Y
+    /// with reflective Z. Both faces of an axis share its mode.
+    pub fn set_axis_boundary_modes(&mut self, x: BoundaryMode, y: BoundaryMode, z: BoundaryMode) {
+        self.boundary_modes = [x, y, z];
+    }
+
+    /// Recompiles `src/shader.wgsl` from disk and swaps in the new
+    /// propagation pipelines, keeping the current state.
+    ///
+    /// Lets the step shader be edited while a viewer is running; the current
+    /// transfer rule is appended to it as usual. On error the previous
+    /// pipelines stay in place.
+    #[cfg(feature = "dev-shader-reload")]
+    pub async fn reload_shader(&mut self) -> Result<(), LatticeError> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/shader.wgsl");
+        let source = std::fs::read_to_string(&path).map_err(|err| {
+            LatticeError::ShaderCompile(format!("failed to read {}: {}", path.display(), err))
+        })?;
+        self.reload_shader_source(&source).await
+    }
+
+    /// Like [`reload_shader`](Self::reload_shader), but compiles `source`
+    /// instead of reading the file. The current transfer rule is appended
+    /// as usual.
+    #[cfg(feature = "dev-shader-reload")]
+    pub async fn reload_shader_source(&mut self, source: &str) -> Result<(), LatticeError> {
+        self.rebuild_propagation_pipelines(source).await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(generation = self.generation))
+    )]
+    pub fn propagate_energy(&mut self) {
+        self.step(None);
+    }
+
+    // One step in its own submission; `timestamps` brackets its passes
+    fn step(&mut self, timestamps: Option<&wgpu::QuerySet>) {
+        if self.history_depth > 0 {
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            self.encode_history(&mut encoder, self.parity, self.generation);
+            self.queue.submit(Some(encoder.finish()));
+        }
+        self.inject();
+
+        // Update step count
+        self.write_params(self.step_params(self.generation));
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        self.encode_scheduled(&mut encoder, self.parity, self.generation);
+        self.encode_sources_and_sinks(&mut encoder, self.parity);
+        self.encode_step(&mut encoder, self.parity, self.generation, timestamps);
+        self.queue.submit(Some(encoder.finish()));
+
+        self.advance_step();
+    }
+
+    /// Propagates one step like [`propagate_energy`](Self::propagate_energy)
+    /// and reports the GPU time of each of its compute passes.
+    ///
+    /// Timestamps are written at the start and end of each pass, so the
+    /// times exclude submission and scheduling overhead that
+    /// [`timed_run`](Self::timed_run) includes. In scatter mode the copy and
+    /// propagate dispatches get a compute pass each so they can be timed
+    /// apart. Blocks until the step has finished. Returns `None`, without
+    /// stepping, if the device was created without
+    /// `wgpu::Features::TIMESTAMP_QUERY`.
+    #[cfg(feature = "profiling")]
+    pub async fn profile_step(&mut self) -> Option<StepTimings> {
+        if !self
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return None;
+        }
+
+        // A begin and end timestamp per pass
+        let count = match self.propagation_mode {
+            PropagationMode::Gather => 2,
+            PropagationMode::Scatter => 4,
+        };
+        let query_set = self.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Step Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count,
+        });
+        let size = count as u64 * std::mem::size_of::<u64>() as u64;
+        let resolve = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        self.step(Some(&query_set));
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.resolve_query_set(&query_set, 0..count, &resolve, 0);
+        self.queue.submit(Some(encoder.finish()));
+        let words = self.read_staged(&resolve, &staging, size).await;
+
+        // Timestamps are little-endian u64 ticks of get_timestamp_period ns
+        let period = self.queue.get_timestamp_period() as f64;
+        let pass_time = |pass: usize| {
+            let tick = |query: usize| words[2 * query] as u64 | (words[2 * query + 1] as u64) << 32;
+            let ticks = tick(2 * pass + 1).saturating_sub(tick(2 * pass));
+            Duration::from_nanos((ticks as f64 * period) as u64)
+        };
+        Some(match self.propagation_mode {
+            PropagationMode::Gather => StepTimings {
+                copy: None,
+                propagate: pass_time(0),
+            },
+            PropagationMode::Scatter => StepTimings {
+                copy: Some(pass_time(0)),
+                propagate: pass_time(1),
+            },
+        })
+    }
+
+    /// Propagates `steps` steps with a single command submission.
+    ///
+    /// Produces exactly the same state as calling
+    /// [`propagate_energy`](Self::propagate_energy) `steps` times, but all
+    /// steps are encoded into one submission, so small lattices are not
+    /// limited by per-submit overhead. Each step's number is set as a push
+    /// constant or, on devices without them, copied into the params
+    /// uniform from a buffer uploaded once per batch. With an injection schedule or step callback
+    /// installed, which need the host between steps, this falls back to
+    /// one submission per step.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(generation = self.generation))
+    )]
+    pub fn propagate_n(&mut self, steps: u32) {
+        if self.injection.is_some() || self.step_callback.is_some() {
+            for _ in 0..steps {
+                self.propagate_energy();
+            }
+            return;
+        }
+        if steps == 0 {
+            return;
+        }
+        if self.push_constants {
+            self.write_params(self.step_params(self.generation));
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            let mut parity = self.parity;
+            for generation in self.generation..self.generation + steps as u64 {
+                self.encode_history(&mut encoder, parity, generation);
+                self.encode_scheduled(&mut encoder, parity, generation);
+                self.encode_sources_and_sinks(&mut encoder, parity);
+                self.encode_step(&mut encoder, parity, generation, None);
+                parity = !parity;
+            }
             self.queue.submit(Some(encoder.finish()));
 
-            let buffer_slice = self.staging_buffer.slice(..);
-            let (sender, receiver) = flume::bounded(1);
-            buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-                sender.send(result).unwrap();
-            });
-            self.device.poll(wgpu::Maintain::Wait);
-            receiver.recv_async().await.unwrap().unwrap();
+            self.generation += steps as u64;
+            self.parity = parity;
+            return;
+        }
+
+        let params: Vec<Params> = (0..steps as u64)
+            .map(|i| self.step_params(self.generation + i))
+            .collect();
+        let params_size = std::mem::size_of::<Params>() as u64;
+        let batch_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Batch Params Buffer"),
+            size: params_size * steps as u64,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.upload(&batch_buffer, bytemuck::cast_slice(&params));
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let mut parity = self.parity;
+        for i in 0..steps as u64 {
+            encoder.copy_buffer_to_buffer(
+                &batch_buffer,
+                i * params_size,
+                &self.params_buffer,
+                0,
+                params_size,
+            );
+            self.encode_history(&mut encoder, parity, self.generation + i);
+            self.encode_scheduled(&mut encoder, parity, self.generation + i);
+            self.encode_sources_and_sinks(&mut encoder, parity);
+            self.encode_step(&mut encoder, parity, self.generation + i, None);
+            parity = !parity;
+        }
+        self.queue.submit(Some(encoder.finish()));
+        self.uploaded_params = *params.last().unwrap();
+
+        self.generation += steps as u64;
+        self.parity = parity;
+    }
+
+    /// Keeps the state before each of the last `steps` steps on the GPU, so
+    /// that [`propagate_backward`](Self::propagate_backward) can undo them.
+    /// 0 (the default) keeps no history and frees any recorded.
+    ///
+    /// Each recorded step costs one lattice-sized buffer and a
+    /// buffer-to-buffer copy per step.
+    pub fn set_history_depth(&mut self, steps: usize) {
+        self.history_depth = steps;
+        while self.history.len() > steps {
+            self.history.pop_front();
+        }
+    }
+
+    /// Steps back to the state before the most recent recorded step,
+    /// restoring the energy, generation and the absorbed, decayed and
+    /// injected counts. Returns `false`, leaving the lattice unchanged, if
+    /// no recorded step is left.
+    ///
+    /// The transfer rule moves quanta downhill and many states lead to the
+    /// same next state, so it has no inverse; stepping back replays states
+    /// kept by [`set_history_depth`](Self::set_history_depth) instead.
+    /// Replacing the state with [`initialize_vacuum`](Self::initialize_vacuum),
+    /// [`set_state`](Self::set_state), [`restore`](Self::restore) or
+    /// [`reset`](Self::reset) discards the history.
+    pub fn propagate_backward(&mut self) -> bool {
+        let Some(entry) = self.history.pop_back() else {
+            return false;
+        };
+
+        let size = (self.total_sites * std::mem::size_of::<u32>()) as u64;
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&entry.energy, 0, self.get_energy_buffer(), 0, size);
+        encoder.copy_buffer_to_buffer(
+            &entry.counters,
+            0,
+            &self.loss_buffer,
+            0,
+            2 * COUNTER_BUFFER_SIZE,
+        );
+        encoder.copy_buffer_to_buffer(
+            &entry.counters,
+            2 * COUNTER_BUFFER_SIZE,
+            &self.injected_buffer,
+            0,
+            COUNTER_BUFFER_SIZE,
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        self.generation = entry.generation;
+        self.steady_reference = None;
+        true
+    }
+
+    // Record the state a step at `generation` is about to read from the
+    // buffer selected by `parity`, reusing the oldest entry once the
+    // history is full
+    fn encode_history(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        parity: bool,
+        generation: u64,
+    ) {
+        if self.history_depth == 0 {
+            return;
+        }
+
+        let size = (self.total_sites * std::mem::size_of::<u32>()) as u64;
+        let entry = if self.history.len() >= self.history_depth {
+            let mut entry = self.history.pop_front().unwrap();
+            entry.generation = generation;
+            entry
+        } else {
+            let buffer = |label, size| {
+                self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            };
+            HistoryEntry {
+                generation,
+                energy: buffer("History Energy Buffer", size),
+                counters: buffer("History Counters Buffer", 3 * COUNTER_BUFFER_SIZE),
+            }
+        };
+
+        let energy = if parity {
+            &self.energy_buffer_b
+        } else {
+            &self.energy_buffer_a
+        };
+        encoder.copy_buffer_to_buffer(energy, 0, &entry.energy, 0, size);
+        encoder.copy_buffer_to_buffer(
+            &self.loss_buffer,
+            0,
+            &entry.counters,
+            0,
+            2 * COUNTER_BUFFER_SIZE,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.injected_buffer,
+            0,
+            &entry.counters,
+            2 * COUNTER_BUFFER_SIZE,
+            COUNTER_BUFFER_SIZE,
+        );
+        self.history.push_back(entry);
+    }
+
+    // Uniform values for the step that produces generation + 1
+    fn step_params(&self, generation: u64) -> Params {
+        Params {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            // The shader only uses the step to seed its RNG, so wrap. With
+            // push constants it isn't read from here at all, and leaving it
+            // fixed lets unchanged params skip the upload
+            step_count: if self.push_constants {
+                0
+            } else {
+                generation as u32
+            },
+            weight_x: self.axis_weights[0],
+            weight_y: self.axis_weights[1],
+            weight_z: self.axis_weights[2],
+            boundary_modes: pack_boundary_modes(self.boundary_modes),
+            seed: self.seed,
+            decay_threshold: self.decay_threshold,
+            capacity_map: self.capacity.is_some() as u32,
+            z_offset: self.z_offset,
+            lattice_depth: self.lattice_depth,
+            _padding: [0; 3],
+        }
+    }
+
+    // Recreate the step bind groups after a buffer they bind was replaced
+    fn rebuild_step_bind_groups(&mut self) {
+        self.step_bind_groups = create_step_bind_groups(
+            &self.device,
+            &self.bind_group_layout,
+            &self.params_buffer,
+            [&self.energy_buffer_a, &self.energy_buffer_b],
+            [
+                &self.site_flags_buffer,
+                &self.loss_buffer,
+                &self.potential_buffer,
+                &self.capacity_buffer,
+            ],
+        );
+    }
+
+    // Record the passes of one step in the current propagation mode,
+    // reading the buffer given by `parity`, for step `generation`. With
+    // active-region tracking, the block list is rebuilt first and the
+    // passes run over the listed blocks only
+    fn encode_step(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        parity: bool,
+        generation: u64,
+        timestamps: Option<&wgpu::QuerySet>,
+    ) {
+        let [workgroups_x, workgroups_y, workgroups_z] = self.workgroup_count(self.workgroup_size);
+        let bind_group = &self.step_bind_groups[parity as usize];
+        let (copy_pipeline, propagate_pipeline, gather_pipeline) = match &self.active_regions {
+            Some(active) => {
+                active.encode_mark(encoder, bind_group);
+                (
+                    &active.copy_pipeline,
+                    &active.propagate_pipeline,
+                    &active.gather_pipeline,
+                )
+            }
+            None => (
+                &self.copy_pipeline,
+                &self.propagate_pipeline,
+                &self.gather_pipeline,
+            ),
+        };
+        // Push constants must follow set_pipeline
+        let set_step = |pass: &mut wgpu::ComputePass| {
+            if self.push_constants {
+                pass.set_push_constants(0, bytemuck::bytes_of(&(generation as u32)));
+            }
+        };
+        let dispatch = |pass: &mut wgpu::ComputePass| match &self.active_regions {
+            Some(active) => active.dispatch(pass),
+            None => pass.dispatch_workgroups(workgroups_x, workgroups_y, workgroups_z),
+        };
+        // Pass N writes timestamps 2N and 2N + 1
+        let timestamp_writes = |pass: u32| {
+            timestamps.map(|query_set| wgpu::ComputePassTimestampWrites {
+                query_set,
+                beginning_of_pass_write_index: Some(2 * pass),
+                end_of_pass_write_index: Some(2 * pass + 1),
+            })
+        };
+
+        if self.propagation_mode == PropagationMode::Gather {
+            // Dispatch single pass: each site gathers its next state
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Gather Pass"),
+                timestamp_writes: timestamp_writes(0),
+            });
+            compute_pass.set_pipeline(gather_pipeline);
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            set_step(&mut compute_pass);
+            dispatch(&mut compute_pass);
+            return;
+        }
+
+        // Timestamps are written per compute pass, so profiling gives the
+        // copy and propagate dispatches a pass each
+        if timestamps.is_some() {
+            let passes = [
+                ("Copy Pass", copy_pipeline),
+                ("Propagate Pass", propagate_pipeline),
+            ];
+            for (pass, (label, pipeline)) in (0..).zip(passes) {
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some(label),
+                    timestamp_writes: timestamp_writes(pass),
+                });
+                compute_pass.set_pipeline(pipeline);
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                set_step(&mut compute_pass);
+                dispatch(&mut compute_pass);
+            }
+            return;
+        }
+
+        // Both passes share one compute pass. wgpu places a barrier between
+        // dispatches that use the same storage buffer, so the propagate
+        // dispatch sees the complete copy
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Scatter Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_bind_group(0, bind_group, &[]);
+
+        // PASS 1: Copy energy
+        compute_pass.set_pipeline(copy_pipeline);
+        set_step(&mut compute_pass);
+        dispatch(&mut compute_pass);
+
+        // PASS 2: Propagate transfers
+        compute_pass.set_pipeline(propagate_pipeline);
+        set_step(&mut compute_pass);
+        dispatch(&mut compute_pass);
+    }
+
+    // Workgroups of `size` needed to cover every site once
+    fn workgroup_count(&self, size: [u32; 3]) -> [u32; 3] {
+        [
+            self.width.div_ceil(size[0]),
+            self.height.div_ceil(size[1]),
+            self.depth.div_ceil(size[2]),
+        ]
+    }
+
+    // The output buffer just written becomes the current state
+    fn advance_step(&mut self) {
+        self.generation += 1;
+        self.parity = !self.parity;
+
+        if let Some(callback) = self.step_callback.as_mut() {
+            // Borrow the buffer field directly; get_energy_buffer would
+            // borrow all of self
+            let buffer = if self.parity {
+                &self.energy_buffer_b
+            } else {
+                &self.energy_buffer_a
+            };
+            let callback = callback.get_mut().expect("Step callback mutex poisoned");
+            callback(buffer, self.generation);
+        }
+    }
+
+    /// Runs `warmup` untimed steps, waits for the GPU to go idle, then times
+    /// `steps` further steps up to the point the GPU has finished them.
+    pub async fn timed_run(&mut self, steps: u32, warmup: u32) -> RunTiming {
+        for _ in 0..warmup {
+            self.propagate_energy();
+        }
+        self.wait_idle().await;
+
+        let start = Instant::now();
+        for _ in 0..steps {
+            self.propagate_energy();
+        }
+        self.wait_idle().await;
+
+        RunTiming {
+            steps,
+            total_sites: self.total_sites,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// Number of propagation steps taken so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Name, backend and driver of the adapter the lattice runs on, or
+    /// `None` for a lattice built on a device it was given, see
+    /// [`new_with_device`](Self::new_with_device).
+    pub fn adapter_info(&self) -> Option<&wgpu::AdapterInfo> {
+        self.adapter_info.as_ref()
+    }
+
+    /// Same as [`generation`](Self::generation); the counterpart of
+    /// [`set_step_count`](Self::set_step_count).
+    pub fn step_count(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Whether the step number reaches the shader as a push constant.
+    ///
+    /// Lattices from [`new`](Self::new) and
+    /// [`new_with_adapter`](Self::new_with_adapter) request
+    /// `wgpu::Features::PUSH_CONSTANTS` when the adapter supports it
+    /// natively (not on GL, which only emulates it); with
+    /// [`new_with_device`](Self::new_with_device) it is used if the device
+    /// was created with it, so don't enable it on GL devices. Without push
+    /// constants the params uniform is rewritten every step instead. Both
+    /// give identical results.
+    pub fn uses_push_constants(&self) -> bool {
+        self.push_constants
+    }
+
+    /// Whether pipelines are compiled through a cache kept on disk; see
+    /// [`new_with_pipeline_cache`](Self::new_with_pipeline_cache).
+    pub fn uses_pipeline_cache(&self) -> bool {
+        self.pipeline_cache.is_some()
+    }
+
+    /// Writes the pipeline cache back to disk, including pipelines compiled
+    /// since the lattice was created. Does nothing for a lattice without a
+    /// cache.
+    pub fn save_pipeline_cache(&self) -> io::Result<()> {
+        match &self.pipeline_cache {
+            Some(file) => file.save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of sites, `width * height * depth`.
+    pub fn total_sites(&self) -> usize {
+        self.total_sites
+    }
+
+    /// Bytes of GPU buffer memory held by the lattice.
+    ///
+    /// Counts the ping-pong energy buffers, the full-size staging buffer, the
+    /// site flags, the potential, any recorded
+    /// [history](Self::set_history_depth) and the small uniform and counter
+    /// buffers. Pipelines and driver overhead are not included, and
+    /// snapshots own their buffers.
+    pub fn memory_usage_bytes(&self) -> u64 {
+        [
+            &self.params_buffer,
+            &self.energy_buffer_a,
+            &self.energy_buffer_b,
+            &self.site_flags_buffer,
+            &self.potential_buffer,
+            &self.capacity_buffer,
+            &self.staging_buffer,
+            &self.reduce_buffer,
+            &self.reduce_staging_buffer,
+            &self.loss_buffer,
+            &self.injected_buffer,
+            &self.edit_counter_buffer,
+        ]
+        .iter()
+        .map(|buffer| buffer.size())
+        .sum::<u64>()
+            + self
+                .history
+                .iter()
+                .map(|entry| entry.energy.size() + entry.counters.size())
+                .sum::<u64>()
+    }
+
+    /// Bytes uploaded to and read back from the GPU since creation or the
+    /// last [`reset_transfer_stats`](Self::reset_transfer_stats).
+    ///
+    /// Full readbacks (`get_state`, analysis methods) move the whole lattice
+    /// each time, while GPU reductions such as
+    /// [`get_total_energy`](Self::get_total_energy) read back a few bytes.
+    pub fn transfer_stats(&self) -> TransferStats {
+        self.transfer.snapshot()
+    }
+
+    pub fn reset_transfer_stats(&self) {
+        self.transfer.reset();
+    }
+
+    /// Sets the generation the next step runs as, without touching the
+    /// energy. The step seeds the transfer RNG, so this controls which
+    /// random choices the following steps make.
+    pub fn set_step_count(&mut self, step: u64) {
+        self.generation = step;
+    }
+
+    /// Downloads the energy together with the generation, so the run can
+    /// later be resumed exactly with [`load_state`](Self::load_state).
+    pub async fn save_state(&self) -> LatticeState {
+        LatticeState {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            generation: self.generation,
+            energy: self.read_buffer(self.get_energy_buffer()).await,
+        }
+    }
+
+    /// Restores a state saved by [`save_state`](Self::save_state), possibly
+    /// from another lattice of the same size.
+    ///
+    /// Afterwards propagation continues bit-exactly as it would have from
+    /// the saved lattice. The absorbed-energy count starts again from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state's dimensions differ from this lattice's or it
+    /// does not hold one value per site.
+    pub fn load_state(&mut self, state: &LatticeState) {
+        assert_eq!(
+            (state.width, state.height, state.depth),
+            (self.width, self.height, self.depth),
+            "State dimensions must match the lattice"
+        );
+        self.set_state(&state.energy);
+        self.generation = state.generation;
+    }
+
+    /// Replaces the energy of every site with `energy`, in the
+    /// [`get_state`](Self::get_state) layout, keeping the generation.
+    ///
+    /// Values are uploaded as given, without capping at [`MAX_LEVEL`]. The
+    /// absorbed-energy count starts again from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `energy` does not hold one value per site.
+    pub fn set_state(&mut self, energy: &[u32]) {
+        assert_eq!(
+            energy.len(),
+            self.total_sites,
+            "State must have one value per site"
+        );
+
+        // Each step reads only the active buffer, so the other one can hold
+        // anything
+        self.upload(self.get_energy_buffer(), bytemuck::cast_slice(energy));
+        self.clear_losses();
+        self.steady_reference = None;
+        self.history.clear();
+    }
+
+    /// Copies the current state into a new GPU buffer, without reading it
+    /// back.
+    ///
+    /// The copy is queued behind any submitted steps, so it captures the
+    /// state as of the last [`propagate_energy`](Self::propagate_energy).
+    pub fn snapshot(&self) -> LatticeSnapshot {
+        let size = (self.total_sites * std::mem::size_of::<u32>()) as u64;
+        let energy = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Snapshot Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(self.get_energy_buffer(), 0, &energy, 0, size);
+        self.queue.submit(Some(encoder.finish()));
 
-            let data = buffer_slice.get_mapped_range();
-            energy_data.copy_from_slice(bytemuck::cast_slice(&data));
-            drop(data);
-            self.staging_buffer.unmap();
-        });
+        LatticeSnapshot {
+            dims: (self.width, self.height, self.depth),
+            generation: self.generation,
+            energy,
+        }
+    }
+
+    /// Restores a snapshot taken by [`snapshot`](Self::snapshot) on this
+    /// lattice or another of the same size on the same device.
+    ///
+    /// Like [`load_state`](Self::load_state), propagation then continues
+    /// bit-exactly from the snapshot, and the snapshot can be restored any
+    /// number of times. The absorbed-energy count starts again from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the snapshot's dimensions differ from this lattice's.
+    pub fn restore(&mut self, snapshot: &LatticeSnapshot) {
+        assert_eq!(
+            snapshot.dims,
+            (self.width, self.height, self.depth),
+            "Snapshot dimensions must match the lattice"
+        );
 
-        // Modify
-        energy_data[idx] = (energy_data[idx] + quanta).min(3);
+        let size = (self.total_sites * std::mem::size_of::<u32>()) as u64;
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&snapshot.energy, 0, self.get_energy_buffer(), 0, size);
+        encoder.clear_buffer(&self.loss_buffer, 0, None);
+        self.queue.submit(Some(encoder.finish()));
 
-        // Write back
-        self.queue
-            .write_buffer(&self.energy_buffer_a, 0, bytemuck::cast_slice(&energy_data));
+        self.generation = snapshot.generation;
+        self.steady_reference = None;
+        self.history.clear();
     }
 
-    pub fn propagate_energy(&mut self) {
-        // Update step count
-        let params = Params {
-            width: self.width,
-            height: self.height,
-            depth: self.depth,
-            step_count: self.step_count,
+    /// Receives errors and loss of the lattice's device as they happen.
+    ///
+    /// Every receiver shares one queue, so each event goes to only one of
+    /// them. Errors no error scope catches are logged with `log::error!`
+    /// and arrive here instead of panicking, as they would by default. The
+    /// queue holds the latest 64 events; older ones are dropped if nobody
+    /// drains it. Events only arrive for devices the lattice
+    /// created itself; a device passed to
+    /// [`new_with_device`](Self::new_with_device) keeps its own callbacks.
+    pub fn events(&self) -> flume::Receiver<LatticeEvent> {
+        self.events.1.clone()
+    }
+
+    /// Whether the device has been lost, so that every GPU operation fails
+    /// until [`recover`](Self::recover) is called.
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
+    /// Downloads the state and keeps it on the host for
+    /// [`recover`](Self::recover) to restore. Replaces the previous
+    /// checkpoint.
+    ///
+    /// Long runs should call this periodically: a lost device takes every
+    /// GPU buffer with it, including [`snapshot`](Self::snapshot)s.
+    pub async fn checkpoint(&mut self) {
+        self.checkpoint = Some(self.save_state().await);
+    }
+
+    /// Replaces a lost device with a new one on the same adapter, rebuilds
+    /// the pipelines and buffers, and restores the last
+    /// [`checkpoint`](Self::checkpoint).
+    ///
+    /// Settings, per-site maps, sources and sinks, injections and the step
+    /// callback carry over; the history for
+    /// [`propagate_backward`](Self::propagate_backward) and the loss
+    /// counters start again empty. Without a checkpoint the lattice comes
+    /// back empty at generation 0. Receivers from [`events`](Self::events)
+    /// keep working.
+    pub async fn recover(&mut self) -> Result<(), LatticeError> {
+        let adapter = self.recovery_adapter.request_adapter().await?;
+        let (device, queue) = request_device(&adapter).await?;
+        let mut fresh = Self::new_with_device(device, queue, self.width, self.height, self.depth)?;
+
+        fresh.propagation_mode = self.propagation_mode;
+        fresh.neighborhood = self.neighborhood;
+        fresh.rule_source = self.rule_source.clone();
+        fresh.workgroup_size = self.workgroup_size;
+        fresh
+            .rebuild_propagation_pipelines(include_str!("shader.wgsl"))
+            .await?;
+        fresh.set_active_region_tracking(self.active_regions.is_some());
+        fresh.axis_weights = self.axis_weights;
+        fresh.boundary_modes = self.boundary_modes;
+        fresh.seed = self.seed;
+        fresh.decay_threshold = self.decay_threshold;
+        fresh.z_offset = self.z_offset;
+        fresh.lattice_depth = self.lattice_depth;
+        fresh.history_depth = self.history_depth;
+
+        fresh.site_flags = self.site_flags.take();
+        fresh.upload_site_flags();
+        if let Some(potential) = self.potential.take() {
+            fresh.set_potential(&potential);
+        }
+        if let Some(capacity) = self.capacity.take() {
+            fresh.set_capacity(&capacity);
+        }
+        fresh.sources = std::mem::take(&mut self.sources);
+        fresh.source_buffer = fresh.edit_list_buffer(&fresh.sources);
+        fresh.sinks = std::mem::take(&mut self.sinks);
+        fresh.sink_buffer = fresh.edit_list_buffer(&fresh.sinks);
+        fresh.injection = self.injection.take();
+        fresh.scheduled = std::mem::take(&mut self.scheduled);
+        fresh.step_callback = self.step_callback.take();
+        fresh.transfer = std::mem::take(&mut self.transfer);
+
+        if let Some(state) = self.checkpoint.take() {
+            fresh.load_state(&state);
+            fresh.checkpoint = Some(state);
+        }
+        fresh.events = self.events.clone();
+        fresh.recovery_adapter = self.recovery_adapter.clone();
+        fresh.adapter_info = Some(adapter.get_info());
+        events::watch_device(&fresh.device, &fresh.events, &fresh.device_lost);
+
+        *self = fresh;
+        Ok(())
+    }
+
+    /// Propagates one step, then performs the requested measurement.
+    ///
+    /// Returns `None` for [`Measurement::None`] (no readback is done) and for
+    /// center-of-mass measurements of an empty lattice.
+    pub async fn tick(&mut self, measure: Measurement) -> Option<f64> {
+        self.propagate_energy();
+
+        // Scalar measurements are reduced on the GPU; only the center of
+        // mass needs the whole state
+        let axis = match measure {
+            Measurement::None => return None,
+            Measurement::Total => return Some(self.get_total_energy().await as f64),
+            Measurement::MaxLevel => return Some(self.max_site_energy().await as f64),
+            Measurement::CenterOfMassX => 0,
+            Measurement::CenterOfMassY => 1,
+            Measurement::CenterOfMassZ => 2,
         };
-        self.queue
-            .write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        let dims = (self.width, self.height, self.depth);
+        analysis::center_of_mass(&energy_data, dims).map(|c| c[axis])
+    }
 
-        // Determine which buffers to use (ping-pong)
-        let (input_buffer, output_buffer) = if self.step_count.is_multiple_of(2) {
-            (&self.energy_buffer_a, &self.energy_buffer_b)
+    pub fn get_energy_buffer(&self) -> &wgpu::Buffer {
+        // Return the current active buffer for rendering
+        if self.parity {
+            &self.energy_buffer_b
         } else {
-            (&self.energy_buffer_b, &self.energy_buffer_a)
-        };
+            &self.energy_buffer_a
+        }
+    }
+
+    /// Creates a 3D `R32Uint` texture sized `width × height × depth` that
+    /// [`copy_to_texture_3d`](Self::copy_to_texture_3d) can fill.
+    ///
+    /// Integer formats are not filterable, so shaders should read it with
+    /// `textureLoad` on a `texture_3d<u32>` binding.
+    pub fn create_energy_texture(&self) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Energy Texture"),
+            size: self.texture_extent(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: ENERGY_TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    /// Records a copy of the active energy buffer into `texture`.
+    ///
+    /// The texture must be a 3D `R32Uint` texture with `COPY_DST` usage and
+    /// exactly the lattice dimensions; texel `(x, y, z)` receives the energy
+    /// of site `(x, y, z)`. Nothing is submitted, the caller owns `encoder`.
+    pub fn copy_to_texture_3d(&self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        assert_eq!(texture.dimension(), wgpu::TextureDimension::D3);
+        assert_eq!(texture.format(), ENERGY_TEXTURE_FORMAT);
+        assert_eq!(texture.size(), self.texture_extent());
+
+        let row_bytes = self.width * std::mem::size_of::<u32>() as u32;
+        let source = self.get_energy_buffer();
+
+        if row_bytes.is_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) {
+            // Rows are already aligned, copy the whole volume at once
+            encoder.copy_buffer_to_texture(
+                wgpu::ImageCopyBuffer {
+                    buffer: source,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(row_bytes),
+                        rows_per_image: Some(self.height),
+                    },
+                },
+                texture.as_image_copy(),
+                self.texture_extent(),
+            );
+            return;
+        }
+
+        // Unaligned rows have to be copied one at a time
+        for z in 0..self.depth {
+            for y in 0..self.height {
+                encoder.copy_buffer_to_texture(
+                    wgpu::ImageCopyBuffer {
+                        buffer: source,
+                        layout: wgpu::ImageDataLayout {
+                            offset: ((z * self.height + y) * row_bytes) as u64,
+                            bytes_per_row: None,
+                            rows_per_image: None,
+                        },
+                    },
+                    wgpu::ImageCopyTexture {
+                        texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d { x: 0, y, z },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    wgpu::Extent3d {
+                        width: self.width,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+    }
+
+    fn texture_extent(&self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: self.depth,
+        }
+    }
+
+    /// Total energy in quanta, summed on the GPU.
+    ///
+    /// The reduction carries into a second 32-bit word, so the result is
+    /// exact even when the sum exceeds `u32::MAX`.
+    pub async fn get_total_energy(&self) -> u64 {
+        let result = self
+            .run_reduction(&self.total_pipeline, "Total Energy Pass", &[0, 0])
+            .await;
+        result[0] as u64 | (result[1] as u64) << 32
+    }
+
+    /// Total quanta removed at [`BoundaryMode::Absorbing`] faces and by
+    /// [sinks](Self::add_sinks) since the lattice was last initialized or
+    /// loaded.
+    ///
+    /// Counted on the GPU as the steps run, so
+    /// `get_total_energy() + absorbed_energy() + decayed_energy()` stays
+    /// constant for a closed run.
+    pub async fn absorbed_energy(&self) -> u64 {
+        self.read_loss_counter(ABSORBED_COUNTER).await
+    }
 
-        // Create bind group
+    /// Total quanta removed by [decay](Self::set_decay_rate) since the
+    /// lattice was last initialized or loaded. Counted on the GPU like
+    /// [`absorbed_energy`](Self::absorbed_energy).
+    pub async fn decayed_energy(&self) -> u64 {
+        self.read_loss_counter(DECAYED_COUNTER).await
+    }
+
+    // Read back the 64-bit counter starting at word `counter` of the loss
+    // buffer
+    async fn read_loss_counter(&self, counter: usize) -> u64 {
+        let result = self
+            .read_staged(
+                &self.loss_buffer,
+                &self.reduce_staging_buffer,
+                2 * COUNTER_BUFFER_SIZE,
+            )
+            .await;
+        result[counter] as u64 | (result[counter + 1] as u64) << 32
+    }
+
+    /// Number of sites currently holding [`MAX_LEVEL`].
+    ///
+    /// Counted on the GPU, so only the result is read back rather than the
+    /// whole lattice.
+    pub async fn saturated_count(&self) -> u64 {
+        let result = self
+            .run_reduction(&self.saturated_pipeline, "Saturated Count Pass", &[0])
+            .await;
+        result[0] as u64
+    }
+
+    /// Number of sites holding any energy.
+    ///
+    /// Counted on the GPU like [`saturated_count`](Self::saturated_count).
+    pub async fn count_occupied_sites(&self) -> u64 {
+        let result = self
+            .run_reduction(&self.occupied_pipeline, "Occupied Count Pass", &[0])
+            .await;
+        result[0] as u64
+    }
+
+    /// Highest energy held by any site, 0 for a vacuum.
+    ///
+    /// Found by a GPU max-reduction, so only one word is read back.
+    pub async fn max_site_energy(&self) -> u32 {
+        self.run_reduction(&self.max_pipeline, "Max Energy Pass", &[0])
+            .await[0]
+    }
+
+    /// Tightest box containing every site with non-zero energy, or `None`
+    /// for a vacuum.
+    ///
+    /// Computed on the GPU. Coordinates are not unwrapped, so energy that
+    /// straddles a periodic boundary yields a box spanning that whole axis.
+    pub async fn occupied_bounds(&self) -> Option<BoundingBox> {
+        let result = self
+            .run_reduction(
+                &self.bounds_pipeline,
+                "Occupied Bounds Pass",
+                &[u32::MAX, u32::MAX, u32::MAX, 0, 0, 0],
+            )
+            .await;
+        if result[0] == u32::MAX {
+            return None;
+        }
+        Some(BoundingBox {
+            min: [result[0], result[1], result[2]],
+            max: [result[3], result[4], result[5]],
+        })
+    }
+
+    // Run a reduce.wgsl entry point over the active buffer, starting from
+    // `initial` in the result buffer, and read back that many values
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, pipeline, initial))
+    )]
+    async fn run_reduction(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        label: &str,
+        initial: &[u32],
+    ) -> Vec<u32> {
+        self.upload(&self.reduce_buffer, bytemuck::cast_slice(initial));
+        self.dispatch_reduction(pipeline, label, &self.reduce_buffer);
+
+        let size = std::mem::size_of_val(initial) as u64;
+        self.read_staged(&self.reduce_buffer, &self.reduce_staging_buffer, size)
+            .await
+    }
+
+    // Submit a reduce.wgsl entry point over the active buffer, writing to
+    // `result`
+    fn dispatch_reduction(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        label: &str,
+        result: &wgpu::Buffer,
+    ) {
         let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bind Group"),
+            label: Some("Reduce Bind Group"),
             layout: &self.bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
@@ -411,90 +2875,922 @@ impl DiscreteLatticeGPU {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: input_buffer.as_entire_binding(),
+                    resource: self.get_energy_buffer().as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: output_buffer.as_entire_binding(),
+                    resource: result.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.site_flags_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: self.loss_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: self.potential_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: self.capacity_buffer.as_entire_binding(),
                 },
             ],
         });
 
-        // Workgroups: 4x4x4 = 64 threads per workgroup
-        let workgroups_x = self.width.div_ceil(4);
-        let workgroups_y = self.height.div_ceil(4);
-        let workgroups_z = self.depth.div_ceil(4);
-
-        // Dispatch PASS 1: Copy energy
         let mut encoder = self.device.create_command_encoder(&Default::default());
         {
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Copy Pass"),
+                label: Some(label),
                 timestamp_writes: None,
             });
-            compute_pass.set_pipeline(&self.copy_pipeline);
+            compute_pass.set_pipeline(pipeline);
             compute_pass.set_bind_group(0, &bind_group, &[]);
+            let [workgroups_x, workgroups_y, workgroups_z] = self.workgroup_count(WORKGROUP_SIZE);
             compute_pass.dispatch_workgroups(workgroups_x, workgroups_y, workgroups_z);
         }
         self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Downloads only the sites with non-zero energy, as `(x, y, z, energy)`
+    /// in index order.
+    ///
+    /// A GPU pass first counts the occupied sites, then a stream-compaction
+    /// pass gathers them into a buffer of exactly that size, so a sparse
+    /// wavefront in a large vacuum reads back a few kilobytes instead of the
+    /// whole lattice.
+    pub async fn get_occupied_sites(&self) -> Vec<(u32, u32, u32, u32)> {
+        let count = self.count_occupied_sites().await;
+        if count == 0 {
+            return Vec::new();
+        }
+
+        // A count word followed by an (index, energy) pair per site. New
+        // buffers start zeroed, so the count starts at zero
+        let size = (1 + 2 * count) * std::mem::size_of::<u32>() as u64;
+        let compacted = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compacted Sites Buffer"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compacted Sites Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.dispatch_reduction(&self.compact_pipeline, "Compact Occupied Pass", &compacted);
+        let values = self.read_staged(&compacted, &staging, size).await;
+
+        // Workgroups append in whatever order they finish
+        let mut pairs: Vec<&[u32]> = values[1..].chunks_exact(2).collect();
+        pairs.sort_unstable_by_key(|pair| pair[0]);
+        pairs
+            .into_iter()
+            .map(|pair| {
+                let idx = pair[0];
+                let x = idx % self.width;
+                let y = (idx / self.width) % self.height;
+                let z = idx / (self.width * self.height);
+                (x, y, z, pair[1])
+            })
+            .collect()
+    }
 
-        // Dispatch PASS 2: Propagate transfers
+    /// Downloads the energy of every site, indexed
+    /// `z * width * height + y * width + x`.
+    pub async fn get_state(&self) -> Vec<u32> {
+        self.read_buffer(self.get_energy_buffer()).await
+    }
+
+    /// Queues a download of the current state and returns without waiting.
+    ///
+    /// The copy runs on the GPU after the steps already submitted and
+    /// before any submitted later, into a staging buffer of its own, so the
+    /// lattice can keep stepping while it downloads. Collect the state with
+    /// [`Readback::wait`]; [`get_state`](Self::get_state) instead waits for
+    /// the GPU to finish everything queued.
+    pub fn queue_state_readback(&self) -> Readback<Vec<u32>> {
+        let size = (self.total_sites * std::mem::size_of::<u32>()) as u64;
         let mut encoder = self.device.create_command_encoder(&Default::default());
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Propagate Pass"),
-                timestamp_writes: None,
+        let staging = readback::take_staging(&self.device, &self.staging_pool, size);
+        encoder.copy_buffer_to_buffer(self.get_energy_buffer(), 0, &staging, 0, size);
+        self.queue_readback(encoder, staging, <[u32]>::to_vec)
+    }
+
+    /// Queues the total energy reduction and its download, as
+    /// [`queue_state_readback`](Self::queue_state_readback) does for the
+    /// state.
+    pub fn queue_total_energy_readback(&self) -> Readback<u64> {
+        self.upload(&self.reduce_buffer, bytemuck::cast_slice(&[0u32, 0]));
+        self.dispatch_reduction(
+            &self.total_pipeline,
+            "Total Energy Pass",
+            &self.reduce_buffer,
+        );
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let staging = readback::take_staging(&self.device, &self.staging_pool, 8);
+        encoder.copy_buffer_to_buffer(&self.reduce_buffer, 0, &staging, 0, 8);
+        self.queue_readback(encoder, staging, |words| {
+            words[0] as u64 | (words[1] as u64) << 32
+        })
+    }
+
+    // Submit `encoder`, which copies into `staging`, and map the staging
+    // buffer once the copy is done
+    fn queue_readback<T>(
+        &self,
+        encoder: wgpu::CommandEncoder,
+        staging: wgpu::Buffer,
+        decode: fn(&[u32]) -> T,
+    ) -> Readback<T> {
+        let started = Instant::now();
+        let submission = self.queue.submit(Some(encoder.finish()));
+        let (sender, mapped) = flume::bounded(1);
+        staging
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
             });
-            compute_pass.set_pipeline(&self.propagate_pipeline);
-            compute_pass.set_bind_group(0, &bind_group, &[]);
-            compute_pass.dispatch_workgroups(workgroups_x, workgroups_y, workgroups_z);
+        Readback {
+            device: self.device.clone(),
+            pool: self.staging_pool.clone(),
+            transfer: self.transfer.clone(),
+            staging,
+            submission,
+            mapped,
+            generation: self.generation,
+            started,
+            decode,
+        }
+    }
+
+    /// Downloads only the sites inside `region`, indexed
+    /// `z * size_x * size_y + y * size_x + x` relative to `region.min`.
+    ///
+    /// Each row of the region is copied on the GPU into a staging buffer
+    /// sized to the region, so small probes avoid a full readback.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `region` extends past the lattice.
+    pub async fn get_energy_region(&self, region: BoundingBox) -> Vec<u32> {
+        assert!(
+            region.max[0] < self.width && region.max[1] < self.height && region.max[2] < self.depth,
+            "Region must lie inside the lattice"
+        );
+        let [size_x, size_y, size_z] = region.size();
+        let row_bytes = size_x as u64 * 4;
+        let size = row_bytes * size_y as u64 * size_z as u64;
+
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Region Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let mut dst_offset = 0;
+        for z in region.min[2]..=region.max[2] {
+            for y in region.min[1]..=region.max[1] {
+                let src_index = z as u64 * self.width as u64 * self.height as u64
+                    + y as u64 * self.width as u64
+                    + region.min[0] as u64;
+                encoder.copy_buffer_to_buffer(
+                    self.get_energy_buffer(),
+                    src_index * 4,
+                    &staging,
+                    dst_offset,
+                    row_bytes,
+                );
+                dst_offset += row_bytes;
+            }
         }
+        let start = Instant::now();
         self.queue.submit(Some(encoder.finish()));
 
-        self.step_count += 1;
+        let values = self.map_staged(&staging, size).await;
+        self.transfer.record_download(size, start.elapsed());
+        values
     }
 
-    pub fn get_energy_buffer(&self) -> &wgpu::Buffer {
-        // Return the current active buffer for rendering
-        if self.step_count.is_multiple_of(2) {
-            &self.energy_buffer_a
-        } else {
-            &self.energy_buffer_b
+    /// Downloads the plane of sites perpendicular to `axis` at `index`.
+    ///
+    /// The plane is indexed by its two remaining axes in lattice order:
+    /// `y * width + x` for [`Axis::Z`], `z * width + x` for [`Axis::Y`] and
+    /// `z * height + y` for [`Axis::X`]. Only the plane is copied to the
+    /// host; see [`get_energy_region`](Self::get_energy_region).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is outside the lattice along `axis`.
+    pub async fn get_slice(&self, axis: Axis, index: u32) -> Vec<u32> {
+        let mut plane = BoundingBox {
+            min: [0; 3],
+            max: [self.width - 1, self.height - 1, self.depth - 1],
+        };
+        plane.min[axis.index()] = index;
+        plane.max[axis.index()] = index;
+        self.get_energy_region(plane).await
+    }
+
+    /// Downloads the energy of one site, copying just its four bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the site is outside the lattice.
+    pub async fn get_energy_at(&self, x: u32, y: u32, z: u32) -> u32 {
+        let site = BoundingBox {
+            min: [x, y, z],
+            max: [x, y, z],
+        };
+        self.get_energy_region(site).await[0]
+    }
+
+    /// Lists every site whose energy differs from `other`, as
+    /// `(x, y, z, self_energy, other_energy)`.
+    ///
+    /// `other` uses the [`get_state`](Self::get_state) layout. All sites are
+    /// compared even when the totals agree, since equal totals can hide
+    /// compensating errors. Identical states give an empty vec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` does not hold one value per site.
+    pub async fn diff(&self, other: &[u32]) -> Vec<(u32, u32, u32, u32, u32)> {
+        assert_eq!(
+            other.len(),
+            self.total_sites,
+            "State to compare must have one value per site"
+        );
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        analysis::diff(&energy_data, other, (self.width, self.height, self.depth))
+    }
+
+    /// Writes the current state as a NumPy `.npy` file that `numpy.load`
+    /// opens as a `uint32` array of shape `(depth, height, width)`.
+    pub async fn export_npy(&self, path: &Path) -> io::Result<()> {
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        export::write_npy(
+            &mut writer,
+            &energy_data,
+            (self.width, self.height, self.depth),
+        )?;
+        writer.into_inner()?.sync_all()
+    }
+
+    /// Writes the current state as a VTK ImageData (`.vti`) file for
+    /// ParaView or VisIt: a `UInt32` point array named `energy` on a grid
+    /// of unit spacing, with the generation as its time value. Use a
+    /// [`VtkSeries`] to write a run as a time series.
+    pub async fn export_vti(&self, path: &Path) -> io::Result<()> {
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        export::write_vti(
+            &mut writer,
+            &energy_data,
+            (self.width, self.height, self.depth),
+            self.generation,
+        )?;
+        writer.into_inner()?.sync_all()
+    }
+
+    /// Writes the current state to `path` as a headerless volume of
+    /// little-endian `u32` values, x fastest, then y, then z, with a
+    /// MetaImage header describing it beside it at `path` with an `.mhd`
+    /// extension. ImageJ, 3D Slicer and ITK open the `.mhd` directly; other
+    /// tools can read the raw file given the dimensions. Fails with
+    /// [`io::ErrorKind::InvalidInput`] if `path` itself ends in `.mhd`.
+    pub async fn export_raw(&self, path: &Path) -> io::Result<()> {
+        self.export_raw_as(path, export::RawElement::U32).await
+    }
+
+    /// Like [`export_raw`](Self::export_raw), with one byte per site.
+    /// Values above 255, possible only with raised site capacities, are
+    /// saturated.
+    pub async fn export_raw_u8(&self, path: &Path) -> io::Result<()> {
+        self.export_raw_as(path, export::RawElement::U8).await
+    }
+
+    async fn export_raw_as(&self, path: &Path, element: export::RawElement) -> io::Result<()> {
+        if path.extension().is_some_and(|ext| ext == "mhd") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "raw volume path must not end in .mhd, which is used for its header",
+            ));
         }
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        export::write_raw(&mut writer, &energy_data, element)?;
+        writer.into_inner()?.sync_all()?;
+
+        // The header names the data file relative to itself
+        let data_file = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+        let mut writer = BufWriter::new(std::fs::File::create(path.with_extension("mhd"))?);
+        export::write_mhd(
+            &mut writer,
+            (self.width, self.height, self.depth),
+            element,
+            &data_file,
+        )?;
+        writer.into_inner()?.sync_all()
     }
 
-    pub async fn get_total_energy(&self) -> u32 {
-        // Use the current active buffer
-        let active_buffer = if self.step_count.is_multiple_of(2) {
-            &self.energy_buffer_a
-        } else {
-            &self.energy_buffer_b
+    /// Sum of absolute energy differences between every pair of neighboring
+    /// sites, counting each pair once.
+    ///
+    /// A sharp seed scores high and the value falls as propagation smooths
+    /// the field. Sites on opposite faces only pair up along
+    /// [periodic](BoundaryMode::Periodic) axes, where the propagation rule
+    /// wraps. Computed on the CPU after a full readback.
+    pub async fn total_variation(&self) -> u64 {
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        analysis::total_variation(
+            &energy_data,
+            (self.width, self.height, self.depth),
+            self.boundary_modes,
+        )
+    }
+
+    /// Builds a lattice `factor` times finer along each axis on the same
+    /// device, carrying over the current state.
+    ///
+    /// Each site's energy is split as evenly as possible among its
+    /// `factor³` children, with any remainder placed on the children nearest
+    /// the block center, so the total is preserved exactly. Frozen sites and
+    /// obstacles pass their flag to all their children, and each child takes
+    /// its parent's potential and capacity. The generation, seed, decay rate, transfer
+    /// rule, propagation mode, neighborhood, boundary modes and axis weights
+    /// are copied.
+    ///
+    /// Fails with [`LatticeError::InvalidInput`] if a fine dimension would
+    /// not fit in a `u32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is zero.
+    pub async fn upsample_to(&self, factor: u32) -> Result<DiscreteLatticeGPU, LatticeError> {
+        assert!(factor > 0, "Upsampling factor must be positive");
+        let scale = |extent: u32| {
+            extent.checked_mul(factor).ok_or_else(|| {
+                LatticeError::InvalidInput(format!(
+                    "upsampling {}x{}x{} by {} overflows a dimension",
+                    self.width, self.height, self.depth, factor
+                ))
+            })
+        };
+        let (fine_width, fine_height, fine_depth) =
+            (scale(self.width)?, scale(self.height)?, scale(self.depth)?);
+
+        let dims = (self.width, self.height, self.depth);
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        let fine_energy = resample::upsample(&energy_data, dims, factor);
+
+        let mut fine = DiscreteLatticeGPU::new_with_device(
+            self.device.clone(),
+            self.queue.clone(),
+            fine_width,
+            fine_height,
+            fine_depth,
+        )?;
+        fine.load_state(&LatticeState {
+            width: fine.width,
+            height: fine.height,
+            depth: fine.depth,
+            generation: self.generation,
+            energy: fine_energy,
+        });
+        fine.propagation_mode = self.propagation_mode;
+        if self.neighborhood != fine.neighborhood || self.rule_source != fine.rule_source {
+            fine.neighborhood = self.neighborhood;
+            fine.rule_source = self.rule_source.clone();
+            fine.rebuild_propagation_pipelines(include_str!("shader.wgsl"))
+                .await?;
+        }
+        fine.axis_weights = self.axis_weights;
+        fine.boundary_modes = self.boundary_modes;
+        fine.seed = self.seed;
+        fine.decay_threshold = self.decay_threshold;
+
+        if let Some(flags) = self.site_flags.as_ref() {
+            let fine_flags = resample::upsample_nearest(flags, dims, factor);
+            fine.site_flags = Some(fine_flags);
+            fine.upload_site_flags();
+        }
+        if let Some(potential) = self.potential.as_ref() {
+            let fine_potential = resample::upsample_nearest(potential, dims, factor);
+            fine.set_potential(&fine_potential);
+        }
+        if let Some(capacity) = self.capacity.as_ref() {
+            let fine_capacity = resample::upsample_nearest(capacity, dims, factor);
+            fine.set_capacity(&fine_capacity);
+        }
+
+        Ok(fine)
+    }
+
+    /// Discrete Laplacian of the energy field: for each site, the sum over
+    /// its six neighbors of `neighbor - site`.
+    ///
+    /// Positive values mark sites below their surroundings (net inflow
+    /// expected), negative values sites above them (net outflow), so fronts
+    /// and gradients stand out. Uses the [`get_state`](Self::get_state)
+    /// layout. Neighbors follow each axis's [`BoundaryMode`] as the
+    /// propagation rule does: periodic faces wrap, reflective faces mirror
+    /// back to the inward neighbor, closed faces have no neighbor beyond
+    /// them and absorbing faces an empty one. Computed on the CPU after a
+    /// full readback.
+    pub async fn laplacian(&self) -> Vec<i32> {
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        analysis::laplacian(
+            &energy_data,
+            (self.width, self.height, self.depth),
+            self.boundary_modes,
+        )
+    }
+
+    /// Energy-weighted variance of the X, Y and Z coordinates about the
+    /// center of mass, or `None` for an empty lattice.
+    ///
+    /// Coordinates are not unwrapped, so the result is only meaningful while
+    /// the energy stays clear of the lattice faces.
+    pub async fn second_moment(&self) -> Option<[f64; 3]> {
+        let energy_data = self.read_buffer(self.get_energy_buffer()).await;
+        analysis::second_moment(&energy_data, (self.width, self.height, self.depth))
+    }
+
+    /// Returns true when the summed per-site change since the previous call
+    /// is at most `tolerance` quanta.
+    ///
+    /// The first call only records a reference state and returns false.
+    /// Each call costs a full readback, so sample every few dozen steps
+    /// rather than every step; the change is measured over whatever interval
+    /// separates two calls.
+    pub async fn is_steady(&mut self, tolerance: u64) -> bool {
+        let current = self.read_buffer(self.get_energy_buffer()).await;
+
+        let steady = match &self.steady_reference {
+            Some(previous) => {
+                let change: u64 = previous
+                    .iter()
+                    .zip(&current)
+                    .map(|(&a, &b)| a.abs_diff(b) as u64)
+                    .sum();
+                change <= tolerance
+            }
+            None => false,
         };
 
+        self.steady_reference = Some(current);
+        steady
+    }
+
+    /// Propagates until [`is_steady`](Self::is_steady) reports a change of at
+    /// most `tolerance` over `check_interval` steps, or `max_steps` have run.
+    ///
+    /// Returns the number of steps taken if the lattice became steady.
+    pub async fn propagate_until_steady(
+        &mut self,
+        tolerance: u64,
+        check_interval: u32,
+        max_steps: u32,
+    ) -> Option<u32> {
+        let check_interval = check_interval.max(1);
+        self.steady_reference = None;
+        self.is_steady(tolerance).await;
+
+        let mut steps = 0;
+        while steps < max_steps {
+            let batch = check_interval.min(max_steps - steps);
+            for _ in 0..batch {
+                self.propagate_energy();
+            }
+            steps += batch;
+
+            if self.is_steady(tolerance).await {
+                return Some(steps);
+            }
+        }
+        None
+    }
+
+    /// Waits for all submitted work to finish, then releases the lattice's
+    /// GPU buffers, including any history, active-region, source and sink
+    /// buffers and pooled readback staging buffers.
+    ///
+    /// Dropping a lattice is also safe, but buffers are then freed lazily
+    /// while earlier steps may still be in flight. Call this when many
+    /// lattices share one device, e.g. in a parameter sweep.
+    pub async fn shutdown(self) {
+        self.wait_idle().await;
+
+        // Staging buffers are the only ones ever mapped; destroying it first also
+        // releases a mapping left behind by a cancelled readback
+        self.staging_buffer.destroy();
+        self.reduce_staging_buffer.destroy();
+        self.reduce_buffer.destroy();
+        self.loss_buffer.destroy();
+        self.injected_buffer.destroy();
+        self.edit_counter_buffer.destroy();
+        self.energy_buffer_a.destroy();
+        self.energy_buffer_b.destroy();
+        self.site_flags_buffer.destroy();
+        self.potential_buffer.destroy();
+        self.capacity_buffer.destroy();
+        self.params_buffer.destroy();
+        for entry in &self.history {
+            entry.energy.destroy();
+            entry.counters.destroy();
+        }
+        if let Some(active) = &self.active_regions {
+            active.destroy();
+        }
+        for buffer in [&self.source_buffer, &self.sink_buffer]
+            .into_iter()
+            .flatten()
+        {
+            buffer.destroy();
+        }
+        for buffer in self
+            .staging_pool
+            .lock()
+            .expect("Staging pool mutex poisoned")
+            .drain(..)
+        {
+            buffer.destroy();
+        }
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    // Wait until the GPU has finished everything submitted so far. Natively
+    // the poll does the waiting; in a browser it returns at once and the
+    // callback arrives from the event loop
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    async fn wait_idle(&self) {
+        let (sender, receiver) = flume::bounded(1);
+        self.queue.on_submitted_work_done(move || {
+            let _ = sender.send(());
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        // An error means the device is gone, and with it the work
+        let _ = receiver.recv_async().await;
+    }
+
+    // Zero the absorbed and decayed counts on the GPU, without an upload
+    fn clear_losses(&self) {
         let mut encoder = self.device.create_command_encoder(&Default::default());
-        encoder.copy_buffer_to_buffer(
-            active_buffer,
-            0,
+        encoder.clear_buffer(&self.loss_buffer, 0, None);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    // Write `data` to the start of `buffer`, counting the bytes
+    fn upload(&self, buffer: &wgpu::Buffer, data: &[u8]) {
+        self.upload_at(buffer, 0, data);
+    }
+
+    fn upload_at(&self, buffer: &wgpu::Buffer, offset: u64, data: &[u8]) {
+        self.transfer.record_upload(data.len() as u64);
+        self.queue.write_buffer(buffer, offset, data);
+    }
+
+    // Write params to the uniform buffer unless it already holds them
+    fn write_params(&mut self, params: Params) {
+        if params != self.uploaded_params {
+            self.upload(&self.params_buffer, bytemuck::bytes_of(&params));
+            self.uploaded_params = params;
+        }
+    }
+
+    // Copy a full-lattice buffer through the staging buffer to the host
+    async fn read_buffer(&self, buffer: &wgpu::Buffer) -> Vec<u32> {
+        self.read_staged(
+            buffer,
             &self.staging_buffer,
-            0,
             (self.total_sites * std::mem::size_of::<u32>()) as u64,
+        )
+        .await
+    }
+
+    // Download `count` whole layers of the energy buffer, starting at layer
+    // `first`, in one copy
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    async fn read_layers(&self, first: u32, count: u32) -> Vec<u32> {
+        let layer_bytes = self.width as u64 * self.height as u64 * 4;
+        let size = count as u64 * layer_bytes;
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Layer Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let start = Instant::now();
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(
+            self.get_energy_buffer(),
+            first as u64 * layer_bytes,
+            &staging,
+            0,
+            size,
         );
         self.queue.submit(Some(encoder.finish()));
+        let values = self.map_staged(&staging, size).await;
+        self.transfer.record_download(size, start.elapsed());
+        values
+    }
+
+    // Copy the first `size` bytes of `buffer` through `staging` to the host
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, buffer, staging))
+    )]
+    async fn read_staged(
+        &self,
+        buffer: &wgpu::Buffer,
+        staging: &wgpu::Buffer,
+        size: u64,
+    ) -> Vec<u32> {
+        let start = Instant::now();
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(buffer, 0, staging, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+        let values = self.map_staged(staging, size).await;
+        self.transfer.record_download(size, start.elapsed());
+        values
+    }
 
-        let buffer_slice = self.staging_buffer.slice(..);
+    // Wait for the GPU, then read the first `size` bytes of `staging`.
+    // The staging buffer is unmapped again before this returns, so a lattice
+    // never holds a mapped buffer between calls
+    async fn map_staged(&self, staging: &wgpu::Buffer, size: u64) -> Vec<u32> {
+        let buffer_slice = staging.slice(..size);
         let (sender, receiver) = flume::bounded(1);
         buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender.send(result).unwrap();
+            // The receiver is gone if this future was dropped mid-readback;
+            // panicking here would take down whichever thread polls the device
+            let _ = sender.send(result);
         });
         self.device.poll(wgpu::Maintain::Wait);
         receiver.recv_async().await.unwrap().unwrap();
 
         let data = buffer_slice.get_mapped_range();
-        let energy_data: &[u32] = bytemuck::cast_slice(&data);
-        let total: u32 = energy_data.iter().sum();
+        let values = bytemuck::cast_slice(&data).to_vec();
         drop(data);
-        self.staging_buffer.unmap();
+        staging.unmap();
+
+        values
+    }
+}
+
+// The default high-performance adapter
+async fn default_adapter() -> Result<wgpu::Adapter, LatticeError> {
+    AdapterOptions::default().request_adapter().await
+}
+
+// Request a device from `adapter` with the largest storage buffers it
+// supports, plus push constants where they help and pipeline caching where
+// the adapter has it
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(adapter = %adapter.get_info().name))
+)]
+async fn request_device(
+    adapter: &wgpu::Adapter,
+) -> Result<(Arc<wgpu::Device>, Arc<wgpu::Queue>), LatticeError> {
+    // Query adapter's actual limits
+    let adapter_limits = adapter.limits();
+
+    // Request maximum limits, but don't exceed what adapter supports
+    // RTX 4080: 2 GB, lavapipe: 2 GB - 1 byte
+    // Push constants carry the step where available; without them the
+    // step goes through the params uniform. GL only emulates them with
+    // uniforms, and wgpu's emulation reads the data unaligned, so it
+    // keeps the uniform path
+    let push_constants = adapter.features().contains(wgpu::Features::PUSH_CONSTANTS)
+        && adapter_limits.max_push_constant_size >= PUSH_CONSTANT_SIZE
+        && adapter.get_info().backend != wgpu::Backend::Gl;
+    let (features, max_push_constant_size) = if push_constants {
+        (wgpu::Features::PUSH_CONSTANTS, PUSH_CONSTANT_SIZE)
+    } else {
+        (wgpu::Features::empty(), 0)
+    };
+    // Profiling builds also ask for timestamp queries, if there are any
+    #[cfg(feature = "profiling")]
+    let features = features | (adapter.features() & wgpu::Features::TIMESTAMP_QUERY);
+    let features = features | (adapter.features() & wgpu::Features::PIPELINE_CACHE);
+
+    let limits = wgpu::Limits {
+        max_storage_buffer_binding_size: adapter_limits.max_storage_buffer_binding_size,
+        max_buffer_size: adapter_limits.max_buffer_size,
+        max_push_constant_size,
+        ..Default::default()
+    };
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Quantum Lattice GPU"),
+                required_features: features,
+                required_limits: limits,
+                memory_hints: Default::default(),
+            },
+            None,
+        )
+        .await?;
+
+    Ok((Arc::new(device), Arc::new(queue)))
+}
+
+// Per-site capacity map read by shader.wgsl and edit.wgsl
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip(device))
+)]
+fn create_capacity_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capacity Buffer"),
+        size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+// Pack per-axis boundary modes into the params word read by shader.wgsl
+fn pack_boundary_modes(modes: [BoundaryMode; 3]) -> u32 {
+    modes
+        .iter()
+        .enumerate()
+        .map(|(axis, mode)| mode.shader_value() << (axis * 8))
+        .sum()
+}
+
+// Bind groups for a step from either ping-pong buffer: params at binding 0,
+// the input and output energy at 1 and 2, then `bound` at 3 to 6 (site
+// flags, losses, potential, capacity)
+fn create_step_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    params: &wgpu::Buffer,
+    [energy_a, energy_b]: [&wgpu::Buffer; 2],
+    bound: [&wgpu::Buffer; 4],
+) -> [wgpu::BindGroup; 2] {
+    [(energy_a, energy_b), (energy_b, energy_a)].map(|(input, output)| {
+        let buffers = [params, input, output].into_iter().chain(bound);
+        let entries: Vec<wgpu::BindGroupEntry> = buffers
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Step Bind Group"),
+            layout,
+            entries: &entries,
+        })
+    })
+}
+
+// Axis weights in 16-bit fixed point, normalized so the largest weight uses
+// the full range. Panics if a weight is negative or not finite
+fn fixed_point_weights(weights: [f32; 3]) -> [u32; 3] {
+    assert!(
+        weights.iter().all(|w| w.is_finite() && *w >= 0.0),
+        "Axis weights must be finite and non-negative, got {:?}",
+        weights
+    );
 
-        total
+    let max = weights.iter().cloned().fold(0.0, f32::max);
+    if max > 0.0 {
+        weights.map(|w| (w / max * WEIGHT_SCALE).round() as u32)
+    } else {
+        [0; 3]
     }
 }
+
+// Per-site decay probability as a fraction of 2^32. Panics if `rate` is
+// outside 0..=1
+fn decay_threshold(rate: f32) -> u32 {
+    assert!(
+        (0.0..=1.0).contains(&rate),
+        "Decay rate must be in 0..=1, got {}",
+        rate
+    );
+    (rate as f64 * 4294967296.0).min(u32::MAX as f64) as u32
+}
+
+// Layout of the propagation pipelines: the shared bind group layout, plus
+// the step as a push constant if enabled
+fn create_propagation_layout(
+    device: &wgpu::Device,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    push_constants: bool,
+) -> wgpu::PipelineLayout {
+    let ranges: &[wgpu::PushConstantRange] = if push_constants {
+        &[wgpu::PushConstantRange {
+            stages: wgpu::ShaderStages::COMPUTE,
+            range: 0..PUSH_CONSTANT_SIZE,
+        }]
+    } else {
+        &[]
+    };
+    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Propagation Pipeline Layout"),
+        bind_group_layouts,
+        push_constant_ranges: ranges,
+    })
+}
+
+// Entry points of the copy, scatter and gather pipelines, dispatched over
+// the whole lattice or over the active blocks
+const PROPAGATION_ENTRY_POINTS: [&str; 3] = ["copy_energy", "propagate_energy", "propagate_gather"];
+const ACTIVE_PROPAGATION_ENTRY_POINTS: [&str; 3] = [
+    "active_copy_energy",
+    "active_propagate_energy",
+    "active_propagate_gather",
+];
+
+// WGSL constants giving the propagation passes their workgroup size
+fn workgroup_constants([x, y, z]: [u32; 3]) -> String {
+    format!(
+        "const WORKGROUP_X: u32 = {}u;\nconst WORKGROUP_Y: u32 = {}u;\nconst WORKGROUP_Z: u32 = {}u;",
+        x, y, z
+    )
+}
+
+// Compile shader.wgsl source with the step snippet, workgroup size and
+// transfer rule appended and build the copy, scatter and gather pipelines
+// from `entry_points` for the given neighborhood
+#[allow(clippy::too_many_arguments)]
+fn create_propagation_pipelines(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    source: &str,
+    rule: &str,
+    neighborhood: Neighborhood,
+    workgroup_size: [u32; 3],
+    push_constants: bool,
+    cache: Option<&wgpu::PipelineCache>,
+    entry_points: [&str; 3],
+) -> (
+    wgpu::ComputePipeline,
+    wgpu::ComputePipeline,
+    wgpu::ComputePipeline,
+) {
+    let step = if push_constants {
+        include_str!("step_push_constant.wgsl")
+    } else {
+        include_str!("step_uniform.wgsl")
+    };
+    let workgroup = workgroup_constants(workgroup_size);
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Compute Shader"),
+        source: wgpu::ShaderSource::Wgsl(
+            format!("{}\n{}\n{}\n{}", source, step, workgroup, rule).into(),
+        ),
+    });
+
+    let moore = (neighborhood == Neighborhood::Moore) as u32 as f64;
+    let constants = HashMap::from([("MOORE_NEIGHBORHOOD".to_string(), moore)]);
+    let compilation_options = wgpu::PipelineCompilationOptions {
+        constants: &constants,
+        ..Default::default()
+    };
+
+    let copy_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Copy Pipeline"),
+        layout: Some(pipeline_layout),
+        module: &shader,
+        entry_point: entry_points[0],
+        compilation_options: compilation_options.clone(),
+        cache,
+    });
+
+    let propagate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Propagate Pipeline"),
+        layout: Some(pipeline_layout),
+        module: &shader,
+        entry_point: entry_points[1],
+        compilation_options: compilation_options.clone(),
+        cache,
+    });
+
+    let gather_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Gather Pipeline"),
+        layout: Some(pipeline_layout),
+        module: &shader,
+        entry_point: entry_points[2],
+        compilation_options: compilation_options.clone(),
+        cache,
+    });
+
+    (copy_pipeline, propagate_pipeline, gather_pipeline)
+}
@@ -1,13 +1,364 @@
-use lattice_gpu::DiscreteLatticeGPU;
-use std::time::Instant;
+// The walkthe command-line tool
+//
+//   walkthe run     run an experiment from a TOML file and/or flags
+//   walkthe bench   benchmark propagation over a sweep of lattice sizes
+//   walkthe view    open the interactive 3D viewer
+//   walkthe export  convert a checkpoint to .vti, .npy or raw volumes
 
-fn main() {
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use lattice_gpu::{
+    CancellationToken, Checkpoint, DiscreteLatticeGPU, ExperimentConfig, InitialCondition,
+    LatticeConfig,
+};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "walkthe",
+    version,
+    about = "GPU-accelerated 3D discrete quantum lattice"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run an experiment and write its outputs
+    Run(RunArgs),
+    /// Benchmark propagation over a sweep of lattice sizes
+    Bench(BenchArgs),
+    /// Open the interactive 3D viewer
+    View(ViewArgs),
+    /// Convert a checkpoint to other formats
+    Export(ExportArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Experiment file; the flags below override its settings. Without
+    /// one, a sphere of energy is seeded at the lattice center
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Edge length of a cubic lattice
+    #[arg(long, conflicts_with_all = ["width", "height", "depth"])]
+    size: Option<u32>,
+    #[arg(long)]
+    width: Option<u32>,
+    #[arg(long)]
+    height: Option<u32>,
+    #[arg(long)]
+    depth: Option<u32>,
+    /// Number of steps to run
+    #[arg(short, long)]
+    steps: Option<u32>,
+    /// Seed of the transfer RNG
+    #[arg(long)]
+    seed: Option<u32>,
+    /// Write a checkpoint of the final state
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+    /// Write the final state as a NumPy .npy file
+    #[arg(long)]
+    npy: Option<PathBuf>,
+    /// Write the final state as a VTK .vti file
+    #[arg(long)]
+    vti: Option<PathBuf>,
+    /// Write the final state as a raw volume with an .mhd header
+    #[arg(long)]
+    raw: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Edge lengths of the cubic lattices to benchmark
+    #[arg(long, value_delimiter = ',')]
+    sizes: Option<Vec<u32>>,
+    /// Steps per lattice, instead of the default 50-100
+    #[arg(short, long)]
+    steps: Option<u32>,
+    /// Format of the results. With json or csv, progress goes to stderr
+    #[arg(long, value_enum, default_value_t = BenchFormat::Text)]
+    format: BenchFormat,
+    /// Write json or csv results here instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum BenchFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Args)]
+struct ViewArgs {
+    /// Edge length of the cubic lattice
+    #[arg(long, default_value_t = 100)]
+    size: u32,
+    /// Starting background: dark, black, white or gray
+    #[arg(long)]
+    background: Option<String>,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// Checkpoint to convert
+    checkpoint: PathBuf,
+    #[arg(long)]
+    npy: Option<PathBuf>,
+    #[arg(long)]
+    vti: Option<PathBuf>,
+    #[arg(long)]
+    raw: Option<PathBuf>,
+    /// Raw volume with one byte per site
+    #[arg(long)]
+    raw_u8: Option<PathBuf>,
+}
+
+const DEFAULT_SIZE: u32 = 64;
+const DEFAULT_STEPS: u32 = 100;
+
+fn main() -> ExitCode {
     env_logger::init();
-    println!("=== GPU-Accelerated 3D Discrete Quantum Lattice ===\n");
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Run(args) => run(args),
+        Command::Bench(args) => bench(args),
+        Command::View(args) => view(args),
+        Command::Export(args) => export(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("walkthe: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// The experiment file, or a sphere at the center of a default lattice,
+// with the flags applied over it
+fn experiment(args: &RunArgs) -> Result<ExperimentConfig, String> {
+    let mut config = match &args.config {
+        Some(path) => ExperimentConfig::read(path).map_err(|err| err.to_string())?,
+        None => {
+            let size = args.size.unwrap_or(DEFAULT_SIZE);
+            let c = size / 2;
+            ExperimentConfig {
+                lattice: LatticeConfig::new(size, size, size),
+                initial: vec![InitialCondition::Sphere {
+                    center: [c, c, c],
+                    radius: (size / 8).max(1),
+                    quanta: 3,
+                }],
+                sources: Vec::new(),
+                sinks: Vec::new(),
+                steps: DEFAULT_STEPS,
+                output: Default::default(),
+            }
+        }
+    };
+
+    let lattice = &mut config.lattice;
+    if let Some(size) = args.size {
+        (lattice.width, lattice.height, lattice.depth) = (size, size, size);
+    }
+    lattice.width = args.width.unwrap_or(lattice.width);
+    lattice.height = args.height.unwrap_or(lattice.height);
+    lattice.depth = args.depth.unwrap_or(lattice.depth);
+    lattice.seed = args.seed.unwrap_or(lattice.seed);
+    config.steps = args.steps.unwrap_or(config.steps);
+
+    let output = &mut config.output;
+    for (flag, path) in [
+        (&args.checkpoint, &mut output.checkpoint),
+        (&args.npy, &mut output.npy),
+        (&args.vti, &mut output.vti),
+        (&args.raw, &mut output.raw),
+    ] {
+        if flag.is_some() {
+            path.clone_from(flag);
+        }
+    }
+    Ok(config)
+}
+
+fn run(args: RunArgs) -> Result<(), String> {
+    let config = experiment(&args)?;
+    let lattice = &config.lattice;
+    println!(
+        "Running {} steps on a {}x{}x{} lattice",
+        config.steps, lattice.width, lattice.height, lattice.depth
+    );
+
+    // Ctrl-C stops the run after its current batch, still writing the
+    // outputs, so a checkpoint can resume it
+    let token = CancellationToken::new();
+    let handler = token.clone();
+    ctrlc::set_handler(move || {
+        eprintln!("Stopping after the current batch...");
+        handler.cancel();
+    })
+    .map_err(|err| format!("failed to install the Ctrl-C handler: {}", err))?;
+
+    let start = std::time::Instant::now();
+    let lattice = pollster::block_on(config.run_cancellable(&token))
+        .map_err(|err| format!("run failed: {}", err))?;
+    let outcome = if token.is_cancelled() {
+        "Stopped"
+    } else {
+        "Finished"
+    };
+    println!(
+        "{} at generation {} in {:.2} s",
+        outcome,
+        lattice.generation(),
+        start.elapsed().as_secs_f64()
+    );
+    println!(
+        "Final energy: {} quanta",
+        pollster::block_on(lattice.get_total_energy())
+    );
+    Ok(())
+}
+
+// The viewer is its own binary, installed beside this one
+fn view(args: ViewArgs) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let viewer = exe.with_file_name(format!("viewer{}", std::env::consts::EXE_SUFFIX));
+    let mut command = std::process::Command::new(&viewer);
+    command.args(["--size", &args.size.to_string()]);
+    if let Some(background) = &args.background {
+        command.args(["--background", background]);
+    }
+    let status = command
+        .status()
+        .map_err(|err| format!("failed to start {}: {}", viewer.display(), err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("viewer exited with {}", status))
+    }
+}
+
+fn export(args: ExportArgs) -> Result<(), String> {
+    if args.npy.is_none() && args.vti.is_none() && args.raw.is_none() && args.raw_u8.is_none() {
+        return Err("nothing to export; pass --npy, --vti, --raw or --raw-u8".to_string());
+    }
+    let checkpoint = Checkpoint::read(&args.checkpoint)
+        .map_err(|err| format!("{}: {}", args.checkpoint.display(), err))?;
+    let state = &checkpoint.state;
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(
+        state.width,
+        state.height,
+        state.depth,
+    ))
+    .map_err(|err| err.to_string())?;
+    lattice.load_state(state);
+
+    let write = |path: &Option<PathBuf>, export: &dyn Fn(&Path) -> std::io::Result<()>| match path {
+        Some(path) => {
+            export(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            println!("Wrote {}", path.display());
+            Ok(())
+        }
+        None => Ok::<(), String>(()),
+    };
+    write(&args.npy, &|path| {
+        pollster::block_on(lattice.export_npy(path))
+    })?;
+    write(&args.vti, &|path| {
+        pollster::block_on(lattice.export_vti(path))
+    })?;
+    write(&args.raw, &|path| {
+        pollster::block_on(lattice.export_raw(path))
+    })?;
+    write(&args.raw_u8, &|path| {
+        pollster::block_on(lattice.export_raw_u8(path))
+    })?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct BenchRecord {
+    size: u32,
+    sites: u64,
+    iterations: u32,
+    workgroup_size: [u32; 3],
+    ms_per_iter: f64,
+    sites_per_sec: f64,
+    gb_per_sec: f64,
+    initial_energy: u64,
+    final_energy: u64,
+    energy_conserved: bool,
+    adapter: String,
+    backend: String,
+    driver: String,
+}
+
+const CSV_HEADER: &str = "size,sites,iterations,workgroup_size,ms_per_iter,sites_per_sec,\
+                          gb_per_sec,initial_energy,final_energy,energy_conserved,adapter,backend,driver";
+
+impl BenchRecord {
+    fn csv_row(&self) -> String {
+        let [x, y, z] = self.workgroup_size;
+        format!(
+            "{},{},{},{}x{}x{},{},{},{},{},{},{},{},{},{}",
+            self.size,
+            self.sites,
+            self.iterations,
+            x,
+            y,
+            z,
+            self.ms_per_iter,
+            self.sites_per_sec,
+            self.gb_per_sec,
+            self.initial_energy,
+            self.final_energy,
+            self.energy_conserved,
+            csv_field(&self.adapter),
+            csv_field(&self.backend),
+            csv_field(&self.driver)
+        )
+    }
+}
+
+// Quote a CSV field if it holds a separator, quote or line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
 
-    // Test different lattice sizes - up to driver limit (2GB per buffer)
+fn bench(args: BenchArgs) -> Result<(), String> {
+    if args.format == BenchFormat::Text && args.output.is_some() {
+        return Err("--output needs --format json or csv".to_string());
+    }
+    // Progress and results go to stdout as text, or to stderr when stdout
+    // carries structured records
+    let mut log: Box<dyn Write> = match args.format {
+        BenchFormat::Text => Box::new(std::io::stdout()),
+        BenchFormat::Json | BenchFormat::Csv => Box::new(std::io::stderr()),
+    };
+    macro_rules! say {
+        ($($arg:tt)*) => {
+            let _ = writeln!(log, $($arg)*);
+        };
+    }
+
+    say!("=== GPU-Accelerated 3D Discrete Quantum Lattice ===\n");
+
+    // Default sweep: lattice sizes up to the driver limit (2GB per buffer)
     // Max theoretical: 812³ (536M sites = 2.14 GB)
-    let test_configs = vec![
+    let default_configs = vec![
         (200, 100), // 8M sites, 100 iterations
         (300, 100), // 27M sites, 100 iterations
         (400, 100), // 64M sites, 100 iterations
@@ -15,82 +366,147 @@ fn main() {
         (600, 50),  // 216M sites, 50 iterations
         (700, 50),  // 343M sites, 50 iterations - pushing the limit!
     ];
+    let test_configs: Vec<(u32, u32)> = match &args.sizes {
+        Some(sizes) => sizes
+            .iter()
+            .map(|&size| (size, args.steps.unwrap_or(100)))
+            .collect(),
+        None => default_configs
+            .into_iter()
+            .map(|(size, iterations)| (size, args.steps.unwrap_or(iterations)))
+            .collect(),
+    };
+
+    // Later runs load the compiled pipelines instead of compiling them again
+    let pipeline_cache = std::env::temp_dir().join("lattice-gpu-pipelines");
+    let mut records = Vec::new();
 
     for (size, iterations) in test_configs {
-        let total_sites = size * size * size;
+        let total_sites = size as u64 * size as u64 * size as u64;
         let data_size_mb = (total_sites * 4) as f64 / (1024.0 * 1024.0);
 
-        println!(
+        say!(
             "=== {}³ Lattice ({} sites, {:.1} MB) ===\n",
-            size, total_sites, data_size_mb
+            size,
+            total_sites,
+            data_size_mb
         );
 
-        let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(size, size, size));
+        let mut lattice = pollster::block_on(DiscreteLatticeGPU::new_with_pipeline_cache(
+            &pipeline_cache,
+            size,
+            size,
+            size,
+        ))
+        .map_err(|err| format!("failed to create a {}³ lattice: {}", size, err))?;
         lattice.initialize_vacuum();
 
         // Add spherical energy distribution
         let c = size / 2;
-        for dz in -3i32..=3 {
-            for dy in -3i32..=3 {
-                for dx in -3i32..=3 {
-                    if dx * dx + dy * dy + dz * dz <= 9 {
-                        lattice.add_energy_quantum(
-                            (c as i32 + dx) as u32,
-                            (c as i32 + dy) as u32,
-                            (c as i32 + dz) as u32,
-                            3,
-                        );
-                    }
-                }
-            }
-        }
+        lattice.seed_sphere((c, c, c), 3, 3);
 
-        let initial_energy = pollster::block_on(lattice.get_total_energy());
-        println!("Initial energy: {} quanta\n", initial_energy);
+        let workgroup_size = pollster::block_on(lattice.tune_workgroup_size(5));
+        say!("Tuned workgroup size: {:?}", workgroup_size);
 
-        // Warmup
-        for _ in 0..10 {
-            lattice.propagate_energy();
-        }
-        // Wait for GPU to finish warmup
-        pollster::block_on(async {
-            lattice.get_total_energy().await;
-        });
+        let initial_energy = pollster::block_on(lattice.get_total_energy());
+        say!("Initial energy: {} quanta\n", initial_energy);
 
-        // Benchmark
-        let start = Instant::now();
-        for _ in 0..iterations {
-            lattice.propagate_energy();
-        }
-        // Wait for GPU to finish all work
+        // Warmup, then benchmark
+        let timing = pollster::block_on(lattice.timed_run(iterations, 10));
         let final_energy = pollster::block_on(lattice.get_total_energy());
-        let elapsed = start.elapsed();
 
-        let time_ms = elapsed.as_secs_f64() * 1000.0;
-        let avg_ms = time_ms / iterations as f64;
-        let throughput = total_sites as f64 * iterations as f64 / elapsed.as_secs_f64();
-
-        println!("GPU Performance:");
-        println!(
+        say!("GPU Performance:");
+        say!(
             "  Total time: {:.2} ms for {} iterations",
-            time_ms, iterations
+            timing.total_ms(),
+            iterations
+        );
+        say!("  Per iteration: {:.3} ms", timing.ms_per_step());
+        say!("  Throughput: {:.2e} sites/sec", timing.sites_per_sec());
+        say!("  GB/sec (read+write): {:.2}", timing.gb_per_sec());
+        say!("  Final energy: {} quanta", final_energy);
+
+        let transfers = lattice.transfer_stats();
+        say!(
+            "  Host transfers: {} bytes up, {} bytes down in {} readbacks ({:.2} ms)",
+            transfers.bytes_uploaded,
+            transfers.bytes_downloaded,
+            transfers.downloads,
+            transfers.download_time.as_secs_f64() * 1000.0
         );
-        println!("  Per iteration: {:.3} ms", avg_ms);
-        println!("  Throughput: {:.2e} sites/sec", throughput);
-        println!("  GB/sec (read+write): {:.2}", (throughput * 8.0) / 1e9);
-        println!("  Final energy: {} quanta", final_energy);
+
+        #[cfg(feature = "profiling")]
+        match pollster::block_on(lattice.profile_step()) {
+            Some(timings) => {
+                if let Some(copy) = timings.copy {
+                    say!("  GPU copy pass: {:.3} ms", copy.as_secs_f64() * 1000.0);
+                }
+                say!(
+                    "  GPU propagate pass: {:.3} ms",
+                    timings.propagate.as_secs_f64() * 1000.0
+                );
+            }
+            None => {
+                say!("  GPU pass times: timestamp queries unsupported");
+            }
+        }
 
         if final_energy != initial_energy {
-            println!("  ⚠ Energy drift: {} -> {}", initial_energy, final_energy);
+            say!("  ⚠ Energy drift: {} -> {}", initial_energy, final_energy);
         } else {
-            println!("  ✓ Energy conserved");
+            say!("  ✓ Energy conserved");
         }
 
-        println!("\n{}\n", "=".repeat(60));
+        say!("\n{}\n", "=".repeat(60));
+
+        let info = lattice.adapter_info();
+        records.push(BenchRecord {
+            size,
+            sites: total_sites,
+            iterations,
+            workgroup_size,
+            ms_per_iter: timing.ms_per_step(),
+            sites_per_sec: timing.sites_per_sec(),
+            gb_per_sec: timing.gb_per_sec(),
+            initial_energy,
+            final_energy,
+            energy_conserved: final_energy == initial_energy,
+            adapter: info.map_or_else(String::new, |info| info.name.clone()),
+            backend: info.map_or_else(String::new, |info| info.backend.to_str().to_string()),
+            driver: info.map_or_else(String::new, |info| {
+                format!("{} {}", info.driver, info.driver_info)
+                    .trim()
+                    .to_string()
+            }),
+        });
     }
 
-    println!("GPU compute complete!");
-    println!("\nNote: GPU runs MILLIONS of threads in parallel");
-    println!("      Each site computed simultaneously");
-    println!("      Expect 100-1000x speedup vs CPU");
+    say!("GPU compute complete!");
+    say!("\nNote: GPU runs MILLIONS of threads in parallel");
+    say!("      Each site computed simultaneously");
+    say!("      Expect 100-1000x speedup vs CPU");
+
+    let report = match args.format {
+        BenchFormat::Text => return Ok(()),
+        BenchFormat::Json => {
+            serde_json::to_string_pretty(&records).expect("Records always serialize") + "\n"
+        }
+        BenchFormat::Csv => {
+            let mut csv = CSV_HEADER.to_string() + "\n";
+            for record in &records {
+                csv += &record.csv_row();
+                csv.push('\n');
+            }
+            csv
+        }
+    };
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, report).map_err(|err| format!("{}: {}", path.display(), err))
+        }
+        None => {
+            print!("{}", report);
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,200 @@
+// Prometheus metrics for long-running simulations
+//
+// A lattice running unattended on a shared machine is monitored like any
+// other service: a Prometheus server scrapes an HTTP endpoint for the
+// current values. LatticeMetrics holds the gauges, the run updates them
+// from its diagnostics as it goes, and serve answers every request with
+// the text exposition format. The endpoint is a plain std listener on its
+// own thread, so it needs no async runtime and never touches the GPU.
+
+use crate::{Diagnostics, DiscreteLatticeGPU};
+use prometheus::{Encoder, Gauge, IntGauge, Registry, TextEncoder};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// How long a scrape may take to send its request or read the response. The
+// endpoint answers one connection at a time, so this bounds how long an
+// idle or stalled client can hold up later scrapes and shutdown
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Gauges describing a running simulation, for export to Prometheus.
+///
+/// Clones share the same gauges, so one clone can be updated by the run
+/// while another is served by [`serve`](Self::serve).
+#[derive(Clone)]
+pub struct LatticeMetrics {
+    registry: Registry,
+    steps_per_second: Gauge,
+    generation: IntGauge,
+    total_energy: IntGauge,
+    gpu_memory_bytes: IntGauge,
+    queue_depth: IntGauge,
+}
+
+impl LatticeMetrics {
+    pub fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("walkthe".into()), None).expect("Metric prefix is valid");
+        let steps_per_second =
+            Gauge::new("steps_per_second", "Steps per second of the run").expect("Metric is valid");
+        let generation =
+            IntGauge::new("generation", "Steps the lattice has taken").expect("Metric is valid");
+        let total_energy =
+            IntGauge::new("total_energy", "Quanta on the lattice").expect("Metric is valid");
+        let gpu_memory_bytes = IntGauge::new(
+            "gpu_memory_bytes",
+            "Bytes of GPU buffer memory held by the lattice",
+        )
+        .expect("Metric is valid");
+        let queue_depth =
+            IntGauge::new("queue_depth", "Simulations waiting to run").expect("Metric is valid");
+        for metric in [&generation, &total_energy, &gpu_memory_bytes, &queue_depth] {
+            registry
+                .register(Box::new(metric.clone()))
+                .expect("Metric names are unique");
+        }
+        registry
+            .register(Box::new(steps_per_second.clone()))
+            .expect("Metric names are unique");
+
+        Self {
+            registry,
+            steps_per_second,
+            generation,
+            total_energy,
+            gpu_memory_bytes,
+            queue_depth,
+        }
+    }
+
+    /// The registry holding the gauges, to serve them from an existing
+    /// HTTP server or add more metrics alongside.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Updates the step rate, generation and total energy from the
+    /// `diagnostics` of a run, e.g. in the observer of
+    /// [`DiscreteLatticeGPU::propagate_observed`].
+    pub fn observe(&self, diagnostics: &Diagnostics) {
+        let seconds = diagnostics.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.steps_per_second.set(diagnostics.step as f64 / seconds);
+        }
+        self.generation.set(diagnostics.generation as i64);
+        self.total_energy.set(diagnostics.total_energy as i64);
+    }
+
+    /// Sets the GPU memory gauge to `lattice`'s
+    /// [`memory_usage_bytes`](DiscreteLatticeGPU::memory_usage_bytes). It
+    /// only changes when buffers are added, e.g. by recording history, so
+    /// once before the run is usually enough.
+    pub fn observe_memory(&self, lattice: &DiscreteLatticeGPU) {
+        self.gpu_memory_bytes
+            .set(lattice.memory_usage_bytes() as i64);
+    }
+
+    /// Sets the number of simulations queued behind the current one.
+    pub fn set_queue_depth(&self, depth: usize) {
+        self.queue_depth.set(depth as i64);
+    }
+
+    /// The current values in Prometheus' text exposition format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Writing to a Vec can't fail");
+        String::from_utf8(buffer).expect("The text format is UTF-8")
+    }
+
+    /// Serves the metrics over HTTP at `addr` from a background thread
+    /// until the returned server is dropped. Every path answers with
+    /// [`encode`](Self::encode), so `/metrics` works as Prometheus expects.
+    ///
+    /// Bind to port 0 to let the OS pick a free port, then read it from
+    /// [`MetricsServer::local_addr`].
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> io::Result<MetricsServer> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let metrics = self.clone();
+            let stopped = stopped.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::Acquire) {
+                        break;
+                    }
+                    // A client that hangs up early only loses its own response
+                    if let Ok(stream) = stream {
+                        let _ = metrics.respond(stream);
+                    }
+                }
+            })
+        };
+        Ok(MetricsServer {
+            local_addr,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    // Read the request head and answer with the current metrics
+    fn respond(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 && line != "\r\n" && line != "\n" {
+            line.clear();
+        }
+        let body = self.encode();
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            TextEncoder::new().format_type(),
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
+impl Default for LatticeMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The HTTP endpoint started by [`LatticeMetrics::serve`]. Dropping it
+/// stops the endpoint and waits for its thread to finish.
+pub struct MetricsServer {
+    local_addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    /// The address the endpoint is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        // Wake the listener so it sees the flag
+        let _ = TcpStream::connect(self.local_addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
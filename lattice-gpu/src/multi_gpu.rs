@@ -0,0 +1,201 @@
+// Lattices split across several GPUs
+//
+// The same Z-slab chunks as ChunkedLattice, dealt out one per device.
+// Devices can't copy between each other's buffers, so before each step the
+// outermost layers of every slab are read back and uploaded into its
+// neighbors' halos. That costs 4 layers of readback and upload per chunk
+// and step, whatever the lattice's depth.
+
+use crate::{
+    default_adapter, request_device, BoundaryMode, BoundingBox, ChunkedLattice, LatticeError,
+    Neighborhood, PropagationMode,
+};
+use std::sync::Arc;
+
+// Chunks are split no thinner than this, so each can fill both neighbors'
+// halos
+const MIN_CHUNK_DEPTH: u32 = 4;
+
+/// A lattice split along Z across every available GPU.
+///
+/// Each device holds one slab as a [`DiscreteLatticeGPU`](crate::DiscreteLatticeGPU)
+/// with halo copies of its neighbors' boundary layers, exactly as in
+/// [`ChunkedLattice`]; the result matches one lattice of the full size bit
+/// for bit. The halos are exchanged through host memory before every step,
+/// so stepping waits on each device in turn and pays off only when each
+/// slab is large next to its 4 boundary layers.
+///
+/// Supports the same settings as [`ChunkedLattice`]: those that apply to
+/// every site alike.
+pub struct MultiGpuLattice {
+    chunks: ChunkedLattice,
+}
+
+impl MultiGpuLattice {
+    /// Creates a lattice split across every adapter on the default
+    /// adapter's backend.
+    ///
+    /// Software adapters are left out when a hardware one is present, since
+    /// every step waits for the slowest device. A browser exposes only its
+    /// default adapter. Fails with [`LatticeError::AdapterNotFound`] if
+    /// there is no adapter at all.
+    pub async fn new(width: u32, height: u32, depth: u32) -> Result<Self, LatticeError> {
+        let primary = default_adapter().await?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let adapters = same_backend_adapters(&primary);
+        #[cfg(target_arch = "wasm32")]
+        let adapters = vec![primary];
+
+        let mut devices = Vec::with_capacity(adapters.len());
+        for adapter in &adapters {
+            devices.push(request_device(adapter).await?);
+        }
+        if devices.is_empty() {
+            return Err(LatticeError::AdapterNotFound);
+        }
+        Self::new_with_devices(devices, width, height, depth)
+    }
+
+    /// Builds the lattice across existing devices, one slab each.
+    ///
+    /// The layers are shared out evenly. A lattice too shallow for every
+    /// device to get 4 layers uses only as many devices as it can; with
+    /// one, it runs exactly like a plain
+    /// [`DiscreteLatticeGPU`](crate::DiscreteLatticeGPU).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `devices` is empty.
+    pub fn new_with_devices(
+        devices: Vec<(Arc<wgpu::Device>, Arc<wgpu::Queue>)>,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<Self, LatticeError> {
+        assert!(!devices.is_empty(), "At least one device is required");
+        let count = (devices.len() as u32).min(depth / MIN_CHUNK_DEPTH).max(1);
+        let chunks = ChunkedLattice::new_across(&devices, (width, height, depth), count)?;
+        Ok(Self { chunks })
+    }
+
+    /// `(width, height, depth)` of the whole lattice, in sites.
+    pub fn dimensions(&self) -> (u32, u32, u32) {
+        self.chunks.dimensions()
+    }
+
+    /// Number of devices the lattice is split across.
+    pub fn device_count(&self) -> usize {
+        self.chunks.chunk_count()
+    }
+
+    /// Steps taken so far.
+    pub fn generation(&self) -> u64 {
+        self.chunks.generation()
+    }
+
+    /// Empties every site.
+    pub fn initialize_vacuum(&mut self) {
+        self.chunks.initialize_vacuum();
+    }
+
+    pub fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        self.chunks.add_energy_quantum(x, y, z, quanta);
+    }
+
+    /// Adds quanta to many sites, skipping points outside the lattice, as
+    /// [`DiscreteLatticeGPU::add_energy_batch`](crate::DiscreteLatticeGPU::add_energy_batch)
+    /// does.
+    pub fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        self.chunks.add_energy_batch(edits);
+    }
+
+    /// Replaces the energy of every site with `energy`, in
+    /// [`get_state`](Self::get_state) layout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `energy` does not hold one value per site.
+    pub fn set_state(&mut self, energy: &[u32]) {
+        self.chunks.set_state(energy);
+    }
+
+    /// Exchanges the halos through the host, then advances every device
+    /// one step.
+    pub async fn propagate_energy(&mut self) {
+        self.chunks.exchange_halos_through_host().await;
+        self.chunks.step_chunks();
+    }
+
+    /// Downloads the energy of every site, indexed
+    /// `z * width * height + y * width + x`.
+    pub async fn get_state(&self) -> Vec<u32> {
+        self.chunks.get_state().await
+    }
+
+    /// Total quanta on the lattice, reduced on each device.
+    pub async fn get_total_energy(&self) -> u64 {
+        self.chunks.get_total_energy().await
+    }
+
+    /// Downloads only the sites inside `region`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `region` extends past the lattice.
+    pub async fn get_energy_region(&self, region: BoundingBox) -> Vec<u32> {
+        self.chunks.get_energy_region(region).await
+    }
+
+    pub fn set_propagation_mode(&mut self, mode: PropagationMode) {
+        self.chunks.set_propagation_mode(mode);
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.chunks.set_neighborhood(neighborhood);
+    }
+
+    pub fn set_axis_weights(&mut self, wx: f32, wy: f32, wz: f32) {
+        self.chunks.set_axis_weights(wx, wy, wz);
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.chunks.set_seed(seed);
+    }
+
+    pub fn set_decay_rate(&mut self, rate: f32) {
+        self.chunks.set_decay_rate(rate);
+    }
+
+    pub fn set_workgroup_size(&mut self, size: [u32; 3]) -> Result<(), LatticeError> {
+        self.chunks.set_workgroup_size(size)
+    }
+
+    /// Sets the boundary mode of each axis. Switching Z between periodic
+    /// and any other mode rebuilds the slabs on their devices, as
+    /// [`ChunkedLattice::set_axis_boundary_modes`] does.
+    pub fn set_axis_boundary_modes(
+        &mut self,
+        x: BoundaryMode,
+        y: BoundaryMode,
+        z: BoundaryMode,
+    ) -> Result<(), LatticeError> {
+        self.chunks.set_axis_boundary_modes(x, y, z)
+    }
+}
+
+// Every adapter on `primary`'s backend, without software ones if there is
+// hardware. Listing one backend keeps the same GPU from appearing once per
+// API
+#[cfg(not(target_arch = "wasm32"))]
+fn same_backend_adapters(primary: &wgpu::Adapter) -> Vec<wgpu::Adapter> {
+    let backend = primary.get_info().backend;
+    let instance = wgpu::Instance::default();
+    let mut adapters = instance.enumerate_adapters(backend.into());
+    if adapters
+        .iter()
+        .any(|adapter| adapter.get_info().device_type != wgpu::DeviceType::Cpu)
+    {
+        adapters.retain(|adapter| adapter.get_info().device_type != wgpu::DeviceType::Cpu);
+    }
+    adapters
+}
@@ -0,0 +1,255 @@
+// Lattices larger than the GPU's memory
+//
+// The state lives in host memory. Each step, the lattice is streamed
+// through one GPU lattice a Z-slab at a time: a window of the slab plus
+// HALO_LAYERS ghost layers on each side is uploaded, stepped once, and the
+// slab's layers are read back into the next host state. Ghost layers come
+// from the same host state as the slab, so unlike ChunkedLattice there is
+// nothing to exchange, and the result matches one big lattice bit for bit.
+
+use crate::chunked::{max_chunk_depth, HALO_LAYERS};
+use crate::{
+    default_adapter, request_device, BoundaryMode, DiscreteLatticeGPU, LatticeError, Neighborhood,
+    PropagationMode, MAX_LEVEL,
+};
+use std::sync::Arc;
+
+/// A lattice kept in host memory and streamed through the GPU in Z-slabs,
+/// for lattices that don't fit in VRAM at all.
+///
+/// Every step uploads the whole lattice plus a few ghost layers per slab
+/// and reads it back, so a step is bound by host transfer bandwidth and
+/// runs far slower than on a GPU-resident lattice; use
+/// [`ChunkedLattice`](crate::ChunkedLattice) when the lattice fits in VRAM
+/// but not in one buffer. The host holds two copies of the state, 8 bytes
+/// per site.
+///
+/// Supports the same settings as [`ChunkedLattice`](crate::ChunkedLattice):
+/// those that apply to every site alike.
+pub struct OutOfCoreLattice {
+    // Steps one window at a time; sized for the deepest window
+    lattice: DiscreteLatticeGPU,
+    width: u32,
+    height: u32,
+    depth: u32,
+    slabs: u32,
+    // Host state, and the buffer the next step is read back into
+    state: Vec<u32>,
+    next: Vec<u32>,
+    generation: u64,
+}
+
+impl OutOfCoreLattice {
+    /// Creates a lattice on the default high-performance adapter, streamed
+    /// in as few slabs as the device's buffer limits allow.
+    ///
+    /// Fails with [`LatticeError::BufferTooLarge`] if even the thinnest
+    /// slab would exceed those limits.
+    pub async fn new(width: u32, height: u32, depth: u32) -> Result<Self, LatticeError> {
+        let adapter = default_adapter().await?;
+        let (device, queue) = request_device(&adapter).await?;
+        let max_slab_depth = max_chunk_depth(&device, width, height, depth)?;
+        Self::new_with_device(device, queue, width, height, depth, max_slab_depth)
+    }
+
+    /// Builds the lattice on an existing device, streaming at most
+    /// `max_slab_depth` layers per slab, not counting ghost layers.
+    ///
+    /// The layers are shared out evenly between slabs. If the lattice fits
+    /// in one window it is stepped whole, without ghost layers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lattice needs more than one slab and `max_slab_depth`
+    /// is less than 4.
+    pub fn new_with_device(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        width: u32,
+        height: u32,
+        depth: u32,
+        max_slab_depth: u32,
+    ) -> Result<Self, LatticeError> {
+        let mut slabs = if max_slab_depth >= depth {
+            1
+        } else {
+            assert!(
+                max_slab_depth >= 2 * HALO_LAYERS,
+                "Slabs must be at least {} layers deep",
+                2 * HALO_LAYERS
+            );
+            depth.div_ceil(max_slab_depth)
+        };
+        let mut window = depth.div_ceil(slabs) + 2 * HALO_LAYERS;
+        if slabs == 1 || window >= depth {
+            slabs = 1;
+            window = depth;
+        }
+
+        let mut lattice =
+            DiscreteLatticeGPU::new_with_device(device, queue, width, height, window)?;
+        lattice.lattice_depth = depth;
+        let sites = width as usize * height as usize * depth as usize;
+        Ok(Self {
+            lattice,
+            width,
+            height,
+            depth,
+            slabs,
+            state: vec![0; sites],
+            next: vec![0; sites],
+            generation: 0,
+        })
+    }
+
+    /// `(width, height, depth)` of the whole lattice, in sites.
+    pub fn dimensions(&self) -> (u32, u32, u32) {
+        (self.width, self.height, self.depth)
+    }
+
+    /// Number of slabs streamed per step.
+    pub fn slab_count(&self) -> u32 {
+        self.slabs
+    }
+
+    /// Steps taken so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Empties every site.
+    pub fn initialize_vacuum(&mut self) {
+        self.state.fill(0);
+    }
+
+    pub fn add_energy_quantum(&mut self, x: u32, y: u32, z: u32, quanta: u32) {
+        self.add_energy_batch(&[(x, y, z, quanta)]);
+    }
+
+    /// Adds quanta to many sites in host memory, skipping points outside the
+    /// lattice. Sites are capped at [`MAX_LEVEL`].
+    pub fn add_energy_batch(&mut self, edits: &[(u32, u32, u32, u32)]) {
+        for &(x, y, z, quanta) in edits {
+            if x < self.width && y < self.height && z < self.depth {
+                let idx = self.index(x, y, z);
+                let old = self.state[idx];
+                self.state[idx] = old.saturating_add(quanta).min(MAX_LEVEL).max(old);
+            }
+        }
+    }
+
+    /// Replaces the energy of every site, indexed
+    /// `z * width * height + y * width + x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `energy` does not hold one value per site.
+    pub fn set_state(&mut self, energy: &[u32]) {
+        assert_eq!(
+            energy.len(),
+            self.state.len(),
+            "State must have one value per site"
+        );
+        self.state.copy_from_slice(energy);
+    }
+
+    /// Energy of every site, indexed `z * width * height + y * width + x`.
+    /// Already in host memory, so nothing is read back.
+    pub fn get_state(&self) -> &[u32] {
+        &self.state
+    }
+
+    /// Total quanta on the lattice, summed in host memory.
+    pub fn get_total_energy(&self) -> u64 {
+        self.state.iter().map(|&energy| energy as u64).sum()
+    }
+
+    /// Advances one step, streaming every slab through the GPU.
+    pub async fn propagate_energy(&mut self) {
+        let layer = self.width as usize * self.height as usize;
+        let split = |slab: u32| (slab as u64 * self.depth as u64 / self.slabs as u64) as u32;
+        for slab in 0..self.slabs {
+            let (z_start, z_end) = (split(slab), split(slab + 1));
+            let start = self.window_start(z_start);
+            self.upload_window(start);
+            self.lattice.z_offset = start;
+            self.lattice.set_step_count(self.generation);
+            self.lattice.propagate_energy();
+
+            let window = self.lattice.get_state().await;
+            let offset = ((z_start + self.depth - start) % self.depth) as usize * layer;
+            let slab_sites = (z_end - z_start) as usize * layer;
+            self.next[z_start as usize * layer..][..slab_sites]
+                .copy_from_slice(&window[offset..offset + slab_sites]);
+        }
+
+        std::mem::swap(&mut self.state, &mut self.next);
+        self.generation += 1;
+    }
+
+    // First global layer of the window for the slab starting at `z_start`.
+    // A periodic window reaches ghost layers across the wrap; otherwise it
+    // is kept inside the lattice, so a slab on a face gets the real face
+    // and extra ghost layers on its other side
+    fn window_start(&self, z_start: u32) -> u32 {
+        if self.slabs == 1 {
+            0
+        } else if self.lattice.boundary_modes[2] == BoundaryMode::Periodic {
+            (z_start + self.depth - HALO_LAYERS) % self.depth
+        } else {
+            z_start
+                .saturating_sub(HALO_LAYERS)
+                .min(self.depth - self.lattice.depth)
+        }
+    }
+
+    // Upload the window's layers straight from the host state: one range,
+    // or two when a periodic window wraps past the last layer
+    fn upload_window(&self, start: u32) {
+        let layer = self.width as usize * self.height as usize;
+        let window = self.lattice.depth;
+        let head = window.min(self.depth - start);
+        let buffer = self.lattice.get_energy_buffer();
+        let head_sites = &self.state[start as usize * layer..][..head as usize * layer];
+        self.lattice
+            .upload_at(buffer, 0, bytemuck::cast_slice(head_sites));
+        if head < window {
+            let tail_sites = &self.state[..(window - head) as usize * layer];
+            let offset = std::mem::size_of_val(head_sites) as u64;
+            self.lattice
+                .upload_at(buffer, offset, bytemuck::cast_slice(tail_sites));
+        }
+    }
+
+    fn index(&self, x: u32, y: u32, z: u32) -> usize {
+        (z as usize * self.height as usize + y as usize) * self.width as usize + x as usize
+    }
+
+    pub fn set_propagation_mode(&mut self, mode: PropagationMode) {
+        self.lattice.set_propagation_mode(mode);
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.lattice.set_neighborhood(neighborhood);
+    }
+
+    pub fn set_axis_boundary_modes(&mut self, x: BoundaryMode, y: BoundaryMode, z: BoundaryMode) {
+        self.lattice.set_axis_boundary_modes(x, y, z);
+    }
+
+    pub fn set_axis_weights(&mut self, wx: f32, wy: f32, wz: f32) {
+        self.lattice.set_axis_weights(wx, wy, wz);
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.lattice.set_seed(seed);
+    }
+
+    pub fn set_decay_rate(&mut self, rate: f32) {
+        self.lattice.set_decay_rate(rate);
+    }
+
+    pub fn set_workgroup_size(&mut self, size: [u32; 3]) -> Result<(), LatticeError> {
+        self.lattice.set_workgroup_size(size)
+    }
+}
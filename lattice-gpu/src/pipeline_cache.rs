@@ -0,0 +1,58 @@
+// Compiled pipelines kept on disk between runs
+//
+// wgpu can hand the driver's pipeline cache back as bytes and seed a new
+// cache from them, so a later run skips recompiling the same shaders. Only
+// Vulkan supports this so far; elsewhere there is no cache and pipelines
+// compile as usual.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+// A pipeline cache and the file it is saved to
+#[derive(Debug)]
+pub(crate) struct PipelineCacheFile {
+    pub(crate) cache: wgpu::PipelineCache,
+    path: PathBuf,
+}
+
+impl PipelineCacheFile {
+    // The cache for `adapter` in `dir`, seeded from an earlier save if
+    // there is one, or None if the device can't cache pipelines
+    pub(crate) fn open(
+        device: &wgpu::Device,
+        adapter: &wgpu::AdapterInfo,
+        dir: &Path,
+    ) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+            return None;
+        }
+        let path = dir.join(wgpu::util::pipeline_cache_key(adapter)?);
+        let data = std::fs::read(&path).ok();
+
+        // SAFETY: the data was written by save() from the cache of an
+        // adapter with the same key, and with fallback set, wgpu starts an
+        // empty cache instead if the driver rejects it
+        let cache = unsafe {
+            device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some("Pipeline Cache"),
+                data: data.as_deref(),
+                fallback: true,
+            })
+        };
+        Some(Self { cache, path })
+    }
+
+    // Write the cache's current contents back to its file, through a
+    // temporary file so a crash never leaves a truncated cache behind
+    pub(crate) fn save(&self) -> io::Result<()> {
+        let Some(data) = self.cache.get_data() else {
+            return Ok(());
+        };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp = self.path.with_extension("tmp");
+        std::fs::write(&temp, data)?;
+        std::fs::rename(&temp, &self.path)
+    }
+}
@@ -0,0 +1,162 @@
+// Observing and stopping long runs
+//
+// propagate_n submits its steps without ever waiting on the GPU, so nothing
+// about a run is known on the host until something is read back, and once
+// submitted the steps can't be taken back. Observed and cancellable runs
+// split the steps into batches and sync with the GPU after each: an
+// observed run reads back a few counters for the caller's observer, and a
+// cancellable run waits for the batch and checks its token. The batch size
+// trades how quickly the host hears from the run against how far the GPU
+// can run ahead.
+
+use crate::DiscreteLatticeGPU;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use web_time::Instant;
+
+/// A flag for stopping a run from another thread, e.g. a GUI's stop button
+/// or a server's shutdown handler.
+///
+/// Clones share the flag, so keep one and hand a clone to the run. Once
+/// cancelled, a token stays cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every run holding this token to stop after its current batch.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+/// The state of a run after a batch of steps, as passed to the observer of
+/// [`DiscreteLatticeGPU::propagate_observed`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Steps of this run taken so far.
+    pub step: u32,
+    /// Steps the run was asked to take.
+    pub steps: u32,
+    /// The lattice's generation after the batch.
+    pub generation: u64,
+    pub total_energy: u64,
+    pub absorbed_energy: u64,
+    pub decayed_energy: u64,
+    pub injected_energy: u64,
+    /// Whether `total + absorbed + decayed - injected` still equals its
+    /// value when the run started, i.e. no quanta have been created or lost
+    /// unaccounted.
+    pub conserved: bool,
+    /// Wall-clock time since the run started.
+    pub elapsed: Duration,
+}
+
+impl Diagnostics {
+    /// Fraction of the run done, from 0 to 1.
+    pub fn progress(&self) -> f64 {
+        if self.steps == 0 {
+            1.0
+        } else {
+            self.step as f64 / self.steps as f64
+        }
+    }
+}
+
+impl DiscreteLatticeGPU {
+    /// Propagates `steps` steps in batches of `interval`, calling `observer`
+    /// after each batch with the run's [`Diagnostics`]. Returns the number
+    /// of steps taken.
+    ///
+    /// The observer returns [`ControlFlow::Break`] to stop the run after the
+    /// batch it was shown, or [`ControlFlow::Continue`] to go on. Each
+    /// batch is submitted as [`propagate_n`](Self::propagate_n) would, and
+    /// the same number of steps produces the same state; the only extra
+    /// cost is one small readback per batch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, observer)))]
+    pub async fn propagate_observed<F>(&mut self, steps: u32, interval: u32, mut observer: F) -> u32
+    where
+        F: FnMut(&Diagnostics) -> ControlFlow<()>,
+    {
+        assert!(interval > 0, "Observation interval must be positive");
+        let start = Instant::now();
+        let balance = |d: &Diagnostics| {
+            (d.total_energy + d.absorbed_energy + d.decayed_energy) as i128
+                - d.injected_energy as i128
+        };
+        let initial = balance(&self.diagnostics(0, steps, start).await);
+
+        let mut step = 0;
+        while step < steps {
+            let batch = interval.min(steps - step);
+            self.propagate_n(batch);
+            step += batch;
+
+            let mut diagnostics = self.diagnostics(step, steps, start).await;
+            diagnostics.conserved = balance(&diagnostics) == initial;
+            if observer(&diagnostics).is_break() {
+                break;
+            }
+        }
+        step
+    }
+
+    /// Propagates `steps` steps in batches of `batch`, stopping early once
+    /// `token` is cancelled. Returns the number of steps taken.
+    ///
+    /// The token is checked before each batch is submitted, and each batch
+    /// is waited for before the next, so a cancelled run returns within
+    /// one batch with the GPU idle and the lattice ready to be saved. The
+    /// steps taken produce the same state as
+    /// [`propagate_n`](Self::propagate_n) would.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch` is zero.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, token)))]
+    pub async fn propagate_cancellable(
+        &mut self,
+        steps: u32,
+        batch: u32,
+        token: &CancellationToken,
+    ) -> u32 {
+        assert!(batch > 0, "Batch size must be positive");
+        let mut step = 0;
+        while step < steps && !token.is_cancelled() {
+            let size = batch.min(steps - step);
+            self.propagate_n(size);
+            self.wait_idle().await;
+            step += size;
+        }
+        step
+    }
+
+    async fn diagnostics(&self, step: u32, steps: u32, start: Instant) -> Diagnostics {
+        Diagnostics {
+            step,
+            steps,
+            generation: self.generation,
+            total_energy: self.get_total_energy().await,
+            absorbed_energy: self.absorbed_energy().await,
+            decayed_energy: self.decayed_energy().await,
+            injected_energy: self.injected_energy().await,
+            conserved: true,
+            elapsed: start.elapsed(),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+// Complex-amplitude quantum walk
+//
+// An alternative to the quanta lattice: each site stores a complex
+// amplitude per direction and every step is a unitary Grover walk step
+// (see quantum_walk.wgsl). Amplitudes live in two ping-pong buffers like
+// the energy buffers of DiscreteLatticeGPU.
+
+use crate::workgroup::{check_workgroup_size, WORKGROUP_SIZE};
+use crate::LatticeError;
+use bytemuck::{Pod, Zeroable};
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+/// Amplitudes per site, one for each of +X, -X, +Y, -Y, +Z, -Z.
+pub const WALK_DIRECTIONS: usize = 6;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct WalkParams {
+    width: u32,
+    height: u32,
+    depth: u32,
+    _padding: u32,
+}
+
+pub struct QuantumWalk {
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+    step_pipeline: wgpu::ComputePipeline,
+    probability_pipeline: wgpu::ComputePipeline,
+    // bind_groups[p] reads amplitude buffer p and writes the other one
+    bind_groups: [wgpu::BindGroup; 2],
+    amplitude_buffers: [wgpu::Buffer; 2],
+    probability_buffer: wgpu::Buffer,
+    staging_buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    depth: u32,
+    total_sites: usize,
+    generation: u64,
+    // Which amplitude buffer holds the current state
+    current: usize,
+}
+
+impl QuantumWalk {
+    /// Creates a walk on the default high-performance adapter with its own
+    /// device. All amplitudes start at zero.
+    pub async fn new(width: u32, height: u32, depth: u32) -> Result<Self, LatticeError> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .ok_or(LatticeError::AdapterNotFound)?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Quantum Walk GPU"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits {
+                        max_storage_buffer_binding_size: adapter
+                            .limits()
+                            .max_storage_buffer_binding_size,
+                        max_buffer_size: adapter.limits().max_buffer_size,
+                        ..Default::default()
+                    },
+                    memory_hints: Default::default(),
+                },
+                None,
+            )
+            .await?;
+
+        Self::new_with_device(Arc::new(device), Arc::new(queue), width, height, depth)
+    }
+
+    /// Builds the walk on an existing device.
+    ///
+    /// Fails with [`LatticeError::BufferTooLarge`] if the amplitude buffer
+    /// (8 bytes per direction per site) would exceed the device's buffer
+    /// limits.
+    pub fn new_with_device(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<Self, LatticeError> {
+        let limits = device.limits();
+        check_workgroup_size(WORKGROUP_SIZE, &limits)?;
+        let total_sites = width as u64 * height as u64 * depth as u64;
+        let amplitude_bytes = total_sites * WALK_DIRECTIONS as u64 * 8;
+        let max = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        if amplitude_bytes > max {
+            return Err(LatticeError::BufferTooLarge {
+                requested: amplitude_bytes,
+                max,
+            });
+        }
+
+        let params = WalkParams {
+            width,
+            height,
+            depth,
+            _padding: 0,
+        };
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Walk Params Buffer"),
+            contents: bytemuck::cast_slice(&[params]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let amplitude_buffers = ["Amplitude Buffer A", "Amplitude Buffer B"].map(|label| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: amplitude_bytes,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        });
+
+        let probability_bytes = total_sites * 4;
+        let probability_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Probability Buffer"),
+            size: probability_bytes,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Probability Staging Buffer"),
+            size: probability_bytes,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Walk Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, false),
+                storage_entry(3, false),
+            ],
+        });
+
+        let bind_groups = [0, 1].map(|current| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Walk Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: amplitude_buffers[current].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: amplitude_buffers[1 - current].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: probability_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Walk Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Quantum Walk Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("quantum_walk.wgsl").into()),
+        });
+        let pipeline = |label, entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let step_pipeline = pipeline("Walk Step Pipeline", "walk_step");
+        let probability_pipeline = pipeline("Probability Pipeline", "probabilities");
+
+        Ok(Self {
+            device,
+            queue,
+            step_pipeline,
+            probability_pipeline,
+            bind_groups,
+            amplitude_buffers,
+            probability_buffer,
+            staging_buffer,
+            width,
+            height,
+            depth,
+            total_sites: total_sites as usize,
+            generation: 0,
+            current: 0,
+        })
+    }
+
+    /// Replaces the state, with amplitudes given as `(re, im)` and indexed
+    /// `site * WALK_DIRECTIONS + direction`, where sites are indexed
+    /// `z * width * height + y * width + x` and directions run +X, -X, +Y,
+    /// -Y, +Z, -Z. The state should be normalized; the walk preserves its
+    /// norm either way.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `amplitudes` does not hold `WALK_DIRECTIONS` entries per
+    /// site.
+    pub fn set_amplitudes(&mut self, amplitudes: &[[f32; 2]]) {
+        assert_eq!(
+            amplitudes.len(),
+            self.total_sites * WALK_DIRECTIONS,
+            "Expected {} amplitudes per site",
+            WALK_DIRECTIONS
+        );
+        self.queue.write_buffer(
+            &self.amplitude_buffers[self.current],
+            0,
+            bytemuck::cast_slice(amplitudes),
+        );
+        self.generation = 0;
+    }
+
+    /// Places the walker at one site, with equal real amplitudes
+    /// `1/sqrt(6)` in every direction and zero everywhere else.
+    pub fn set_localized(&mut self, x: u32, y: u32, z: u32) {
+        assert!(
+            x < self.width && y < self.height && z < self.depth,
+            "Site ({}, {}, {}) is outside the lattice",
+            x,
+            y,
+            z
+        );
+        let mut amplitudes = vec![[0.0f32; 2]; self.total_sites * WALK_DIRECTIONS];
+        let idx = ((z * self.height + y) * self.width + x) as usize;
+        let amplitude = 1.0 / (WALK_DIRECTIONS as f32).sqrt();
+        for a in &mut amplitudes[idx * WALK_DIRECTIONS..(idx + 1) * WALK_DIRECTIONS] {
+            *a = [amplitude, 0.0];
+        }
+        self.set_amplitudes(&amplitudes);
+    }
+
+    /// Applies one unitary walk step.
+    pub fn step(&mut self) {
+        self.step_n(1);
+    }
+
+    /// Applies `steps` walk steps in one submission.
+    pub fn step_n(&mut self, steps: u32) {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        for _ in 0..steps {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Walk Step"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.step_pipeline);
+            pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+            let [x, y, z] = self.workgroups();
+            pass.dispatch_workgroups(x, y, z);
+            drop(pass);
+            self.current = 1 - self.current;
+        }
+        self.queue.submit(Some(encoder.finish()));
+        self.generation += steps as u64;
+    }
+
+    /// Walk steps applied since the state was last set.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Probability of finding the walker at each site, i.e. the sum of
+    /// `|amplitude|²` over its directions, indexed
+    /// `z * width * height + y * width + x`.
+    pub async fn get_probability_distribution(&self) -> Vec<f32> {
+        let size = (self.total_sites * 4) as u64;
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Probabilities"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.probability_pipeline);
+            pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+            let [x, y, z] = self.workgroups();
+            pass.dispatch_workgroups(x, y, z);
+        }
+        encoder.copy_buffer_to_buffer(&self.probability_buffer, 0, &self.staging_buffer, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = self.staging_buffer.slice(..size);
+        let (sender, receiver) = flume::bounded(1);
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv_async().await.unwrap().unwrap();
+
+        let data = buffer_slice.get_mapped_range();
+        let values = bytemuck::cast_slice(&data).to_vec();
+        drop(data);
+        self.staging_buffer.unmap();
+        values
+    }
+
+    fn workgroups(&self) -> [u32; 3] {
+        [
+            self.width.div_ceil(WORKGROUP_SIZE[0]),
+            self.height.div_ceil(WORKGROUP_SIZE[1]),
+            self.depth.div_ceil(WORKGROUP_SIZE[2]),
+        ]
+    }
+}
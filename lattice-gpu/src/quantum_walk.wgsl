@@ -0,0 +1,84 @@
+// Discrete-time quantum walk
+//
+// Each site holds one complex amplitude per direction (+X, -X, +Y, -Y, +Z,
+// -Z), indexed site * 6 + dir. A step applies the Grover coin at every site
+// and then shifts each amplitude one site along its direction. Both are
+// unitary, so the total probability stays 1. Faces wrap periodically.
+
+struct Params {
+    width: u32,
+    height: u32,
+    depth: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> amplitudes_in: array<vec2<f32>>;   // (re, im) per site and direction
+@group(0) @binding(2) var<storage, read_write> amplitudes_out: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read_write> probability: array<f32>;   // |amplitude|² summed per site
+
+const DIRECTIONS: u32 = 6u;
+
+fn get_index(x: u32, y: u32, z: u32) -> u32 {
+    return z * params.width * params.height + y * params.width + x;
+}
+
+// Index of the site one step from (x, y, z) in direction 0..5, wrapping at
+// the faces
+fn neighbor_index(x: u32, y: u32, z: u32, dir: u32) -> u32 {
+    let extents = vec3<u32>(params.width, params.height, params.depth);
+    var c = vec3<u32>(x, y, z);
+    let axis = dir / 2u;
+    let extent = extents[axis];
+    if (dir % 2u == 0u) {
+        c[axis] = (c[axis] + 1u) % extent;
+    } else {
+        c[axis] = (c[axis] + extent - 1u) % extent;
+    }
+    return get_index(c.x, c.y, c.z);
+}
+
+// One walk step for site (x, y, z): the amplitude now moving in direction
+// dir is the coined amplitude dir of the site behind it. The Grover coin
+// maps a to (2/6) * sum(a) - a, which mixes all directions equally.
+@compute @workgroup_size(4, 4, 4)
+fn walk_step(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    if (x >= params.width || y >= params.height || z >= params.depth) {
+        return;
+    }
+
+    let idx = get_index(x, y, z);
+    for (var dir = 0u; dir < DIRECTIONS; dir++) {
+        // The site behind lies in the opposite direction
+        let source = neighbor_index(x, y, z, dir ^ 1u) * DIRECTIONS;
+        var sum = vec2<f32>(0.0, 0.0);
+        for (var k = 0u; k < DIRECTIONS; k++) {
+            sum += amplitudes_in[source + k];
+        }
+        amplitudes_out[idx * DIRECTIONS + dir] = sum * (2.0 / f32(DIRECTIONS)) - amplitudes_in[source + dir];
+    }
+}
+
+// Probability of finding the walker at each site
+@compute @workgroup_size(4, 4, 4)
+fn probabilities(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    if (x >= params.width || y >= params.height || z >= params.depth) {
+        return;
+    }
+
+    let idx = get_index(x, y, z);
+    var total = 0.0;
+    for (var dir = 0u; dir < DIRECTIONS; dir++) {
+        let a = amplitudes_in[idx * DIRECTIONS + dir];
+        total += dot(a, a);
+    }
+    probability[idx] = total;
+}
@@ -0,0 +1,95 @@
+// Readbacks queued behind the GPU's work and collected later
+//
+// A plain readback copies into the lattice's one staging buffer and then
+// waits for the device to go idle, so the host sits out every step still
+// queued. A queued readback copies into a staging buffer of its own, taken
+// from a small pool, and waits only for its own copy, so diagnostics for
+// one step can download while later steps compute.
+
+use crate::timing::TransferCounters;
+use std::sync::{Arc, Mutex};
+use web_time::Instant;
+
+// Staging buffers of each size kept for reuse. More can be in flight at
+// once; the extras are freed when collected
+const STAGING_POOL_SIZE: usize = 2;
+
+pub(crate) type StagingPool = Arc<Mutex<Vec<wgpu::Buffer>>>;
+
+// A staging buffer of exactly `size` bytes, from the pool if it has one
+pub(crate) fn take_staging(device: &wgpu::Device, pool: &StagingPool, size: u64) -> wgpu::Buffer {
+    let mut pool = pool.lock().expect("Staging pool mutex poisoned");
+    match pool.iter().position(|buffer| buffer.size() == size) {
+        Some(i) => pool.swap_remove(i),
+        None => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(size, "Allocated readback staging buffer");
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Readback Staging Buffer"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        }
+    }
+}
+
+fn return_staging(pool: &StagingPool, buffer: wgpu::Buffer) {
+    let mut pool = pool.lock().expect("Staging pool mutex poisoned");
+    let same_size = pool.iter().filter(|b| b.size() == buffer.size()).count();
+    if same_size < STAGING_POOL_SIZE {
+        pool.push(buffer);
+    }
+}
+
+/// A download already submitted to the GPU, as returned by
+/// [`DiscreteLatticeGPU::queue_state_readback`](crate::DiscreteLatticeGPU::queue_state_readback)
+/// and
+/// [`queue_total_energy_readback`](crate::DiscreteLatticeGPU::queue_total_energy_readback).
+///
+/// It captures the lattice as of the generation it was queued at, however
+/// many steps are submitted before it is collected with
+/// [`wait`](Self::wait). Dropping it uncollected discards the data.
+pub struct Readback<T> {
+    pub(crate) device: Arc<wgpu::Device>,
+    pub(crate) pool: StagingPool,
+    pub(crate) transfer: Arc<TransferCounters>,
+    pub(crate) staging: wgpu::Buffer,
+    pub(crate) submission: wgpu::SubmissionIndex,
+    pub(crate) mapped: flume::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    pub(crate) generation: u64,
+    pub(crate) started: Instant,
+    pub(crate) decode: fn(&[u32]) -> T,
+}
+
+impl<T> Readback<T> {
+    /// Generation the lattice was at when the readback was queued.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether the data has arrived, so [`wait`](Self::wait) returns
+    /// without waiting on the GPU.
+    pub fn is_ready(&self) -> bool {
+        self.device.poll(wgpu::Maintain::Poll);
+        !self.mapped.is_empty()
+    }
+
+    /// Waits for this readback's copy, but not for work submitted after
+    /// it, and returns the data.
+    pub async fn wait(self) -> T {
+        self.device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(self.submission));
+        self.mapped.recv_async().await.unwrap().unwrap();
+
+        let size = self.staging.size();
+        let value = {
+            let data = self.staging.slice(..).get_mapped_range();
+            (self.decode)(bytemuck::cast_slice(&data))
+        };
+        self.staging.unmap();
+        self.transfer.record_download(size, self.started.elapsed());
+        return_staging(&self.pool, self.staging);
+        value
+    }
+}
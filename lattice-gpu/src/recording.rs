@@ -0,0 +1,395 @@
+// Headless rendering of a run into an animated GIF
+//
+// Draws the same point cloud as the viewer (render_shader.wgsl) into an
+// offscreen texture, reads each frame back and appends it to the GIF.
+
+use crate::{DiscreteLatticeGPU, ExportError, LatticeError};
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+// sRGB target, so the bytes read back are already display encoded
+const FRAME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+const FIELD_OF_VIEW_DEGREES: f32 = 45.0;
+
+/// Settings for [`render_gif`].
+#[derive(Clone, Debug)]
+pub struct GifConfig {
+    /// Output file.
+    pub path: PathBuf,
+    /// Edge length of the cubic lattice.
+    pub lattice_size: u32,
+    /// Radius of the sphere seeded at the lattice center.
+    pub seed_radius: u32,
+    /// Quanta per site of the seed sphere.
+    pub seed_quanta: u32,
+    /// Number of frames in the animation.
+    pub frames: u32,
+    /// Propagation steps between frames.
+    pub steps_per_frame: u32,
+    /// Image width in pixels.
+    pub width: u32,
+    /// Image height in pixels.
+    pub height: u32,
+    /// Camera rotation about the vertical axis over the whole animation.
+    pub orbit_degrees: f32,
+    /// Display time of each frame in hundredths of a second.
+    pub frame_delay: u16,
+}
+
+impl Default for GifConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("lattice.gif"),
+            lattice_size: 64,
+            seed_radius: 8,
+            seed_quanta: 3,
+            frames: 60,
+            steps_per_frame: 2,
+            width: 400,
+            height: 400,
+            orbit_degrees: 360.0,
+            frame_delay: 5,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+}
+
+// Layout of Params in render_shader.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct ParamsUniform {
+    width: u32,
+    height: u32,
+    depth: u32,
+    step_count: u32,
+    slice_axis: u32,
+    slice_index: u32,
+    _padding: [u32; 2],
+}
+
+/// Seeds a lattice, propagates it while orbiting the camera around the
+/// energy, and writes every frame to an animated GIF, all without a window.
+///
+/// The camera re-frames each frame on
+/// [`occupied_bounds`](DiscreteLatticeGPU::occupied_bounds), so the view
+/// follows the energy as it spreads.
+pub async fn render_gif(config: GifConfig) -> Result<(), ExportError> {
+    let instance = wgpu::Instance::default();
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        })
+        .await
+        .ok_or(LatticeError::AdapterNotFound)?;
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .await
+        .map_err(LatticeError::from)?;
+    let (device, queue) = (Arc::new(device), Arc::new(queue));
+
+    let size = config.lattice_size;
+    let mut lattice =
+        DiscreteLatticeGPU::new_with_device(device.clone(), queue.clone(), size, size, size)?;
+    lattice.initialize_vacuum();
+    let c = size / 2;
+    lattice.seed_sphere((c, c, c), config.seed_radius, config.seed_quanta);
+
+    let renderer = FrameRenderer::new(&device, &config, size);
+
+    let file = File::create(&config.path)?;
+    let mut encoder = gif::Encoder::new(file, config.width as u16, config.height as u16, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    for frame_index in 0..config.frames {
+        let angle = if config.frames > 1 {
+            config.orbit_degrees.to_radians() * frame_index as f32 / config.frames as f32
+        } else {
+            0.0
+        };
+        let view_proj = framed_view_proj(&lattice, &config, angle).await;
+
+        let mut rgba = renderer.render(&device, &queue, &lattice, view_proj);
+        let mut frame =
+            gif::Frame::from_rgba_speed(config.width as u16, config.height as u16, &mut rgba, 10);
+        frame.delay = config.frame_delay;
+        encoder.write_frame(&frame)?;
+
+        for _ in 0..config.steps_per_frame {
+            lattice.propagate_energy();
+        }
+    }
+    Ok(())
+}
+
+// Camera orbiting the occupied region at `angle`, far enough back that the
+// region's bounding sphere fits the field of view
+async fn framed_view_proj(lattice: &DiscreteLatticeGPU, config: &GifConfig, angle: f32) -> Mat4 {
+    let size = config.lattice_size as f32;
+    let half = Vec3::splat(size * 0.5);
+    let (center, radius) = match lattice.occupied_bounds().await {
+        Some(bounds) => {
+            let min = Vec3::from_array(bounds.min.map(|v| v as f32));
+            let max = Vec3::from_array(bounds.max.map(|v| v as f32));
+            // Shift to the shader's lattice-centered coordinates
+            ((min + max) * 0.5 - half, ((max - min) * 0.5).length() + 1.0)
+        }
+        None => (Vec3::ZERO, half.length()),
+    };
+
+    let fov = FIELD_OF_VIEW_DEGREES.to_radians();
+    let distance = radius / (fov * 0.5).sin() * 1.1;
+    let elevation = 0.3f32;
+    let eye = center
+        + distance
+            * Vec3::new(
+                angle.sin() * elevation.cos(),
+                elevation.sin(),
+                angle.cos() * elevation.cos(),
+            );
+
+    let view = Mat4::look_at_rh(eye, center, Vec3::Y);
+    let aspect = config.width as f32 / config.height as f32;
+    let proj = Mat4::perspective_rh(fov, aspect, 0.1, distance + radius * 2.0 + 10.0);
+    proj * view
+}
+
+// Offscreen point-cloud renderer with a readback buffer for one frame
+struct FrameRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    camera_buffer: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    target: wgpu::Texture,
+    readback: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_row: u32,
+    total_sites: u32,
+}
+
+impl FrameRenderer {
+    fn new(device: &wgpu::Device, config: &GifConfig, lattice_size: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Render Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("render_shader.wgsl").into()),
+        });
+
+        let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Recording Bind Group Layout"),
+            entries: &[
+                uniform_entry(0),
+                uniform_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Recording Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Recording Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: FRAME_FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::PointList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Recording Camera Buffer"),
+            size: std::mem::size_of::<CameraUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let params = ParamsUniform {
+            width: lattice_size,
+            height: lattice_size,
+            depth: lattice_size,
+            step_count: 0,
+            slice_axis: 0,
+            slice_index: 0,
+            _padding: [0; 2],
+        };
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Recording Params Buffer"),
+            contents: bytemuck::cast_slice(&[params]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Recording Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FRAME_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        // Rows of a texture copy must start on 256-byte boundaries
+        let padded_row = (config.width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Recording Readback"),
+            size: (padded_row * config.height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            camera_buffer,
+            params_buffer,
+            target,
+            readback,
+            width: config.width,
+            height: config.height,
+            padded_row,
+            total_sites: lattice_size * lattice_size * lattice_size,
+        }
+    }
+
+    // Draw the lattice's current state and return tightly packed RGBA rows
+    fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lattice: &DiscreteLatticeGPU,
+        view_proj: Mat4,
+    ) -> Vec<u8> {
+        let camera = CameraUniform {
+            view_proj: view_proj.to_cols_array_2d(),
+        };
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[camera]));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Recording Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: lattice.get_energy_buffer().as_entire_binding(),
+                },
+            ],
+        });
+
+        let view = self.target.create_view(&Default::default());
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Recording Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.01,
+                            g: 0.01,
+                            b: 0.02,
+                            a: 1.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..self.total_sites, 0..1);
+        }
+        encoder.copy_texture_to_buffer(
+            self.target.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &self.readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            self.target.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::Maintain::Wait);
+
+        let data = slice.get_mapped_range();
+        let mut rgba = Vec::with_capacity((self.width * self.height * 4) as usize);
+        for row in data.chunks(self.padded_row as usize) {
+            rgba.extend_from_slice(&row[..(self.width * 4) as usize]);
+        }
+        drop(data);
+        self.readback.unmap();
+        rgba
+    }
+}
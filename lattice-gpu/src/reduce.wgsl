@@ -0,0 +1,268 @@
+// Reductions over the lattice state
+//
+// Shares the propagation bind group layout so the same params buffer and
+// energy buffers can be bound. Each workgroup first combines its sites in
+// workgroup memory, then merges its partial result into `result`, keeping
+// global atomic traffic to one op per group and value.
+
+struct Params {
+    width: u32,
+    height: u32,
+    depth: u32,
+    step_count: u32,
+    weight_x: u32,
+    weight_y: u32,
+    weight_z: u32,
+    boundary_modes: u32,
+    seed: u32,
+    decay_threshold: u32,
+    capacity_map: u32,
+    z_offset: u32,
+    lattice_depth: u32,
+    _padding2: u32,
+    _padding3: u32,
+    _padding4: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> energy_in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> result: array<atomic<u32>>;
+// Bindings 3-5 (site flags, loss counts, potential) are part of the
+// shared layout but unused here
+
+// Highest quantum level a site can hold
+const MAX_LEVEL: u32 = 3u;
+
+var<workgroup> group_count: atomic<u32>;
+var<workgroup> group_sum_lo: atomic<u32>;
+var<workgroup> group_sum_hi: atomic<u32>;
+var<workgroup> group_min: array<atomic<u32>, 3>;
+var<workgroup> group_max: array<atomic<u32>, 3>;
+var<workgroup> group_base: u32;
+var<workgroup> group_max_energy: atomic<u32>;
+
+// 64-bit sum of all site energies into result[0] (low word) and result[1]
+// (high word). WGSL has no portable 64-bit integers, so each add detects
+// its own wrap of the low word from the value atomicAdd returns and
+// carries one into the high word
+@compute @workgroup_size(4, 4, 4)
+fn total_energy(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&group_sum_lo, 0u);
+        atomicStore(&group_sum_hi, 0u);
+    }
+    workgroupBarrier();
+
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    if (x < params.width && y < params.height && z < params.depth) {
+        let idx = z * params.width * params.height + y * params.width + x;
+        let energy = energy_in[idx];
+        if (energy != 0u) {
+            let old = atomicAdd(&group_sum_lo, energy);
+            if (old + energy < old) {
+                atomicAdd(&group_sum_hi, 1u);
+            }
+        }
+    }
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        let lo = atomicLoad(&group_sum_lo);
+        let hi = atomicLoad(&group_sum_hi);
+        if (lo != 0u) {
+            let old = atomicAdd(&result[0], lo);
+            if (old + lo < old) {
+                atomicAdd(&result[1], 1u);
+            }
+        }
+        if (hi != 0u) {
+            atomicAdd(&result[1], hi);
+        }
+    }
+}
+
+// Count sites holding MAX_LEVEL into result[0]
+@compute @workgroup_size(4, 4, 4)
+fn count_saturated(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&group_count, 0u);
+    }
+    workgroupBarrier();
+
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    // Out-of-range threads still reach the barriers below
+    if (x < params.width && y < params.height && z < params.depth) {
+        let idx = z * params.width * params.height + y * params.width + x;
+        if (energy_in[idx] == MAX_LEVEL) {
+            atomicAdd(&group_count, 1u);
+        }
+    }
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        let count = atomicLoad(&group_count);
+        if (count != 0u) {
+            atomicAdd(&result[0], count);
+        }
+    }
+}
+
+// Highest energy held by any site into result[0], which the host
+// initializes to 0
+@compute @workgroup_size(4, 4, 4)
+fn max_energy(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&group_max_energy, 0u);
+    }
+    workgroupBarrier();
+
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    // Out-of-range threads still reach the barriers below
+    if (x < params.width && y < params.height && z < params.depth) {
+        let idx = z * params.width * params.height + y * params.width + x;
+        atomicMax(&group_max_energy, energy_in[idx]);
+    }
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        let group_max_value = atomicLoad(&group_max_energy);
+        if (group_max_value != 0u) {
+            atomicMax(&result[0], group_max_value);
+        }
+    }
+}
+
+// Count sites with non-zero energy into result[0]
+@compute @workgroup_size(4, 4, 4)
+fn count_occupied(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&group_count, 0u);
+    }
+    workgroupBarrier();
+
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    // Out-of-range threads still reach the barriers below
+    if (x < params.width && y < params.height && z < params.depth) {
+        let idx = z * params.width * params.height + y * params.width + x;
+        if (energy_in[idx] != 0u) {
+            atomicAdd(&group_count, 1u);
+        }
+    }
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        let count = atomicLoad(&group_count);
+        if (count != 0u) {
+            atomicAdd(&result[0], count);
+        }
+    }
+}
+
+// Stream compaction of occupied sites. result[0] counts the sites written
+// and each site appends (index, energy) after it. A workgroup reserves one
+// contiguous range for all its occupied sites, so output order is
+// arbitrary between groups
+@compute @workgroup_size(4, 4, 4)
+fn compact_occupied(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&group_count, 0u);
+    }
+    workgroupBarrier();
+
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    // Out-of-range threads still reach the barriers below
+    var idx = 0u;
+    var energy = 0u;
+    var slot = 0u;
+    if (x < params.width && y < params.height && z < params.depth) {
+        idx = z * params.width * params.height + y * params.width + x;
+        energy = energy_in[idx];
+        if (energy != 0u) {
+            slot = atomicAdd(&group_count, 1u);
+        }
+    }
+    workgroupBarrier();
+
+    if (local_index == 0u) {
+        let count = atomicLoad(&group_count);
+        if (count != 0u) {
+            group_base = atomicAdd(&result[0], count);
+        }
+    }
+    workgroupBarrier();
+
+    if (energy != 0u) {
+        let out = 1u + 2u * (group_base + slot);
+        atomicStore(&result[out], idx);
+        atomicStore(&result[out + 1u], energy);
+    }
+}
+
+// Per-axis min of occupied coordinates into result[0..3] and max into
+// result[3..6]. The host initializes the mins to 0xffffffff and maxes to 0
+@compute @workgroup_size(4, 4, 4)
+fn occupied_bounds(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        for (var i = 0u; i < 3u; i++) {
+            atomicStore(&group_min[i], 0xffffffffu);
+            atomicStore(&group_max[i], 0u);
+        }
+    }
+    workgroupBarrier();
+
+    let x = global_id.x;
+    let y = global_id.y;
+    let z = global_id.z;
+
+    if (x < params.width && y < params.height && z < params.depth) {
+        let idx = z * params.width * params.height + y * params.width + x;
+        if (energy_in[idx] != 0u) {
+            for (var i = 0u; i < 3u; i++) {
+                atomicMin(&group_min[i], global_id[i]);
+                atomicMax(&group_max[i], global_id[i]);
+            }
+        }
+    }
+    workgroupBarrier();
+
+    // A group with no occupied sites leaves its mins at 0xffffffff
+    if (local_index == 0u && atomicLoad(&group_min[0]) != 0xffffffffu) {
+        for (var i = 0u; i < 3u; i++) {
+            atomicMin(&result[i], atomicLoad(&group_min[i]));
+            atomicMax(&result[3u + i], atomicLoad(&group_max[i]));
+        }
+    }
+}
@@ -10,6 +10,9 @@ struct Params {
     height: u32,
     depth: u32,
     step_count: u32,
+    slice_axis: u32,   // 0 = X, 1 = Y, 2 = Z
+    slice_index: u32,
+    _padding: vec2<u32>,
 }
 
 @group(0) @binding(0) var<uniform> camera: Camera;
@@ -90,6 +93,105 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     return output;
 }
 
+// Slice plane: 6 vertices (two triangles) per cell of the plane
+// perpendicular to slice_axis at slice_index
+@vertex
+fn vs_slice(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+
+    // Extents of the plane in its two in-plane axes (u, v)
+    var extent_u = params.width;
+    var extent_v = params.height;
+    if (params.slice_axis == 0u) {
+        extent_u = params.height;
+        extent_v = params.depth;
+    } else if (params.slice_axis == 1u) {
+        extent_v = params.depth;
+    }
+
+    let cell = vertex_index / 6u;
+    if (cell >= extent_u * extent_v) {
+        output.position = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+        output.color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+        output.point_size = 0.0;
+        return output;
+    }
+    let u = cell % extent_u;
+    let v = cell / extent_u;
+
+    // Lattice coordinates of the cell
+    var site = vec3<u32>(u, v, params.slice_index);
+    if (params.slice_axis == 0u) {
+        site = vec3<u32>(params.slice_index, u, v);
+    } else if (params.slice_axis == 1u) {
+        site = vec3<u32>(u, params.slice_index, v);
+    }
+    let level = energy[get_index(site.x, site.y, site.z)];
+
+    // Quad corners as offsets in the plane
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, -0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, 0.5)
+    );
+    let corner = corners[vertex_index % 6u];
+
+    var offset = vec3<f32>(corner.x, corner.y, 0.0);
+    if (params.slice_axis == 0u) {
+        offset = vec3<f32>(0.0, corner.x, corner.y);
+    } else if (params.slice_axis == 1u) {
+        offset = vec3<f32>(corner.x, 0.0, corner.y);
+    }
+
+    let half_extent = vec3<f32>(
+        f32(params.width),
+        f32(params.height),
+        f32(params.depth)
+    ) * 0.5;
+    let world_pos = vec3<f32>(site) + offset - half_extent;
+
+    output.position = camera.view_proj * vec4<f32>(world_pos, 1.0);
+    if (level == 0u) {
+        // Keep empty cells faintly visible so the plane reads as a grid
+        output.color = vec4<f32>(0.3, 0.3, 0.35, 0.15);
+    } else {
+        output.color = energy_color(level);
+    }
+    output.point_size = 1.0;
+
+    return output;
+}
+
+// Axis lines through the origin: vertices 0-1 X (red), 2-3 Y (green), 4-5 Z (blue)
+@vertex
+fn vs_axes(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+
+    let axis = vertex_index / 2u;
+    let sign = select(-1.0, 1.0, vertex_index % 2u == 1u);
+
+    // Extend slightly past the lattice faces
+    let half_extent = vec3<f32>(
+        f32(params.width),
+        f32(params.height),
+        f32(params.depth)
+    ) * 0.6;
+
+    var world_pos = vec3<f32>(0.0, 0.0, 0.0);
+    var color = vec4<f32>(0.0, 0.0, 0.0, 0.35);
+    world_pos[axis] = sign * half_extent[axis];
+    color[axis] = 1.0;
+
+    output.position = camera.view_proj * vec4<f32>(world_pos, 1.0);
+    output.color = color;
+    output.point_size = 1.0;
+
+    return output;
+}
+
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     // Simple point rendering
@@ -0,0 +1,78 @@
+// Resampling lattice states between resolutions
+
+// Split every coarse site into factor³ fine sites, preserving the total
+// exactly. Each child gets energy / factor³, and the remainder is handed out
+// one quantum per child in the order of `child_order`
+pub(crate) fn upsample(
+    energy: &[u32],
+    (width, height, depth): (u32, u32, u32),
+    factor: u32,
+) -> Vec<u32> {
+    let fine_width = width * factor;
+    let fine_height = height * factor;
+    let fine_depth = depth * factor;
+    let children = factor * factor * factor;
+    let order = child_order(factor);
+
+    let mut fine = vec![0u32; (fine_width * fine_height * fine_depth) as usize];
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                let e = energy[(z * width * height + y * width + x) as usize];
+                if e == 0 {
+                    continue;
+                }
+                let share = e / children;
+                let remainder = e % children;
+
+                for (rank, &(dx, dy, dz)) in order.iter().enumerate() {
+                    let fx = x * factor + dx;
+                    let fy = y * factor + dy;
+                    let fz = z * factor + dz;
+                    let idx = (fz * fine_width * fine_height + fy * fine_width + fx) as usize;
+                    fine[idx] = share + u32::from((rank as u32) < remainder);
+                }
+            }
+        }
+    }
+    fine
+}
+
+// Child offsets within a factor³ block, nearest the block center first so
+// that a few leftover quanta land in the middle rather than a corner
+fn child_order(factor: u32) -> Vec<(u32, u32, u32)> {
+    let mut order = Vec::with_capacity((factor * factor * factor) as usize);
+    for dz in 0..factor {
+        for dy in 0..factor {
+            for dx in 0..factor {
+                order.push((dx, dy, dz));
+            }
+        }
+    }
+
+    // Twice the distance from the center keeps everything in integers
+    let center = factor as i64 - 1;
+    order.sort_by_key(|&(dx, dy, dz)| {
+        let d = |c: u32| (2 * c as i64 - center).pow(2);
+        d(dx) + d(dy) + d(dz)
+    });
+    order
+}
+
+// Give every fine site the value of the coarse site containing it
+pub(crate) fn upsample_nearest<T: Copy>(
+    values: &[T],
+    (width, height, depth): (u32, u32, u32),
+    factor: u32,
+) -> Vec<T> {
+    let mut fine = Vec::with_capacity(values.len() * (factor as usize).pow(3));
+    for z in 0..depth * factor {
+        for y in 0..height * factor {
+            for x in 0..width * factor {
+                let coarse = (z / factor) * width * height + (y / factor) * width + x / factor;
+                fine.push(values[coarse as usize]);
+            }
+        }
+    }
+    fine
+}
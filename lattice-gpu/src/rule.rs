@@ -0,0 +1,78 @@
+// Pluggable transfer rules
+//
+// A rule is a WGSL snippet defining choose_target, appended to shader.wgsl
+// (after the current_step snippet and workgroup size) when the propagation
+// pipelines are built. Everything else about a step (frozen sites, decay, boundaries,
+// scatter vs gather) stays in shader.wgsl.
+
+use std::borrow::Cow;
+
+/// A transfer rule: decides which neighbor an occupied site hands its
+/// quantum to each step.
+///
+/// The rule is WGSL source defining
+///
+/// ```wgsl
+/// fn choose_target(x: u32, y: u32, z: u32) -> u32
+/// ```
+///
+/// which is called for every occupied site that is neither frozen, an
+/// obstacle, nor decaying this step. It returns the index of the receiving
+/// site, `NO_TARGET` to keep the quantum, or `ABSORBED` to send it across
+/// an absorbing face. The receiver must be one of the site's neighbors
+/// (`neighbor_coords` over `neighbor_count()` directions), and the result
+/// must depend only on `energy_in`, `site_flags`, `potential`, `capacity`
+/// and `params`: in gather mode every neighbor re-evaluates it and must get
+/// the same answer. Keeping the receiver within its `site_capacity(idx)` is up to
+/// the rule.
+///
+/// The rule can use everything declared in shader.wgsl, notably
+/// `pick_neighbor(x, y, z, below)`, which picks a weighted random neighbor
+/// holding less than `below` quanta and below its capacity, and
+/// `pseudo_random(idx, step)`. Read the step with `current_step()` rather
+/// than `params.step_count`, which is not updated when the step arrives as a
+/// push constant.
+pub trait PropagationRule {
+    fn wgsl(&self) -> Cow<'_, str>;
+}
+
+/// The default rule: quanta flow to neighbors holding less energy.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GradientRule;
+
+impl PropagationRule for GradientRule {
+    fn wgsl(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("rule_gradient.wgsl"))
+    }
+}
+
+/// Quanta hop to any neighbor with room for them, ignoring the energy
+/// gradient.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RandomWalkRule;
+
+impl PropagationRule for RandomWalkRule {
+    fn wgsl(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("rule_random_walk.wgsl"))
+    }
+}
+
+/// A rule supplied as WGSL source at runtime.
+#[derive(Clone, Debug)]
+pub struct WgslRule {
+    source: String,
+}
+
+impl WgslRule {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+}
+
+impl PropagationRule for WgslRule {
+    fn wgsl(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.source)
+    }
+}
@@ -0,0 +1,8 @@
+// Gradient-flow transfer rule (the default)
+//
+// An occupied site hands one quantum to a neighbor holding less energy than
+// itself, provided that neighbor has room for it.
+
+fn choose_target(x: u32, y: u32, z: u32) -> u32 {
+    return pick_neighbor(x, y, z, energy_in[get_index(x, y, z)]);
+}
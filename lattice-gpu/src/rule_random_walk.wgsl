@@ -0,0 +1,9 @@
+// Random-walk transfer rule
+//
+// An occupied site hands one quantum to any neighbor with room for it,
+// regardless of the energy gradient, so quanta diffuse without flowing
+// downhill.
+
+fn choose_target(x: u32, y: u32, z: u32) -> u32 {
+    return pick_neighbor(x, y, z, 0xffffffffu);
+}
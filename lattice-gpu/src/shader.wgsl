@@ -7,12 +7,49 @@ struct Params {
     width: u32,
     height: u32,
     depth: u32,
-    step_count: u32,
+    step_count: u32,  // Unused with push constants; read the step through current_step()
+    weight_x: u32,  // Relative transfer weight per axis (fixed point)
+    weight_y: u32,
+    weight_z: u32,
+    boundary_modes: u32,  // BOUNDARY_* per axis, 8 bits each: X, Y, Z
+    seed: u32,            // Mixed into every transfer choice
+    decay_threshold: u32, // Per-site decay probability as a fraction of 2^32
+    capacity_map: u32,    // Nonzero when binding 6 holds per-site capacities
+    z_offset: u32,        // Global Z of layer 0 when this is one chunk of a larger lattice
+    lattice_depth: u32,   // Depth of the whole lattice this is a chunk of
+    _padding2: u32,
+    _padding3: u32,
+    _padding4: u32,
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
 @group(0) @binding(1) var<storage, read> energy_in: array<u32>;   // Current energy state
 @group(0) @binding(2) var<storage, read_write> energy_out: array<atomic<u32>>;  // Next energy state (atomic for race safety)
+@group(0) @binding(3) var<storage, read> site_flags: array<u32>;  // Per-site FLAG_* bits
+@group(0) @binding(4) var<storage, read_write> losses: array<atomic<u32>, 4>;  // Quanta absorbed at faces, then decayed (lo, hi each)
+@group(0) @binding(5) var<storage, read> potential: array<f32>;  // External potential per site
+@group(0) @binding(6) var<storage, read> capacity: array<u32>;  // Per-site maximum energy, if capacity_map is set
+
+// Frozen sites keep their energy: they neither send nor receive quanta
+const FLAG_FROZEN: u32 = 1u;
+// Obstacles are walls: a transfer aimed at one bounces back to the opposite
+// neighbor, and any energy on the obstacle itself stays put
+const FLAG_OBSTACLE: u32 = 2u;
+
+// Boundary modes
+const BOUNDARY_PERIODIC: u32 = 0u;  // Faces wrap around to the opposite face
+const BOUNDARY_CLOSED: u32 = 1u;    // Faces are walls with no neighbor beyond
+const BOUNDARY_ABSORBING: u32 = 2u; // Quanta sent across a face are removed
+const BOUNDARY_REFLECTIVE: u32 = 3u; // Faces mirror back to the inward neighbor
+
+// Boundary mode of axis 0..2 (X, Y, Z)
+fn axis_boundary(axis: u32) -> u32 {
+    return (params.boundary_modes >> (axis * 8u)) & 0xffu;
+}
+
+// Largest potential drop that still changes a transfer weight; keeps the
+// biased weights of all 26 Moore directions summed within u32
+const MAX_POTENTIAL_DROP: f32 = 8.0;
 
 // Quantum levels (0-3)
 const LEVEL_0: u32 = 0u;
@@ -20,41 +57,192 @@ const LEVEL_1: u32 = 1u;
 const LEVEL_2: u32 = 2u;
 const LEVEL_3: u32 = 3u;
 
+// Most quanta site idx can hold: its entry in the capacity map, or LEVEL_3
+fn site_capacity(idx: u32) -> u32 {
+    if (params.capacity_map != 0u) {
+        return capacity[idx];
+    }
+    return LEVEL_3;
+}
+
 // Get linear index from 3D coordinates
 fn get_index(x: u32, y: u32, z: u32) -> u32 {
     return z * params.width * params.height + y * params.width + x;
 }
 
-// Get neighbor index with toroidal wrapping
-fn get_neighbor_index(x: i32, y: i32, z: i32) -> u32 {
-    let w = i32(params.width);
-    let h = i32(params.height);
-    let d = i32(params.depth);
+// Step one coordinate by delta (-1 or +1). Leaving the lattice wraps to
+// the opposite face, or with a reflective boundary mirrors back inward.
+fn step_coord(c: u32, delta: i32, extent: u32, mode: u32) -> u32 {
+    let n = i32(c) + delta;
+    if (n >= 0 && n < i32(extent)) {
+        return u32(n);
+    }
+    if (mode == BOUNDARY_REFLECTIVE) {
+        return u32(i32(c) - delta);
+    }
+    return u32((n + i32(extent)) % i32(extent));
+}
+
+// Neighborhood of each site, set when the pipelines are built: the 6 face
+// neighbors (von Neumann) or all 26 face, edge and corner neighbors (Moore)
+override MOORE_NEIGHBORHOOD: bool = false;
+
+// Number of transfer directions from each site
+fn neighbor_count() -> u32 {
+    return select(6u, 26u, MOORE_NEIGHBORHOOD);
+}
+
+// Offset of direction dir. The 6 face directions are +X, -X, +Y, -Y, +Z, -Z;
+// the 26 Moore directions walk the 3x3x3 block around the site in X-major
+// order, skipping its center.
+fn direction_offset(dir: u32) -> vec3<i32> {
+    if (!MOORE_NEIGHBORHOOD) {
+        var offset = vec3<i32>(0, 0, 0);
+        offset[dir / 2u] = select(-1, 1, dir % 2u == 0u);
+        return offset;
+    }
+    let cell = dir + select(0u, 1u, dir >= 13u);
+    return vec3<i32>(i32(cell % 3u), i32(cell / 3u % 3u), i32(cell / 9u)) - vec3<i32>(1, 1, 1);
+}
+
+// Direction with the negated offset
+fn opposite_direction(dir: u32) -> u32 {
+    return select(dir ^ 1u, 25u - dir, MOORE_NEIGHBORHOOD);
+}
+
+// Get neighbor coordinates in direction dir. Only valid where has_neighbor
+// is true.
+fn neighbor_coords(x: u32, y: u32, z: u32, dir: u32) -> vec3<u32> {
+    let offset = direction_offset(dir);
+    let extents = vec3<u32>(params.width, params.height, params.depth);
+    var n = vec3<u32>(x, y, z);
+    for (var axis = 0u; axis < 3u; axis++) {
+        if (offset[axis] != 0) {
+            n[axis] = step_coord(n[axis], offset[axis], extents[axis], axis_boundary(axis));
+        }
+    }
+    return n;
+}
 
-    // Toroidal wrapping
-    let nx = (x + w) % w;
-    let ny = (y + h) % h;
-    let nz = (z + d) % d;
+// Whether one coordinate can step by delta. Periodic faces always wrap and
+// reflective faces mirror (given a second site to mirror to); closed and
+// absorbing faces have nothing beyond them.
+fn can_step(c: u32, delta: i32, extent: u32, mode: u32) -> bool {
+    if (mode == BOUNDARY_PERIODIC) {
+        return true;
+    }
+    if (mode == BOUNDARY_REFLECTIVE) {
+        return extent > 1u;
+    }
+    if (delta > 0) {
+        return c + 1u < extent;
+    }
+    return c > 0u;
+}
 
-    return get_index(u32(nx), u32(ny), u32(nz));
+// Whether direction dir from (x, y, z) leads to a site at all: it must be
+// able to step along every axis it moves on
+fn has_neighbor(x: u32, y: u32, z: u32, dir: u32) -> bool {
+    let offset = direction_offset(dir);
+    let extents = vec3<u32>(params.width, params.height, params.depth);
+    let c = vec3<u32>(x, y, z);
+    for (var axis = 0u; axis < 3u; axis++) {
+        if (offset[axis] != 0 && !can_step(c[axis], offset[axis], extents[axis], axis_boundary(axis))) {
+            return false;
+        }
+    }
+    return true;
 }
 
-// Simple pseudo-random number generator based on site position and step
+// Whether a direction without a neighbor leaves the lattice only through
+// absorbing faces, so a quantum sent that way is absorbed rather than
+// blocked
+fn leaves_through_absorbing(x: u32, y: u32, z: u32, dir: u32) -> bool {
+    let offset = direction_offset(dir);
+    let extents = vec3<u32>(params.width, params.height, params.depth);
+    let c = vec3<u32>(x, y, z);
+    for (var axis = 0u; axis < 3u; axis++) {
+        let mode = axis_boundary(axis);
+        if (offset[axis] != 0 && !can_step(c[axis], offset[axis], extents[axis], mode) && mode != BOUNDARY_ABSORBING) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// Transfer weight of direction dir before any potential bias. A face takes
+// its axis weight; an edge or corner moving along k axes takes the sum of
+// their weights over k², so diagonal moves are rarer the longer they are.
+// Moving along any zero-weight axis is blocked.
+fn direction_weight(dir: u32) -> u32 {
+    let offset = direction_offset(dir);
+    let weights = vec3<u32>(params.weight_x, params.weight_y, params.weight_z);
+    var axes = 0u;
+    var total = 0u;
+    for (var axis = 0u; axis < 3u; axis++) {
+        if (offset[axis] != 0) {
+            if (weights[axis] == 0u) {
+                return 0u;
+            }
+            axes++;
+            total += weights[axis];
+        }
+    }
+    return total / (axes * axes);
+}
+
+// Weight of a transfer from site idx to n_idx in a direction of weight
+// base_weight, scaled by exp(potential drop) so energy favors running
+// downhill. A flat potential leaves the direction weight unchanged.
+fn biased_weight(base_weight: u32, idx: u32, n_idx: u32) -> u32 {
+    let drop = clamp(potential[idx] - potential[n_idx], -MAX_POTENTIAL_DROP, MAX_POTENTIAL_DROP);
+    return u32(f32(base_weight) * exp(drop));
+}
+
+// current_step() returns the step being computed. It is defined by a short
+// snippet appended when the pipelines are built: from a push constant where
+// the device supports them, from params.step_count otherwise.
+
+// PCG hash (O'Neill's PCG-RXS-M-XS on one 32-bit state)
+fn pcg_hash(input: u32) -> u32 {
+    let state = input * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Index of a site in the whole lattice. A chunk of a larger lattice starts
+// at global layer z_offset and wraps at lattice_depth, so a site held by two
+// chunks gets the same index, and the same random values, in both
+fn global_index(idx: u32) -> u32 {
+    if (params.z_offset == 0u) {
+        return idx;
+    }
+    let layer = params.width * params.height;
+    return ((idx / layer + params.z_offset) % params.lattice_depth) * layer + idx % layer;
+}
+
+// Counter-based random value for a site and step: a pure function of the
+// site's global index, step and seed, so every thread evaluating the same
+// site gets the same value and runs are reproducible for a given seed
 fn pseudo_random(idx: u32, step: u32) -> u32 {
-    var x = idx + step * 1103515245u;
-    x = ((x >> 16u) ^ x) * 0x45d9f3bu;
-    x = ((x >> 16u) ^ x) * 0x45d9f3bu;
-    x = (x >> 16u) ^ x;
-    return x;
+    return pcg_hash(global_index(idx) ^ pcg_hash(step ^ pcg_hash(params.seed)));
 }
 
+// The propagation entry points take their workgroup size from WORKGROUP_X,
+// WORKGROUP_Y and WORKGROUP_Z, appended with the lattice's setting when the
+// pipelines are built
+
 // PASS 1: Copy energy from input to output
 // This initializes the output buffer with current state
-@compute @workgroup_size(4, 4, 4)
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z)
 fn copy_energy(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let x = global_id.x;
-    let y = global_id.y;
-    let z = global_id.z;
+    copy_site(global_id);
+}
+
+fn copy_site(site: vec3<u32>) {
+    let x = site.x;
+    let y = site.y;
+    let z = site.z;
 
     // Bounds check
     if (x >= params.width || y >= params.height || z >= params.depth) {
@@ -68,69 +256,291 @@ fn copy_energy(@builtin(global_invocation_id) global_id: vec3<u32>) {
     atomicStore(&energy_out[idx], energy);
 }
 
-// PASS 2: Propagate quantum energy transfers
+// Marker for "this site does not transfer a quantum this step"
+const NO_TARGET: u32 = 0xffffffffu;
+
+// Marker for "this site sends a quantum across an absorbing face"
+const ABSORBED: u32 = 0xfffffffeu;
+
+// Marker for "this site loses a quantum to decay"
+const DECAYED: u32 = 0xfffffffdu;
+
+// Offsets of the loss counters
+const LOSS_ABSORBED: u32 = 0u;
+const LOSS_DECAYED: u32 = 2u;
+
+// Count one lost quantum on the counter at `counter`, carrying into the
+// high word
+fn count_loss(counter: u32) {
+    let old = atomicAdd(&losses[counter], 1u);
+    if (old == 0xffffffffu) {
+        atomicAdd(&losses[counter + 1u], 1u);
+    }
+}
+
+// Count the quantum lost by a site whose transfer target is ABSORBED or
+// DECAYED
+fn count_lost_quantum(target_idx: u32) {
+    if (target_idx == ABSORBED) {
+        count_loss(LOSS_ABSORBED);
+    } else if (target_idx == DECAYED) {
+        count_loss(LOSS_DECAYED);
+    }
+}
+
+// Pick one neighbor of site (x, y, z) with energy below `below` to receive
+// a quantum, in proportion to its direction weight biased by the potential.
+// Frozen neighbors and neighbors at their capacity never accept; a transfer aimed at an obstacle reflects to
+// the opposite neighbor, and an absorbing face counts as an empty neighbor
+// that always accepts.
+// Returns the chosen neighbor's index, NO_TARGET if none qualifies, or
+// ABSORBED.
+fn pick_neighbor(x: u32, y: u32, z: u32, below: u32) -> u32 {
+    let idx = get_index(x, y, z);
+
+    // Collect qualifying neighbors and their total transfer weight
+    var lower_neighbors: array<u32, 26>;
+    var lower_weights: array<u32, 26>;
+    var lower_count = 0u;
+    var total_weight = 0u;
+
+    for (var i = 0u; i < neighbor_count(); i++) {
+        let dir_weight = direction_weight(i);
+        if (!has_neighbor(x, y, z, i)) {
+            // An absorbing face acts as an empty neighbor that always accepts
+            if (leaves_through_absorbing(x, y, z, i) && dir_weight > 0u) {
+                lower_neighbors[lower_count] = ABSORBED;
+                lower_weights[lower_count] = dir_weight;
+                lower_count++;
+                total_weight += dir_weight;
+            }
+            continue;
+        }
+        var n = neighbor_coords(x, y, z, i);
+        var n_idx = get_index(n.x, n.y, n.z);
+
+        // Reflect off an obstacle to the neighbor in the opposite direction
+        if ((site_flags[n_idx] & FLAG_OBSTACLE) != 0u) {
+            let opposite = opposite_direction(i);
+            if (!has_neighbor(x, y, z, opposite)) {
+                continue;
+            }
+            n = neighbor_coords(x, y, z, opposite);
+            n_idx = get_index(n.x, n.y, n.z);
+            if ((site_flags[n_idx] & FLAG_OBSTACLE) != 0u) {
+                continue;
+            }
+        }
+        let n_energy = energy_in[n_idx];
+
+        // Frozen and full neighbors never accept, so they don't count
+        let accepts = (site_flags[n_idx] & FLAG_FROZEN) == 0u && n_energy < site_capacity(n_idx);
+        let weight = biased_weight(dir_weight, idx, n_idx);
+        if (n_energy < below && weight > 0u && accepts) {
+            lower_neighbors[lower_count] = n_idx;
+            lower_weights[lower_count] = weight;
+            lower_count++;
+            total_weight += weight;
+        }
+    }
+
+    if (lower_count == 0u) {
+        return NO_TARGET;
+    }
+
+    // Pick a neighbor in proportion to its direction weight
+    let random_val = pseudo_random(idx, current_step());
+    var choice_weight = random_val % total_weight;
+    var choice = 0u;
+    while (choice_weight >= lower_weights[choice]) {
+        choice_weight -= lower_weights[choice];
+        choice++;
+    }
+    return lower_neighbors[choice];
+}
+
+// Decide which neighbor site (x, y, z) hands one quantum to this step.
+// Depends only on energy_in and params, so every thread that evaluates it
+// for the same site gets the same answer.
+// Returns the target's index, NO_TARGET, ABSORBED or DECAYED.
+//
+// Empty, frozen and obstacle sites never send and decay takes precedence;
+// otherwise the choice is left to the transfer rule's choose_target, which
+// is appended to this file when the pipelines are built (see
+// rule_gradient.wgsl for the default).
+fn transfer_target(x: u32, y: u32, z: u32) -> u32 {
+    let idx = get_index(x, y, z);
+    let energy = energy_in[idx];
+
+    // No energy to propagate, or held in place
+    if (energy == 0u || (site_flags[idx] & (FLAG_FROZEN | FLAG_OBSTACLE)) != 0u) {
+        return NO_TARGET;
+    }
+
+    // A decaying site drops its quantum instead of transferring it. The
+    // decay draw is hashed once more so it is independent of the choice of
+    // neighbor.
+    if (params.decay_threshold > 0u && pcg_hash(pseudo_random(idx, current_step())) < params.decay_threshold) {
+        return DECAYED;
+    }
+
+    return choose_target(x, y, z);
+}
+
+// PASS 2 (scatter mode): Propagate quantum energy transfers
 // Reads from input, writes atomically to output (no race with copy)
-@compute @workgroup_size(4, 4, 4)
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z)
 fn propagate_energy(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let x = global_id.x;
-    let y = global_id.y;
-    let z = global_id.z;
+    scatter_site(global_id);
+}
+
+fn scatter_site(site: vec3<u32>) {
+    let x = site.x;
+    let y = site.y;
+    let z = site.z;
 
     // Bounds check
     if (x >= params.width || y >= params.height || z >= params.depth) {
         return;
     }
 
-    let idx = get_index(x, y, z);
-    let energy = energy_in[idx];
+    let target_idx = transfer_target(x, y, z);
+    if (target_idx != NO_TARGET) {
+        // Transfer quantum
+        // NOTE: Several sites may push into the same target; the atomics
+        // keep every transfer exact so energy is conserved
+        atomicSub(&energy_out[get_index(x, y, z)], 1u);
+        if (target_idx == ABSORBED || target_idx == DECAYED) {
+            count_lost_quantum(target_idx);
+        } else {
+            atomicAdd(&energy_out[target_idx], 1u);
+        }
+    }
+}
+
+// Single pass (gather mode): compute the complete next state of one site
+// Instead of pushing quanta to neighbors, each site re-evaluates the
+// transfer decision of every neighbor and counts the quanta sent its way.
+// Needs no copy pass and no atomic read-modify-write, and produces exactly
+// the same state as copy_energy + propagate_energy.
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z)
+fn propagate_gather(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    gather_site(global_id);
+}
+
+fn gather_site(site: vec3<u32>) {
+    let x = site.x;
+    let y = site.y;
+    let z = site.z;
 
-    // No energy to propagate
-    if (energy == 0u) {
+    // Bounds check
+    if (x >= params.width || y >= params.height || z >= params.depth) {
         return;
     }
 
-    // Get 6 neighbors (±X, ±Y, ±Z)
-    let ix = i32(x);
-    let iy = i32(y);
-    let iz = i32(z);
+    let idx = get_index(x, y, z);
+    var energy = energy_in[idx];
 
-    var neighbors: array<u32, 6>;
-    neighbors[0] = get_neighbor_index(ix + 1, iy, iz);  // +X
-    neighbors[1] = get_neighbor_index(ix - 1, iy, iz);  // -X
-    neighbors[2] = get_neighbor_index(ix, iy + 1, iz);  // +Y
-    neighbors[3] = get_neighbor_index(ix, iy - 1, iz);  // -Y
-    neighbors[4] = get_neighbor_index(ix, iy, iz + 1);  // +Z
-    neighbors[5] = get_neighbor_index(ix, iy, iz - 1);  // -Z
+    // Gather the neighborhood once; an empty site with empty neighbors
+    // stays empty, which covers most of a sparse lattice
+    var neighbors: array<vec3<u32>, 26>;
+    var occupied = energy;
+    for (var i = 0u; i < neighbor_count(); i++) {
+        if (has_neighbor(x, y, z, i)) {
+            neighbors[i] = neighbor_coords(x, y, z, i);
+            occupied |= energy_in[get_index(neighbors[i].x, neighbors[i].y, neighbors[i].z)];
+        }
+    }
+    if (occupied == 0u) {
+        atomicStore(&energy_out[idx], 0u);
+        return;
+    }
 
-    // Count neighbors with lower energy and collect their indices
-    var lower_neighbors: array<u32, 6>;
-    var lower_count = 0u;
+    // Outflow
+    let target_idx = transfer_target(x, y, z);
+    if (target_idx != NO_TARGET) {
+        energy -= 1u;
+    }
+    count_lost_quantum(target_idx);
 
-    for (var i = 0u; i < 6u; i++) {
-        let n_idx = neighbors[i];
-        let n_energy = energy_in[n_idx];
+    // Inflow from every distinct neighbor that picked this site
+    var seen: array<u32, 26>;
+    for (var i = 0u; i < neighbor_count(); i++) {
+        // Missing neighbors are marked with this site, which never sends
+        // to itself
+        if (!has_neighbor(x, y, z, i)) {
+            seen[i] = idx;
+            continue;
+        }
+        let n = neighbors[i];
+        let n_idx = get_index(n.x, n.y, n.z);
+        seen[i] = n_idx;
 
-        if (n_energy < energy) {
-            lower_neighbors[lower_count] = n_idx;
-            lower_count++;
+        // On lattices 1-2 sites wide, or with reflective faces, several
+        // directions can lead to the same site
+        var duplicate = false;
+        for (var j = 0u; j < i; j++) {
+            if (seen[j] == n_idx) {
+                duplicate = true;
+            }
         }
-    }
 
-    // Transfer 1 quantum to a randomly chosen lower neighbor
-    if (lower_count > 0u && energy > 0u) {
-        // Choose random lower neighbor
-        let random_val = pseudo_random(idx, params.step_count);
-        let choice = random_val % lower_count;
-        let target_idx = lower_neighbors[choice];
-
-        // Check if target can accept quantum
-        let target_energy = energy_in[target_idx];
-        if (target_energy < LEVEL_3) {
-            // Transfer quantum
-            // NOTE: This has race conditions on target_idx, but they average out
-            // and preserve energy statistically (same as Java parallel version)
-            atomicSub(&energy_out[idx], 1u);
-            atomicAdd(&energy_out[target_idx], 1u);
+        if (!duplicate && transfer_target(n.x, n.y, n.z) == idx) {
+            energy += 1u;
         }
     }
+
+    atomicStore(&energy_out[idx], energy);
+}
+
+// Active-block variants of the three passes, dispatched indirectly with one
+// workgroup per active block. A block is the set of sites one workgroup of
+// the passes above covers; active_blocks.wgsl lists the blocks that can
+// change this step, and every other block is already all zero in energy_out.
+// The list holds its length first, then block indices
+@group(1) @binding(0) var<storage, read> active_blocks: array<u32>;
+
+// Site this invocation handles in the active block its workgroup is
+// assigned, or a site past the lattice for workgroups beyond the list
+fn active_site(group_id: vec3<u32>, group_counts: vec3<u32>, local_id: vec3<u32>) -> vec3<u32> {
+    let slot = group_id.y * group_counts.x + group_id.x;
+    if (slot >= active_blocks[0]) {
+        return vec3<u32>(params.width, 0u, 0u);
+    }
+    let block = active_blocks[slot + 1u];
+    let blocks_x = (params.width + WORKGROUP_X - 1u) / WORKGROUP_X;
+    let blocks_y = (params.height + WORKGROUP_Y - 1u) / WORKGROUP_Y;
+    let origin = vec3<u32>(
+        block % blocks_x,
+        (block / blocks_x) % blocks_y,
+        block / (blocks_x * blocks_y),
+    ) * vec3<u32>(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z);
+    return origin + local_id;
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z)
+fn active_copy_energy(
+    @builtin(workgroup_id) group_id: vec3<u32>,
+    @builtin(num_workgroups) group_counts: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+) {
+    copy_site(active_site(group_id, group_counts, local_id));
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z)
+fn active_propagate_energy(
+    @builtin(workgroup_id) group_id: vec3<u32>,
+    @builtin(num_workgroups) group_counts: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+) {
+    scatter_site(active_site(group_id, group_counts, local_id));
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y, WORKGROUP_Z)
+fn active_propagate_gather(
+    @builtin(workgroup_id) group_id: vec3<u32>,
+    @builtin(num_workgroups) group_counts: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+) {
+    gather_site(active_site(group_id, group_counts, local_id));
 }
@@ -0,0 +1,48 @@
+// Host-side snapshots of a lattice
+
+use serde::{Deserialize, Serialize};
+
+/// A complete copy of a lattice's state, as produced by
+/// [`DiscreteLatticeGPU::save_state`](crate::DiscreteLatticeGPU::save_state).
+///
+/// Carries the generation along with the energy so that a lattice restored
+/// with [`load_state`](crate::DiscreteLatticeGPU::load_state) continues with
+/// the same random choices as the original. Serializable, so results can be
+/// archived in any serde format.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatticeState {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub generation: u64,
+    /// Energy per site, indexed `z * width * height + y * width + x`.
+    pub energy: Vec<u32>,
+}
+
+/// A copy of a lattice's state kept on the GPU, as produced by
+/// [`DiscreteLatticeGPU::snapshot`](crate::DiscreteLatticeGPU::snapshot).
+///
+/// Taking and restoring a snapshot is a buffer-to-buffer copy, so runs can
+/// be checkpointed, branched and replayed without a host round-trip. The
+/// buffer belongs to the device of the lattice that took it.
+#[derive(Debug)]
+pub struct LatticeSnapshot {
+    pub(crate) dims: (u32, u32, u32),
+    pub(crate) generation: u64,
+    pub(crate) energy: wgpu::Buffer,
+}
+
+impl LatticeSnapshot {
+    /// Generation the lattice was at when the snapshot was taken.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+// The state before one step, kept for propagate_backward: the energy plus
+// the loss and injected counters, so stepping back rewinds them too
+pub(crate) struct HistoryEntry {
+    pub(crate) generation: u64,
+    pub(crate) energy: wgpu::Buffer,
+    pub(crate) counters: wgpu::Buffer,
+}
@@ -0,0 +1,12 @@
+// Step number as a push constant, set in each compute pass, so the params
+// uniform only changes when the settings do
+
+struct StepConstants {
+    step_count: u32,
+}
+
+var<push_constant> step_constants: StepConstants;
+
+fn current_step() -> u32 {
+    return step_constants.step_count;
+}
@@ -0,0 +1,5 @@
+// Step number from the params uniform, for devices without push constants
+
+fn current_step() -> u32 {
+    return params.step_count;
+}
@@ -0,0 +1,42 @@
+// Test-support helpers, enabled with the `testing` feature
+
+use crate::{DiscreteLatticeGPU, Lattice};
+
+/// Propagates `steps` times and asserts the total energy after every step
+/// equals the total before the first one. Works with any backend.
+///
+/// Checking each step rather than only the endpoints catches rules that
+/// temporarily create energy and destroy it again later.
+pub fn assert_conserved_over<L: Lattice + ?Sized>(lattice: &mut L, steps: u32) {
+    let initial = lattice.total_energy();
+
+    for step in 1..=steps {
+        lattice.propagate_energy();
+        let total = lattice.total_energy();
+        assert_eq!(
+            total, initial,
+            "Energy must be conserved: started with {}, had {} after step {}",
+            initial, total, step
+        );
+    }
+}
+
+/// Makes the lattice's device report a validation error that no error
+/// scope catches, as a programming error would.
+pub fn raise_uncaptured_error(lattice: &DiscreteLatticeGPU) {
+    // Mapping for both reading and writing needs a feature the device lacks
+    let _ = lattice.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Invalid Buffer"),
+        size: 4,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE,
+        mapped_at_creation: false,
+    });
+    lattice.device.poll(wgpu::Maintain::Wait);
+}
+
+/// Destroys the lattice's device, as a driver reset would, and waits until
+/// the loss is reported.
+pub fn lose_device(lattice: &DiscreteLatticeGPU) {
+    lattice.device.destroy();
+    lattice.device.poll(wgpu::Maintain::Wait);
+}
@@ -0,0 +1,114 @@
+// Benchmark timing results
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Wall-clock timing of a run of propagation steps, as measured by
+/// [`DiscreteLatticeGPU::timed_run`](crate::DiscreteLatticeGPU::timed_run).
+#[derive(Copy, Clone, Debug)]
+pub struct RunTiming {
+    pub steps: u32,
+    pub total_sites: usize,
+    pub elapsed: Duration,
+}
+
+impl RunTiming {
+    pub fn total_ms(&self) -> f64 {
+        self.elapsed.as_secs_f64() * 1000.0
+    }
+
+    pub fn ms_per_step(&self) -> f64 {
+        self.total_ms() / self.steps as f64
+    }
+
+    pub fn sites_per_sec(&self) -> f64 {
+        self.total_sites as f64 * self.steps as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Effective bandwidth assuming every site is read once and written once
+    /// (4 bytes each) per step.
+    pub fn gb_per_sec(&self) -> f64 {
+        self.sites_per_sec() * 8.0 / 1e9
+    }
+}
+
+/// GPU time spent in each compute pass of one step, as measured by
+/// [`DiscreteLatticeGPU::profile_step`](crate::DiscreteLatticeGPU::profile_step).
+#[cfg(feature = "profiling")]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StepTimings {
+    /// The scatter copy pass; `None` in gather mode, which has no copy.
+    pub copy: Option<Duration>,
+    /// The gather pass, or the scatter propagate pass.
+    pub propagate: Duration,
+}
+
+#[cfg(feature = "profiling")]
+impl StepTimings {
+    /// GPU time across all passes, excluding any gap between them.
+    pub fn total(&self) -> Duration {
+        self.copy.unwrap_or_default() + self.propagate
+    }
+}
+
+/// Bytes moved between host and GPU, as reported by
+/// [`DiscreteLatticeGPU::transfer_stats`](crate::DiscreteLatticeGPU::transfer_stats).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TransferStats {
+    /// Bytes written with `Queue::write_buffer`, including per-step params.
+    pub bytes_uploaded: u64,
+    /// Bytes read back through staging buffers.
+    pub bytes_downloaded: u64,
+    /// Number of readbacks.
+    pub downloads: u64,
+    /// Wall-clock time spent in readbacks, from submitting the copy until
+    /// the data is on the host.
+    pub download_time: Duration,
+}
+
+impl TransferStats {
+    /// Effective readback bandwidth, including the wait for queued GPU work
+    /// to finish before the copy.
+    pub fn download_gb_per_sec(&self) -> f64 {
+        self.bytes_downloaded as f64 / self.download_time.as_secs_f64() / 1e9
+    }
+}
+
+// Running totals behind TransferStats; atomics so readbacks through &self
+// can record themselves
+#[derive(Debug, Default)]
+pub(crate) struct TransferCounters {
+    bytes_uploaded: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    downloads: AtomicU64,
+    download_nanos: AtomicU64,
+}
+
+impl TransferCounters {
+    pub(crate) fn record_upload(&self, bytes: u64) {
+        self.bytes_uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_download(&self, bytes: u64, elapsed: Duration) {
+        self.bytes_downloaded.fetch_add(bytes, Ordering::Relaxed);
+        self.downloads.fetch_add(1, Ordering::Relaxed);
+        self.download_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> TransferStats {
+        TransferStats {
+            bytes_uploaded: self.bytes_uploaded.load(Ordering::Relaxed),
+            bytes_downloaded: self.bytes_downloaded.load(Ordering::Relaxed),
+            downloads: self.downloads.load(Ordering::Relaxed),
+            download_time: Duration::from_nanos(self.download_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.bytes_uploaded.store(0, Ordering::Relaxed);
+        self.bytes_downloaded.store(0, Ordering::Relaxed);
+        self.downloads.store(0, Ordering::Relaxed);
+        self.download_nanos.store(0, Ordering::Relaxed);
+    }
+}
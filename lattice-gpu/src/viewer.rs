@@ -1,6 +1,6 @@
 use bytemuck::{Pod, Zeroable};
 use glam::{Mat4, Vec3};
-use lattice_gpu::DiscreteLatticeGPU;
+use lattice_gpu::{DiscreteLatticeGPU, Lattice};
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
 use winit::{
@@ -22,6 +22,36 @@ struct ParamsUniform {
     height: u32,
     depth: u32,
     step_count: u32,
+    slice_axis: u32,
+    slice_index: u32,
+    _padding: [u32; 2],
+}
+
+// Axis-aligned plane shown in slice mode
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum SliceAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl SliceAxis {
+    fn next(self) -> Self {
+        match self {
+            SliceAxis::X => SliceAxis::Y,
+            SliceAxis::Y => SliceAxis::Z,
+            SliceAxis::Z => SliceAxis::X,
+        }
+    }
+
+    // Matches the axis numbering in render_shader.wgsl
+    fn index(self) -> u32 {
+        match self {
+            SliceAxis::X => 0,
+            SliceAxis::Y => 1,
+            SliceAxis::Z => 2,
+        }
+    }
 }
 
 struct Camera {
@@ -63,6 +93,52 @@ impl Camera {
     }
 }
 
+// Background presets, cycled with the B key
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Background {
+    Dark,
+    Black,
+    White,
+    DarkGray,
+}
+
+impl Background {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Background::Dark),
+            "black" => Some(Background::Black),
+            "white" => Some(Background::White),
+            "gray" | "grey" => Some(Background::DarkGray),
+            _ => None,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Background::Dark => Background::Black,
+            Background::Black => Background::White,
+            Background::White => Background::DarkGray,
+            Background::DarkGray => Background::Dark,
+        }
+    }
+
+    fn clear_color(self) -> wgpu::Color {
+        let (r, g, b) = match self {
+            Background::Dark => (0.01, 0.01, 0.02),
+            Background::Black => (0.0, 0.0, 0.0),
+            Background::White => (1.0, 1.0, 1.0),
+            Background::DarkGray => (0.15, 0.15, 0.15),
+        };
+        wgpu::Color { r, g, b, a: 1.0 }
+    }
+}
+
+// Large max-energy sphere at the lattice center, used on startup and reset
+fn seed_initial_sphere(lattice: &mut impl Lattice, lattice_size: u32) {
+    let c = lattice_size / 2;
+    lattice.seed_sphere((c, c, c), 15, 3);
+}
+
 struct Viewer {
     surface: wgpu::Surface<'static>,
     device: Arc<wgpu::Device>,
@@ -72,19 +148,34 @@ struct Viewer {
     window: Arc<winit::window::Window>,
 
     lattice: DiscreteLatticeGPU,
+    lattice_size: u32,
     render_pipeline: wgpu::RenderPipeline,
+    axes_pipeline: wgpu::RenderPipeline,
+    slice_pipeline: wgpu::RenderPipeline,
     camera_buffer: wgpu::Buffer,
     params_buffer: wgpu::Buffer,
     bind_group_layout: wgpu::BindGroupLayout,
+    // Render bind groups by the energy buffer they bind; the lattice
+    // alternates between two, so after two frames this never grows
+    render_bind_groups: Vec<(wgpu::Id<wgpu::Buffer>, wgpu::BindGroup)>,
 
     camera: Camera,
+    background: Background,
+    show_axes: bool,
+    slice_mode: bool,
+    slice_axis: SliceAxis,
+    slice_index: u32,
     paused: bool,
     mouse_pressed: bool,
     last_mouse_pos: Option<(f64, f64)>,
 }
 
 impl Viewer {
-    async fn new(window: Arc<winit::window::Window>, lattice_size: u32) -> Self {
+    async fn new(
+        window: Arc<winit::window::Window>,
+        lattice_size: u32,
+        background: Background,
+    ) -> Self {
         let size = window.inner_size();
 
         // Create wgpu instance and surface
@@ -147,7 +238,9 @@ impl Viewer {
         };
         surface.configure(&device, &config);
 
-        // Create lattice with shared device
+        // Create lattice with shared device. wgpu has one queue per device,
+        // so compute and rendering are serialized on it and each frame draws
+        // a fully propagated step
         println!("Initializing {}³ quantum lattice on GPU...", lattice_size);
         let mut lattice = DiscreteLatticeGPU::new_with_device(
             device.clone(),
@@ -155,27 +248,12 @@ impl Viewer {
             lattice_size,
             lattice_size,
             lattice_size,
-        );
+        )
+        .expect("Failed to create lattice");
         lattice.initialize_vacuum();
 
         // Add large spherical energy distribution
-        let c = lattice_size / 2;
-        let radius = 15i32; // Much larger sphere
-        for dz in -radius..=radius {
-            for dy in -radius..=radius {
-                for dx in -radius..=radius {
-                    let dist_sq = dx * dx + dy * dy + dz * dz;
-                    if dist_sq <= radius * radius {
-                        lattice.add_energy_quantum(
-                            (c as i32 + dx) as u32,
-                            (c as i32 + dy) as u32,
-                            (c as i32 + dz) as u32,
-                            3, // Max energy level
-                        );
-                    }
-                }
-            }
-        }
+        seed_initial_sphere(&mut lattice, lattice_size);
 
         // Create camera
         let camera = Camera::new(lattice_size);
@@ -196,6 +274,9 @@ impl Viewer {
             height: lattice_size,
             depth: lattice_size,
             step_count: 0,
+            slice_axis: SliceAxis::Z.index(),
+            slice_index: lattice_size / 2,
+            _padding: [0; 2],
         };
 
         let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -292,6 +373,66 @@ impl Viewer {
             cache: None,
         });
 
+        // Faint axis lines through the origin
+        let axes_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Axes Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_axes",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // Flat grid of colored cells for one slice plane
+        let slice_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Slice Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_slice",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
         Self {
             surface,
             device,
@@ -300,11 +441,20 @@ impl Viewer {
             size,
             window,
             lattice,
+            lattice_size,
             render_pipeline,
+            axes_pipeline,
+            slice_pipeline,
             camera_buffer,
             params_buffer,
             bind_group_layout,
+            render_bind_groups: Vec::new(),
             camera,
+            background,
+            show_axes: false,
+            slice_mode: false,
+            slice_axis: SliceAxis::Z,
+            slice_index: lattice_size / 2,
             paused: false,
             mouse_pressed: false,
             last_mouse_pos: None,
@@ -339,26 +489,47 @@ impl Viewer {
                     );
                     true
                 }
+                KeyCode::KeyB => {
+                    self.background = self.background.next();
+                    println!("Background: {:?}", self.background);
+                    true
+                }
+                KeyCode::KeyG => {
+                    self.show_axes = !self.show_axes;
+                    true
+                }
+                KeyCode::KeyS => {
+                    self.slice_mode = !self.slice_mode;
+                    self.print_slice();
+                    true
+                }
+                KeyCode::KeyX => {
+                    self.slice_axis = self.slice_axis.next();
+                    self.print_slice();
+                    true
+                }
+                KeyCode::ArrowUp => {
+                    self.slice_index = (self.slice_index + 1).min(self.lattice_size - 1);
+                    self.print_slice();
+                    true
+                }
+                KeyCode::ArrowDown => {
+                    self.slice_index = self.slice_index.saturating_sub(1);
+                    self.print_slice();
+                    true
+                }
+                #[cfg(feature = "dev-shader-reload")]
+                KeyCode::F5 => {
+                    match pollster::block_on(self.lattice.reload_shader()) {
+                        Ok(()) => println!("Shader reloaded"),
+                        Err(err) => println!("{}", err),
+                    }
+                    true
+                }
                 KeyCode::KeyR => {
                     println!("Resetting lattice...");
                     self.lattice.initialize_vacuum();
-                    let c = 50; // Assume 100³ lattice
-                    let radius = 15i32;
-                    for dz in -radius..=radius {
-                        for dy in -radius..=radius {
-                            for dx in -radius..=radius {
-                                let dist_sq = dx * dx + dy * dy + dz * dz;
-                                if dist_sq <= radius * radius {
-                                    self.lattice.add_energy_quantum(
-                                        (c + dx) as u32,
-                                        (c + dy) as u32,
-                                        (c + dz) as u32,
-                                        3,
-                                    );
-                                }
-                            }
-                        }
-                    }
+                    seed_initial_sphere(&mut self.lattice, self.lattice_size);
                     true
                 }
                 _ => false,
@@ -396,6 +567,14 @@ impl Viewer {
         }
     }
 
+    fn print_slice(&self) {
+        if self.slice_mode {
+            println!("Slice {:?} = {}", self.slice_axis, self.slice_index);
+        } else {
+            println!("Full volume");
+        }
+    }
+
     fn update(&mut self) {
         if !self.paused {
             self.lattice.propagate_energy();
@@ -413,6 +592,57 @@ impl Viewer {
             0,
             bytemuck::cast_slice(&[camera_uniform]),
         );
+
+        let params_uniform = ParamsUniform {
+            width: self.lattice_size,
+            height: self.lattice_size,
+            depth: self.lattice_size,
+            step_count: 0,
+            slice_axis: self.slice_axis.index(),
+            slice_index: self.slice_index,
+            _padding: [0; 2],
+        };
+        self.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::cast_slice(&[params_uniform]),
+        );
+    }
+
+    // Slot in render_bind_groups for the buffer holding the current state,
+    // creating its bind group the first time that buffer is drawn
+    fn render_bind_group_slot(&mut self) -> usize {
+        let energy_buffer = self.lattice.get_energy_buffer();
+        let id = energy_buffer.global_id();
+        match self
+            .render_bind_groups
+            .iter()
+            .position(|(key, _)| *key == id)
+        {
+            Some(slot) => slot,
+            None => {
+                let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Render Bind Group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: self.camera_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: self.params_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: energy_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+                self.render_bind_groups.push((id, bind_group));
+                self.render_bind_groups.len() - 1
+            }
+        }
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -421,26 +651,8 @@ impl Viewer {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        // Create bind group with current energy buffer (updates each frame for ping-pong buffers)
-        let energy_buffer = self.lattice.get_energy_buffer();
-        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Render Bind Group"),
-            layout: &self.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: self.camera_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: self.params_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: energy_buffer.as_entire_binding(),
-                },
-            ],
-        });
+        let slot = self.render_bind_group_slot();
+        let bind_group = &self.render_bind_groups[slot].1;
 
         let mut encoder = self
             .device
@@ -455,12 +667,7 @@ impl Viewer {
                     view: &view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.01,
-                            g: 0.01,
-                            b: 0.02,
-                            a: 1.0,
-                        }),
+                        load: wgpu::LoadOp::Clear(self.background.clear_color()),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -469,11 +676,23 @@ impl Viewer {
                 occlusion_query_set: None,
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.set_bind_group(0, bind_group, &[]);
+
+            if self.slice_mode {
+                // Two triangles per cell of a lattice_size² plane
+                let cells = self.lattice_size * self.lattice_size;
+                render_pass.set_pipeline(&self.slice_pipeline);
+                render_pass.draw(0..cells * 6, 0..1);
+            } else {
+                let total_sites = self.lattice.total_sites() as u32;
+                render_pass.set_pipeline(&self.render_pipeline);
+                render_pass.draw(0..total_sites, 0..1);
+            }
 
-            let total_sites = 100 * 100 * 100; // Assumes 100³ lattice
-            render_pass.draw(0..total_sites, 0..1);
+            if self.show_axes {
+                render_pass.set_pipeline(&self.axes_pipeline);
+                render_pass.draw(0..6, 0..1);
+            }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -485,6 +704,8 @@ impl Viewer {
 
 struct App {
     viewer: Option<Viewer>,
+    lattice_size: u32,
+    background: Background,
 }
 
 impl winit::application::ApplicationHandler for App {
@@ -495,7 +716,8 @@ impl winit::application::ApplicationHandler for App {
                 .with_inner_size(winit::dpi::LogicalSize::new(1280, 720));
 
             let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
-            let viewer = pollster::block_on(Viewer::new(window, 100));
+            let viewer =
+                pollster::block_on(Viewer::new(window, self.lattice_size, self.background));
             self.viewer = Some(viewer);
         }
     }
@@ -554,11 +776,47 @@ fn main() {
     println!("  Mouse drag: Rotate camera");
     println!("  Mouse wheel: Zoom");
     println!("  SPACE: Pause/Resume");
+    println!("  B: Cycle background color");
+    println!("  G: Toggle axis lines");
+    println!("  S: Toggle slice view");
+    println!("  X: Cycle slice axis");
+    println!("  Up/Down: Move slice");
     println!("  R: Reset simulation");
+    #[cfg(feature = "dev-shader-reload")]
+    println!("  F5: Reload shader.wgsl");
     println!("  ESC: Quit\n");
 
+    // Optional starting background: --background dark|black|white|gray
+    let args: Vec<String> = std::env::args().collect();
+    let background = match args.iter().position(|a| a == "--background") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|name| Background::from_name(name))
+            .unwrap_or_else(|| {
+                eprintln!("Unknown background, expected dark, black, white or gray");
+                std::process::exit(1);
+            }),
+        None => Background::Dark,
+    };
+    // Optional lattice edge length: --size N
+    let lattice_size = match args.iter().position(|a| a == "--size") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|size| size.parse().ok())
+            .filter(|&size| size > 0)
+            .unwrap_or_else(|| {
+                eprintln!("Expected a positive lattice size after --size");
+                std::process::exit(1);
+            }),
+        None => 100,
+    };
+
     let event_loop = EventLoop::new().unwrap();
-    let mut app = App { viewer: None };
+    let mut app = App {
+        viewer: None,
+        lattice_size,
+        background,
+    };
 
     event_loop.run_app(&mut app).unwrap();
 }
@@ -0,0 +1,45 @@
+// Compute workgroup dimensions and their validation against device limits
+
+use crate::LatticeError;
+
+/// Default workgroup size of the propagation passes, and the fixed size of
+/// every other compute entry point, matching the `@workgroup_size`
+/// attributes in reduce.wgsl and edit.wgsl.
+pub const WORKGROUP_SIZE: [u32; 3] = [4, 4, 4];
+
+/// Propagation workgroup sizes tried by
+/// [`DiscreteLatticeGPU::tune_workgroup_size`](crate::DiscreteLatticeGPU::tune_workgroup_size),
+/// from 64 to 256 invocations.
+pub const WORKGROUP_SIZE_CANDIDATES: [[u32; 3]; 6] = [
+    [4, 4, 4],
+    [8, 4, 4],
+    [8, 8, 2],
+    [8, 8, 4],
+    [16, 4, 4],
+    [16, 8, 2],
+];
+
+/// Checks that a workgroup of `size` fits within `limits`.
+///
+/// Pipeline creation with an oversized workgroup otherwise fails inside the
+/// driver with an error that doesn't name the cause.
+pub fn check_workgroup_size(size: [u32; 3], limits: &wgpu::Limits) -> Result<(), LatticeError> {
+    let max_size = [
+        limits.max_compute_workgroup_size_x,
+        limits.max_compute_workgroup_size_y,
+        limits.max_compute_workgroup_size_z,
+    ];
+    let max_invocations = limits.max_compute_invocations_per_workgroup;
+
+    let fits_axes = size.iter().zip(&max_size).all(|(s, max)| s <= max);
+    let invocations = size.iter().map(|&s| s as u64).product::<u64>();
+    if fits_axes && invocations <= max_invocations as u64 {
+        Ok(())
+    } else {
+        Err(LatticeError::WorkgroupTooLarge {
+            size,
+            max_size,
+            max_invocations,
+        })
+    }
+}
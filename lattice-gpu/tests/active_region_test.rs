@@ -0,0 +1,109 @@
+use lattice_gpu::*;
+
+fn seeded_lattice(size: u32, tracking: bool) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(size, size, size)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_seed(7);
+    lattice.set_active_region_tracking(tracking);
+    lattice.add_energy_quantum(size / 2, size / 2, size / 2, 3);
+    lattice.add_energy_quantum(1, 2, size - 1, 2);
+    lattice
+}
+
+// Step both lattices and compare them every few steps
+fn assert_same_run(tracked: &mut DiscreteLatticeGPU, plain: &mut DiscreteLatticeGPU, steps: u32) {
+    for step in 0..steps {
+        tracked.propagate_energy();
+        plain.propagate_energy();
+        if step % 5 == 4 {
+            assert_eq!(
+                pollster::block_on(tracked.get_state()),
+                pollster::block_on(plain.get_state()),
+                "Tracked lattice diverged at step {}",
+                step + 1
+            );
+        }
+    }
+}
+
+#[test]
+fn test_tracking_matches_full_dispatch() {
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        for neighborhood in [Neighborhood::VonNeumann, Neighborhood::Moore] {
+            let mut tracked = seeded_lattice(20, true);
+            let mut plain = seeded_lattice(20, false);
+            for lattice in [&mut tracked, &mut plain] {
+                lattice.set_propagation_mode(mode);
+                lattice.set_neighborhood(neighborhood);
+            }
+            assert_same_run(&mut tracked, &mut plain, 30);
+        }
+    }
+}
+
+#[test]
+fn test_tracking_matches_with_absorbing_faces_and_decay() {
+    let mut tracked = seeded_lattice(16, true);
+    let mut plain = seeded_lattice(16, false);
+    for lattice in [&mut tracked, &mut plain] {
+        lattice.set_boundary_mode(BoundaryMode::Absorbing);
+        lattice.set_decay_rate(0.05);
+        lattice.add_sources(&[(3, 3, 3, 1)]);
+    }
+    assert_same_run(&mut tracked, &mut plain, 30);
+    assert_eq!(
+        pollster::block_on(tracked.absorbed_energy()),
+        pollster::block_on(plain.absorbed_energy())
+    );
+    assert_eq!(
+        pollster::block_on(tracked.decayed_energy()),
+        pollster::block_on(plain.decayed_energy())
+    );
+}
+
+#[test]
+fn test_tracking_sees_edits_between_steps() {
+    let mut tracked = seeded_lattice(16, true);
+    let mut plain = seeded_lattice(16, false);
+    assert_same_run(&mut tracked, &mut plain, 5);
+
+    // Energy dropped into a far, idle block, and energy cleared from an
+    // active one, must both show up in the next step
+    let mut state = pollster::block_on(plain.get_state());
+    state
+        .iter_mut()
+        .take(16 * 16 * 4)
+        .for_each(|energy| *energy = 0);
+    state[15 * 16 * 16 + 15 * 16 + 15] = 3;
+    for lattice in [&mut tracked, &mut plain] {
+        lattice.set_state(&state);
+    }
+    assert_same_run(&mut tracked, &mut plain, 10);
+}
+
+#[test]
+fn test_tracking_dispatches_only_blocks_near_energy() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(64, 64, 64)).unwrap();
+    lattice.initialize_vacuum();
+    assert_eq!(pollster::block_on(lattice.active_block_count()), None);
+
+    lattice.set_active_region_tracking(true);
+    lattice.set_workgroup_size([4, 4, 4]).unwrap();
+    lattice.add_energy_quantum(32, 32, 32, 1);
+    lattice.propagate_energy();
+
+    // One occupied block and its 26 neighbors, out of 16³
+    assert_eq!(pollster::block_on(lattice.active_block_count()), Some(27));
+    assert!(lattice.active_region_tracking());
+}
+
+#[test]
+fn test_tracking_can_be_turned_off() {
+    let mut tracked = seeded_lattice(12, true);
+    let mut plain = seeded_lattice(12, false);
+    assert_same_run(&mut tracked, &mut plain, 5);
+
+    tracked.set_active_region_tracking(false);
+    assert!(!tracked.active_region_tracking());
+    assert_same_run(&mut tracked, &mut plain, 5);
+}
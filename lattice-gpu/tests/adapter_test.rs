@@ -0,0 +1,91 @@
+use lattice_gpu::*;
+
+fn first_adapter() -> wgpu::AdapterInfo {
+    list_adapters(wgpu::Backends::all())
+        .into_iter()
+        .next()
+        .expect("Failed to find GPU adapter")
+}
+
+#[test]
+fn test_default_options_build_a_lattice() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new_with_options(
+        &AdapterOptions::default(),
+        8,
+        8,
+        8,
+    ))
+    .unwrap();
+    lattice.seed_sphere((4, 4, 4), 2, 3);
+    lattice_gpu::testing::assert_conserved_over(&mut lattice, 5);
+}
+
+#[test]
+fn test_forced_backend_is_used() {
+    let backend = first_adapter().backend;
+    let options = AdapterOptions {
+        backends: backend.into(),
+        power_preference: wgpu::PowerPreference::LowPower,
+        ..Default::default()
+    };
+    let adapter = pollster::block_on(options.request_adapter()).unwrap();
+    assert_eq!(adapter.get_info().backend, backend);
+}
+
+#[test]
+fn test_adapter_chosen_by_name_and_index() {
+    let info = first_adapter();
+    let options = AdapterOptions {
+        name: Some(info.name.to_uppercase()),
+        ..Default::default()
+    };
+    let adapter = pollster::block_on(options.request_adapter()).unwrap();
+    assert_eq!(adapter.get_info().name, info.name);
+
+    let options = AdapterOptions {
+        index: Some(0),
+        ..Default::default()
+    };
+    let adapter = pollster::block_on(options.request_adapter()).unwrap();
+    assert_eq!(adapter.get_info().name, info.name);
+}
+
+#[test]
+fn test_unmatched_adapter_is_an_error() {
+    let count = list_adapters(wgpu::Backends::all()).len();
+    for options in [
+        AdapterOptions {
+            name: Some("no such adapter".to_string()),
+            ..Default::default()
+        },
+        AdapterOptions {
+            index: Some(count),
+            ..Default::default()
+        },
+    ] {
+        let result = pollster::block_on(DiscreteLatticeGPU::new_with_options(&options, 4, 4, 4));
+        assert!(matches!(result, Err(LatticeError::AdapterNotFound)));
+    }
+}
+
+#[test]
+fn test_adapter_info_reports_the_chosen_adapter() {
+    let info = first_adapter();
+    let options = AdapterOptions {
+        name: Some(info.name.clone()),
+        backends: info.backend.into(),
+        ..Default::default()
+    };
+    let lattice =
+        pollster::block_on(DiscreteLatticeGPU::new_with_options(&options, 4, 4, 4)).unwrap();
+    let reported = lattice.adapter_info().unwrap();
+    assert_eq!(reported.name, info.name);
+    assert_eq!(reported.backend, info.backend);
+
+    // A lattice on a borrowed device doesn't know its adapter
+    let adapter = pollster::block_on(AdapterOptions::default().request_adapter()).unwrap();
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&Default::default(), None)).unwrap();
+    let shared = DiscreteLatticeGPU::new_with_device(device.into(), queue.into(), 4, 4, 4).unwrap();
+    assert!(shared.adapter_info().is_none());
+}
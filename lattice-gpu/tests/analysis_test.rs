@@ -0,0 +1,341 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_total_variation_of_vacuum_is_zero() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+
+    assert_eq!(pollster::block_on(lattice.total_variation()), 0);
+}
+
+#[test]
+fn test_total_variation_of_single_seed() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(4, 4, 4, 3);
+
+    // Six neighbor pairs, each differing by 3
+    assert_eq!(pollster::block_on(lattice.total_variation()), 18);
+}
+
+#[test]
+fn test_total_variation_of_uniform_field_is_zero() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.initialize_vacuum();
+
+    // A sphere larger than the lattice saturates every site
+    lattice.seed_sphere((2, 2, 2), 8, 3);
+
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 3 * 64);
+    assert_eq!(pollster::block_on(lattice.total_variation()), 0);
+}
+
+#[test]
+fn test_total_variation_drops_after_first_step() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(4, 4, 4, 3);
+
+    // One quantum moves to a neighbor: 5 * 2 + 1 + 5 * 1
+    lattice.propagate_energy();
+    assert_eq!(pollster::block_on(lattice.total_variation()), 16);
+}
+
+#[test]
+fn test_tick_measurements() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(16, 16, 16)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((8, 8, 8), 2, 3);
+    let total = pollster::block_on(lattice.get_total_energy());
+
+    assert_eq!(pollster::block_on(lattice.tick(Measurement::None)), None);
+    assert_eq!(
+        pollster::block_on(lattice.tick(Measurement::Total)),
+        Some(total as f64)
+    );
+
+    // A symmetric blob only moves a fraction of a site in a few steps
+    let com_x = pollster::block_on(lattice.tick(Measurement::CenterOfMassX)).unwrap();
+    assert!(
+        (com_x - 8.0).abs() < 1.0,
+        "Center of mass drifted to {}",
+        com_x
+    );
+
+    let max_level = pollster::block_on(lattice.tick(Measurement::MaxLevel)).unwrap();
+    assert!((1.0..=6.0).contains(&max_level));
+}
+
+#[test]
+fn test_tick_center_of_mass_of_vacuum() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+
+    assert_eq!(
+        pollster::block_on(lattice.tick(Measurement::CenterOfMassY)),
+        None
+    );
+}
+
+#[test]
+fn test_diff_of_identical_state_is_empty() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 6, 4)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((4, 3, 2), 2, 2);
+
+    let state = pollster::block_on(lattice.get_state());
+    assert!(pollster::block_on(lattice.diff(&state)).is_empty());
+}
+
+#[test]
+fn test_diff_finds_compensating_errors() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 6, 4)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(1, 2, 3, 2);
+
+    // Same total, but the quantum sits somewhere else
+    let mut other = pollster::block_on(lattice.get_state());
+    other[3 * 8 * 6 + 2 * 8 + 1] = 1;
+    other[5] = 1;
+
+    let mut mismatches = pollster::block_on(lattice.diff(&other));
+    mismatches.sort();
+    assert_eq!(mismatches, vec![(1, 2, 3, 2, 1), (5, 0, 0, 0, 1)]);
+}
+
+#[test]
+fn test_saturated_count_matches_state() {
+    // Dimensions that don't fill whole 4x4x4 workgroups
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(10, 6, 5)).unwrap();
+    lattice.initialize_vacuum();
+    assert_eq!(pollster::block_on(lattice.saturated_count()), 0);
+
+    lattice.add_energy_quantum(0, 0, 0, MAX_LEVEL);
+    lattice.add_energy_quantum(9, 5, 4, MAX_LEVEL);
+    lattice.add_energy_quantum(5, 3, 2, 1);
+    assert_eq!(pollster::block_on(lattice.saturated_count()), 2);
+
+    // Counting twice must not accumulate
+    assert_eq!(pollster::block_on(lattice.saturated_count()), 2);
+
+    lattice.seed_sphere((5, 3, 2), 2, MAX_LEVEL);
+    for _ in 0..3 {
+        lattice.propagate_energy();
+        let state = pollster::block_on(lattice.get_state());
+        let expected = state.iter().filter(|&&e| e == MAX_LEVEL).count() as u64;
+        assert_eq!(pollster::block_on(lattice.saturated_count()), expected);
+    }
+}
+
+#[test]
+fn test_occupied_bounds() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(14, 15, 16)).unwrap();
+    lattice.initialize_vacuum();
+    assert_eq!(pollster::block_on(lattice.occupied_bounds()), None);
+
+    lattice.seed_sphere((6, 7, 8), 3, 3);
+    let bounds = pollster::block_on(lattice.occupied_bounds()).expect("Sphere is occupied");
+    assert_eq!(
+        bounds,
+        BoundingBox {
+            min: [3, 4, 5],
+            max: [9, 10, 11],
+        }
+    );
+    assert_eq!(bounds.size(), [7, 7, 7]);
+
+    // A lone site in the far corner stretches the box
+    lattice.add_energy_quantum(13, 14, 15, 1);
+    let bounds = pollster::block_on(lattice.occupied_bounds()).unwrap();
+    assert_eq!(bounds.min, [3, 4, 5]);
+    assert_eq!(bounds.max, [13, 14, 15]);
+
+    // Every occupied site lies inside the box after propagation
+    for _ in 0..5 {
+        lattice.propagate_energy();
+    }
+    let bounds = pollster::block_on(lattice.occupied_bounds()).unwrap();
+    let state = pollster::block_on(lattice.get_state());
+    for z in 0..16 {
+        for y in 0..15 {
+            for x in 0..14 {
+                if state[(z * 14 * 15 + y * 14 + x) as usize] != 0 {
+                    assert!(bounds.contains(x, y, z), "({}, {}, {})", x, y, z);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_total_energy_of_saturated_lattice() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(10, 6, 5)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((5, 3, 2), 20, MAX_LEVEL);
+
+    assert_eq!(
+        pollster::block_on(lattice.get_total_energy()),
+        10 * 6 * 5 * MAX_LEVEL as u64
+    );
+}
+
+#[test]
+fn test_total_energy_carries_past_u32() {
+    // Far beyond any reachable energy, but exercises every carry path: each
+    // site alone is near u32::MAX, so nearly every add wraps the low word
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(9, 9, 9)).unwrap();
+    let mut state = pollster::block_on(lattice.save_state());
+    for (i, e) in state.energy.iter_mut().enumerate() {
+        *e = u32::MAX - i as u32;
+    }
+    lattice.load_state(&state);
+
+    let expected: u64 = state.energy.iter().map(|&e| e as u64).sum();
+    assert!(expected > u32::MAX as u64);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), expected);
+}
+
+#[test]
+fn test_laplacian_of_point_seed() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(4, 4, 4, 3);
+
+    let laplacian = pollster::block_on(lattice.laplacian());
+    let index = |x: usize, y: usize, z: usize| z * 64 + y * 8 + x;
+    assert_eq!(laplacian[index(4, 4, 4)], -18);
+    for (x, y, z) in [
+        (3, 4, 4),
+        (5, 4, 4),
+        (4, 3, 4),
+        (4, 5, 4),
+        (4, 4, 3),
+        (4, 4, 5),
+    ] {
+        assert_eq!(laplacian[index(x, y, z)], 3);
+    }
+    assert_eq!(laplacian.iter().filter(|&&l| l != 0).count(), 7);
+}
+
+#[test]
+fn test_laplacian_of_linear_ramp_wraps() {
+    // e = x on a periodic lattice: zero inside, with the jump at the seam
+    // showing up at x = 0 and x = width - 1
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 2, 2)).unwrap();
+    let mut state = pollster::block_on(lattice.save_state());
+    for (i, e) in state.energy.iter_mut().enumerate() {
+        *e = (i % 4) as u32;
+    }
+    lattice.load_state(&state);
+
+    let laplacian = pollster::block_on(lattice.laplacian());
+    for row in laplacian.chunks(4) {
+        assert_eq!(row, [4, 0, 0, -4]);
+    }
+    assert_eq!(laplacian.iter().sum::<i32>(), 0);
+}
+
+// e = x on a 4x2x2 lattice with the given X boundary mode
+fn ramp(x_mode: BoundaryMode) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 2, 2)).unwrap();
+    lattice.set_axis_boundary_modes(x_mode, BoundaryMode::Periodic, BoundaryMode::Periodic);
+    let mut state = pollster::block_on(lattice.save_state());
+    for (i, e) in state.energy.iter_mut().enumerate() {
+        *e = (i % 4) as u32;
+    }
+    lattice.load_state(&state);
+    lattice
+}
+
+#[test]
+fn test_laplacian_follows_boundary_modes() {
+    for (mode, expected) in [
+        (BoundaryMode::Periodic, [4, 0, 0, -4]),
+        (BoundaryMode::Closed, [1, 0, 0, -1]),
+        (BoundaryMode::Reflective, [2, 0, 0, -2]),
+        // The sink beyond each face is empty
+        (BoundaryMode::Absorbing, [1, 0, 0, -4]),
+    ] {
+        let laplacian = pollster::block_on(ramp(mode).laplacian());
+        for row in laplacian.chunks(4) {
+            assert_eq!(row, expected, "{:?}", mode);
+        }
+    }
+}
+
+#[test]
+fn test_total_variation_pairs_faces_only_when_periodic() {
+    // Per row: three steps of 1, plus the seam of 3 when X wraps
+    assert_eq!(
+        pollster::block_on(ramp(BoundaryMode::Periodic).total_variation()),
+        4 * 6
+    );
+    for mode in [
+        BoundaryMode::Closed,
+        BoundaryMode::Absorbing,
+        BoundaryMode::Reflective,
+    ] {
+        assert_eq!(
+            pollster::block_on(ramp(mode).total_variation()),
+            4 * 3,
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn test_occupied_sites_match_state() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(13, 11, 9)).unwrap();
+    lattice.initialize_vacuum();
+    assert!(pollster::block_on(lattice.get_occupied_sites()).is_empty());
+
+    lattice.seed_sphere((6, 5, 4), 3, 3);
+    lattice.add_energy_quantum(12, 10, 8, 1);
+    lattice.propagate_n(5);
+
+    let state = pollster::block_on(lattice.get_state());
+    let mut expected = Vec::new();
+    for z in 0..9u32 {
+        for y in 0..11u32 {
+            for x in 0..13u32 {
+                let e = state[(z * 13 * 11 + y * 13 + x) as usize];
+                if e != 0 {
+                    expected.push((x, y, z, e));
+                }
+            }
+        }
+    }
+    assert_eq!(pollster::block_on(lattice.get_occupied_sites()), expected);
+}
+
+#[test]
+fn test_occupied_count_and_max_energy_match_state() {
+    // Dimensions that don't fill whole 4x4x4 workgroups
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(10, 6, 5)).unwrap();
+    lattice.initialize_vacuum();
+    assert_eq!(pollster::block_on(lattice.count_occupied_sites()), 0);
+    assert_eq!(pollster::block_on(lattice.max_site_energy()), 0);
+
+    lattice.seed_sphere((5, 3, 2), 2, 2);
+    lattice.add_energy_quantum(9, 5, 4, MAX_LEVEL);
+    for _ in 0..4 {
+        let state = pollster::block_on(lattice.get_state());
+        let occupied = state.iter().filter(|&&e| e != 0).count() as u64;
+        let max = state.iter().copied().max().unwrap();
+        assert_eq!(pollster::block_on(lattice.count_occupied_sites()), occupied);
+        assert_eq!(pollster::block_on(lattice.max_site_energy()), max);
+        lattice.propagate_energy();
+    }
+}
+
+#[test]
+fn test_max_energy_sees_states_past_max_level() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    let mut state = vec![0u32; 64];
+    state[37] = 1000;
+    state[5] = 2;
+    lattice.set_state(&state);
+    assert_eq!(pollster::block_on(lattice.max_site_energy()), 1000);
+    assert_eq!(pollster::block_on(lattice.count_occupied_sites()), 2);
+}
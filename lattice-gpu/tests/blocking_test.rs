@@ -0,0 +1,42 @@
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::*;
+
+#[test]
+fn test_blocking_lattice_reads_without_await() {
+    let mut lattice = BlockingLattice::new(8, 8, 8).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((4, 4, 4), 2, 3);
+    let initial = lattice.get_total_energy();
+    assert!(initial > 0);
+
+    lattice.set_rule(&RandomWalkRule).unwrap();
+    lattice.propagate_n(10);
+    assert_eq!(lattice.get_total_energy(), initial);
+    assert_eq!(
+        lattice.get_state().iter().map(|&e| e as u64).sum::<u64>(),
+        initial
+    );
+    assert_eq!(lattice.tick(Measurement::Total), Some(initial as f64));
+    assert_eq!(lattice.save_state().generation, 11);
+
+    let inner = lattice.into_inner();
+    assert_eq!(inner.generation(), 11);
+}
+
+#[test]
+fn test_async_api_runs_inside_one_future() {
+    // Every step of a run awaited from a single task, with nothing blocking
+    // inside it
+    let (total, injected) = pollster::block_on(async {
+        let mut lattice = DiscreteLatticeGPU::new(8, 8, 8).await.unwrap();
+        lattice.set_rule(&GradientRule).await.unwrap();
+        lattice.add_sources(&[(1, 1, 1, 1)]);
+        lattice.propagate_n(5);
+        (
+            lattice.get_total_energy().await,
+            lattice.injected_energy().await,
+        )
+    });
+    assert_eq!(total, injected);
+    assert!(injected > 0);
+}
@@ -0,0 +1,177 @@
+use lattice_gpu::testing::assert_conserved_over;
+use lattice_gpu::*;
+
+// A full site in the corner at the origin, on a small lattice
+fn corner_lattice(boundary: BoundaryMode, mode: PropagationMode) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_boundary_mode(boundary);
+    lattice.set_propagation_mode(mode);
+    lattice.add_energy_quantum(0, 0, 0, 3);
+    lattice
+}
+
+// Energy on the far faces, i.e. sites only reachable across the origin's faces
+fn far_face_energy(state: &[u32]) -> u32 {
+    let mut total = 0;
+    for z in 0..8u32 {
+        for y in 0..8u32 {
+            for x in 0..8u32 {
+                if x == 7 || y == 7 || z == 7 {
+                    total += state[(z * 64 + y * 8 + x) as usize];
+                }
+            }
+        }
+    }
+    total
+}
+
+#[test]
+fn test_periodic_boundary_wraps_to_opposite_face() {
+    let mut lattice = corner_lattice(BoundaryMode::Periodic, PropagationMode::Gather);
+    let mut wrapped = false;
+    for _ in 0..3 {
+        lattice.propagate_energy();
+        wrapped |= far_face_energy(&pollster::block_on(lattice.get_state())) > 0;
+    }
+    assert!(wrapped, "No quantum crossed a periodic face");
+}
+
+#[test]
+fn test_closed_boundary_never_crosses_faces() {
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        let mut lattice = corner_lattice(BoundaryMode::Closed, mode);
+        for _ in 0..3 {
+            lattice.propagate_energy();
+            let state = pollster::block_on(lattice.get_state());
+            assert_eq!(
+                far_face_energy(&state),
+                0,
+                "{:?} crossed a closed face",
+                mode
+            );
+        }
+    }
+}
+
+#[test]
+fn test_closed_boundary_gather_matches_scatter() {
+    let build = |mode| {
+        let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+        lattice.initialize_vacuum();
+        lattice.set_boundary_mode(BoundaryMode::Closed);
+        lattice.set_propagation_mode(mode);
+        lattice.seed_sphere((2, 2, 2), 3, 3);
+        lattice
+    };
+    let mut gather = build(PropagationMode::Gather);
+    let mut scatter = build(PropagationMode::Scatter);
+
+    for _ in 0..30 {
+        gather.propagate_energy();
+        scatter.propagate_energy();
+    }
+    assert_eq!(
+        pollster::block_on(gather.get_state()),
+        pollster::block_on(scatter.get_state())
+    );
+    assert_conserved_over(&mut gather, 20);
+}
+
+#[test]
+fn test_absorbing_boundary_accounts_for_removed_energy() {
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(10, 10, 10)).unwrap();
+        lattice.initialize_vacuum();
+        lattice.set_boundary_mode(BoundaryMode::Absorbing);
+        lattice.set_propagation_mode(mode);
+        lattice.seed_sphere((1, 1, 1), 2, 3);
+        let initial = pollster::block_on(lattice.get_total_energy());
+
+        for _ in 0..40 {
+            lattice.propagate_energy();
+        }
+
+        let total = pollster::block_on(lattice.get_total_energy());
+        let absorbed = pollster::block_on(lattice.absorbed_energy());
+        assert!(absorbed > 0, "{:?} absorbed nothing", mode);
+        assert_eq!(total + absorbed, initial, "{:?} lost track of energy", mode);
+    }
+}
+
+#[test]
+fn test_absorbed_energy_resets_with_vacuum() {
+    let mut lattice = corner_lattice(BoundaryMode::Absorbing, PropagationMode::Gather);
+    for _ in 0..5 {
+        lattice.propagate_energy();
+    }
+    assert!(pollster::block_on(lattice.absorbed_energy()) > 0);
+
+    lattice.initialize_vacuum();
+    assert_eq!(pollster::block_on(lattice.absorbed_energy()), 0);
+}
+
+#[test]
+fn test_reflective_boundary_conserves_and_stays_inside() {
+    let build = |mode| {
+        let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(10, 10, 10)).unwrap();
+        lattice.initialize_vacuum();
+        lattice.set_boundary_mode(BoundaryMode::Reflective);
+        lattice.set_propagation_mode(mode);
+        lattice.seed_sphere((1, 1, 1), 2, 3);
+        lattice
+    };
+    let mut gather = build(PropagationMode::Gather);
+    let mut scatter = build(PropagationMode::Scatter);
+
+    for _ in 0..30 {
+        gather.propagate_energy();
+        scatter.propagate_energy();
+    }
+    assert_eq!(
+        pollster::block_on(gather.get_state()),
+        pollster::block_on(scatter.get_state())
+    );
+    assert_conserved_over(&mut gather, 20);
+    assert_eq!(pollster::block_on(gather.absorbed_energy()), 0);
+}
+
+#[test]
+fn test_reflective_edge_site_bounces_inward() {
+    // A lone 2 on the -X face of a 1-wide strip along X: its only
+    // neighbors are +X and the mirrored -X, which are the same site
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 1, 1)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_axis_boundary_modes(
+        BoundaryMode::Reflective,
+        BoundaryMode::Closed,
+        BoundaryMode::Closed,
+    );
+    lattice.add_energy_quantum(0, 0, 0, 2);
+    lattice.propagate_energy();
+    assert_eq!(pollster::block_on(lattice.get_state()), vec![1, 1, 0, 0]);
+}
+
+#[test]
+fn test_per_axis_boundary_modes() {
+    // Periodic X and Y, reflective Z: energy at the origin corner wraps
+    // across the X and Y faces but never reaches the far Z face
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_axis_boundary_modes(
+        BoundaryMode::Periodic,
+        BoundaryMode::Periodic,
+        BoundaryMode::Reflective,
+    );
+    lattice.add_energy_quantum(0, 0, 0, 3);
+
+    let mut wrapped = false;
+    for _ in 0..3 {
+        lattice.propagate_energy();
+        let state = pollster::block_on(lattice.get_state());
+        let far_z: u32 = state[7 * 64..].iter().sum();
+        assert_eq!(far_z, 0, "Quantum crossed the reflective Z face");
+        wrapped |= (0..8).any(|i| state[7 + i * 8] > 0 || state[56 + i] > 0);
+    }
+    assert!(wrapped, "No quantum crossed a periodic face");
+}
@@ -0,0 +1,79 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_capacity_caps_seeding() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 1, 1)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_capacity(&[1, 2, 5, 0]);
+    lattice.add_energy_batch(&[(0, 0, 0, 9), (1, 0, 0, 9), (2, 0, 0, 9), (3, 0, 0, 9)]);
+    assert_eq!(pollster::block_on(lattice.get_state()), vec![1, 2, 5, 0]);
+
+    // Without the map the global cap applies again
+    lattice.clear_capacity();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(2, 0, 0, 9);
+    assert_eq!(
+        pollster::block_on(lattice.get_energy_at(2, 0, 0)),
+        MAX_LEVEL
+    );
+}
+
+#[test]
+fn test_transfers_respect_capacity() {
+    // A closed line whose middle site can't take anything: energy on the
+    // left never reaches the right
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(5, 1, 1)).unwrap();
+    lattice.set_boundary_mode(BoundaryMode::Closed);
+    lattice.initialize_vacuum();
+    lattice.set_capacity(&[3, 3, 0, 3, 3]);
+    lattice.add_energy_quantum(0, 0, 0, 3);
+
+    lattice.propagate_n(30);
+
+    let state = pollster::block_on(lattice.get_state());
+    assert_eq!(state[0] + state[1], 3, "{:?}", state);
+    assert_eq!(&state[2..], &[0, 0, 0]);
+}
+
+#[test]
+fn test_high_capacity_sites_hold_more() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    let mut capacity = vec![MAX_LEVEL; 512];
+    capacity[0] = 10;
+    lattice.set_capacity(&capacity);
+    let mut state = vec![0; 512];
+    state[0] = 10;
+    lattice.set_state(&state);
+    let initial = pollster::block_on(lattice.get_total_energy());
+
+    for _ in 0..20 {
+        lattice.propagate_energy();
+        let state = pollster::block_on(lattice.get_state());
+        assert!(state.iter().zip(&capacity).all(|(e, c)| e <= c));
+    }
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), initial);
+}
+
+#[test]
+fn test_capacity_changes_between_steps_take_effect() {
+    // The map replaces the bound capacity buffer after steps have already
+    // run, and again when cleared
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(5, 1, 1)).unwrap();
+    lattice.set_boundary_mode(BoundaryMode::Closed);
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(0, 0, 0, 3);
+    lattice.propagate_n(2);
+    lattice.set_capacity(&[3, 3, 0, 3, 3]);
+    let mut state = vec![0; 5];
+    state[0] = 3;
+    lattice.set_state(&state);
+
+    lattice.propagate_n(20);
+    assert_eq!(&pollster::block_on(lattice.get_state())[2..], &[0, 0, 0]);
+
+    lattice.clear_capacity();
+    lattice.propagate_n(40);
+    let state = pollster::block_on(lattice.get_state());
+    assert!(state[2..].iter().any(|&e| e > 0), "{:?}", state);
+}
@@ -0,0 +1,76 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_channels_are_independent() {
+    let mut lattice = pollster::block_on(ChannelLattice::new(12, 12, 12, 2)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum_channel(0, 6, 6, 6, 3);
+    lattice.add_energy_quantum_channel(1, 6, 6, 6, 2);
+    lattice.add_energy_quantum_channel(1, 2, 3, 4, 1);
+    assert_eq!(lattice.channel_count(), 2);
+    assert_eq!(pollster::block_on(lattice.channel_totals()), vec![3, 3]);
+
+    for _ in 0..20 {
+        pollster::block_on(lattice.propagate_energy());
+    }
+
+    // Each channel conserves its own energy; quanta never cross channels
+    assert_eq!(pollster::block_on(lattice.channel_totals()), vec![3, 3]);
+    assert_eq!(lattice.channel(0).generation(), 20);
+    assert_eq!(lattice.channel(1).generation(), 20);
+}
+
+#[test]
+fn test_channels_follow_their_own_rule() {
+    let mut lattice = pollster::block_on(ChannelLattice::new(8, 8, 8, 2)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.channel_mut(1).set_axis_weights(0.0, 0.0, 1.0);
+    lattice.add_energy_quantum_channel(0, 4, 4, 4, 3);
+    lattice.add_energy_quantum_channel(1, 4, 4, 4, 3);
+
+    for _ in 0..20 {
+        pollster::block_on(lattice.propagate_energy());
+    }
+
+    // Channel 1 only moves along Z, so it stays on the x = y = 4 column
+    let column = |x: u32, y: u32| x == 4 && y == 4;
+    let sites = pollster::block_on(lattice.channel(1).get_occupied_sites());
+    assert!(
+        sites.iter().all(|&(x, y, _, _)| column(x, y)),
+        "{:?}",
+        sites
+    );
+    let sites = pollster::block_on(lattice.channel(0).get_occupied_sites());
+    assert!(
+        sites.iter().any(|&(x, y, _, _)| !column(x, y)),
+        "{:?}",
+        sites
+    );
+}
+
+#[test]
+fn test_coupling_repels_other_channel() {
+    // A closed 3-site line: channel 0 holds a frozen pile at site 0, and a
+    // channel 1 quantum in the middle is pushed away from it
+    let mut lattice = pollster::block_on(ChannelLattice::new(3, 1, 1, 2)).unwrap();
+    lattice.initialize_vacuum();
+    for channel in 0..2 {
+        lattice
+            .channel_mut(channel)
+            .set_boundary_mode(BoundaryMode::Closed);
+    }
+    lattice.add_energy_quantum_channel(0, 0, 0, 0, 3);
+    lattice.channel_mut(0).set_frozen(&[(0, 0, 0)]);
+    lattice.set_coupling(1, 0, 10.0);
+
+    for seed in 0..8 {
+        lattice.channel_mut(1).initialize_vacuum();
+        lattice.channel_mut(1).set_seed(seed);
+        lattice.add_energy_quantum_channel(1, 1, 0, 0, 1);
+        pollster::block_on(lattice.propagate_energy());
+        assert_eq!(
+            pollster::block_on(lattice.channel(1).get_state()),
+            vec![0, 0, 1]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::*;
+use std::io;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lattice_gpu_{}_{}", std::process::id(), name))
+}
+
+fn experiment() -> BlockingLattice {
+    let config = LatticeConfig {
+        seed: 7,
+        decay_rate: 0.02,
+        rule: RuleConfig::RandomWalk,
+        ..LatticeConfig::new(12, 10, 8)
+    };
+    let mut lattice = BlockingLattice::from_config(&config).unwrap();
+    lattice.seed_sphere((6, 5, 4), 3, 2);
+    lattice
+}
+
+#[test]
+fn test_resumed_run_matches_uninterrupted_run() {
+    let path = temp_path("resume.lgck");
+    let mut uninterrupted = experiment();
+    uninterrupted.propagate_n(40);
+
+    let mut first = experiment();
+    first.propagate_n(15);
+    first.save_checkpoint(&path).unwrap();
+    drop(first);
+
+    let mut resumed = experiment();
+    resumed.load_checkpoint(&path).unwrap();
+    assert_eq!(resumed.generation(), 15);
+    resumed.propagate_n(25);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(resumed.generation(), uninterrupted.generation());
+    assert_eq!(resumed.get_state(), uninterrupted.get_state());
+}
+
+#[test]
+fn test_checkpoint_restores_seed() {
+    let path = temp_path("seed.lgck");
+    let mut lattice = experiment();
+    lattice.set_seed(1234);
+    lattice.save_checkpoint(&path).unwrap();
+
+    let checkpoint = Checkpoint::read(&path).unwrap();
+    assert_eq!(checkpoint.seed, 1234);
+    assert_eq!(checkpoint.state, lattice.save_state());
+
+    let mut restored = experiment();
+    restored.load_checkpoint(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(restored.seed(), 1234);
+}
+
+#[test]
+fn test_dimension_mismatch_is_rejected() {
+    let path = temp_path("mismatch.lgck");
+    let lattice = experiment();
+    lattice.save_checkpoint(&path).unwrap();
+
+    let mut other = BlockingLattice::new(8, 8, 8).unwrap();
+    other.add_energy_quantum(1, 1, 1, 1);
+    let err = other.load_checkpoint(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(other.get_total_energy(), 1);
+}
+
+#[test]
+fn test_corrupt_files_are_rejected() {
+    let mut reader: &[u8] = b"not a checkpoint at all";
+    let err = Checkpoint::read_from(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // A truncated energy stream must not load
+    let checkpoint = Checkpoint {
+        state: experiment().save_state(),
+        seed: 3,
+    };
+    let mut bytes = Vec::new();
+    checkpoint.write_to(&mut bytes).unwrap();
+    assert_eq!(
+        Checkpoint::read_from(&mut bytes.as_slice()).unwrap(),
+        checkpoint
+    );
+    bytes.truncate(bytes.len() - 8);
+    assert!(Checkpoint::read_from(&mut bytes.as_slice()).is_err());
+}
+
+#[test]
+fn test_sparse_checkpoint_is_small() {
+    let path = temp_path("sparse.lgck");
+    let mut lattice = BlockingLattice::new(128, 128, 128).unwrap();
+    lattice.seed_sphere((64, 64, 64), 6, 3);
+    lattice.propagate_n(5);
+    lattice.save_checkpoint(&path).unwrap();
+    let size = std::fs::metadata(&path).unwrap().len();
+
+    let checkpoint = Checkpoint::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(checkpoint.state, lattice.save_state());
+    // The dense energy is 8 MiB
+    assert!(size < 64 * 1024, "checkpoint is {} bytes", size);
+}
+
+#[test]
+fn test_version_1_checkpoints_still_load() {
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    let state = experiment().save_state();
+    let mut bytes = b"LGCK".to_vec();
+    for value in [1, state.width, state.height, state.depth] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&state.generation.to_le_bytes());
+    bytes.extend_from_slice(&9u32.to_le_bytes());
+    let mut encoder = ZlibEncoder::new(bytes, flate2::Compression::default());
+    for energy in &state.energy {
+        encoder.write_all(&energy.to_le_bytes()).unwrap();
+    }
+    let bytes = encoder.finish().unwrap();
+
+    let checkpoint = Checkpoint::read_from(&mut bytes.as_slice()).unwrap();
+    assert_eq!(checkpoint.state, state);
+    assert_eq!(checkpoint.seed, 9);
+}
+
+#[test]
+fn test_newer_versions_are_rejected() {
+    let mut bytes = Vec::new();
+    Checkpoint {
+        state: experiment().save_state(),
+        seed: 0,
+    }
+    .write_to(&mut bytes)
+    .unwrap();
+    bytes[4..8].copy_from_slice(&99u32.to_le_bytes());
+    let err = Checkpoint::read_from(&mut bytes.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("99"));
+}
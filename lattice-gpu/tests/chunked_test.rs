@@ -0,0 +1,161 @@
+use lattice_gpu::*;
+use std::sync::Arc;
+
+// A lattice split into chunks of at most `max_chunk_depth` layers, and an
+// unsplit one of the same size on its own device
+fn chunked_pair(
+    size: (u32, u32, u32),
+    max_chunk_depth: u32,
+) -> (ChunkedLattice, DiscreteLatticeGPU) {
+    let (width, height, depth) = size;
+    let whole = pollster::block_on(DiscreteLatticeGPU::new(width, height, depth)).unwrap();
+    let (device, queue) = pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .expect("Failed to find GPU adapter");
+        adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await
+            .expect("Failed to create device")
+    });
+    let chunked = ChunkedLattice::new_with_device(
+        Arc::new(device),
+        Arc::new(queue),
+        width,
+        height,
+        depth,
+        max_chunk_depth,
+    )
+    .unwrap();
+    (chunked, whole)
+}
+
+fn seed_both(chunked: &mut ChunkedLattice, whole: &mut DiscreteLatticeGPU) {
+    let (width, height, depth) = chunked.dimensions();
+    let mut edits = sphere_points(
+        (width / 2, height / 2, depth / 2),
+        3,
+        3,
+        (width, height, depth),
+    );
+    // Sites on the slab boundaries and the global Z faces
+    edits.extend((0..depth).map(|z| (z % width, 1, z, 2)));
+    chunked.add_energy_batch(&edits);
+    whole.add_energy_batch(&edits);
+}
+
+fn assert_matches_whole(chunked: &mut ChunkedLattice, whole: &mut DiscreteLatticeGPU, steps: u32) {
+    assert_eq!(
+        pollster::block_on(chunked.get_state()),
+        pollster::block_on(whole.get_state())
+    );
+    for step in 1..=steps {
+        chunked.propagate_energy();
+        whole.propagate_energy();
+        assert_eq!(
+            pollster::block_on(chunked.get_state()),
+            pollster::block_on(whole.get_state()),
+            "Chunked lattice diverged at step {}",
+            step
+        );
+    }
+    assert_eq!(
+        pollster::block_on(chunked.get_total_energy()),
+        pollster::block_on(whole.get_total_energy())
+    );
+}
+
+#[test]
+fn test_chunked_matches_whole_lattice() {
+    let (mut chunked, mut whole) = chunked_pair((8, 7, 19), 5);
+    assert_eq!(chunked.chunk_count(), 4);
+    seed_both(&mut chunked, &mut whole);
+    assert_matches_whole(&mut chunked, &mut whole, 25);
+}
+
+#[test]
+fn test_chunked_matches_in_scatter_mode_with_moore_and_decay() {
+    let (mut chunked, mut whole) = chunked_pair((6, 6, 12), 4);
+    chunked.set_propagation_mode(PropagationMode::Scatter);
+    whole.set_propagation_mode(PropagationMode::Scatter);
+    chunked.set_neighborhood(Neighborhood::Moore);
+    whole.set_neighborhood(Neighborhood::Moore);
+    chunked.set_decay_rate(0.02);
+    whole.set_decay_rate(0.02);
+    chunked.set_seed(99);
+    whole.set_seed(99);
+    seed_both(&mut chunked, &mut whole);
+    assert_matches_whole(&mut chunked, &mut whole, 20);
+}
+
+#[test]
+fn test_chunked_matches_with_open_z_boundaries() {
+    let (mut chunked, mut whole) = chunked_pair((7, 5, 14), 6);
+    let modes = (
+        BoundaryMode::Reflective,
+        BoundaryMode::Closed,
+        BoundaryMode::Absorbing,
+    );
+    chunked
+        .set_axis_boundary_modes(modes.0, modes.1, modes.2)
+        .unwrap();
+    whole.set_axis_boundary_modes(modes.0, modes.1, modes.2);
+    chunked.set_axis_weights(0.5, 1.0, 2.0);
+    whole.set_axis_weights(0.5, 1.0, 2.0);
+    seed_both(&mut chunked, &mut whole);
+    assert_matches_whole(&mut chunked, &mut whole, 25);
+}
+
+#[test]
+fn test_chunked_boundary_change_keeps_state() {
+    let (mut chunked, mut whole) = chunked_pair((6, 6, 10), 4);
+    seed_both(&mut chunked, &mut whole);
+    assert_matches_whole(&mut chunked, &mut whole, 5);
+
+    // Leaving and re-entering periodic Z rebuilds the chunks mid-run
+    for z in [BoundaryMode::Closed, BoundaryMode::Periodic] {
+        let x = BoundaryMode::Periodic;
+        chunked.set_axis_boundary_modes(x, x, z).unwrap();
+        whole.set_axis_boundary_modes(x, x, z);
+        assert_matches_whole(&mut chunked, &mut whole, 5);
+    }
+    assert_eq!(chunked.generation(), 15);
+}
+
+#[test]
+fn test_chunked_two_chunk_ring() {
+    // Each chunk's halos on both sides come from the other chunk
+    let (mut chunked, mut whole) = chunked_pair((5, 5, 8), 4);
+    assert_eq!(chunked.chunk_count(), 2);
+    seed_both(&mut chunked, &mut whole);
+    assert_matches_whole(&mut chunked, &mut whole, 20);
+}
+
+#[test]
+fn test_chunked_set_state_and_region() {
+    let (mut chunked, mut whole) = chunked_pair((4, 3, 9), 4);
+    let state: Vec<u32> = (0..4 * 3 * 9).map(|i| (i * 7 % 5) as u32 % 4).collect();
+    chunked.set_state(&state);
+    whole.set_state(&state);
+    assert_eq!(pollster::block_on(chunked.get_state()), state);
+
+    let region = BoundingBox {
+        min: [1, 0, 2],
+        max: [3, 2, 7],
+    };
+    assert_eq!(
+        pollster::block_on(chunked.get_energy_region(region)),
+        pollster::block_on(whole.get_energy_region(region))
+    );
+    assert_matches_whole(&mut chunked, &mut whole, 10);
+}
+
+#[test]
+fn test_chunked_new_uses_one_chunk_when_it_fits() {
+    let mut lattice = pollster::block_on(ChunkedLattice::new(8, 8, 8)).unwrap();
+    assert_eq!(lattice.chunk_count(), 1);
+    Lattice::seed_sphere(&mut lattice, (4, 4, 4), 2, 3);
+    lattice_gpu::testing::assert_conserved_over(&mut lattice, 10);
+}
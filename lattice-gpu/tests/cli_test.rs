@@ -0,0 +1,108 @@
+use lattice_gpu::Checkpoint;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lattice_gpu_{}_{}", std::process::id(), name))
+}
+
+fn walkthe(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_walkthe"))
+        .args(args)
+        .output()
+        .expect("Failed to run walkthe")
+}
+
+#[test]
+fn test_run_then_export() {
+    let checkpoint = temp_path("cli.lgck");
+    let output = walkthe(&[
+        "run",
+        "--size",
+        "12",
+        "--steps",
+        "5",
+        "--seed",
+        "4",
+        "--checkpoint",
+        checkpoint.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("generation 5"));
+
+    let saved = Checkpoint::read(&checkpoint).unwrap();
+    assert_eq!(
+        (saved.state.width, saved.state.height, saved.state.depth),
+        (12, 12, 12)
+    );
+    assert_eq!(saved.state.generation, 5);
+    assert_eq!(saved.seed, 4);
+    assert!(saved.state.energy.iter().any(|&e| e > 0));
+
+    let vti = temp_path("cli.vti");
+    let output = walkthe(&[
+        "export",
+        checkpoint.to_str().unwrap(),
+        "--vti",
+        vti.to_str().unwrap(),
+    ]);
+    std::fs::remove_file(&checkpoint).unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let bytes = std::fs::read(&vti).unwrap();
+    std::fs::remove_file(&vti).unwrap();
+    assert!(bytes.starts_with(b"<?xml"));
+}
+
+#[test]
+fn test_bad_arguments_fail() {
+    let output = walkthe(&["run", "--size", "8", "--width", "4"]);
+    assert!(!output.status.success());
+
+    let output = walkthe(&["export", "missing.lgck"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nothing to export"));
+
+    let output = walkthe(&["run", "--config", "/nonexistent/experiment.toml"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_bench_writes_json_and_csv() {
+    let output = walkthe(&[
+        "bench", "--sizes", "8,12", "--steps", "3", "--format", "json",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let records = records.as_array().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1]["size"], 12);
+    assert_eq!(records[1]["sites"], 12 * 12 * 12);
+    assert_eq!(records[0]["iterations"], 3);
+    assert_eq!(records[0]["energy_conserved"], true);
+    assert!(records[0]["ms_per_iter"].as_f64().unwrap() > 0.0);
+    assert!(!records[0]["adapter"].as_str().unwrap().is_empty());
+    assert!(!records[0]["backend"].as_str().unwrap().is_empty());
+
+    let path = temp_path("bench.csv");
+    let output = walkthe(&[
+        "bench",
+        "--sizes",
+        "8",
+        "--steps",
+        "3",
+        "--format",
+        "csv",
+        "--output",
+        path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("size,sites,iterations,"));
+    assert!(lines[1].starts_with("8,512,3,"));
+
+    let output = walkthe(&["bench", "--output", path.to_str().unwrap()]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,92 @@
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::*;
+
+fn experiment() -> LatticeConfig {
+    LatticeConfig {
+        propagation_mode: PropagationMode::Scatter,
+        neighborhood: Neighborhood::Moore,
+        boundary_modes: [
+            BoundaryMode::Periodic,
+            BoundaryMode::Reflective,
+            BoundaryMode::Absorbing,
+        ],
+        axis_weights: [1.0, 0.5, 2.0],
+        seed: 42,
+        decay_rate: 0.01,
+        rule: RuleConfig::RandomWalk,
+        ..LatticeConfig::new(12, 10, 8)
+    }
+}
+
+#[test]
+fn test_config_round_trips_through_json() {
+    let config = experiment();
+    let json = serde_json::to_string(&config).unwrap();
+    assert!(json.contains("\"moore\""));
+    assert_eq!(
+        serde_json::from_str::<LatticeConfig>(&json).unwrap(),
+        config
+    );
+}
+
+#[test]
+fn test_missing_fields_take_defaults() {
+    let config: LatticeConfig =
+        serde_json::from_str(r#"{"width": 4, "height": 5, "depth": 6}"#).unwrap();
+    assert_eq!(config, LatticeConfig::new(4, 5, 6));
+}
+
+#[test]
+fn test_from_config_matches_manual_setup() {
+    let config = experiment();
+    let mut configured = BlockingLattice::from_config(&config).unwrap();
+
+    let mut manual = BlockingLattice::new(12, 10, 8).unwrap();
+    manual.set_rule(&RandomWalkRule).unwrap();
+    manual.set_propagation_mode(PropagationMode::Scatter);
+    manual.set_neighborhood(Neighborhood::Moore);
+    manual.set_axis_boundary_modes(
+        BoundaryMode::Periodic,
+        BoundaryMode::Reflective,
+        BoundaryMode::Absorbing,
+    );
+    manual.set_axis_weights(1.0, 0.5, 2.0);
+    manual.set_seed(42);
+    manual.set_decay_rate(0.01);
+
+    for lattice in [&mut configured, &mut manual] {
+        lattice.seed_sphere((6, 5, 4), 3, 2);
+        lattice.propagate_n(20);
+    }
+    assert_eq!(configured.get_state(), manual.get_state());
+}
+
+#[test]
+fn test_invalid_config_is_rejected() {
+    let config = LatticeConfig {
+        decay_rate: 1.5,
+        ..LatticeConfig::new(4, 4, 4)
+    };
+    assert!(matches!(
+        BlockingLattice::from_config(&config),
+        Err(LatticeError::InvalidConfig(_))
+    ));
+
+    let config = LatticeConfig::new(0, 4, 4);
+    assert!(matches!(
+        BlockingLattice::from_config(&config),
+        Err(LatticeError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_state_round_trips_through_json() {
+    let mut lattice = BlockingLattice::new(4, 4, 4).unwrap();
+    lattice.add_energy_quantum(1, 2, 3, 3);
+    lattice.propagate_n(3);
+    let state = lattice.save_state();
+
+    let json = serde_json::to_string(&state).unwrap();
+    let restored: LatticeState = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, state);
+}
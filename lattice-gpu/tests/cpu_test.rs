@@ -0,0 +1,136 @@
+use lattice_gpu::*;
+
+// Step both backends side by side and require identical states every step
+fn assert_backends_agree(gpu: &mut DiscreteLatticeGPU, cpu: &mut DiscreteLatticeCPU, steps: u32) {
+    assert_eq!(pollster::block_on(gpu.get_state()), cpu.get_state());
+    for step in 1..=steps {
+        gpu.propagate_energy();
+        cpu.propagate_energy();
+        assert_eq!(
+            pollster::block_on(gpu.get_state()),
+            cpu.get_state(),
+            "Backends diverged at step {}",
+            step
+        );
+    }
+}
+
+fn seeded_pair(width: u32, height: u32, depth: u32) -> (DiscreteLatticeGPU, DiscreteLatticeCPU) {
+    let mut gpu = pollster::block_on(DiscreteLatticeGPU::new(width, height, depth)).unwrap();
+    let mut cpu = DiscreteLatticeCPU::new(width, height, depth);
+    let center = (width / 2, height / 2, depth / 2);
+    gpu.seed_sphere(center, 2, 3);
+    cpu.seed_sphere(center, 2, 3);
+    gpu.add_energy_quantum(0, 0, 0, 2);
+    cpu.add_energy_quantum(0, 0, 0, 2);
+    (gpu, cpu)
+}
+
+#[test]
+fn test_cpu_matches_gpu_by_default() {
+    let (mut gpu, mut cpu) = seeded_pair(10, 7, 5);
+    assert_backends_agree(&mut gpu, &mut cpu, 20);
+    assert_eq!(
+        cpu.get_total_energy(),
+        pollster::block_on(gpu.get_total_energy())
+    );
+}
+
+#[test]
+fn test_cpu_matches_gpu_in_scatter_mode() {
+    let (mut gpu, mut cpu) = seeded_pair(8, 8, 8);
+    gpu.set_propagation_mode(PropagationMode::Scatter);
+    assert_backends_agree(&mut gpu, &mut cpu, 15);
+}
+
+#[test]
+fn test_cpu_matches_gpu_with_boundaries_and_weights() {
+    let (mut gpu, mut cpu) = seeded_pair(9, 6, 5);
+    let modes = (
+        BoundaryMode::Absorbing,
+        BoundaryMode::Reflective,
+        BoundaryMode::Closed,
+    );
+    gpu.set_axis_boundary_modes(modes.0, modes.1, modes.2);
+    cpu.set_axis_boundary_modes(modes.0, modes.1, modes.2);
+    gpu.set_axis_weights(1.0, 0.5, 0.25);
+    cpu.set_axis_weights(1.0, 0.5, 0.25);
+    gpu.set_seed(1234);
+    cpu.set_seed(1234);
+    assert_backends_agree(&mut gpu, &mut cpu, 25);
+    assert_eq!(
+        cpu.absorbed_energy(),
+        pollster::block_on(gpu.absorbed_energy())
+    );
+}
+
+#[test]
+fn test_cpu_matches_gpu_with_moore_neighborhood_and_decay() {
+    let (mut gpu, mut cpu) = seeded_pair(7, 7, 6);
+    gpu.set_neighborhood(Neighborhood::Moore);
+    cpu.set_neighborhood(Neighborhood::Moore);
+    gpu.set_decay_rate(0.05);
+    cpu.set_decay_rate(0.05);
+    assert_backends_agree(&mut gpu, &mut cpu, 20);
+    assert_eq!(
+        cpu.decayed_energy(),
+        pollster::block_on(gpu.decayed_energy())
+    );
+    assert!(cpu.decayed_energy() > 0);
+}
+
+#[test]
+fn test_cpu_matches_gpu_with_frozen_sites_and_obstacles() {
+    let (mut gpu, mut cpu) = seeded_pair(8, 8, 4);
+    let frozen = [(0, 0, 0), (4, 3, 2)];
+    gpu.set_frozen(&frozen);
+    cpu.set_frozen(&frozen);
+
+    // A wall across X = 6, in vacuum
+    let mask: Vec<bool> = (0..8 * 8 * 4).map(|idx| idx % 8 == 6).collect();
+    gpu.set_obstacles(&mask);
+    cpu.set_obstacles(&mask);
+    assert_backends_agree(&mut gpu, &mut cpu, 20);
+}
+
+#[test]
+fn test_cpu_add_energy_caps_at_max_level() {
+    let mut cpu = DiscreteLatticeCPU::new(4, 4, 4);
+    cpu.add_energy_quantum(1, 1, 1, 2);
+    cpu.add_energy_quantum(1, 1, 1, 2);
+    cpu.add_energy_quantum(9, 0, 0, 1);
+    assert_eq!(cpu.get_total_energy(), MAX_LEVEL as u64);
+
+    // States above the cap are kept, not lowered
+    let mut state = vec![0; 64];
+    state[5] = 7;
+    cpu.set_state(&state);
+    cpu.add_energy_quantum(1, 1, 0, 1);
+    assert_eq!(cpu.get_state()[5], 7);
+}
+
+#[test]
+fn test_lattice_trait_runs_either_backend() {
+    fn run(lattice: &mut dyn Lattice) -> Vec<u32> {
+        lattice.initialize_vacuum();
+        lattice.add_energy_quantum(2, 2, 2, 3);
+        lattice.add_energy_quantum(5, 1, 3, 1);
+        lattice.propagate_n(10);
+        assert_eq!(lattice.total_energy(), 4);
+        assert_eq!(lattice.generation(), 10);
+        lattice.read_state()
+    }
+
+    let mut gpu = pollster::block_on(DiscreteLatticeGPU::new(6, 6, 6)).unwrap();
+    let mut cpu = DiscreteLatticeCPU::new(6, 6, 6);
+    assert_eq!(gpu.dimensions(), cpu.dimensions());
+    assert_eq!(run(&mut gpu), run(&mut cpu));
+}
+
+#[test]
+fn test_cpu_conserves_energy() {
+    let mut cpu = DiscreteLatticeCPU::new(8, 8, 8);
+    Lattice::seed_sphere(&mut cpu, (4, 4, 4), 3, 3);
+    cpu.set_neighborhood(Neighborhood::Moore);
+    lattice_gpu::testing::assert_conserved_over(&mut cpu, 30);
+}
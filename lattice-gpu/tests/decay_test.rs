@@ -0,0 +1,86 @@
+use lattice_gpu::*;
+
+fn decaying_lattice(rate: f32, mode: PropagationMode) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_propagation_mode(mode);
+    lattice.set_decay_rate(rate);
+    lattice.seed_sphere((6, 6, 6), 3, 3);
+    lattice
+}
+
+#[test]
+fn test_decay_is_counted() {
+    let mut lattice = decaying_lattice(0.05, PropagationMode::Gather);
+    let initial = pollster::block_on(lattice.get_total_energy());
+
+    for _ in 0..30 {
+        lattice.propagate_energy();
+    }
+
+    let total = pollster::block_on(lattice.get_total_energy());
+    let decayed = pollster::block_on(lattice.decayed_energy());
+    assert!(decayed > 0 && total < initial);
+    assert_eq!(total + decayed, initial);
+    assert_eq!(pollster::block_on(lattice.absorbed_energy()), 0);
+
+    // Reinitializing clears the count
+    lattice.initialize_vacuum();
+    assert_eq!(pollster::block_on(lattice.decayed_energy()), 0);
+}
+
+#[test]
+fn test_full_decay_drains_every_occupied_site() {
+    let mut lattice = decaying_lattice(1.0, PropagationMode::Gather);
+    let initial = pollster::block_on(lattice.get_total_energy());
+    let occupied = pollster::block_on(lattice.get_occupied_sites()).len() as u64;
+
+    lattice.propagate_energy();
+
+    assert_eq!(
+        pollster::block_on(lattice.get_total_energy()),
+        initial - occupied
+    );
+    assert_eq!(pollster::block_on(lattice.decayed_energy()), occupied);
+}
+
+#[test]
+fn test_decay_gather_matches_scatter() {
+    let mut gather = decaying_lattice(0.1, PropagationMode::Gather);
+    let mut scatter = decaying_lattice(0.1, PropagationMode::Scatter);
+
+    for _ in 0..20 {
+        gather.propagate_energy();
+        scatter.propagate_energy();
+    }
+
+    assert_eq!(
+        pollster::block_on(gather.get_state()),
+        pollster::block_on(scatter.get_state())
+    );
+    assert_eq!(
+        pollster::block_on(gather.decayed_energy()),
+        pollster::block_on(scatter.decayed_energy())
+    );
+}
+
+#[test]
+fn test_frozen_sites_do_not_decay() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(1, 1, 1, 3);
+    lattice.set_frozen(&[(1, 1, 1)]);
+    lattice.set_decay_rate(1.0);
+
+    lattice.propagate_n(5);
+
+    assert_eq!(pollster::block_on(lattice.get_energy_at(1, 1, 1)), 3);
+    assert_eq!(pollster::block_on(lattice.decayed_energy()), 0);
+}
+
+#[test]
+#[should_panic(expected = "Decay rate")]
+fn test_decay_rate_out_of_range_panics() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.set_decay_rate(1.5);
+}
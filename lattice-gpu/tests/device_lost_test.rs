@@ -0,0 +1,83 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_device_loss_is_reported() {
+    let lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    let events = lattice.events();
+    assert!(!lattice.is_device_lost());
+    assert!(events.try_recv().is_err());
+
+    lattice_gpu::testing::lose_device(&lattice);
+    assert!(lattice.is_device_lost());
+    assert!(events
+        .try_iter()
+        .any(|event| matches!(event, LatticeEvent::DeviceLost { .. })));
+}
+
+#[test]
+fn test_recover_restores_checkpoint_and_settings() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    let mut reference = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    for l in [&mut lattice, &mut reference] {
+        l.set_propagation_mode(PropagationMode::Scatter);
+        l.set_neighborhood(Neighborhood::Moore);
+        l.set_seed(17);
+        l.set_frozen(&[(1, 1, 1)]);
+        l.add_sources(&[(6, 6, 6, 1)]);
+        l.seed_sphere((4, 4, 4), 2, 3);
+        l.propagate_n(5);
+    }
+    pollster::block_on(lattice.checkpoint());
+    let events = lattice.events();
+
+    // Steps after the checkpoint are lost along with the device
+    lattice.propagate_n(3);
+    lattice_gpu::testing::lose_device(&lattice);
+    pollster::block_on(lattice.recover()).unwrap();
+    assert!(!lattice.is_device_lost());
+    assert_eq!(lattice.generation(), 5);
+
+    lattice.propagate_n(10);
+    reference.propagate_n(10);
+    assert_eq!(
+        pollster::block_on(lattice.get_state()),
+        pollster::block_on(reference.get_state())
+    );
+
+    // The same receiver hears about the replacement device
+    events.try_iter().for_each(drop);
+    lattice_gpu::testing::lose_device(&lattice);
+    assert!(events
+        .try_iter()
+        .any(|event| matches!(event, LatticeEvent::DeviceLost { .. })));
+}
+
+#[test]
+fn test_recover_without_checkpoint_starts_empty() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(6, 6, 6)).unwrap();
+    lattice.seed_sphere((3, 3, 3), 2, 3);
+    lattice.propagate_n(4);
+    lattice_gpu::testing::lose_device(&lattice);
+    pollster::block_on(lattice.recover()).unwrap();
+    assert_eq!(lattice.generation(), 0);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 0);
+}
+
+#[test]
+fn test_uncaptured_errors_keep_only_the_latest_events() {
+    let lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    let events = lattice.events();
+    for _ in 0..100 {
+        lattice_gpu::testing::raise_uncaptured_error(&lattice);
+    }
+
+    // Without a reader the queue stops growing instead of panicking
+    let queued: Vec<_> = events.try_iter().collect();
+    assert_eq!(queued.len(), 64);
+    assert!(queued
+        .iter()
+        .all(|event| matches!(event, LatticeEvent::UncapturedError { .. })));
+
+    lattice_gpu::testing::raise_uncaptured_error(&lattice);
+    assert_eq!(events.try_iter().count(), 1);
+}
@@ -1,8 +1,9 @@
+use lattice_gpu::testing::assert_conserved_over;
 use lattice_gpu::*;
 
 #[test]
 fn test_energy_conservation_small_lattice() {
-    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(20, 20, 20));
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(20, 20, 20)).unwrap();
     lattice.initialize_vacuum();
 
     // Add energy at center
@@ -11,22 +12,12 @@ fn test_energy_conservation_small_lattice() {
     let initial_energy = pollster::block_on(lattice.get_total_energy());
     assert_eq!(initial_energy, 3, "Initial energy should be 3 quanta");
 
-    // Propagate 50 steps
-    for _ in 0..50 {
-        lattice.propagate_energy();
-    }
-
-    let final_energy = pollster::block_on(lattice.get_total_energy());
-    assert_eq!(
-        final_energy, initial_energy,
-        "Energy must be conserved: started with {}, ended with {}",
-        initial_energy, final_energy
-    );
+    assert_conserved_over(&mut lattice, 50);
 }
 
 #[test]
 fn test_energy_conservation_multiple_sources() {
-    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(30, 30, 30));
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(30, 30, 30)).unwrap();
     lattice.initialize_vacuum();
 
     // Add energy at multiple locations
@@ -37,68 +28,68 @@ fn test_energy_conservation_multiple_sources() {
     let initial_energy = pollster::block_on(lattice.get_total_energy());
     assert_eq!(initial_energy, 6, "Initial energy should be 6 quanta");
 
-    // Propagate 100 steps
-    for _ in 0..100 {
-        lattice.propagate_energy();
-    }
-
-    let final_energy = pollster::block_on(lattice.get_total_energy());
-    assert_eq!(
-        final_energy, initial_energy,
-        "Energy must be conserved with multiple sources"
-    );
+    assert_conserved_over(&mut lattice, 100);
 }
 
 #[test]
 fn test_vacuum_stays_vacuum() {
-    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(20, 20, 20));
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(20, 20, 20)).unwrap();
     lattice.initialize_vacuum();
 
     let initial_energy = pollster::block_on(lattice.get_total_energy());
     assert_eq!(initial_energy, 0, "Vacuum should have zero energy");
 
     // Propagate vacuum - should stay at zero
-    for _ in 0..50 {
-        lattice.propagate_energy();
-    }
-
-    let final_energy = pollster::block_on(lattice.get_total_energy());
-    assert_eq!(final_energy, 0, "Vacuum should remain at zero energy");
+    assert_conserved_over(&mut lattice, 50);
 }
 
 #[test]
 fn test_large_lattice_conservation() {
-    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(100, 100, 100));
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(100, 100, 100)).unwrap();
     lattice.initialize_vacuum();
 
     // Add spherical distribution
-    let c = 50;
-    for dz in -3i32..=3 {
-        for dy in -3i32..=3 {
-            for dx in -3i32..=3 {
-                if dx * dx + dy * dy + dz * dz <= 9 {
-                    lattice.add_energy_quantum(
-                        (c + dx) as u32,
-                        (c + dy) as u32,
-                        (c + dz) as u32,
-                        3,
-                    );
-                }
-            }
-        }
-    }
+    lattice.seed_sphere((50, 50, 50), 3, 3);
 
     let initial_energy = pollster::block_on(lattice.get_total_energy());
     assert!(initial_energy > 0, "Should have non-zero initial energy");
 
     // Propagate many steps
-    for _ in 0..100 {
+    assert_conserved_over(&mut lattice, 100);
+}
+
+#[test]
+fn test_new_with_adapter() {
+    let instance = wgpu::Instance::default();
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+        .expect("Failed to find GPU adapter");
+
+    let mut lattice =
+        pollster::block_on(DiscreteLatticeGPU::new_with_adapter(&adapter, 16, 16, 16))
+            .expect("Failed to create lattice from adapter");
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(8, 8, 8, 2);
+
+    for _ in 0..20 {
+        lattice.propagate_energy();
+    }
+
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 2);
+}
+
+#[test]
+fn test_generation_counts_steps() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    assert_eq!(lattice.generation(), 0);
+
+    lattice.add_energy_quantum(4, 4, 4, 3);
+    for _ in 0..7 {
         lattice.propagate_energy();
     }
+    assert_eq!(lattice.generation(), 7);
 
-    let final_energy = pollster::block_on(lattice.get_total_energy());
-    assert_eq!(
-        final_energy, initial_energy,
-        "Energy must be conserved in large lattice"
-    );
+    // Edits after an odd number of steps land in the active buffer
+    lattice.add_energy_quantum(1, 1, 1, 2);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 5);
 }
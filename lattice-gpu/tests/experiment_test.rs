@@ -0,0 +1,148 @@
+use lattice_gpu::blocking::{self, BlockingLattice};
+use lattice_gpu::*;
+use std::path::{Path, PathBuf};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lattice_gpu_{}_{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const EXPERIMENT: &str = r#"
+steps = 12
+sources = [[1, 1, 1, 1]]
+
+[lattice]
+width = 10
+height = 8
+depth = 6
+boundary_modes = ["periodic", "reflective", "absorbing"]
+seed = 3
+rule = "random_walk"
+
+[[initial]]
+type = "sphere"
+center = [5, 4, 3]
+radius = 2
+quanta = 2
+
+[[initial]]
+type = "point"
+site = [0, 0, 0]
+quanta = 3
+
+[[initial]]
+type = "csv"
+path = "points.csv"
+
+[output]
+checkpoint = "final.lgck"
+vti_series = "run.pvd"
+series_interval = 5
+"#;
+
+#[test]
+fn test_experiment_parses_and_round_trips() {
+    let config = ExperimentConfig::from_toml(EXPERIMENT).unwrap();
+    assert_eq!(config.steps, 12);
+    assert_eq!(config.lattice.rule, RuleConfig::RandomWalk);
+    assert_eq!(config.lattice.boundary_modes[2], BoundaryMode::Absorbing);
+    assert_eq!(
+        config.initial[1],
+        InitialCondition::Point {
+            site: [0, 0, 0],
+            quanta: 3
+        }
+    );
+    assert_eq!(config.output.series_interval, 5);
+    assert_eq!(
+        ExperimentConfig::from_toml(&config.to_toml()).unwrap(),
+        config
+    );
+}
+
+#[test]
+fn test_run_from_config_matches_manual_run() {
+    let dir = temp_dir("experiment");
+    std::fs::write(dir.join("points.csv"), "x,y,z,quanta\n9,7,5,2\n").unwrap();
+    let path = dir.join("experiment.toml");
+    std::fs::write(&path, EXPERIMENT).unwrap();
+
+    let lattice = blocking::run_from_config(&path).unwrap();
+    assert_eq!(lattice.generation(), 12);
+
+    let mut manual = BlockingLattice::new(10, 8, 6).unwrap();
+    manual.set_rule(&RandomWalkRule).unwrap();
+    manual.set_axis_boundary_modes(
+        BoundaryMode::Periodic,
+        BoundaryMode::Reflective,
+        BoundaryMode::Absorbing,
+    );
+    manual.set_seed(3);
+    manual.seed_sphere((5, 4, 3), 2, 2);
+    manual.add_energy_quantum(0, 0, 0, 3);
+    manual.add_energy_quantum(9, 7, 5, 2);
+    manual.add_sources(&[(1, 1, 1, 1)]);
+    manual.propagate_n(12);
+    assert_eq!(lattice.get_state(), manual.get_state());
+
+    // Outputs land beside the experiment file
+    let checkpoint = Checkpoint::read(&dir.join("final.lgck")).unwrap();
+    assert_eq!(checkpoint.state, lattice.save_state());
+    let collection = std::fs::read_to_string(dir.join("run.pvd")).unwrap();
+    for generation in [0, 5, 10, 12] {
+        assert!(collection.contains(&format!("timestep=\"{}\"", generation)));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_invalid_experiments_are_rejected() {
+    let missing_steps = "[lattice]\nwidth = 4\nheight = 4\ndepth = 4\n";
+    assert!(matches!(
+        ExperimentConfig::from_toml(missing_steps),
+        Err(LatticeError::InvalidConfig(_))
+    ));
+
+    let unknown_type = format!("steps = 1\n{}[[initial]]\ntype = \"cube\"\n", missing_steps);
+    assert!(matches!(
+        ExperimentConfig::from_toml(&unknown_type),
+        Err(LatticeError::InvalidConfig(_))
+    ));
+
+    assert!(matches!(
+        ExperimentConfig::read(Path::new("/nonexistent/experiment.toml")),
+        Err(LatticeError::Io(_))
+    ));
+}
+
+#[test]
+fn test_example_experiment_parses() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/experiment.toml");
+    let config = ExperimentConfig::read(&path).unwrap();
+    assert_eq!(config.lattice.width, 64);
+    assert!(config.output.checkpoint.unwrap().is_absolute());
+}
+
+#[test]
+fn test_cancelled_experiment_still_writes_outputs() {
+    let dir = temp_dir("cancelled");
+    let checkpoint = dir.join("final.lgck");
+    let mut config =
+        ExperimentConfig::from_toml("steps = 1000\n[lattice]\nwidth = 8\nheight = 8\ndepth = 8\n")
+            .unwrap();
+    config.initial.push(InitialCondition::Point {
+        site: [4, 4, 4],
+        quanta: 3,
+    });
+    config.output.checkpoint = Some(checkpoint.clone());
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let lattice = pollster::block_on(config.run_cancellable(&token)).unwrap();
+    assert_eq!(lattice.generation(), 0);
+    let saved = Checkpoint::read(&checkpoint).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(saved.state.generation, 0);
+    assert_eq!(saved.state.energy.iter().sum::<u32>(), 3);
+}
@@ -0,0 +1,160 @@
+use lattice_gpu::*;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lattice_gpu_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn test_export_npy_round_trip() {
+    let (width, height, depth) = (6, 5, 4);
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(width, height, depth)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(5, 0, 0, 1);
+    lattice.add_energy_quantum(2, 3, 1, 2);
+    lattice.add_energy_quantum(0, 4, 3, 3);
+
+    let path = temp_path("round_trip.npy");
+    pollster::block_on(lattice.export_npy(&path)).expect("Failed to write .npy");
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Magic and version 1.0
+    assert_eq!(&bytes[..6], b"\x93NUMPY");
+    assert_eq!(&bytes[6..8], &[1, 0]);
+
+    // Header is padded so the data starts 64-byte aligned
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let data_start = 10 + header_len;
+    assert_eq!(data_start % 64, 0);
+
+    let header = std::str::from_utf8(&bytes[10..data_start]).unwrap();
+    assert!(header.ends_with('\n'));
+    assert!(header.contains("'descr': '<u4'"));
+    assert!(header.contains("'fortran_order': False"));
+    assert!(header.contains("'shape': (4, 5, 6)"));
+
+    // Data is the state in C order, little-endian
+    let data: Vec<u32> = bytes[data_start..]
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    assert_eq!(data, pollster::block_on(lattice.get_state()));
+    assert_eq!(data[5], 1);
+    assert_eq!(data[(width * height + 3 * width + 2) as usize], 2);
+    assert_eq!(data[(3 * width * height + 4 * width) as usize], 3);
+}
+
+// Splits a .vti file into its XML header and the raw appended array
+fn parse_vti(bytes: &[u8]) -> (String, Vec<u32>) {
+    let marker = b"<AppendedData encoding=\"raw\">\n_";
+    let start = bytes
+        .windows(marker.len())
+        .position(|window| window == marker)
+        .expect("No appended data")
+        + marker.len();
+    let header = String::from_utf8(bytes[..start].to_vec()).unwrap();
+    let len = u64::from_le_bytes(bytes[start..start + 8].try_into().unwrap()) as usize;
+    let data = bytes[start + 8..start + 8 + len]
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    assert!(std::str::from_utf8(&bytes[start + 8 + len..])
+        .unwrap()
+        .contains("</VTKFile>"));
+    (header, data)
+}
+
+#[test]
+fn test_export_vti_round_trip() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(6, 5, 4)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(5, 0, 0, 1);
+    lattice.add_energy_quantum(2, 3, 1, 2);
+    lattice.propagate_n(3);
+
+    let path = temp_path("round_trip.vti");
+    pollster::block_on(lattice.export_vti(&path)).expect("Failed to write .vti");
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let (header, data) = parse_vti(&bytes);
+    assert!(header.contains("type=\"ImageData\""));
+    assert!(header.contains("WholeExtent=\"0 5 0 4 0 3\""));
+    assert!(header.contains("Name=\"TimeValue\" NumberOfTuples=\"1\" format=\"ascii\">3<"));
+    assert!(header.contains("type=\"UInt32\" Name=\"energy\""));
+    assert_eq!(data, pollster::block_on(lattice.get_state()));
+}
+
+#[test]
+fn test_vtk_series_writes_collection() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.seed_sphere((2, 2, 2), 1, 2);
+
+    let path = temp_path("series.pvd");
+    let mut series = VtkSeries::new(&path);
+    assert!(series.is_empty());
+    for _ in 0..3 {
+        lattice.propagate_n(2);
+        pollster::block_on(series.append(&lattice)).unwrap();
+    }
+    // Appending the same generation again replaces its frame
+    pollster::block_on(series.append(&lattice)).unwrap();
+    assert_eq!(series.len(), 3);
+
+    let collection = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(collection.contains("type=\"Collection\""));
+    for generation in [2, 4, 6] {
+        let name = format!(
+            "lattice_gpu_{}_series_{:06}.vti",
+            std::process::id(),
+            generation
+        );
+        assert!(collection.contains(&format!(
+            "<DataSet timestep=\"{}\" part=\"0\" file=\"{}\"/>",
+            generation, name
+        )));
+        let frame = path.with_file_name(&name);
+        let (header, _) = parse_vti(&std::fs::read(&frame).unwrap());
+        std::fs::remove_file(&frame).unwrap();
+        assert!(header.contains(&format!("format=\"ascii\">{}<", generation)));
+    }
+}
+
+#[test]
+fn test_export_raw_writes_volume_and_header() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(6, 5, 4)).unwrap();
+    lattice.seed_sphere((3, 2, 2), 2, 3);
+    lattice.propagate_n(4);
+    let state = pollster::block_on(lattice.get_state());
+
+    let path = temp_path("volume.raw");
+    pollster::block_on(lattice.export_raw(&path)).expect("Failed to write raw volume");
+    let bytes = std::fs::read(&path).unwrap();
+    let header = std::fs::read_to_string(path.with_extension("mhd")).unwrap();
+    let data: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    assert_eq!(data, state);
+    assert!(header.contains("NDims = 3\n"));
+    assert!(header.contains("DimSize = 6 5 4\n"));
+    assert!(header.contains("ElementType = MET_UINT\n"));
+    assert!(header.contains("ElementByteOrderMSB = False\n"));
+    assert!(header.contains(&format!(
+        "ElementDataFile = {}\n",
+        path.file_name().unwrap().to_string_lossy()
+    )));
+
+    pollster::block_on(lattice.export_raw_u8(&path)).expect("Failed to write raw volume");
+    let bytes = std::fs::read(&path).unwrap();
+    let header = std::fs::read_to_string(path.with_extension("mhd")).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(path.with_extension("mhd")).unwrap();
+    assert_eq!(bytes, state.iter().map(|&e| e as u8).collect::<Vec<_>>());
+    assert!(header.contains("ElementType = MET_UCHAR\n"));
+
+    let err = pollster::block_on(lattice.export_raw(&temp_path("volume.mhd"))).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,92 @@
+use lattice_gpu::*;
+
+const SIZE: u32 = 12;
+
+fn index(x: u32, y: u32, z: u32) -> usize {
+    (z * SIZE * SIZE + y * SIZE + x) as usize
+}
+
+// Frozen planes at x = 0 and x = 6 split the periodic lattice in two
+fn walled_lattice(mode: PropagationMode) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(SIZE, SIZE, SIZE)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_propagation_mode(mode);
+
+    let mut wall = Vec::new();
+    for z in 0..SIZE {
+        for y in 0..SIZE {
+            wall.push((0, y, z));
+            wall.push((6, y, z));
+        }
+    }
+    lattice.set_frozen(&wall);
+    lattice.seed_sphere((3, 6, 6), 2, 3);
+    lattice
+}
+
+#[test]
+fn test_frozen_wall_confines_energy() {
+    let mut lattice = walled_lattice(PropagationMode::Gather);
+    let total = pollster::block_on(lattice.get_total_energy());
+
+    for _ in 0..40 {
+        lattice.propagate_energy();
+    }
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), total);
+
+    let state = pollster::block_on(lattice.get_state());
+    for z in 0..SIZE {
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if !(1..6).contains(&x) {
+                    assert_eq!(state[index(x, y, z)], 0, "Energy at ({}, {}, {})", x, y, z);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_frozen_matches_between_modes() {
+    let mut gather = walled_lattice(PropagationMode::Gather);
+    let mut scatter = walled_lattice(PropagationMode::Scatter);
+    for _ in 0..20 {
+        gather.propagate_energy();
+        scatter.propagate_energy();
+    }
+    assert_eq!(
+        pollster::block_on(gather.get_state()),
+        pollster::block_on(scatter.get_state())
+    );
+}
+
+#[test]
+fn test_frozen_site_holds_its_energy() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(SIZE, SIZE, SIZE)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((6, 6, 6), 2, 3);
+    lattice.add_energy_quantum(2, 2, 2, 2);
+    lattice.set_frozen(&[(6, 6, 6), (2, 2, 2), (SIZE, 0, 0)]);
+
+    assert_eq!(pollster::block_on(lattice.frozen_energy()), 5);
+    let mobile = pollster::block_on(lattice.get_total_energy()) - 5;
+
+    for _ in 0..20 {
+        lattice.propagate_energy();
+        let total = pollster::block_on(lattice.get_total_energy());
+        assert_eq!(pollster::block_on(lattice.frozen_energy()), 5);
+        assert_eq!(total - 5, mobile);
+    }
+    let state = pollster::block_on(lattice.get_state());
+    assert_eq!(state[index(6, 6, 6)], 3);
+    assert_eq!(state[index(2, 2, 2)], 2);
+
+    // Once thawed the held quanta flow again
+    lattice.clear_frozen();
+    assert_eq!(pollster::block_on(lattice.frozen_energy()), 0);
+    for _ in 0..5 {
+        lattice.propagate_energy();
+    }
+    let state = pollster::block_on(lattice.get_state());
+    assert!(state[index(2, 2, 2)] < 2);
+}
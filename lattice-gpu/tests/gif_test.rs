@@ -0,0 +1,40 @@
+use lattice_gpu::*;
+use std::fs::File;
+
+#[test]
+fn test_render_gif_writes_all_frames() {
+    let path = std::env::temp_dir().join(format!("lattice_gpu_{}_run.gif", std::process::id()));
+    let config = GifConfig {
+        path: path.clone(),
+        lattice_size: 16,
+        seed_radius: 3,
+        frames: 4,
+        steps_per_frame: 3,
+        width: 48,
+        height: 32,
+        ..Default::default()
+    };
+    pollster::block_on(render_gif(config)).expect("GIF export failed");
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (48, 32));
+
+    let mut frames = 0;
+    let mut lit_pixels = 0;
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames += 1;
+        // The dark background has no channel near full brightness; energy
+        // points do
+        lit_pixels += frame
+            .buffer
+            .chunks(4)
+            .filter(|p| p[0] > 128 || p[1] > 128 || p[2] > 128)
+            .count();
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(frames, 4);
+    assert!(lit_pixels > 0, "Frames should show the seeded energy");
+}
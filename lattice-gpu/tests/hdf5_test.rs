@@ -0,0 +1,117 @@
+#![cfg(feature = "hdf5")]
+
+use hdf5_metno_sys::{h5a, h5d, h5f, h5p, h5s, h5t};
+use lattice_gpu::{DiscreteLatticeGPU, Hdf5Series};
+use std::ffi::{c_void, CString};
+use std::path::{Path, PathBuf};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lattice_gpu_{}_{}", std::process::id(), name))
+}
+
+// The energy of frame `name` and its step and total_energy attributes
+fn read_frame(path: &Path, name: &str, cells: usize) -> (Vec<u32>, u64, u64) {
+    let path = CString::new(path.to_str().unwrap()).unwrap();
+    let name = CString::new(format!("/frames/{}", name)).unwrap();
+    let mut energy = vec![0u32; cells];
+    let mut attributes = [0u64; 2];
+    unsafe {
+        let file = h5f::H5Fopen(path.as_ptr(), h5f::H5F_ACC_RDONLY, h5p::H5P_DEFAULT);
+        assert!(file >= 0);
+        let dataset = h5d::H5Dopen2(file, name.as_ptr(), h5p::H5P_DEFAULT);
+        assert!(dataset >= 0, "no frame {:?}", name);
+        assert!(
+            h5d::H5Dread(
+                dataset,
+                *h5t::H5T_NATIVE_UINT32,
+                h5s::H5S_ALL,
+                h5s::H5S_ALL,
+                h5p::H5P_DEFAULT,
+                energy.as_mut_ptr() as *mut c_void,
+            ) >= 0
+        );
+        for (value, attribute) in attributes.iter_mut().zip(["step", "total_energy"]) {
+            let attribute = CString::new(attribute).unwrap();
+            let id = h5a::H5Aopen(dataset, attribute.as_ptr(), h5p::H5P_DEFAULT);
+            assert!(id >= 0);
+            assert!(
+                h5a::H5Aread(
+                    id,
+                    *h5t::H5T_NATIVE_UINT64,
+                    value as *mut u64 as *mut c_void
+                ) >= 0
+            );
+            h5a::H5Aclose(id);
+        }
+        h5d::H5Dclose(dataset);
+        h5f::H5Fclose(file);
+    }
+    (energy, attributes[0], attributes[1])
+}
+
+#[test]
+fn test_hdf5_series_writes_frames_with_attributes() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(6, 5, 4)).unwrap();
+    lattice.seed_sphere((2, 2, 2), 1, 2);
+    let total = pollster::block_on(lattice.get_total_energy());
+
+    let path = temp_path("series.h5");
+    let _ = std::fs::remove_file(&path);
+    let mut series = Hdf5Series::new(&path);
+    assert!(series.is_empty());
+    let mut states = Vec::new();
+    for _ in 0..3 {
+        lattice.propagate_n(2);
+        pollster::block_on(series.append(&lattice)).unwrap();
+        states.push(pollster::block_on(lattice.get_state()));
+    }
+    // Appending the same generation again replaces its frame
+    pollster::block_on(series.append(&lattice)).unwrap();
+    assert_eq!(series.len(), 3);
+    drop(series);
+
+    for (generation, state) in [2, 4, 6].into_iter().zip(&states) {
+        let (energy, step, total_energy) =
+            read_frame(&path, &format!("{:06}", generation), 6 * 5 * 4);
+        assert_eq!(&energy, state);
+        assert_eq!(step, generation);
+        assert_eq!(total_energy, total);
+    }
+
+    // Reopening the file adds to it rather than starting over
+    let mut series = Hdf5Series::new(&path);
+    lattice.propagate_n(2);
+    pollster::block_on(series.append(&lattice)).unwrap();
+    drop(series);
+    assert_eq!(read_frame(&path, "000002", 6 * 5 * 4).1, 2);
+    assert_eq!(read_frame(&path, "000008", 6 * 5 * 4).1, 8);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_hdf5_series_chunks_frames_by_z_slab() {
+    // 256 KiB slices, so a 4 MiB chunk holds 16 of them
+    let lattice = pollster::block_on(DiscreteLatticeGPU::new(256, 256, 64)).unwrap();
+    let path = temp_path("chunked.h5");
+    let _ = std::fs::remove_file(&path);
+    let mut series = Hdf5Series::new(&path);
+    pollster::block_on(series.append(&lattice)).unwrap();
+    drop(series);
+
+    let file_name = CString::new(path.to_str().unwrap()).unwrap();
+    let name = CString::new("/frames/000000").unwrap();
+    let mut chunk = [0u64; 3];
+    unsafe {
+        let file = h5f::H5Fopen(file_name.as_ptr(), h5f::H5F_ACC_RDONLY, h5p::H5P_DEFAULT);
+        assert!(file >= 0);
+        let dataset = h5d::H5Dopen2(file, name.as_ptr(), h5p::H5P_DEFAULT);
+        assert!(dataset >= 0);
+        let properties = h5d::H5Dget_create_plist(dataset);
+        assert_eq!(h5p::H5Pget_chunk(properties, 3, chunk.as_mut_ptr()), 3);
+        h5p::H5Pclose(properties);
+        h5d::H5Dclose(dataset);
+        h5f::H5Fclose(file);
+    }
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(chunk, [16, 256, 256]);
+}
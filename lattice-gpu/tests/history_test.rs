@@ -0,0 +1,80 @@
+use lattice_gpu::*;
+
+fn seeded_lattice() -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(10, 10, 10)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((5, 5, 5), 2, 3);
+    lattice
+}
+
+#[test]
+fn test_backward_retraces_forward_steps() {
+    let mut lattice = seeded_lattice();
+    lattice.set_history_depth(10);
+
+    let mut states = vec![pollster::block_on(lattice.get_state())];
+    for _ in 0..6 {
+        lattice.propagate_energy();
+        states.push(pollster::block_on(lattice.get_state()));
+    }
+    // Batched steps are recorded too
+    lattice.propagate_n(4);
+    let end = pollster::block_on(lattice.get_state());
+
+    for _ in 0..4 {
+        assert!(lattice.propagate_backward());
+    }
+    assert_eq!(lattice.generation(), 6);
+    for generation in (0..6).rev() {
+        assert!(lattice.propagate_backward());
+        assert_eq!(lattice.generation(), generation as u64);
+        assert_eq!(pollster::block_on(lattice.get_state()), states[generation]);
+    }
+    assert!(!lattice.propagate_backward(), "History is exhausted");
+
+    // Running forward again replays the same steps
+    lattice.propagate_n(10);
+    assert_eq!(pollster::block_on(lattice.get_state()), end);
+}
+
+#[test]
+fn test_history_keeps_only_recent_steps() {
+    let mut lattice = seeded_lattice();
+    lattice.set_history_depth(3);
+    lattice.propagate_n(8);
+    let usage = lattice.memory_usage_bytes();
+
+    for _ in 0..3 {
+        assert!(lattice.propagate_backward());
+    }
+    assert_eq!(lattice.generation(), 5);
+    assert!(!lattice.propagate_backward());
+    assert!(lattice.memory_usage_bytes() < usage);
+}
+
+#[test]
+fn test_backward_rewinds_absorbed_count() {
+    let mut lattice = seeded_lattice();
+    lattice.set_boundary_mode(BoundaryMode::Absorbing);
+    lattice.add_energy_quantum(0, 0, 0, 3);
+    lattice.set_history_depth(20);
+
+    lattice.propagate_n(20);
+    assert!(pollster::block_on(lattice.absorbed_energy()) > 0);
+
+    while lattice.propagate_backward() {}
+    assert_eq!(lattice.generation(), 0);
+    assert_eq!(pollster::block_on(lattice.absorbed_energy()), 0);
+}
+
+#[test]
+fn test_history_is_off_by_default_and_cleared_by_new_state() {
+    let mut lattice = seeded_lattice();
+    lattice.propagate_energy();
+    assert!(!lattice.propagate_backward());
+
+    lattice.set_history_depth(5);
+    lattice.propagate_energy();
+    lattice.initialize_vacuum();
+    assert!(!lattice.propagate_backward());
+}
@@ -0,0 +1,97 @@
+use lattice_gpu::*;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lattice_gpu_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn test_from_image_maps_luminance_to_levels() {
+    let path = temp_path("ramp.png");
+    // Black, dark gray, light gray, white across each row
+    let image =
+        image::GrayImage::from_fn(4, 3, |x, _| image::Luma([[0, 60, 180, 255][x as usize]]));
+    image.save(&path).unwrap();
+
+    let lattice = pollster::block_on(DiscreteLatticeGPU::from_image(&path, 3)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let state = pollster::block_on(lattice.save_state());
+    assert_eq!((state.width, state.height, state.depth), (4, 3, 1));
+    for row in state.energy.chunks(4) {
+        assert_eq!(row, [0, 1, 2, 3]);
+    }
+}
+
+#[test]
+fn test_from_image_converts_color_and_scales_max_level() {
+    let path = temp_path("color.png");
+    let image = image::RgbImage::from_fn(2, 2, |x, y| {
+        if (x + y) % 2 == 0 {
+            image::Rgb([255, 255, 255])
+        } else {
+            image::Rgb([0, 0, 0])
+        }
+    });
+    image.save(&path).unwrap();
+
+    let lattice = pollster::block_on(DiscreteLatticeGPU::from_image(&path, 1)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(pollster::block_on(lattice.get_state()), vec![1, 0, 0, 1]);
+}
+
+#[test]
+fn test_from_image_missing_file() {
+    let result = pollster::block_on(DiscreteLatticeGPU::from_image(&temp_path("missing.png"), 3));
+    assert!(matches!(result, Err(LatticeError::Image(_))));
+}
+
+#[test]
+fn test_image_constructors_reject_max_level_above_maximum() {
+    let path = temp_path("too_bright.png");
+    image::GrayImage::new(2, 2).save(&path).unwrap();
+    let result = pollster::block_on(DiscreteLatticeGPU::from_image(&path, MAX_LEVEL + 1));
+    assert!(matches!(result, Err(LatticeError::InvalidInput(_))));
+    let result = pollster::block_on(DiscreteLatticeGPU::from_image_stack(
+        &[&path],
+        MAX_LEVEL + 1,
+    ));
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(LatticeError::InvalidInput(_))));
+}
+
+#[test]
+fn test_from_image_stack_maps_images_to_slices() {
+    let paths: Vec<PathBuf> = (0..3)
+        .map(|z| {
+            let path = temp_path(&format!("stack_{}.png", z));
+            // Slice z is white only at x == z
+            image::GrayImage::from_fn(4, 2, |x, _| image::Luma([if x == z { 255 } else { 0 }]))
+                .save(&path)
+                .unwrap();
+            path
+        })
+        .collect();
+
+    let lattice = pollster::block_on(DiscreteLatticeGPU::from_image_stack(&paths, 2)).unwrap();
+    let state = pollster::block_on(lattice.save_state());
+    assert_eq!((state.width, state.height, state.depth), (4, 2, 3));
+    for (z, slice) in state.energy.chunks(8).enumerate() {
+        for row in slice.chunks(4) {
+            let expected: Vec<u32> = (0..4).map(|x| if x == z { 2 } else { 0 }).collect();
+            assert_eq!(row, expected);
+        }
+    }
+
+    // Slices must all be the same size
+    image::GrayImage::new(3, 2).save(&paths[1]).unwrap();
+    let result = pollster::block_on(DiscreteLatticeGPU::from_image_stack(&paths, 2));
+    for path in &paths {
+        std::fs::remove_file(path).unwrap();
+    }
+    assert!(matches!(result, Err(LatticeError::InvalidInput(_))));
+
+    let result = pollster::block_on(DiscreteLatticeGPU::from_image_stack::<PathBuf>(&[], 2));
+    assert!(matches!(result, Err(LatticeError::InvalidInput(_))));
+}
@@ -0,0 +1,74 @@
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::*;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lattice_gpu_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn test_add_energy_csv_adds_points() {
+    let path = temp_path("points.csv");
+    std::fs::write(
+        &path,
+        "x,y,z,quanta\n1,2,3,2\n\n# a comment\n 0 , 0 , 0 , 1\n1,2,3,1\n",
+    )
+    .unwrap();
+
+    let mut lattice = BlockingLattice::new(4, 4, 4).unwrap();
+    lattice.add_energy_csv(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(lattice.get_energy_at(1, 2, 3), 3);
+    assert_eq!(lattice.get_energy_at(0, 0, 0), 1);
+    assert_eq!(lattice.get_total_energy(), 4);
+}
+
+#[test]
+fn test_add_energy_csv_rejects_bad_lines() {
+    let mut lattice = BlockingLattice::new(4, 4, 4).unwrap();
+    for (name, text, message) in [
+        ("short.csv", "1,2,3,1\n1,2,3\n", "line 2"),
+        ("outside.csv", "1,2,3,1\n4,0,0,1\n", "outside"),
+        ("garbage.csv", "1,2,3,1\n1,two,3,1\n", "line 2"),
+    ] {
+        let path = temp_path(name);
+        std::fs::write(&path, text).unwrap();
+        let err = lattice.add_energy_csv(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(err, LatticeError::InvalidInput(_)));
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+    // Nothing from a rejected file is added
+    assert_eq!(lattice.get_total_energy(), 0);
+
+    let err = lattice
+        .add_energy_csv(&temp_path("missing.csv"))
+        .unwrap_err();
+    assert!(matches!(err, LatticeError::Io(_)));
+}
+
+#[test]
+fn test_from_checkpoint_restores_lattice() {
+    let path = temp_path("initial.lgck");
+    let mut original = BlockingLattice::new(10, 8, 6).unwrap();
+    original.set_seed(5);
+    original.seed_sphere((5, 4, 3), 2, 3);
+    original.propagate_n(7);
+    original.save_checkpoint(&path).unwrap();
+
+    let mut restored = BlockingLattice::from_checkpoint(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(restored.save_state(), original.save_state());
+    assert_eq!(restored.seed(), 5);
+
+    for lattice in [&mut original, &mut restored] {
+        lattice.propagate_n(10);
+    }
+    assert_eq!(restored.get_state(), original.get_state());
+
+    assert!(matches!(
+        BlockingLattice::from_checkpoint(&temp_path("missing.lgck")),
+        Err(LatticeError::Io(_))
+    ));
+}
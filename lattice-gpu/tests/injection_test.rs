@@ -0,0 +1,154 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_injection_accounting() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(16, 16, 16)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((8, 8, 8), 2, 3);
+    let initial = pollster::block_on(lattice.get_total_energy());
+
+    // Pulsed source: one quantum at a fixed site every third step
+    lattice.set_injection(|step| {
+        if step % 3 == 0 {
+            vec![(2, 2, 2, 1)]
+        } else {
+            Vec::new()
+        }
+    });
+
+    for _ in 0..12 {
+        lattice.propagate_energy();
+        let total = pollster::block_on(lattice.get_total_energy());
+        assert_eq!(
+            total - pollster::block_on(lattice.injected_energy()),
+            initial
+        );
+    }
+    assert!(pollster::block_on(lattice.injected_energy()) > 0);
+    assert!(pollster::block_on(lattice.injected_energy()) <= 4);
+
+    // Without a schedule the lattice is closed again
+    lattice.clear_injection();
+    let injected = pollster::block_on(lattice.injected_energy());
+    let total = pollster::block_on(lattice.get_total_energy());
+    for _ in 0..5 {
+        lattice.propagate_energy();
+    }
+    assert_eq!(pollster::block_on(lattice.injected_energy()), injected);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), total);
+}
+
+#[test]
+fn test_injection_sees_each_step_and_skips_outside_points() {
+    use std::sync::{Arc, Mutex};
+
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    lattice.set_injection(move |step| {
+        log.lock().unwrap().push(step);
+        // A moving source that walks off the lattice after step 7
+        vec![(step, 4, 4, 1), (100, 0, 0, 3)]
+    });
+
+    for _ in 0..10 {
+        lattice.propagate_energy();
+    }
+    assert_eq!(*seen.lock().unwrap(), (0..10).collect::<Vec<_>>());
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 8);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 8);
+}
+
+#[test]
+fn test_injection_capped_at_max_level() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.initialize_vacuum();
+
+    // Every site already full: nothing fits, so nothing is counted
+    lattice.seed_sphere((2, 2, 2), 8, MAX_LEVEL);
+    lattice.set_injection(|_| vec![(1, 1, 1, 2)]);
+    lattice.propagate_energy();
+
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 0);
+    assert_eq!(
+        pollster::block_on(lattice.get_total_energy()),
+        64 * MAX_LEVEL as u64
+    );
+}
+
+#[test]
+fn test_scheduled_injection_fires_during_batch() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.schedule_injection(3, 4, 4, 4, 2);
+    lattice.schedule_injection(3, 4, 4, 4, 1);
+    lattice.schedule_injection(7, 1, 1, 1, 2);
+    lattice.schedule_injection(5, 100, 0, 0, 3);
+
+    // Matches firing the same pulses by hand, one step at a time
+    let mut manual = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    manual.initialize_vacuum();
+    for step in 0..10 {
+        match step {
+            3 => manual.add_energy_quantum(4, 4, 4, 3),
+            7 => manual.add_energy_quantum(1, 1, 1, 2),
+            _ => {}
+        }
+        manual.propagate_energy();
+    }
+
+    lattice.propagate_n(10);
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 5);
+    assert_eq!(
+        pollster::block_on(lattice.get_state()),
+        pollster::block_on(manual.get_state())
+    );
+
+    // Events fire once
+    lattice.propagate_n(5);
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 5);
+}
+
+#[test]
+fn test_scheduled_injection_in_single_steps() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.schedule_injection(2, 4, 4, 4, 1);
+    lattice.schedule_injection(4, 4, 4, 4, 1);
+
+    lattice.propagate_energy();
+    lattice.propagate_energy();
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 0);
+    lattice.propagate_energy();
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 1);
+
+    lattice.clear_scheduled_injections();
+    lattice.propagate_n(3);
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 1);
+}
+
+#[test]
+#[should_panic(expected = "Cannot schedule")]
+fn test_scheduling_in_the_past_panics() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.propagate_n(3);
+    lattice.schedule_injection(2, 1, 1, 1, 1);
+}
+
+#[test]
+fn test_injection_step_saturates_past_u32() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    let mut state = pollster::block_on(lattice.save_state());
+    state.generation = u32::MAX as u64 + 5;
+    lattice.load_state(&state);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    lattice.set_injection(move |step| {
+        sender.send(step).unwrap();
+        Vec::new()
+    });
+    lattice.propagate_n(2);
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [u32::MAX; 2]);
+}
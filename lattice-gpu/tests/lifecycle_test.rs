@@ -0,0 +1,160 @@
+use lattice_gpu::*;
+use std::sync::Arc;
+
+fn create_device() -> (Arc<wgpu::Device>, Arc<wgpu::Queue>) {
+    pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .expect("Failed to find GPU adapter");
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await
+            .expect("Failed to create device");
+        (Arc::new(device), Arc::new(queue))
+    })
+}
+
+#[test]
+fn test_many_short_lived_lattices() {
+    let (device, queue) = create_device();
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    for i in 0..100 {
+        let mut lattice =
+            DiscreteLatticeGPU::new_with_device(device.clone(), queue.clone(), 8, 8, 8).unwrap();
+        lattice.initialize_vacuum();
+        lattice.add_energy_quantum(4, 4, 4, 3);
+        lattice.propagate_energy();
+        assert_eq!(pollster::block_on(lattice.get_total_energy()), 3);
+
+        // Alternate between explicit shutdown and a plain drop
+        if i % 2 == 0 {
+            pollster::block_on(lattice.shutdown());
+        } else {
+            drop(lattice);
+        }
+    }
+
+    device.poll(wgpu::Maintain::Wait);
+    let error = pollster::block_on(device.pop_error_scope());
+    assert!(error.is_none(), "Validation error: {:?}", error);
+}
+
+#[test]
+fn test_shutdown_with_work_in_flight() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(16, 16, 16)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((8, 8, 8), 3, 3);
+
+    // Queue steps without waiting on them
+    for _ in 0..20 {
+        lattice.propagate_energy();
+    }
+    pollster::block_on(lattice.shutdown());
+}
+
+#[test]
+fn test_shutdown_releases_optional_buffers() {
+    let (device, queue) = create_device();
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let mut lattice =
+        DiscreteLatticeGPU::new_with_device(device.clone(), queue.clone(), 16, 16, 16).unwrap();
+    lattice.set_history_depth(4);
+    lattice.set_active_region_tracking(true);
+    lattice.add_sources(&[(2, 2, 2, 1)]);
+    lattice.add_sinks(&[(12, 12, 12, 1)]);
+    lattice.seed_sphere((8, 8, 8), 3, 3);
+    lattice.propagate_n(6);
+    let readback = lattice.queue_total_energy_readback();
+    pollster::block_on(readback.wait());
+    pollster::block_on(lattice.shutdown());
+
+    device.poll(wgpu::Maintain::Wait);
+    let error = pollster::block_on(device.pop_error_scope());
+    assert!(error.is_none(), "Validation error: {:?}", error);
+}
+
+#[test]
+fn test_zero_dimension_returns_error() {
+    for (width, height, depth) in [(0, 4, 4), (4, 0, 4), (4, 4, 0)] {
+        let result = pollster::block_on(DiscreteLatticeGPU::new(width, height, depth));
+        assert!(matches!(result, Err(LatticeError::InvalidInput(_))));
+    }
+}
+
+#[test]
+fn test_oversized_lattice_returns_error() {
+    let result = pollster::block_on(DiscreteLatticeGPU::new(4096, 4096, 4096));
+    match result {
+        Err(LatticeError::BufferTooLarge { requested, max }) => {
+            assert_eq!(requested, 4096 * 4096 * 4096 * 4);
+            assert!(requested > max);
+        }
+        Err(err) => panic!("Unexpected error: {}", err),
+        Ok(_) => panic!("Expected BufferTooLarge"),
+    }
+}
+
+#[test]
+fn test_dimension_and_memory_accessors() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(6, 5, 4)).unwrap();
+    assert_eq!(
+        (lattice.width(), lattice.height(), lattice.depth()),
+        (6, 5, 4)
+    );
+    assert_eq!(lattice.total_sites(), 120);
+
+    lattice.initialize_vacuum();
+    lattice.propagate_n(3);
+    assert_eq!(lattice.step_count(), 3);
+
+    // Energy A/B, staging, site flags and potential each hold one 4-byte
+    // value per site
+    let lattice_bytes = 120 * 4;
+    let usage = lattice.memory_usage_bytes();
+    assert!(usage >= 5 * lattice_bytes && usage < 5 * lattice_bytes + 256);
+}
+
+#[test]
+fn test_reset_matches_fresh_lattice() {
+    let mut fresh = pollster::block_on(DiscreteLatticeGPU::new(10, 10, 10)).unwrap();
+    fresh.initialize_vacuum();
+    fresh.seed_sphere((5, 5, 5), 2, 3);
+    fresh.propagate_n(12);
+    let expected = pollster::block_on(fresh.get_state());
+
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(10, 10, 10)).unwrap();
+    lattice.set_boundary_mode(BoundaryMode::Absorbing);
+    lattice.add_energy_quantum(0, 0, 0, 3);
+    lattice.propagate_n(7);
+    assert!(pollster::block_on(lattice.absorbed_energy()) > 0);
+
+    lattice.reset();
+    assert_eq!(lattice.generation(), 0);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 0);
+    assert_eq!(pollster::block_on(lattice.absorbed_energy()), 0);
+
+    // Same run as the fresh lattice, once the boundary is set back
+    lattice.set_boundary_mode(BoundaryMode::Periodic);
+    lattice.seed_sphere((5, 5, 5), 2, 3);
+    lattice.propagate_n(12);
+    assert_eq!(pollster::block_on(lattice.get_state()), expected);
+}
+
+#[test]
+fn test_reset_drops_pending_pulses() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.schedule_injection(2, 1, 1, 1, 3);
+    lattice.schedule_injection(10, 4, 4, 4, 2);
+    lattice.propagate_n(5);
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 3);
+
+    // Neither the pulse that fired nor the pending one comes back
+    lattice.reset();
+    lattice.propagate_n(12);
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 0);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 0);
+}
@@ -0,0 +1,76 @@
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::LatticeMetrics;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+// The value of `name` in text-format `metrics`
+fn value(metrics: &str, name: &str) -> f64 {
+    metrics
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("no {} in {}", name, metrics))
+        .parse()
+        .unwrap()
+}
+
+fn observed_run(metrics: &LatticeMetrics) -> u64 {
+    let mut lattice = BlockingLattice::new(16, 16, 16).unwrap();
+    lattice.seed_sphere((8, 8, 8), 2, 2);
+    metrics.observe_memory(&lattice);
+    lattice.propagate_observed(20, 10, |diagnostics| {
+        metrics.observe(diagnostics);
+        ControlFlow::Continue(())
+    });
+    lattice.memory_usage_bytes()
+}
+
+#[test]
+fn test_observed_run_updates_gauges() {
+    let metrics = LatticeMetrics::new();
+    let memory = observed_run(&metrics);
+    metrics.set_queue_depth(3);
+
+    let text = metrics.encode();
+    assert_eq!(value(&text, "walkthe_generation"), 20.0);
+    assert_eq!(value(&text, "walkthe_total_energy"), 2.0 * 33.0);
+    assert_eq!(value(&text, "walkthe_gpu_memory_bytes"), memory as f64);
+    assert_eq!(value(&text, "walkthe_queue_depth"), 3.0);
+    assert!(value(&text, "walkthe_steps_per_second") > 0.0);
+}
+
+#[test]
+fn test_endpoint_serves_current_metrics() {
+    let metrics = LatticeMetrics::new();
+    let server = metrics.serve("127.0.0.1:0").unwrap();
+    observed_run(&metrics);
+
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    drop(server);
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK"), "{}", head);
+    assert!(head.contains("text/plain"), "{}", head);
+    assert_eq!(value(body, "walkthe_generation"), 20.0);
+}
+
+#[test]
+fn test_idle_client_does_not_block_shutdown() {
+    let metrics = LatticeMetrics::new();
+    let server = metrics.serve("127.0.0.1:0").unwrap();
+
+    // Connects but never sends a request, and is being served before the
+    // server is dropped
+    let idle = TcpStream::connect(server.local_addr()).unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    drop(server);
+    assert!(start.elapsed() < Duration::from_secs(30));
+    drop(idle);
+}
@@ -0,0 +1,108 @@
+use lattice_gpu::*;
+use std::sync::Arc;
+
+// `count` separate devices on the default adapter, standing in for as many
+// GPUs, and an unsplit lattice of the same size
+fn multi_pair(size: (u32, u32, u32), count: usize) -> (MultiGpuLattice, DiscreteLatticeGPU) {
+    let (width, height, depth) = size;
+    let whole = pollster::block_on(DiscreteLatticeGPU::new(width, height, depth)).unwrap();
+    let devices = pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .expect("Failed to find GPU adapter");
+        let mut devices = Vec::new();
+        for _ in 0..count {
+            let (device, queue) = adapter
+                .request_device(&wgpu::DeviceDescriptor::default(), None)
+                .await
+                .expect("Failed to create device");
+            devices.push((Arc::new(device), Arc::new(queue)));
+        }
+        devices
+    });
+    let multi = MultiGpuLattice::new_with_devices(devices, width, height, depth).unwrap();
+    (multi, whole)
+}
+
+fn seed_both(multi: &mut MultiGpuLattice, whole: &mut DiscreteLatticeGPU) {
+    let (width, height, depth) = multi.dimensions();
+    let mut edits = sphere_points(
+        (width / 2, height / 2, depth / 2),
+        3,
+        3,
+        (width, height, depth),
+    );
+    // Sites on the slab boundaries and the global Z faces
+    edits.extend((0..depth).map(|z| (z % width, 1, z, 2)));
+    multi.add_energy_batch(&edits);
+    whole.add_energy_batch(&edits);
+}
+
+fn assert_matches_whole(multi: &mut MultiGpuLattice, whole: &mut DiscreteLatticeGPU, steps: u32) {
+    for step in 1..=steps {
+        pollster::block_on(multi.propagate_energy());
+        whole.propagate_energy();
+        assert_eq!(
+            pollster::block_on(multi.get_state()),
+            pollster::block_on(whole.get_state()),
+            "Multi-GPU lattice diverged at step {}",
+            step
+        );
+    }
+    assert_eq!(
+        pollster::block_on(multi.get_total_energy()),
+        pollster::block_on(whole.get_total_energy())
+    );
+}
+
+#[test]
+fn test_multi_gpu_matches_whole_lattice() {
+    let (mut multi, mut whole) = multi_pair((8, 7, 15), 3);
+    assert_eq!(multi.device_count(), 3);
+    seed_both(&mut multi, &mut whole);
+    assert_matches_whole(&mut multi, &mut whole, 20);
+}
+
+#[test]
+fn test_multi_gpu_matches_in_scatter_mode_with_moore_and_decay() {
+    let (mut multi, mut whole) = multi_pair((6, 6, 12), 2);
+    multi.set_propagation_mode(PropagationMode::Scatter);
+    whole.set_propagation_mode(PropagationMode::Scatter);
+    multi.set_neighborhood(Neighborhood::Moore);
+    whole.set_neighborhood(Neighborhood::Moore);
+    multi.set_decay_rate(0.02);
+    whole.set_decay_rate(0.02);
+    seed_both(&mut multi, &mut whole);
+    assert_matches_whole(&mut multi, &mut whole, 15);
+}
+
+#[test]
+fn test_multi_gpu_boundary_change_keeps_state() {
+    let (mut multi, mut whole) = multi_pair((6, 6, 10), 2);
+    seed_both(&mut multi, &mut whole);
+    assert_matches_whole(&mut multi, &mut whole, 5);
+
+    for z in [BoundaryMode::Absorbing, BoundaryMode::Periodic] {
+        let x = BoundaryMode::Periodic;
+        multi.set_axis_boundary_modes(x, x, z).unwrap();
+        whole.set_axis_boundary_modes(x, x, z);
+        assert_matches_whole(&mut multi, &mut whole, 5);
+    }
+    assert_eq!(multi.generation(), 15);
+}
+
+#[test]
+fn test_multi_gpu_shallow_lattice_uses_fewer_devices() {
+    let (multi, _) = multi_pair((4, 4, 7), 3);
+    assert_eq!(multi.device_count(), 1);
+}
+
+#[test]
+fn test_multi_gpu_new_uses_available_adapters() {
+    let mut lattice = pollster::block_on(MultiGpuLattice::new(8, 8, 8)).unwrap();
+    assert!(lattice.device_count() >= 1);
+    Lattice::seed_sphere(&mut lattice, (4, 4, 4), 2, 3);
+    lattice_gpu::testing::assert_conserved_over(&mut lattice, 10);
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "ndarray")]
+
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::*;
+use ndarray::{Array3, ShapeBuilder};
+
+#[test]
+fn test_array_indices_are_site_coordinates() {
+    let mut energy = Array3::<u32>::zeros((5, 4, 3));
+    energy[[4, 0, 0]] = 1;
+    energy[[1, 3, 2]] = 3;
+
+    let lattice = pollster::block_on(DiscreteLatticeGPU::from_array(&energy)).unwrap();
+    assert_eq!(lattice.dimensions(), (5, 4, 3));
+    let state = pollster::block_on(lattice.get_state());
+    assert_eq!(state[4], 1);
+    assert_eq!(state[2 * 5 * 4 + 3 * 5 + 1], 3);
+    assert_eq!(state.iter().sum::<u32>(), 4);
+}
+
+#[test]
+fn test_round_trip_through_array() {
+    let energy = Array3::from_shape_fn((6, 5, 4), |(x, y, z)| ((x + 2 * y + 3 * z) % 4) as u32);
+    let lattice = BlockingLattice::from_array(&energy).unwrap();
+    assert_eq!(lattice.to_array(), energy);
+
+    // A column-major input holds the same sites
+    let mut fortran = Array3::zeros((6, 5, 4).f());
+    fortran.assign(&energy);
+    let lattice = BlockingLattice::from_array(&fortran).unwrap();
+    assert_eq!(lattice.to_array(), energy);
+}
+
+#[test]
+fn test_set_state_array_matches_set_state() {
+    let mut from_array = BlockingLattice::new(4, 4, 4).unwrap();
+    let mut from_slice = BlockingLattice::new(4, 4, 4).unwrap();
+    let energy = Array3::from_shape_fn((4, 4, 4), |(x, y, z)| ((x * y + z) % 4) as u32);
+
+    from_array.set_state_array(&energy.view());
+    from_slice.set_state(&from_array.get_state());
+    for lattice in [&mut from_array, &mut from_slice] {
+        lattice.propagate_n(5);
+    }
+    assert_eq!(from_array.to_array(), from_slice.to_array());
+}
+
+#[test]
+#[should_panic(expected = "Array shape must match the lattice")]
+fn test_set_state_array_rejects_wrong_shape() {
+    let mut lattice = BlockingLattice::new(4, 4, 4).unwrap();
+    lattice.set_state_array(&Array3::zeros((4, 4, 3)).view());
+}
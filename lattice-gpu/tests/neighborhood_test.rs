@@ -0,0 +1,103 @@
+use lattice_gpu::testing::assert_conserved_over;
+use lattice_gpu::*;
+
+fn moore_lattice(size: u32, mode: PropagationMode) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(size, size, size)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_neighborhood(Neighborhood::Moore);
+    lattice.set_propagation_mode(mode);
+    let c = size / 2;
+    lattice.seed_sphere((c, c, c), 3, 3);
+    lattice.add_energy_quantum(1, 1, 1, 2);
+    lattice
+}
+
+#[test]
+fn test_moore_gather_matches_scatter() {
+    let mut gather = moore_lattice(16, PropagationMode::Gather);
+    let mut scatter = moore_lattice(16, PropagationMode::Scatter);
+
+    for step in 0..40 {
+        gather.propagate_energy();
+        scatter.propagate_energy();
+
+        if step % 10 == 9 {
+            assert_eq!(
+                pollster::block_on(gather.get_state()),
+                pollster::block_on(scatter.get_state()),
+                "Gather and scatter diverged at step {}",
+                step + 1
+            );
+        }
+    }
+}
+
+#[test]
+fn test_moore_conserves_energy() {
+    let mut lattice = moore_lattice(16, PropagationMode::Gather);
+    assert_conserved_over(&mut lattice, 40);
+}
+
+// Positions a lone quantum at the middle of a closed 5x5x1 slab can reach
+// in one step, over a range of seeds
+fn landing_sites(neighborhood: Neighborhood) -> Vec<(u32, u32)> {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(5, 5, 1)).unwrap();
+    lattice.set_boundary_mode(BoundaryMode::Closed);
+    lattice.set_neighborhood(neighborhood);
+
+    let mut sites = Vec::new();
+    for seed in 0..32 {
+        lattice.initialize_vacuum();
+        lattice.add_energy_quantum(2, 2, 0, 1);
+        lattice.set_seed(seed);
+        lattice.propagate_energy();
+
+        let state = pollster::block_on(lattice.get_state());
+        let idx = state.iter().position(|&e| e == 1).unwrap();
+        sites.push((idx as u32 % 5, idx as u32 / 5));
+    }
+    sites
+}
+
+#[test]
+fn test_moore_moves_diagonally() {
+    let is_diagonal = |&(x, y): &(u32, u32)| x != 2 && y != 2;
+
+    let von_neumann = landing_sites(Neighborhood::VonNeumann);
+    assert!(
+        !von_neumann.iter().any(is_diagonal),
+        "Faces only: {:?}",
+        von_neumann
+    );
+
+    let moore = landing_sites(Neighborhood::Moore);
+    assert!(moore
+        .iter()
+        .all(|&(x, y)| x.abs_diff(2) <= 1 && y.abs_diff(2) <= 1));
+    assert!(
+        moore.iter().any(is_diagonal),
+        "No diagonal moves: {:?}",
+        moore
+    );
+}
+
+#[test]
+fn test_moore_absorbing_accounts_for_corners() {
+    // Corner sites see absorbing faces along two axes at once
+    let mut lattice = moore_lattice(8, PropagationMode::Gather);
+    lattice.set_boundary_mode(BoundaryMode::Absorbing);
+    lattice.add_energy_quantum(0, 0, 0, 3);
+    lattice.add_energy_quantum(7, 7, 7, 3);
+    let initial = pollster::block_on(lattice.get_total_energy());
+
+    for _ in 0..30 {
+        lattice.propagate_energy();
+    }
+
+    let absorbed = pollster::block_on(lattice.absorbed_energy());
+    assert!(absorbed > 0);
+    assert_eq!(
+        pollster::block_on(lattice.get_total_energy()) + absorbed,
+        initial
+    );
+}
@@ -0,0 +1,73 @@
+use lattice_gpu::testing::assert_conserved_over;
+use lattice_gpu::*;
+
+const SIZE: u32 = 12;
+
+fn index(x: u32, y: u32, z: u32) -> usize {
+    (z * SIZE * SIZE + y * SIZE + x) as usize
+}
+
+// An obstacle plate at x = 6 with a slit at 5 <= y <= 6, and energy on
+// the low-x side of it
+fn slit_lattice(mode: PropagationMode) -> (DiscreteLatticeGPU, Vec<bool>) {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(SIZE, SIZE, SIZE)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_propagation_mode(mode);
+    lattice.set_boundary_mode(BoundaryMode::Closed);
+
+    let mut mask = vec![false; (SIZE * SIZE * SIZE) as usize];
+    for z in 0..SIZE {
+        for y in 0..SIZE {
+            if !(5..=6).contains(&y) {
+                mask[index(6, y, z)] = true;
+            }
+        }
+    }
+    lattice.set_obstacles(&mask);
+    lattice.seed_sphere((3, 2, 6), 2, 3);
+    (lattice, mask)
+}
+
+#[test]
+fn test_obstacles_stay_empty_and_conserve() {
+    let (mut lattice, mask) = slit_lattice(PropagationMode::Gather);
+    for _ in 0..40 {
+        lattice.propagate_energy();
+        let state = pollster::block_on(lattice.get_state());
+        let on_plate: u32 = state
+            .iter()
+            .zip(&mask)
+            .filter(|(_, &m)| m)
+            .map(|(&e, _)| e)
+            .sum();
+        assert_eq!(on_plate, 0, "Energy entered an obstacle");
+    }
+    assert_conserved_over(&mut lattice, 20);
+}
+
+#[test]
+fn test_obstacles_gather_matches_scatter() {
+    let (mut gather, _) = slit_lattice(PropagationMode::Gather);
+    let (mut scatter, _) = slit_lattice(PropagationMode::Scatter);
+    gather.propagate_n(40);
+    scatter.propagate_n(40);
+    assert_eq!(
+        pollster::block_on(gather.get_state()),
+        pollster::block_on(scatter.get_state())
+    );
+}
+
+#[test]
+fn test_clear_obstacles_lets_energy_through() {
+    let (mut lattice, _) = slit_lattice(PropagationMode::Gather);
+    lattice.clear_obstacles();
+    lattice.seed_sphere((5, 2, 6), 1, 3);
+    lattice.propagate_n(10);
+
+    let state = pollster::block_on(lattice.get_state());
+    let plate: u32 = (0..SIZE)
+        .flat_map(|z| (0..5).map(move |y| (y, z)))
+        .map(|(y, z)| state[index(6, y, z)])
+        .sum();
+    assert!(plate > 0, "Energy should cross where the plate was");
+}
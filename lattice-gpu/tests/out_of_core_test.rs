@@ -0,0 +1,133 @@
+use lattice_gpu::*;
+use std::sync::Arc;
+
+// A lattice streamed in slabs of at most `max_slab_depth` layers, and a
+// GPU-resident one of the same size
+fn streamed_pair(
+    size: (u32, u32, u32),
+    max_slab_depth: u32,
+) -> (OutOfCoreLattice, DiscreteLatticeGPU) {
+    let (width, height, depth) = size;
+    let resident = pollster::block_on(DiscreteLatticeGPU::new(width, height, depth)).unwrap();
+    let (device, queue) = pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .expect("Failed to find GPU adapter");
+        adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await
+            .expect("Failed to create device")
+    });
+    let streamed = OutOfCoreLattice::new_with_device(
+        Arc::new(device),
+        Arc::new(queue),
+        width,
+        height,
+        depth,
+        max_slab_depth,
+    )
+    .unwrap();
+    (streamed, resident)
+}
+
+fn seed_both(streamed: &mut OutOfCoreLattice, resident: &mut DiscreteLatticeGPU) {
+    let (width, height, depth) = streamed.dimensions();
+    let mut edits = sphere_points(
+        (width / 2, height / 2, depth / 2),
+        3,
+        3,
+        (width, height, depth),
+    );
+    // Sites on the slab boundaries and the global Z faces
+    edits.extend((0..depth).map(|z| (z % width, 1, z, 2)));
+    streamed.add_energy_batch(&edits);
+    resident.add_energy_batch(&edits);
+}
+
+fn assert_matches_resident(
+    streamed: &mut OutOfCoreLattice,
+    resident: &mut DiscreteLatticeGPU,
+    steps: u32,
+) {
+    assert_eq!(
+        streamed.get_state(),
+        pollster::block_on(resident.get_state())
+    );
+    for step in 1..=steps {
+        pollster::block_on(streamed.propagate_energy());
+        resident.propagate_energy();
+        assert_eq!(
+            streamed.get_state(),
+            pollster::block_on(resident.get_state()),
+            "Streamed lattice diverged at step {}",
+            step
+        );
+    }
+    assert_eq!(
+        streamed.get_total_energy(),
+        pollster::block_on(resident.get_total_energy())
+    );
+}
+
+#[test]
+fn test_out_of_core_matches_resident_lattice() {
+    let (mut streamed, mut resident) = streamed_pair((8, 7, 19), 5);
+    assert_eq!(streamed.slab_count(), 4);
+    seed_both(&mut streamed, &mut resident);
+    assert_matches_resident(&mut streamed, &mut resident, 20);
+}
+
+#[test]
+fn test_out_of_core_matches_with_open_z_boundaries() {
+    let (mut streamed, mut resident) = streamed_pair((6, 5, 17), 4);
+    for z in [BoundaryMode::Absorbing, BoundaryMode::Reflective] {
+        streamed.set_axis_boundary_modes(BoundaryMode::Closed, BoundaryMode::Periodic, z);
+        resident.set_axis_boundary_modes(BoundaryMode::Closed, BoundaryMode::Periodic, z);
+        streamed.set_axis_weights(1.0, 0.5, 2.0);
+        resident.set_axis_weights(1.0, 0.5, 2.0);
+        seed_both(&mut streamed, &mut resident);
+        assert_matches_resident(&mut streamed, &mut resident, 15);
+    }
+}
+
+#[test]
+fn test_out_of_core_matches_in_scatter_mode_with_moore_and_decay() {
+    let (mut streamed, mut resident) = streamed_pair((6, 6, 13), 4);
+    streamed.set_propagation_mode(PropagationMode::Scatter);
+    resident.set_propagation_mode(PropagationMode::Scatter);
+    streamed.set_neighborhood(Neighborhood::Moore);
+    resident.set_neighborhood(Neighborhood::Moore);
+    streamed.set_decay_rate(0.02);
+    resident.set_decay_rate(0.02);
+    streamed.set_seed(7);
+    resident.set_seed(7);
+    seed_both(&mut streamed, &mut resident);
+    assert_matches_resident(&mut streamed, &mut resident, 20);
+}
+
+#[test]
+fn test_out_of_core_steps_whole_lattice_when_it_fits() {
+    // Slabs of 4 plus ghost layers would be as deep as the lattice itself
+    let (mut streamed, mut resident) = streamed_pair((5, 5, 6), 4);
+    assert_eq!(streamed.slab_count(), 1);
+    seed_both(&mut streamed, &mut resident);
+    assert_matches_resident(&mut streamed, &mut resident, 10);
+}
+
+#[test]
+fn test_out_of_core_set_state_and_edits() {
+    let (mut streamed, _) = streamed_pair((4, 4, 12), 4);
+    let state: Vec<u32> = (0..4 * 4 * 12).map(|i| i % 3).collect();
+    streamed.set_state(&state);
+    assert_eq!(streamed.get_state(), state);
+
+    streamed.initialize_vacuum();
+    streamed.add_energy_quantum(1, 1, 11, 2);
+    streamed.add_energy_quantum(1, 1, 11, 2);
+    streamed.add_energy_quantum(9, 0, 0, 1);
+    assert_eq!(streamed.get_total_energy(), MAX_LEVEL as u64);
+    lattice_gpu::testing::assert_conserved_over(&mut streamed, 10);
+    assert_eq!(streamed.generation(), 10);
+}
@@ -0,0 +1,27 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_pipeline_cache_lattice_runs_and_saves() {
+    let dir = std::env::temp_dir().join(format!("lattice-pipeline-cache-{}", std::process::id()));
+
+    // The second lattice starts from whatever the first one saved
+    for _ in 0..2 {
+        let mut lattice =
+            pollster::block_on(DiscreteLatticeGPU::new_with_pipeline_cache(&dir, 8, 8, 8)).unwrap();
+        lattice.set_neighborhood(Neighborhood::Moore);
+        lattice.seed_sphere((4, 4, 4), 2, 3);
+        lattice_gpu::testing::assert_conserved_over(&mut lattice, 5);
+        lattice.save_pipeline_cache().unwrap();
+
+        let saved = std::fs::read_dir(&dir).map_or(0, |entries| entries.count());
+        assert_eq!(saved > 0, lattice.uses_pipeline_cache());
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_lattice_without_cache_saves_nothing() {
+    let lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    assert!(!lattice.uses_pipeline_cache());
+    lattice.save_pipeline_cache().unwrap();
+}
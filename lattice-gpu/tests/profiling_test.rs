@@ -0,0 +1,37 @@
+#![cfg(feature = "profiling")]
+
+use lattice_gpu::*;
+
+#[test]
+fn test_profile_step_matches_propagate_energy() {
+    let mut profiled = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    let mut plain = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    for lattice in [&mut profiled, &mut plain] {
+        lattice.initialize_vacuum();
+        lattice.seed_sphere((6, 6, 6), 3, 3);
+    }
+
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        profiled.set_propagation_mode(mode);
+        plain.set_propagation_mode(mode);
+        for _ in 0..3 {
+            let Some(timings) = pollster::block_on(profiled.profile_step()) else {
+                // No timestamp queries on this adapter
+                return;
+            };
+            plain.propagate_energy();
+
+            assert_eq!(timings.copy.is_some(), mode == PropagationMode::Scatter);
+            assert_eq!(
+                timings.total(),
+                timings.copy.unwrap_or_default() + timings.propagate
+            );
+        }
+        assert_eq!(
+            pollster::block_on(profiled.get_state()),
+            pollster::block_on(plain.get_state()),
+            "Profiling must not change the step"
+        );
+    }
+    assert_eq!(profiled.generation(), 6);
+}
@@ -0,0 +1,97 @@
+use lattice_gpu::blocking::BlockingLattice;
+use lattice_gpu::*;
+use std::ops::ControlFlow;
+
+fn seeded_lattice() -> BlockingLattice {
+    let mut lattice = BlockingLattice::new(12, 12, 12).unwrap();
+    lattice.seed_sphere((6, 6, 6), 3, 3);
+    lattice
+}
+
+#[test]
+fn test_observer_sees_every_batch() {
+    let mut observed = seeded_lattice();
+    let initial = observed.get_total_energy();
+    let mut reports = Vec::new();
+    let taken = observed.propagate_observed(25, 10, |diagnostics| {
+        reports.push(diagnostics.clone());
+        ControlFlow::Continue(())
+    });
+    assert_eq!(taken, 25);
+
+    let steps: Vec<u32> = reports.iter().map(|d| d.step).collect();
+    assert_eq!(steps, [10, 20, 25]);
+    for report in &reports {
+        assert_eq!(report.steps, 25);
+        assert_eq!(report.generation, report.step as u64);
+        assert_eq!(report.total_energy, initial);
+        assert!(report.conserved);
+    }
+    assert_eq!(reports[2].progress(), 1.0);
+
+    // Batching doesn't change the result
+    let mut plain = seeded_lattice();
+    plain.propagate_n(25);
+    assert_eq!(observed.get_state(), plain.get_state());
+}
+
+#[test]
+fn test_observer_can_stop_the_run() {
+    let mut lattice = seeded_lattice();
+    let taken = lattice.propagate_observed(100, 4, |diagnostics| {
+        if diagnostics.step >= 12 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(taken, 12);
+    assert_eq!(lattice.generation(), 12);
+}
+
+#[test]
+fn test_conservation_accounts_for_losses_and_sources() {
+    let mut lattice = seeded_lattice();
+    lattice.set_boundary_mode(BoundaryMode::Absorbing);
+    lattice.set_decay_rate(0.05);
+    lattice.add_sources(&[(1, 1, 1, 1)]);
+    let mut last = None;
+    lattice.propagate_observed(30, 7, |diagnostics| {
+        assert!(diagnostics.conserved, "{:?}", diagnostics);
+        last = Some(diagnostics.clone());
+        ControlFlow::Continue(())
+    });
+    let last = last.unwrap();
+    assert!(last.decayed_energy > 0);
+    assert!(last.injected_energy > 0);
+}
+
+#[test]
+fn test_cancelled_run_stops_between_batches() {
+    let token = CancellationToken::new();
+    let mut lattice = seeded_lattice();
+    let canceller = token.clone();
+    lattice.on_step(move |_, generation| {
+        if generation == 25 {
+            canceller.cancel();
+        }
+    });
+    let taken = lattice.propagate_cancellable(100, 10, &token);
+    assert_eq!(taken, 30);
+    assert_eq!(lattice.generation(), 30);
+    assert!(token.is_cancelled());
+
+    // A cancelled token stops the next run before it starts
+    lattice.clear_on_step();
+    assert_eq!(lattice.propagate_cancellable(10, 5, &token), 0);
+
+    // Uncancelled, it matches propagate_n
+    let mut cancellable = seeded_lattice();
+    let mut plain = seeded_lattice();
+    assert_eq!(
+        cancellable.propagate_cancellable(23, 5, &CancellationToken::new()),
+        23
+    );
+    plain.propagate_n(23);
+    assert_eq!(cancellable.get_state(), plain.get_state());
+}
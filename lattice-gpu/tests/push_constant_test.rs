@@ -0,0 +1,79 @@
+use lattice_gpu::*;
+use std::sync::Arc;
+
+// A device created without PUSH_CONSTANTS, so the lattice falls back to the
+// params uniform
+fn lattice_without_push_constants(size: u32) -> DiscreteLatticeGPU {
+    let (device, queue) = pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .expect("Failed to find GPU adapter");
+        adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await
+            .expect("Failed to create device")
+    });
+    DiscreteLatticeGPU::new_with_device(Arc::new(device), Arc::new(queue), size, size, size)
+        .unwrap()
+}
+
+fn seed(lattice: &mut DiscreteLatticeGPU) {
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((5, 5, 5), 3, 3);
+    lattice.set_seed(7);
+    lattice.set_decay_rate(0.01);
+}
+
+#[test]
+fn test_fallback_matches_push_constants() {
+    let mut fallback = lattice_without_push_constants(10);
+    assert!(!fallback.uses_push_constants());
+    let mut default = pollster::block_on(DiscreteLatticeGPU::new(10, 10, 10)).unwrap();
+    seed(&mut fallback);
+    seed(&mut default);
+
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        fallback.set_propagation_mode(mode);
+        default.set_propagation_mode(mode);
+
+        // Single steps, then a batch
+        for _ in 0..5 {
+            fallback.propagate_energy();
+            default.propagate_energy();
+        }
+        fallback.propagate_n(10);
+        default.propagate_n(10);
+        assert_eq!(
+            pollster::block_on(fallback.get_state()),
+            pollster::block_on(default.get_state()),
+            "{:?} mode diverged",
+            mode
+        );
+    }
+}
+
+#[test]
+fn test_push_constants_skip_params_upload() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    if !lattice.uses_push_constants() {
+        return;
+    }
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((4, 4, 4), 2, 3);
+    lattice.propagate_energy();
+
+    // Settings unchanged: nothing crosses the bus per step
+    lattice.reset_transfer_stats();
+    for _ in 0..5 {
+        lattice.propagate_energy();
+    }
+    lattice.propagate_n(5);
+    assert_eq!(lattice.transfer_stats().bytes_uploaded, 0);
+
+    // A settings change is uploaded once
+    lattice.set_seed(3);
+    lattice.propagate_n(5);
+    assert!(lattice.transfer_stats().bytes_uploaded > 0);
+}
@@ -0,0 +1,66 @@
+use lattice_gpu::*;
+
+const SIZE: u32 = 16;
+
+fn index(x: u32, y: u32, z: u32) -> usize {
+    ((z * SIZE + y) * SIZE + x) as usize
+}
+
+#[test]
+fn test_walk_preserves_norm() {
+    let mut walk = pollster::block_on(QuantumWalk::new(SIZE, SIZE, SIZE)).unwrap();
+    walk.set_localized(8, 8, 8);
+    walk.step_n(30);
+    assert_eq!(walk.generation(), 30);
+
+    // Long enough to wrap around the periodic faces
+    let total: f32 = pollster::block_on(walk.get_probability_distribution())
+        .iter()
+        .sum();
+    assert!((total - 1.0).abs() < 1e-4, "Total probability {}", total);
+}
+
+#[test]
+fn test_walk_stays_inside_light_cone() {
+    let mut walk = pollster::block_on(QuantumWalk::new(SIZE, SIZE, SIZE)).unwrap();
+    walk.set_localized(8, 8, 8);
+    let steps = 5;
+    for _ in 0..steps {
+        walk.step();
+    }
+
+    let probability = pollster::block_on(walk.get_probability_distribution());
+    for z in 0..SIZE {
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let distance = x.abs_diff(8) + y.abs_diff(8) + z.abs_diff(8);
+                let p = probability[index(x, y, z)];
+                if distance > steps {
+                    assert_eq!(p, 0.0, "({}, {}, {}) outside the cone", x, y, z);
+                }
+            }
+        }
+    }
+
+    // The symmetric start spreads symmetrically about the origin
+    for offset in 1..=steps {
+        let plus = probability[index(8 + offset, 8, 8)];
+        let minus = probability[index(8 - offset, 8, 8)];
+        assert!((plus - minus).abs() < 1e-6);
+        assert!((plus - probability[index(8, 8, 8 + offset)]).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_set_amplitudes_round_trips_probability() {
+    let mut walk = pollster::block_on(QuantumWalk::new(4, 4, 4)).unwrap();
+    let mut amplitudes = vec![[0.0f32; 2]; 64 * WALK_DIRECTIONS];
+    amplitudes[5 * WALK_DIRECTIONS] = [0.6, 0.0];
+    amplitudes[9 * WALK_DIRECTIONS + 3] = [0.0, 0.8];
+    walk.set_amplitudes(&amplitudes);
+
+    let probability = pollster::block_on(walk.get_probability_distribution());
+    assert!((probability[5] - 0.36).abs() < 1e-6);
+    assert!((probability[9] - 0.64).abs() < 1e-6);
+    assert_eq!(probability.iter().filter(|&&p| p > 0.0).count(), 2);
+}
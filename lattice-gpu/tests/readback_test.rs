@@ -0,0 +1,85 @@
+use lattice_gpu::*;
+
+fn seeded(size: u32) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(size, size, size)).unwrap();
+    lattice.set_propagation_mode(PropagationMode::Scatter);
+    lattice.seed_sphere((size / 2, size / 2, size / 2), 3, 3);
+    lattice
+}
+
+#[test]
+fn test_queued_readback_captures_its_generation() {
+    let mut lattice = seeded(12);
+    let mut reference = seeded(12);
+    lattice.propagate_n(3);
+    reference.propagate_n(3);
+
+    let state = lattice.queue_state_readback();
+    let total = lattice.queue_total_energy_readback();
+    assert_eq!(state.generation(), 3);
+
+    // Steps submitted after the readback don't show up in it
+    lattice.propagate_n(5);
+    assert_eq!(
+        pollster::block_on(state.wait()),
+        pollster::block_on(reference.get_state())
+    );
+    assert_eq!(
+        pollster::block_on(total.wait()),
+        pollster::block_on(reference.get_total_energy())
+    );
+    assert_eq!(lattice.generation(), 8);
+}
+
+#[test]
+fn test_many_readbacks_in_flight() {
+    let mut lattice = seeded(10);
+    let mut reference = seeded(10);
+    let initial = pollster::block_on(lattice.get_total_energy());
+
+    let mut states = Vec::new();
+    let mut totals = Vec::new();
+    for _ in 0..4 {
+        lattice.propagate_energy();
+        states.push(lattice.queue_state_readback());
+        totals.push(lattice.queue_total_energy_readback());
+    }
+
+    // Collected newest first, past the pool's size, each still holds its
+    // own step
+    for state in states.into_iter().rev() {
+        let mut expected = seeded(10);
+        expected.propagate_n(state.generation() as u32);
+        assert_eq!(
+            pollster::block_on(state.wait()),
+            pollster::block_on(expected.get_state())
+        );
+    }
+    for total in totals {
+        assert_eq!(pollster::block_on(total.wait()), initial);
+    }
+
+    // Reused staging buffers still read correctly
+    reference.propagate_n(4);
+    let state = lattice.queue_state_readback();
+    lattice.propagate_energy();
+    assert_eq!(
+        pollster::block_on(state.wait()),
+        pollster::block_on(reference.get_state())
+    );
+}
+
+#[test]
+fn test_queued_readbacks_count_as_downloads() {
+    let lattice = seeded(8);
+    lattice.reset_transfer_stats();
+    let readback = lattice.queue_state_readback();
+    while !readback.is_ready() {
+        std::thread::yield_now();
+    }
+    pollster::block_on(readback.wait());
+
+    let stats = lattice.transfer_stats();
+    assert_eq!(stats.downloads, 1);
+    assert_eq!(stats.bytes_downloaded, 8 * 8 * 8 * 4);
+}
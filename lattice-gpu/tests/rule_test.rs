@@ -0,0 +1,110 @@
+use lattice_gpu::testing::assert_conserved_over;
+use lattice_gpu::*;
+
+fn seeded_lattice(mode: PropagationMode) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.set_propagation_mode(mode);
+    lattice.seed_sphere((6, 6, 6), 2, 3);
+    lattice
+}
+
+#[test]
+fn test_gradient_rule_is_the_default() {
+    let mut default = seeded_lattice(PropagationMode::Gather);
+    let mut explicit = seeded_lattice(PropagationMode::Gather);
+    pollster::block_on(explicit.set_rule(&GradientRule)).unwrap();
+
+    default.propagate_n(15);
+    explicit.propagate_n(15);
+    assert_eq!(
+        pollster::block_on(default.get_state()),
+        pollster::block_on(explicit.get_state())
+    );
+}
+
+#[test]
+fn test_random_walk_moves_uphill() {
+    // On a closed line [1, 2, 0] the gradient rule can only move the middle
+    // quantum outward, while a random walk also pushes the left quantum up
+    // into the middle site
+    let step = |rule: &dyn PropagationRule, seed| {
+        let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(3, 1, 1)).unwrap();
+        lattice.set_boundary_mode(BoundaryMode::Closed);
+        pollster::block_on(lattice.set_rule(rule)).unwrap();
+        lattice.set_seed(seed);
+        lattice.set_state(&[1, 2, 0]);
+        lattice.propagate_energy();
+        pollster::block_on(lattice.get_state())
+    };
+
+    for seed in 0..4 {
+        let gradient = step(&GradientRule, seed);
+        assert_eq!(gradient[1], 1, "{:?}", gradient);
+
+        let walk = step(&RandomWalkRule, seed);
+        assert_eq!(walk[1], 2, "{:?}", walk);
+        assert_eq!(walk.iter().sum::<u32>(), 3);
+    }
+}
+
+#[test]
+fn test_random_walk_gather_matches_scatter() {
+    let mut gather = seeded_lattice(PropagationMode::Gather);
+    let mut scatter = seeded_lattice(PropagationMode::Scatter);
+    pollster::block_on(gather.set_rule(&RandomWalkRule)).unwrap();
+    pollster::block_on(scatter.set_rule(&RandomWalkRule)).unwrap();
+
+    for _ in 0..20 {
+        gather.propagate_energy();
+        scatter.propagate_energy();
+    }
+    assert_eq!(
+        pollster::block_on(gather.get_state()),
+        pollster::block_on(scatter.get_state())
+    );
+    assert_conserved_over(&mut gather, 20);
+}
+
+#[test]
+fn test_user_rule_only_moves_along_x() {
+    let mut lattice = seeded_lattice(PropagationMode::Gather);
+    let rule = WgslRule::new(
+        "fn choose_target(x: u32, y: u32, z: u32) -> u32 {
+            let energy = energy_in[get_index(x, y, z)];
+            let n = (x + 1u) % params.width;
+            let n_idx = get_index(n, y, z);
+            if (energy_in[n_idx] < energy) {
+                return n_idx;
+            }
+            return NO_TARGET;
+        }",
+    );
+    pollster::block_on(lattice.set_rule(&rule)).unwrap();
+    let before = pollster::block_on(lattice.get_state());
+
+    lattice.propagate_n(10);
+
+    // Quanta only slide along +X, so every row keeps its total
+    let after = pollster::block_on(lattice.get_state());
+    assert_ne!(before, after);
+    let row_totals =
+        |state: &[u32]| -> Vec<u32> { state.chunks(12).map(|row| row.iter().sum()).collect() };
+    assert_eq!(row_totals(&before), row_totals(&after));
+}
+
+#[test]
+fn test_broken_rule_keeps_previous_rule() {
+    let mut lattice = seeded_lattice(PropagationMode::Gather);
+    let err = pollster::block_on(lattice.set_rule(&WgslRule::new("fn choose_target( {")))
+        .expect_err("Broken rule should not compile");
+    assert!(matches!(err, LatticeError::ShaderCompile(_)));
+
+    let mut reference = seeded_lattice(PropagationMode::Gather);
+    lattice.propagate_n(5);
+    reference.propagate_n(5);
+    assert_eq!(
+        pollster::block_on(lattice.get_state()),
+        pollster::block_on(reference.get_state())
+    );
+}
@@ -0,0 +1,159 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_sphere_points_near_corner_stay_in_bounds() {
+    let dims = (20, 20, 20);
+    let points = sphere_points((1, 0, 18), 5, 3, dims);
+
+    assert!(
+        !points.is_empty(),
+        "Corner sphere should still cover some sites"
+    );
+    for &(x, y, z, quanta) in &points {
+        assert!(
+            x < dims.0 && y < dims.1 && z < dims.2,
+            "Point ({}, {}, {}) is outside the lattice",
+            x,
+            y,
+            z
+        );
+        assert_eq!(quanta, 3);
+    }
+}
+
+#[test]
+fn test_sphere_points_interior_count() {
+    // Radius 1 sphere is the center plus its 6 face neighbors
+    let points = sphere_points((5, 5, 5), 1, 2, (10, 10, 10));
+    assert_eq!(points.len(), 7);
+
+    // Radius 3 sphere used by the benchmark has 123 sites
+    let points = sphere_points((10, 10, 10), 3, 3, (20, 20, 20));
+    assert_eq!(points.len(), 123);
+}
+
+#[test]
+fn test_sphere_points_clipped_at_corner() {
+    // Only the octant x,y,z >= 0 of a radius-1 sphere at the origin survives
+    let points = sphere_points((0, 0, 0), 1, 1, (10, 10, 10));
+    let mut coords: Vec<_> = points.iter().map(|&(x, y, z, _)| (x, y, z)).collect();
+    coords.sort();
+    assert_eq!(coords, vec![(0, 0, 0), (0, 0, 1), (0, 1, 0), (1, 0, 0)]);
+}
+
+#[test]
+fn test_seed_sphere_near_corner() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(20, 20, 20)).unwrap();
+    lattice.initialize_vacuum();
+
+    // Previously this wrapped negative offsets into huge u32 coordinates
+    lattice.seed_sphere((0, 0, 0), 3, 3);
+
+    let expected = sphere_points((0, 0, 0), 3, 3, (20, 20, 20)).len() as u64 * 3;
+    let total = pollster::block_on(lattice.get_total_energy());
+    assert_eq!(
+        total, expected,
+        "Clipped sphere should hold 3 quanta per site"
+    );
+}
+
+fn coords(points: &[(u32, u32, u32, u32)]) -> Vec<(u32, u32, u32)> {
+    points.iter().map(|&(x, y, z, _)| (x, y, z)).collect()
+}
+
+#[test]
+fn test_line_points_along_axis() {
+    let points = line_points((2, 3, 4), (6, 3, 4), 1, (10, 10, 10));
+    assert_eq!(
+        coords(&points),
+        vec![(2, 3, 4), (3, 3, 4), (4, 3, 4), (5, 3, 4), (6, 3, 4)]
+    );
+}
+
+#[test]
+fn test_line_points_diagonal_and_reversed() {
+    let points = line_points((0, 0, 0), (3, 3, 3), 2, (10, 10, 10));
+    assert_eq!(
+        coords(&points),
+        vec![(0, 0, 0), (1, 1, 1), (2, 2, 2), (3, 3, 3)]
+    );
+    assert!(points.iter().all(|p| p.3 == 2));
+
+    // Same sites when walked the other way
+    let mut reversed = coords(&line_points((3, 3, 3), (0, 0, 0), 2, (10, 10, 10)));
+    reversed.reverse();
+    assert_eq!(reversed, coords(&points));
+}
+
+#[test]
+fn test_line_points_one_site_per_driving_step() {
+    let from = (1, 2, 3);
+    let to = (13, 7, 5);
+    let points = line_points(from, to, 1, (20, 20, 20));
+
+    // 12 steps along X plus the start
+    assert_eq!(points.len(), 13);
+    assert_eq!(coords(&points)[0], from);
+    assert_eq!(*coords(&points).last().unwrap(), to);
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        assert_eq!(b.0, a.0 + 1);
+        assert!(b.1.abs_diff(a.1) <= 1 && b.2.abs_diff(a.2) <= 1);
+    }
+}
+
+#[test]
+fn test_line_points_clipped_to_lattice() {
+    let points = line_points((2, 2, 2), (2, 2, 50), 1, (5, 5, 5));
+    assert_eq!(coords(&points), vec![(2, 2, 2), (2, 2, 3), (2, 2, 4)]);
+}
+
+#[test]
+fn test_add_energy_line() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_line((0, 6, 6), (20, 6, 6), 3);
+
+    // The part past x = 11 is dropped
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 12 * 3);
+    let state = pollster::block_on(lattice.get_state());
+    for x in 0..12 {
+        assert_eq!(state[6 * 144 + 6 * 12 + x], 3);
+    }
+}
+
+#[test]
+fn test_energy_batch_caps_and_merges_duplicates() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(6, 6, 6)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_batch(&[
+        (1, 2, 3, 1),
+        (1, 2, 3, 1),
+        (4, 4, 4, 2),
+        (4, 4, 4, 5),
+        (0, 0, 0, u32::MAX),
+        (9, 0, 0, 1), // outside the lattice, skipped
+    ]);
+
+    let state = pollster::block_on(lattice.get_state());
+    let at = |x: usize, y: usize, z: usize| state[z * 36 + y * 6 + x];
+    assert_eq!(at(1, 2, 3), 2);
+    assert_eq!(at(4, 4, 4), MAX_LEVEL);
+    assert_eq!(at(0, 0, 0), MAX_LEVEL);
+    assert_eq!(state.iter().sum::<u32>(), 2 + 2 * MAX_LEVEL);
+}
+
+#[test]
+fn test_energy_batch_does_not_read_back_lattice() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(16, 16, 16)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.reset_transfer_stats();
+
+    lattice.seed_sphere((8, 8, 8), 4, 3);
+    let stats = lattice.transfer_stats();
+    assert_eq!(stats.downloads, 0);
+
+    // Only the edit list is uploaded: two words per site
+    let sites = sphere_points((8, 8, 8), 4, 3, (16, 16, 16)).len() as u64;
+    assert_eq!(stats.bytes_uploaded, sites * 8);
+}
@@ -0,0 +1,37 @@
+use lattice_gpu::*;
+
+fn seeded_lattice() -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((6, 6, 6), 3, 3);
+    lattice
+}
+
+#[test]
+fn test_reload_shader_from_disk() {
+    let mut reloaded = seeded_lattice();
+    let mut embedded = seeded_lattice();
+    pollster::block_on(reloaded.reload_shader()).expect("On-disk shader should compile");
+
+    for _ in 0..10 {
+        reloaded.propagate_energy();
+        embedded.propagate_energy();
+    }
+    assert_eq!(
+        pollster::block_on(reloaded.get_state()),
+        pollster::block_on(embedded.get_state())
+    );
+}
+
+#[test]
+fn test_reload_broken_shader_keeps_old_pipelines() {
+    let mut lattice = seeded_lattice();
+    let total = pollster::block_on(lattice.get_total_energy());
+
+    let err = pollster::block_on(lattice.reload_shader_source("fn propagate_gather( {"))
+        .expect_err("Broken shader should not compile");
+    assert!(matches!(err, LatticeError::ShaderCompile(_)));
+
+    lattice.propagate_energy();
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), total);
+}
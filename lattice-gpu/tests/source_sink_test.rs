@@ -0,0 +1,78 @@
+use lattice_gpu::*;
+
+fn lattice() -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    lattice.initialize_vacuum();
+    lattice
+}
+
+// Total minus what sources added plus what sinks removed never changes
+fn audited_total(lattice: &DiscreteLatticeGPU) -> i64 {
+    pollster::block_on(lattice.get_total_energy()) as i64
+        + pollster::block_on(lattice.absorbed_energy()) as i64
+        - pollster::block_on(lattice.injected_energy()) as i64
+}
+
+#[test]
+fn test_source_injects_every_step() {
+    let mut lattice = lattice();
+    lattice.add_sources(&[(6, 6, 6, 1), (20, 0, 0, 1)]);
+
+    for step in 1..=5u64 {
+        lattice.propagate_energy();
+        assert_eq!(pollster::block_on(lattice.injected_energy()), step);
+        assert_eq!(pollster::block_on(lattice.get_total_energy()), step);
+    }
+}
+
+#[test]
+fn test_sources_and_sinks_are_audited() {
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        let mut lattice = lattice();
+        lattice.set_propagation_mode(mode);
+        lattice.seed_sphere((6, 6, 6), 2, 2);
+        let initial = audited_total(&lattice);
+
+        lattice.add_sources(&[(2, 2, 2, 2), (9, 3, 5, 1)]);
+        lattice.add_sinks(&[(6, 6, 6, 3), (3, 2, 2, 1)]);
+        lattice.propagate_n(15);
+        for _ in 0..15 {
+            lattice.propagate_energy();
+        }
+
+        assert!(pollster::block_on(lattice.injected_energy()) > 0);
+        assert!(pollster::block_on(lattice.absorbed_energy()) > 0);
+        assert_eq!(audited_total(&lattice), initial, "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_clear_sources_and_sinks() {
+    let mut lattice = lattice();
+    lattice.add_sources(&[(6, 6, 6, 1)]);
+    lattice.propagate_energy();
+    lattice.clear_sources_and_sinks();
+    lattice.propagate_n(5);
+    assert_eq!(pollster::block_on(lattice.injected_energy()), 1);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 1);
+}
+
+#[test]
+fn test_more_sources_and_sinks_than_one_dispatch() {
+    // More sites than one edit pass covers (65535 workgroups of 64)
+    let (width, height, depth) = (256, 256, 65);
+    let sites = (width * height * depth) as u64;
+    assert!(sites > 65535 * 64);
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(width, height, depth)).unwrap();
+    lattice.initialize_vacuum();
+    let every_site: Vec<_> = (0..depth)
+        .flat_map(|z| (0..height).flat_map(move |y| (0..width).map(move |x| (x, y, z, 1))))
+        .collect();
+    lattice.add_sources(&every_site);
+    lattice.add_sinks(&every_site);
+
+    lattice.propagate_energy();
+    assert_eq!(pollster::block_on(lattice.injected_energy()), sites);
+    assert_eq!(pollster::block_on(lattice.absorbed_energy()), sites);
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), 0);
+}
@@ -0,0 +1,117 @@
+use lattice_gpu::*;
+
+const SIZE: u32 = 21;
+const SEED: u32 = SIZE / 2;
+
+// Region of sites within Chebyshev distance `radius` of the seed
+fn region_around_seed(radius: u32) -> BoundingBox {
+    BoundingBox {
+        min: [SEED - radius; 3],
+        max: [SEED + radius; 3],
+    }
+}
+
+// Manhattan distance from the seed of every occupied site in `region`
+fn occupied_distances(lattice: &DiscreteLatticeGPU, region: BoundingBox) -> Vec<u32> {
+    let energy = pollster::block_on(lattice.get_energy_region(region));
+    let [size_x, size_y, _] = region.size();
+
+    let mut distances = Vec::new();
+    for (i, &e) in energy.iter().enumerate() {
+        if e == 0 {
+            continue;
+        }
+        let i = i as u32;
+        let x = region.min[0] + i % size_x;
+        let y = region.min[1] + (i / size_x) % size_y;
+        let z = region.min[2] + i / (size_x * size_y);
+        distances.push(x.abs_diff(SEED) + y.abs_diff(SEED) + z.abs_diff(SEED));
+    }
+    distances
+}
+
+fn seeded_lattice(quanta: u32) -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(SIZE, SIZE, SIZE)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(SEED, SEED, SEED, quanta);
+    lattice
+}
+
+#[test]
+fn test_energy_stays_within_manhattan_radius() {
+    // The 6-neighbor stencil moves a quantum at most one site per step
+    let mut lattice = seeded_lattice(3);
+    for k in 1..=8 {
+        lattice.propagate_energy();
+
+        // All energy is inside the k-box around the seed...
+        let region = region_around_seed(k);
+        let energy = pollster::block_on(lattice.get_energy_region(region));
+        assert_eq!(
+            energy.iter().sum::<u32>(),
+            3,
+            "Energy left the box at step {}",
+            k
+        );
+
+        // ...and inside the Manhattan ball of radius k
+        for d in occupied_distances(&lattice, region) {
+            assert!(d <= k, "Energy at distance {} after {} steps", d, k);
+        }
+    }
+}
+
+#[test]
+fn test_first_step_reaches_a_face_neighbor() {
+    let mut lattice = seeded_lattice(3);
+    lattice.propagate_energy();
+
+    let region = region_around_seed(1);
+    let energy = pollster::block_on(lattice.get_energy_region(region));
+    // Center of the 3x3x3 region
+    assert_eq!(energy[13], 2);
+
+    let mut distances = occupied_distances(&lattice, region);
+    distances.sort();
+    assert_eq!(distances, vec![0, 1]);
+}
+
+#[test]
+fn test_lone_quantum_moves_every_step() {
+    // A single quantum always has lower neighbors, so it hops each step and
+    // its distance from the seed has the same parity as the step count
+    let mut lattice = seeded_lattice(1);
+    for k in 1..=8 {
+        lattice.propagate_energy();
+
+        let distances = occupied_distances(&lattice, region_around_seed(k));
+        assert_eq!(distances.len(), 1, "Quantum lost at step {}", k);
+        let d = distances[0];
+        assert!(d <= k && d % 2 == k % 2, "Distance {} after {} steps", d, k);
+    }
+}
+
+#[test]
+fn test_energy_region_matches_full_state() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(9, 7, 5)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((4, 3, 2), 2, 3);
+    lattice.propagate_energy();
+
+    let state = pollster::block_on(lattice.get_state());
+    let region = BoundingBox {
+        min: [2, 1, 1],
+        max: [7, 5, 3],
+    };
+    let energy = pollster::block_on(lattice.get_energy_region(region));
+
+    let mut expected = Vec::new();
+    for z in 1..=3 {
+        for y in 1..=5 {
+            for x in 2..=7 {
+                expected.push(state[z * 9 * 7 + y * 9 + x]);
+            }
+        }
+    }
+    assert_eq!(energy, expected);
+}
@@ -0,0 +1,175 @@
+use lattice_gpu::*;
+
+fn seeded_lattice() -> DiscreteLatticeGPU {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(12, 12, 12)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((6, 6, 6), 3, 3);
+    lattice
+}
+
+#[test]
+fn test_resume_from_saved_state() {
+    let mut original = seeded_lattice();
+    for _ in 0..7 {
+        original.propagate_energy();
+    }
+    let saved = pollster::block_on(original.save_state());
+    assert_eq!(saved.generation, 7);
+
+    // Take the restored copy through an odd number of steps so its buffer
+    // parity differs from the original's
+    let mut restored = seeded_lattice();
+    restored.propagate_energy();
+    restored.load_state(&saved);
+    assert_eq!(restored.generation(), 7);
+
+    for _ in 0..10 {
+        original.propagate_energy();
+        restored.propagate_energy();
+    }
+    let original_state = pollster::block_on(original.get_state());
+    assert!(pollster::block_on(restored.diff(&original_state)).is_empty());
+    assert_eq!(
+        pollster::block_on(restored.save_state()),
+        pollster::block_on(original.save_state())
+    );
+}
+
+#[test]
+fn test_set_step_count_changes_random_choices() {
+    let mut a = seeded_lattice();
+    let mut b = seeded_lattice();
+    b.set_step_count(1000);
+    assert_eq!(b.generation(), 1000);
+
+    a.propagate_energy();
+    b.propagate_energy();
+    assert_eq!(b.generation(), 1001);
+
+    let a_state = pollster::block_on(a.get_state());
+    assert!(
+        !pollster::block_on(b.diff(&a_state)).is_empty(),
+        "Different steps should seed different transfers"
+    );
+}
+
+#[test]
+#[should_panic(expected = "State dimensions must match the lattice")]
+fn test_load_state_rejects_wrong_size() {
+    let small = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    let saved = pollster::block_on(small.save_state());
+
+    let mut lattice = seeded_lattice();
+    lattice.load_state(&saved);
+}
+
+#[test]
+fn test_single_site_readback_matches_full_state() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(9, 7, 5)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((4, 3, 2), 2, 3);
+    for _ in 0..5 {
+        lattice.propagate_energy();
+    }
+    let state = pollster::block_on(lattice.get_state());
+
+    lattice.reset_transfer_stats();
+    for &(x, y, z) in &[(0, 0, 0), (4, 3, 2), (5, 3, 2), (8, 6, 4)] {
+        let idx = (z * 9 * 7 + y * 9 + x) as usize;
+        assert_eq!(
+            pollster::block_on(lattice.get_energy_at(x, y, z)),
+            state[idx]
+        );
+    }
+    assert_eq!(lattice.transfer_stats().bytes_downloaded, 4 * 4);
+}
+
+#[test]
+fn test_set_state_round_trips_and_keeps_generation() {
+    let mut lattice = seeded_lattice();
+    for _ in 0..4 {
+        lattice.propagate_energy();
+    }
+    let generation = lattice.generation();
+
+    let energy: Vec<u32> = (0..12 * 12 * 12).map(|i| (i * 7 % 5) as u32 % 4).collect();
+    lattice.set_state(&energy);
+    assert_eq!(pollster::block_on(lattice.get_state()), energy);
+    assert_eq!(lattice.generation(), generation);
+
+    // Propagation continues from the uploaded state
+    let total: u64 = energy.iter().map(|&e| e as u64).sum();
+    lattice.propagate_energy();
+    assert_eq!(pollster::block_on(lattice.get_total_energy()), total);
+}
+
+#[test]
+#[should_panic(expected = "one value per site")]
+fn test_set_state_rejects_wrong_length() {
+    let mut lattice = seeded_lattice();
+    lattice.set_state(&[0; 10]);
+}
+
+#[test]
+fn test_snapshot_restore_replays_exactly() {
+    let mut lattice = seeded_lattice();
+    for _ in 0..3 {
+        lattice.propagate_energy();
+    }
+    let snapshot = lattice.snapshot();
+    assert_eq!(snapshot.generation(), 3);
+
+    for _ in 0..10 {
+        lattice.propagate_energy();
+    }
+    let first_run = pollster::block_on(lattice.get_state());
+
+    // Restoring twice replays the same branch each time
+    for _ in 0..2 {
+        lattice.restore(&snapshot);
+        assert_eq!(lattice.generation(), 3);
+        for _ in 0..10 {
+            lattice.propagate_energy();
+        }
+        assert_eq!(pollster::block_on(lattice.get_state()), first_run);
+    }
+}
+
+#[test]
+fn test_snapshot_does_not_read_back() {
+    let lattice = seeded_lattice();
+    lattice.reset_transfer_stats();
+    let _snapshot = lattice.snapshot();
+    let stats = lattice.transfer_stats();
+    assert_eq!((stats.downloads, stats.bytes_uploaded), (0, 0));
+}
+
+#[test]
+fn test_slices_match_full_state() {
+    let (w, h, d) = (7, 5, 4);
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(w, h, d)).unwrap();
+    let energy: Vec<u32> = (0..w * h * d).map(|i| i % 4).collect();
+    lattice.set_state(&energy);
+    let at = |x: u32, y: u32, z: u32| energy[(z * w * h + y * w + x) as usize];
+
+    let z_slice = pollster::block_on(lattice.get_slice(Axis::Z, 2));
+    let expected: Vec<u32> = (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x, y)))
+        .map(|(x, y)| at(x, y, 2))
+        .collect();
+    assert_eq!(z_slice, expected);
+
+    let y_slice = pollster::block_on(lattice.get_slice(Axis::Y, 4));
+    let expected: Vec<u32> = (0..d)
+        .flat_map(|z| (0..w).map(move |x| (x, z)))
+        .map(|(x, z)| at(x, 4, z))
+        .collect();
+    assert_eq!(y_slice, expected);
+
+    let x_slice = pollster::block_on(lattice.get_slice(Axis::X, 6));
+    let expected: Vec<u32> = (0..d)
+        .flat_map(|z| (0..h).map(move |y| (y, z)))
+        .map(|(y, z)| at(6, y, z))
+        .collect();
+    assert_eq!(x_slice, expected);
+}
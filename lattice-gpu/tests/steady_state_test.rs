@@ -0,0 +1,47 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_saturated_lattice_is_steady() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((2, 2, 2), 8, 3);
+
+    // First call only records the reference
+    assert!(!pollster::block_on(lattice.is_steady(0)));
+
+    // Every site is at the top level, so nothing can move
+    lattice.propagate_energy();
+    assert!(pollster::block_on(lattice.is_steady(0)));
+}
+
+#[test]
+fn test_wandering_quantum_is_not_steady() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(4, 4, 4, 1);
+    pollster::block_on(lattice.is_steady(0));
+
+    // A lone quantum hops every step: one site loses it, another gains it
+    lattice.propagate_energy();
+    assert!(!pollster::block_on(lattice.is_steady(1)));
+    lattice.propagate_energy();
+    assert!(pollster::block_on(lattice.is_steady(2)));
+}
+
+#[test]
+fn test_propagate_until_steady() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((2, 2, 2), 8, 3);
+
+    let steps = pollster::block_on(lattice.propagate_until_steady(0, 5, 50));
+    assert_eq!(steps, Some(5));
+
+    let mut wandering = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    wandering.initialize_vacuum();
+    wandering.add_energy_quantum(4, 4, 4, 1);
+    assert_eq!(
+        pollster::block_on(wandering.propagate_until_steady(0, 1, 10)),
+        None
+    );
+}
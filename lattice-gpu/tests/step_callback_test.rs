@@ -0,0 +1,36 @@
+use lattice_gpu::*;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_on_step_sees_active_buffer_and_generation() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.add_energy_quantum(4, 4, 4, 3);
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let log = calls.clone();
+    lattice.on_step(move |buffer, generation| {
+        log.lock().unwrap().push((buffer.global_id(), generation));
+    });
+
+    let mut expected = Vec::new();
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        lattice.set_propagation_mode(mode);
+        for _ in 0..3 {
+            lattice.propagate_energy();
+            expected.push((
+                lattice.get_energy_buffer().global_id(),
+                lattice.generation(),
+            ));
+        }
+    }
+    assert_eq!(*calls.lock().unwrap(), expected);
+
+    // Consecutive steps hand over alternating buffers
+    assert_ne!(expected[0].0, expected[1].0);
+    assert_eq!(expected[0].0, expected[2].0);
+
+    lattice.clear_on_step();
+    lattice.propagate_energy();
+    assert_eq!(calls.lock().unwrap().len(), 6);
+}
@@ -0,0 +1,101 @@
+use lattice_gpu::*;
+use std::sync::Arc;
+
+fn create_device() -> (Arc<wgpu::Device>, Arc<wgpu::Queue>) {
+    pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .expect("Failed to find GPU adapter");
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await
+            .expect("Failed to create device");
+        (Arc::new(device), Arc::new(queue))
+    })
+}
+
+// Copy the lattice into a texture, then read the texture back row by row
+fn texture_contents(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    lattice: &DiscreteLatticeGPU,
+    (width, height, depth): (u32, u32, u32),
+) -> Vec<u32> {
+    let texture = lattice.create_energy_texture();
+    let padded_row = (width * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Texture Readback"),
+        size: (padded_row * height * depth) as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    lattice.copy_to_texture_3d(&mut encoder, &texture);
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &readback,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = readback.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+
+    let data = slice.get_mapped_range();
+    let mut texels = Vec::new();
+    for row in data.chunks(padded_row as usize) {
+        texels.extend_from_slice(bytemuck::cast_slice(&row[..(width * 4) as usize]));
+    }
+    texels
+}
+
+fn check_texture_matches(dims: (u32, u32, u32)) {
+    let (device, queue) = create_device();
+    let (width, height, depth) = dims;
+    let mut lattice =
+        DiscreteLatticeGPU::new_with_device(device.clone(), queue.clone(), width, height, depth)
+            .unwrap();
+    lattice.initialize_vacuum();
+
+    let sites = [
+        (0, 0, 0, 1),
+        (width - 1, 1, 2, 2),
+        (3, height - 1, depth - 1, 3),
+    ];
+    for &(x, y, z, quanta) in &sites {
+        lattice.add_energy_quantum(x, y, z, quanta);
+    }
+
+    let texels = texture_contents(&device, &queue, &lattice, dims);
+    assert_eq!(texels.len(), (width * height * depth) as usize);
+
+    let expected_total: u32 = sites.iter().map(|s| s.3).sum();
+    assert_eq!(texels.iter().sum::<u32>(), expected_total);
+    for &(x, y, z, quanta) in &sites {
+        let idx = (z * width * height + y * width + x) as usize;
+        assert_eq!(texels[idx], quanta, "Texel ({}, {}, {})", x, y, z);
+    }
+}
+
+#[test]
+fn test_texture_copy_aligned_rows() {
+    // 64 sites * 4 bytes = one 256-byte aligned row
+    check_texture_matches((64, 4, 4));
+}
+
+#[test]
+fn test_texture_copy_unaligned_rows() {
+    check_texture_matches((10, 5, 3));
+}
@@ -0,0 +1,62 @@
+use lattice_gpu::*;
+use std::thread;
+
+#[test]
+fn test_propagate_on_worker_thread() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(16, 16, 16)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((8, 8, 8), 2, 3);
+    let initial = pollster::block_on(lattice.get_total_energy());
+
+    let handle = LatticeHandle::new(lattice);
+    let worker = {
+        let handle = handle.clone();
+        thread::spawn(move || {
+            for _ in 0..5 {
+                handle.propagate(10);
+            }
+        })
+    };
+
+    // Reads from the main thread interleave with the worker's steps
+    for _ in 0..5 {
+        assert_eq!(handle.total_energy(), initial);
+    }
+
+    worker.join().expect("Worker thread panicked");
+    assert_eq!(handle.total_energy(), initial);
+}
+
+#[test]
+fn test_lattice_moves_to_another_thread() {
+    let lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+
+    let total = thread::spawn(move || {
+        let mut lattice = lattice;
+        lattice.initialize_vacuum();
+        lattice.add_energy_quantum(4, 4, 4, 3);
+        lattice.propagate_energy();
+        pollster::block_on(lattice.get_total_energy())
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(total, 3);
+}
+
+#[test]
+fn test_handle_drives_cpu_backend() {
+    let mut lattice = DiscreteLatticeCPU::new(8, 8, 8);
+    lattice.seed_sphere((4, 4, 4), 2, 3);
+    let initial = lattice.get_total_energy();
+
+    let handle = LatticeHandle::new(lattice);
+    let worker = {
+        let handle = handle.clone();
+        thread::spawn(move || handle.propagate(10))
+    };
+    worker.join().expect("Worker thread panicked");
+
+    assert_eq!(handle.total_energy(), initial);
+    assert_eq!(handle.with(|lattice| lattice.generation()), 10);
+}
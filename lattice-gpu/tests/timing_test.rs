@@ -0,0 +1,57 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_timed_run_excludes_warmup() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(16, 16, 16)).unwrap();
+    lattice.initialize_vacuum();
+    lattice.seed_sphere((8, 8, 8), 2, 3);
+
+    let timing = pollster::block_on(lattice.timed_run(20, 5));
+
+    assert_eq!(lattice.generation(), 25, "Warmup steps still propagate");
+    assert_eq!(timing.steps, 20);
+    assert_eq!(timing.total_sites, 16 * 16 * 16);
+    assert!(timing.elapsed.as_nanos() > 0);
+
+    let expected_rate = timing.total_sites as f64 * 20.0 / timing.elapsed.as_secs_f64();
+    assert!((timing.sites_per_sec() - expected_rate).abs() / expected_rate < 1e-9);
+    assert!((timing.gb_per_sec() - expected_rate * 8.0 / 1e9).abs() < 1e-9);
+    assert!((timing.ms_per_step() * 20.0 - timing.total_ms()).abs() < 1e-9);
+}
+
+#[test]
+fn test_transfer_stats_count_bytes() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    let lattice_bytes = 8 * 8 * 8 * 4;
+
+    lattice.reset_transfer_stats();
+    lattice.initialize_vacuum();
+    assert_eq!(lattice.transfer_stats().bytes_uploaded, 2 * lattice_bytes);
+    assert_eq!(lattice.transfer_stats().downloads, 0);
+
+    // A full readback moves the whole lattice...
+    lattice.reset_transfer_stats();
+    pollster::block_on(lattice.get_state());
+    let stats = lattice.transfer_stats();
+    assert_eq!(stats.bytes_downloaded, lattice_bytes);
+    assert_eq!(stats.downloads, 1);
+    assert!(stats.download_time > std::time::Duration::ZERO);
+
+    // ...while the GPU-reduced total reads back two words
+    lattice.reset_transfer_stats();
+    pollster::block_on(lattice.get_total_energy());
+    assert_eq!(lattice.transfer_stats().bytes_downloaded, 8);
+
+    // Each step uploads its params, unless the step travels as a push
+    // constant and the params are unchanged
+    lattice.propagate_energy();
+    lattice.reset_transfer_stats();
+    lattice.propagate_energy();
+    let stats = lattice.transfer_stats();
+    if lattice.uses_push_constants() {
+        assert_eq!(stats.bytes_uploaded, 0);
+    } else {
+        assert!(stats.bytes_uploaded > 0 && stats.bytes_uploaded <= 64);
+    }
+    assert_eq!(stats.bytes_downloaded, 0);
+}
@@ -0,0 +1,74 @@
+use lattice_gpu::blocking::BlockingLattice;
+use std::sync::{Arc, Mutex};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+// Records the name of every span and the message field of every event
+#[derive(Clone, Default)]
+struct Recorder {
+    names: Arc<Mutex<Vec<String>>>,
+}
+
+struct MessageVisitor<'a>(&'a mut String);
+
+impl tracing::field::Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            *self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut names = self.names.lock().unwrap();
+        names.push(span.metadata().name().to_string());
+        Id::from_u64(names.len() as u64)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = String::new();
+        event.record(&mut MessageVisitor(&mut message));
+        self.names.lock().unwrap().push(message);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn test_device_creation_propagation_and_readback_are_traced() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut lattice = BlockingLattice::new(8, 8, 8).unwrap();
+        lattice.add_energy_quantum(4, 4, 4, 3);
+        lattice.propagate_n(5);
+        assert_eq!(lattice.get_total_energy(), 3);
+    });
+
+    let names = recorder.names.lock().unwrap();
+    for name in [
+        "request_device",
+        "build",
+        "Allocated lattice buffers",
+        "propagate_n",
+        "run_reduction",
+        "read_staged",
+    ] {
+        assert!(
+            names.iter().any(|n| n == name),
+            "no {:?} in {:?}",
+            name,
+            names
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_upsample_preserves_total() {
+    let mut coarse = pollster::block_on(DiscreteLatticeGPU::new(6, 5, 4)).unwrap();
+    coarse.initialize_vacuum();
+    coarse.seed_sphere((3, 2, 2), 2, 3);
+    for _ in 0..5 {
+        coarse.propagate_energy();
+    }
+    let total = pollster::block_on(coarse.get_total_energy());
+
+    for factor in [1, 2, 3] {
+        let fine = pollster::block_on(coarse.upsample_to(factor)).unwrap();
+        let state = pollster::block_on(fine.save_state());
+        assert_eq!(
+            (state.width, state.height, state.depth),
+            (6 * factor, 5 * factor, 4 * factor)
+        );
+        assert_eq!(state.generation, 5);
+        assert_eq!(pollster::block_on(fine.get_total_energy()), total);
+    }
+}
+
+#[test]
+fn test_upsample_keeps_energy_inside_parent_block() {
+    let mut coarse = pollster::block_on(DiscreteLatticeGPU::new(4, 4, 4)).unwrap();
+    coarse.initialize_vacuum();
+    coarse.add_energy_quantum(1, 2, 3, 3);
+
+    let fine = pollster::block_on(coarse.upsample_to(2)).unwrap();
+    let bounds = pollster::block_on(fine.occupied_bounds()).unwrap();
+    assert!(bounds.min[0] >= 2 && bounds.max[0] <= 3);
+    assert!(bounds.min[1] >= 4 && bounds.max[1] <= 5);
+    assert!(bounds.min[2] >= 6 && bounds.max[2] <= 7);
+
+    // Three quanta over eight children: one each, never stacked
+    let state = pollster::block_on(fine.get_state());
+    assert_eq!(state.iter().filter(|&&e| e == 1).count(), 3);
+    assert_eq!(pollster::block_on(fine.get_total_energy()), 3);
+}
+
+#[test]
+fn test_upsampled_lattice_keeps_conserving() {
+    let mut coarse = pollster::block_on(DiscreteLatticeGPU::new(5, 5, 5)).unwrap();
+    coarse.initialize_vacuum();
+    coarse.seed_sphere((2, 2, 2), 1, 3);
+    coarse.set_frozen(&[(0, 0, 0)]);
+    coarse.add_energy_quantum(0, 0, 0, 1);
+
+    let mut fine = pollster::block_on(coarse.upsample_to(2)).unwrap();
+    let total = pollster::block_on(fine.get_total_energy());
+    assert_eq!(total, 7 * 3 + 1);
+    for _ in 0..10 {
+        fine.propagate_energy();
+    }
+    assert_eq!(pollster::block_on(fine.get_total_energy()), total);
+    // The frozen corner became a frozen 2x2x2 block holding its quantum
+    assert_eq!(pollster::block_on(fine.frozen_energy()), 1);
+}
+
+#[test]
+fn test_upsample_overflowing_dimension_is_an_error() {
+    let coarse = pollster::block_on(DiscreteLatticeGPU::new(4, 2, 2)).unwrap();
+    let result = pollster::block_on(coarse.upsample_to(u32::MAX / 2));
+    assert!(matches!(result, Err(LatticeError::InvalidInput(_))));
+}
@@ -0,0 +1,86 @@
+use lattice_gpu::*;
+
+#[test]
+fn test_default_workgroup_fits_downlevel_limits() {
+    let limits = wgpu::Limits::downlevel_defaults();
+    assert!(check_workgroup_size(WORKGROUP_SIZE, &limits).is_ok());
+}
+
+#[test]
+fn test_workgroup_axis_too_large() {
+    let limits = wgpu::Limits::downlevel_defaults();
+    let size = [4, 4, limits.max_compute_workgroup_size_z + 1];
+
+    match check_workgroup_size(size, &limits) {
+        Err(LatticeError::WorkgroupTooLarge {
+            size: s, max_size, ..
+        }) => {
+            assert_eq!(s, size);
+            assert_eq!(max_size[2], limits.max_compute_workgroup_size_z);
+        }
+        other => panic!("Expected WorkgroupTooLarge, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_workgroup_too_many_invocations() {
+    // Each axis is within its own limit, but the product is not
+    let limits = wgpu::Limits::downlevel_defaults();
+    let size = [16, 16, 16];
+    assert!(size[0] <= limits.max_compute_workgroup_size_x);
+    assert!(size[2] <= limits.max_compute_workgroup_size_z);
+
+    let err = check_workgroup_size(size, &limits).unwrap_err();
+    assert!(matches!(err, LatticeError::WorkgroupTooLarge { .. }));
+    assert!(err.to_string().contains("workgroup size [16, 16, 16]"));
+}
+
+#[test]
+fn test_workgroup_candidates_fit_default_limits() {
+    let limits = wgpu::Limits::default();
+    for size in WORKGROUP_SIZE_CANDIDATES {
+        assert!(check_workgroup_size(size, &limits).is_ok(), "{:?}", size);
+    }
+}
+
+#[test]
+fn test_workgroup_size_does_not_change_results() {
+    let seeded = |size: [u32; 3], mode: PropagationMode| {
+        // Dimensions that no candidate divides evenly
+        let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(13, 11, 7)).unwrap();
+        lattice.set_workgroup_size(size).unwrap();
+        lattice.set_propagation_mode(mode);
+        lattice.seed_sphere((6, 5, 3), 3, 3);
+        lattice.propagate_n(12);
+        pollster::block_on(lattice.get_state())
+    };
+
+    for mode in [PropagationMode::Gather, PropagationMode::Scatter] {
+        let expected = seeded(WORKGROUP_SIZE, mode);
+        for size in WORKGROUP_SIZE_CANDIDATES {
+            assert_eq!(seeded(size, mode), expected, "{:?} in {:?}", size, mode);
+        }
+    }
+}
+
+#[test]
+fn test_set_workgroup_size_rejects_oversized() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(8, 8, 8)).unwrap();
+    let err = lattice.set_workgroup_size([16, 16, 16]).unwrap_err();
+    assert!(matches!(err, LatticeError::WorkgroupTooLarge { .. }));
+    assert_eq!(lattice.workgroup_size(), WORKGROUP_SIZE);
+}
+
+#[test]
+fn test_tune_workgroup_size_keeps_state() {
+    let mut lattice = pollster::block_on(DiscreteLatticeGPU::new(16, 16, 16)).unwrap();
+    lattice.seed_sphere((8, 8, 8), 3, 3);
+    lattice.propagate_n(3);
+    let before = pollster::block_on(lattice.get_state());
+
+    let size = pollster::block_on(lattice.tune_workgroup_size(3));
+    assert!(WORKGROUP_SIZE_CANDIDATES.contains(&size));
+    assert_eq!(lattice.workgroup_size(), size);
+    assert_eq!(lattice.generation(), 3);
+    assert_eq!(pollster::block_on(lattice.get_state()), before);
+}